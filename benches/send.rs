@@ -0,0 +1,85 @@
+//! Overhead of sending requests through the client, with an in-memory transport.
+//!
+//! Run with `cargo bench --bench send`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use zarinpal::{
+    error::ZarinResult,
+    prelude::*,
+    transport::{HttpTransport, JsonRequest},
+};
+
+const MERCHANT_ID: &str = "1344b5d4-0048-11e8-94db-005056a205be";
+const AUTHORITY: &str = "A00000000000000000000000000217885159";
+
+/// Answers every request successfully, without any I/O.
+#[derive(Debug)]
+struct InMemoryTransport {
+    request: serde_json::Value,
+    verify: serde_json::Value,
+}
+
+impl InMemoryTransport {
+    fn new() -> Self {
+        Self {
+            request: serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Success",
+                    "authority": AUTHORITY,
+                    "fee_type": "Merchant",
+                    "fee": 100
+                },
+                "errors": []
+            }),
+            verify: serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Verified",
+                    "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+                    "card_pan": "502229******5995",
+                    "ref_id": 201,
+                    "fee_type": "Merchant",
+                    "fee": 0
+                },
+                "errors": []
+            }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for InMemoryTransport {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        Ok(if request.url.path().ends_with("request.json") {
+            self.request.clone()
+        } else {
+            self.verify.clone()
+        })
+    }
+}
+
+fn send(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let zarinpal = Zarinpal::new_with_transport(MERCHANT_ID, InMemoryTransport::new()).unwrap();
+    let callback_url: url::Url = "https://example.com/verify".parse().unwrap();
+
+    c.bench_function("verify_payment", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { zarinpal.verify_payment(AUTHORITY, 10000).await.unwrap() })
+    });
+
+    c.bench_function("request_payment", |b| {
+        b.to_async(&runtime).iter(|| async {
+            zarinpal
+                .request_payment(10000, callback_url.clone(), "Test payment")
+                .await
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, send);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+//! Currency aware payment amounts, see [`Amount`].
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    methods::request::Currency,
+};
+
+/// An amount of money, that knows its [`Currency`].
+///
+/// Accepted anywhere an amount is: [`crate::methods::request::RequestPayment`],
+/// [`crate::methods::verify::VerifyPayment`] and [`crate::methods::request::Wage`].
+/// Typed amounts are converted to the currency of the payment before being sent,
+/// so mixing Rials and Tomans can't go wrong.
+///
+/// A plain `u64` converts to an untyped amount, which is sent as is and means
+/// an amount in whatever currency the payment uses.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     // Requested in Tomans, since the amount is.
+///     let request = zarinpal
+///         .request_payment(Amount::tomans(1000), "https://example.com/".parse()?, "...")
+///         .build()
+///         .await?;
+///
+///     // Sent as 1000 Tomans.
+///     let verify = zarinpal
+///         .verify_payment(request.authority(), Amount::rials(10000))
+///         .currency(Currency::IRT)
+///         .build()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Amount {
+    value: u64,
+    currency: Option<Currency>,
+}
+
+impl Amount {
+    /// Creates an amount of `value` in `currency`.
+    pub fn new(value: u64, currency: Currency) -> Self {
+        Self {
+            value,
+            currency: Some(currency),
+        }
+    }
+
+    /// Creates an amount in Iranian Rials.
+    pub fn rials(value: u64) -> Self {
+        Self::new(value, Currency::IRR)
+    }
+
+    /// Creates an amount in Iranian Tomans.
+    pub fn tomans(value: u64) -> Self {
+        Self::new(value, Currency::IRT)
+    }
+
+    /// The raw value, in [`Amount::currency`].
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Currency of the amount, `None` for untyped amounts.
+    pub fn currency(&self) -> Option<&Currency> {
+        self.currency.as_ref()
+    }
+
+    /// Value of this amount in `to` currency.
+    ///
+    /// Returns `None` for untyped amounts, and whenever [`Currency::convert`] does.
+    pub fn to_currency(&self, to: &Currency) -> Option<u64> {
+        self.currency.as_ref()?.convert(self.value, to)
+    }
+
+    /// Value of this amount in Rials, see [`Amount::to_currency`].
+    pub fn to_rials(&self) -> Option<u64> {
+        self.to_currency(&Currency::IRR)
+    }
+
+    /// Value of this amount in Tomans, see [`Amount::to_currency`].
+    pub fn to_tomans(&self) -> Option<u64> {
+        self.to_currency(&Currency::IRT)
+    }
+
+    /// Adds two amounts, converting `other` to the currency of `self`.
+    ///
+    /// Returns `None` on overflow, or if the amounts can't be mixed.
+    pub fn checked_add(&self, other: &Amount) -> Option<Amount> {
+        let other = self.align(other)?;
+        Some(self.with_value(self.value.checked_add(other)?))
+    }
+
+    /// Subtracts `other` from this amount, converting it to the currency of `self`.
+    ///
+    /// Returns `None` on underflow, or if the amounts can't be mixed.
+    pub fn checked_sub(&self, other: &Amount) -> Option<Amount> {
+        let other = self.align(other)?;
+        Some(self.with_value(self.value.checked_sub(other)?))
+    }
+
+    /// Multiplies this amount by `factor`. Returns `None` on overflow.
+    pub fn checked_mul(&self, factor: u64) -> Option<Amount> {
+        Some(self.with_value(self.value.checked_mul(factor)?))
+    }
+
+    /// Value of `other` in the currency of `self`, if they can be mixed.
+    fn align(&self, other: &Amount) -> Option<u64> {
+        match (&self.currency, &other.currency) {
+            (None, None) => Some(other.value),
+            (Some(currency), Some(_)) => other.to_currency(currency),
+            _ => None,
+        }
+    }
+
+    fn with_value(&self, value: u64) -> Amount {
+        Amount {
+            value,
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// Value to send for a payment in `currency`.
+    pub(crate) fn resolve(&self, currency: &Currency) -> ZarinResult<u64> {
+        match &self.currency {
+            None => Ok(self.value),
+            Some(from) => {
+                from.convert(self.value, currency)
+                    .ok_or_else(|| Error::CurrencyConversionError {
+                        amount: self.value,
+                        from: from.clone(),
+                        to: currency.clone(),
+                    })
+            }
+        }
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(value: u64) -> Self {
+        Self {
+            value,
+            currency: None,
+        }
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.currency {
+            Some(currency) => write!(f, "{} {}", self.value, currency),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        crate::results::__private::number_or_string::<D, u64>(deserializer).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Amount::tomans(1000).to_rials(), Some(10000));
+        assert_eq!(Amount::rials(10000).to_tomans(), Some(1000));
+        assert_eq!(Amount::rials(10005).to_tomans(), None);
+        assert_eq!(Amount::from(1000).to_rials(), None);
+
+        assert_eq!(Amount::tomans(1000).resolve(&Currency::IRR).unwrap(), 10000);
+        assert_eq!(Amount::from(1000).resolve(&Currency::IRT).unwrap(), 1000);
+        assert!(matches!(
+            Amount::rials(15).resolve(&Currency::IRT),
+            Err(Error::CurrencyConversionError { amount: 15, .. })
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(
+            Amount::tomans(1000).checked_add(&Amount::rials(500)),
+            Some(Amount::tomans(1050))
+        );
+        assert_eq!(
+            Amount::rials(10000).checked_sub(&Amount::tomans(100)),
+            Some(Amount::rials(9000))
+        );
+        assert_eq!(Amount::tomans(1000).checked_add(&Amount::from(5)), None);
+        assert_eq!(Amount::rials(5).checked_sub(&Amount::rials(10)), None);
+        assert_eq!(Amount::from(u64::MAX).checked_mul(2), None);
+        assert_eq!(Amount::tomans(3).to_string(), "3 IRT");
+    }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(
+            serde_json::to_value(Amount::tomans(1000)).unwrap(),
+            serde_json::json!(1000)
+        );
+        assert_eq!(
+            serde_json::from_value::<Amount>(serde_json::json!("1000")).unwrap(),
+            Amount::from(1000)
+        );
+    }
+}
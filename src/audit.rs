@@ -0,0 +1,259 @@
+//! Audit trail of api calls, see [`AuditSink`].
+
+use std::{
+    fmt::Debug,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::{error::Error, redact::redact_json, results::result_code::ResultCode};
+
+/// A structured record of one api call, retries included.
+///
+/// Payloads are redacted using [`redact_json`], and merchant id is only kept as a hash, so
+/// records are safe to store.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditRecord {
+    /// When the call started, serialized as unix milliseconds.
+    #[serde(serialize_with = "unix_millis")]
+    pub timestamp: SystemTime,
+
+    /// Path of the api method, like `pg/v4/payment/request.json`.
+    pub path: String,
+
+    /// Hex encoded sha256 of the merchant id the request is sent as.
+    pub merchant_id_hash: String,
+
+    /// Amount of the request, if it has one.
+    pub amount: Option<u64>,
+
+    /// Authority of the payment, from the request or its response.
+    pub authority: Option<String>,
+
+    /// Result code returned by api, or `None` if the call failed without one.
+    pub code: Option<ResultCode>,
+
+    /// Time it took to complete the call, serialized as milliseconds.
+    #[serde(rename = "latency_ms", serialize_with = "millis")]
+    pub latency: Duration,
+
+    /// Redacted json body of the request.
+    pub request: serde_json::Value,
+
+    /// Redacted json body of the (last) response, if there's one.
+    pub response: Option<serde_json::Value>,
+
+    /// The error, if the call failed.
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Creates a record of a call, redacting `request` and `response` and hashing merchant id.
+    ///
+    /// `outcome` is the result code of a successful call, or the error of a failed one.
+    /// Amount and authority are picked from the payloads.
+    pub fn new(
+        timestamp: SystemTime,
+        path: impl Into<String>,
+        merchant_id: &str,
+        mut request: serde_json::Value,
+        mut response: Option<serde_json::Value>,
+        latency: Duration,
+        outcome: Result<ResultCode, &Error>,
+    ) -> Self {
+        let amount = request["amount"].as_u64();
+        let authority = [&request, response.as_ref().map_or(&request, |r| &r["data"])]
+            .iter()
+            .find_map(|value| value["authority"].as_str())
+            .map(ToString::to_string);
+
+        redact_json(&mut request);
+        if let Some(response) = &mut response {
+            redact_json(response);
+        }
+
+        Self {
+            timestamp,
+            path: path.into(),
+            merchant_id_hash: hash_merchant_id(merchant_id),
+            amount,
+            authority,
+            code: match outcome {
+                Ok(code) => Some(code),
+                Err(error) => error.result_code(),
+            },
+            latency,
+            request,
+            response,
+            error: outcome.err().map(ToString::to_string),
+        }
+    }
+}
+
+/// Hex encoded sha256 of `merchant_id`.
+pub fn hash_merchant_id(merchant_id: &str) -> String {
+    Sha256::digest(merchant_id.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn unix_millis<S: Serializer>(timestamp: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    let since_epoch = timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    millis(&since_epoch, serializer)
+}
+
+fn millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+/// Receives an [`AuditRecord`] of every api call, once it's done.
+///
+/// Install it using [`crate::Zarinpal::with_audit_sink`], or use [`JsonLinesAuditSink`].
+///
+/// ```no_run
+/// use zarinpal::{audit::{AuditRecord, AuditSink}, prelude::*};
+///
+/// #[derive(Debug)]
+/// struct LogAudit;
+///
+/// impl AuditSink for LogAudit {
+///     fn record(&self, record: &AuditRecord) {
+///         println!("{} {:?} {:?}", record.path, record.authority, record.code);
+///     }
+/// }
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_audit_sink(LogAudit);
+/// # Ok(())
+/// # }
+/// ```
+pub trait AuditSink: Debug + Send + Sync {
+    /// Called when a call is done, successful or not.
+    fn record(&self, record: &AuditRecord);
+}
+
+impl<T: AuditSink + ?Sized> AuditSink for std::sync::Arc<T> {
+    fn record(&self, record: &AuditRecord) {
+        self.as_ref().record(record)
+    }
+}
+
+/// An [`AuditSink`] writing each record as a line of json, like to an append-only file.
+///
+/// Write errors don't fail api calls, the first one is kept for [`JsonLinesAuditSink::take_error`].
+pub struct JsonLinesAuditSink<W> {
+    writer: Mutex<W>,
+    error: Mutex<Option<std::io::Error>>,
+}
+
+impl JsonLinesAuditSink<std::fs::File> {
+    /// Opens (or creates) the file at `path` to append records to.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write + Send> JsonLinesAuditSink<W> {
+    /// Creates a sink writing to `writer`, it's flushed after each record.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Returns the first write error since the last call, if any.
+    pub fn take_error(&self) -> Option<std::io::Error> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// Returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap()
+    }
+
+    fn write(&self, record: &AuditRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&line)?;
+        writer.flush()
+    }
+}
+
+impl<W> Debug for JsonLinesAuditSink<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonLinesAuditSink").finish_non_exhaustive()
+    }
+}
+
+impl<W: Write + Send> AuditSink for JsonLinesAuditSink<W> {
+    fn record(&self, record: &AuditRecord) {
+        if let Err(error) = self.write(record) {
+            self.error.lock().unwrap().get_or_insert(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_json_lines() {
+        let sink = Arc::new(JsonLinesAuditSink::new(Vec::new()));
+        let zarinpal = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::request("A00000000000000000000000000217885159")),
+        )
+        .unwrap()
+        .with_audit_sink(sink.clone());
+
+        zarinpal
+            .request_payment(
+                10000,
+                "https://example.com/callback".parse().unwrap(),
+                "Test",
+            )
+            .build()
+            .await
+            .unwrap();
+        drop(zarinpal);
+
+        let output = Arc::into_inner(sink).unwrap().into_inner();
+        let lines = String::from_utf8(output).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+
+        let record: serde_json::Value = serde_json::from_str(lines.trim()).unwrap();
+        assert_eq!(record["path"], "pg/v4/payment/request.json");
+        assert_eq!(record["amount"], 10000);
+        assert_eq!(record["authority"], "A00000000000000000000000000217885159");
+        assert_eq!(record["code"], 100);
+        assert_eq!(record["merchant_id_hash"], hash_merchant_id(TEST_UUID));
+        assert_eq!(record["merchant_id_hash"].as_str().unwrap().len(), 64);
+        assert_ne!(record["request"]["merchant_id"], TEST_UUID);
+        assert!(record["error"].is_null());
+        assert!(!lines.contains(TEST_UUID));
+    }
+}
@@ -0,0 +1,97 @@
+//! How api requests are authenticated, see [`Auth`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    transport::JsonRequest,
+    ZarinpalClient,
+};
+
+/// How an [`ApiMethod`](crate::methods::ApiMethod) is authenticated, declared by
+/// [`ApiMethod::AUTH`](crate::methods::ApiMethod::AUTH).
+///
+/// Either way `merchant_id` is added to the request body if the method carries one and it's
+/// not set, the same as it always was.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth {
+    /// Only the merchant id in the request body, like payment gateway endpoints.
+    #[default]
+    MerchantId,
+
+    /// Also an `Authorization: Bearer` header with [`ZarinpalClient::access_token`], like
+    /// refund and reporting endpoints that need the access token of zarinpal dashboard.
+    ///
+    /// Sending fails with [`Error::MissingAccessToken`] if the client has no access token.
+    AccessToken,
+}
+
+impl Auth {
+    /// Adds credentials of `zarinpal` that this scheme needs to `request`.
+    pub(crate) fn authenticate<Z: ZarinpalClient + ?Sized>(
+        self,
+        zarinpal: &Z,
+        request: JsonRequest,
+    ) -> ZarinResult<JsonRequest> {
+        match self {
+            Auth::MerchantId => Ok(request),
+            Auth::AccessToken => {
+                let access_token = zarinpal.access_token().ok_or(Error::MissingAccessToken)?;
+                Ok(request.bearer_auth(access_token))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Error,
+        prelude::PaymentStatus,
+        results::inquiry::Inquiry,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    crate::define_api_method! {
+        /// An inquiry that needs an access token.
+        pub struct TokenInquiry("pg/v4/payment/inquiry.json", auth = AccessToken) -> Inquiry {
+            /// The unique authority of the payment.
+            #[builder(setter(into))]
+            authority: String,
+        }
+    }
+
+    /// Responds with a paid inquiry, if the request carries the expected credentials.
+    fn token_transport() -> CannedTransport {
+        CannedTransport::new(|request| {
+            assert_eq!(
+                request.headers,
+                [("Authorization".to_string(), "Bearer token".to_string())]
+            );
+            assert_eq!(request.body["merchant_id"], TEST_UUID);
+            Ok(responses::inquiry("PAID"))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_access_token_auth() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, token_transport())
+            .unwrap()
+            .with_access_token("token");
+
+        let inquiry = TokenInquiry::builder()
+            .authority("A1")
+            .zarinpal(&zarinpal)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(inquiry.status(), PaymentStatus::Paid);
+
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, token_transport()).unwrap();
+        let detached: TokenInquiry = TokenInquiry::builder().authority("A1").build();
+        let result = detached.send_with(&zarinpal).await;
+        assert!(matches!(result, Err(Error::MissingAccessToken)));
+    }
+}
@@ -0,0 +1,268 @@
+//! Configuring a [`Zarinpal`] client in one place, see [`Zarinpal::builder`].
+
+#[cfg(feature = "reqwest")]
+use std::time::Duration;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    callback::CallbackUrlPolicy,
+    environment::{Environment, GatewayUrl},
+    error::{Error, ZarinResult},
+    events::EventHandler,
+    methods::request::RequestDefaults,
+    metrics::MetricsSink,
+    rate_limit::RateLimiter,
+    retry::RetryPolicy,
+    secret::{AccessToken, MerchantId},
+    transport::HttpTransport,
+    Zarinpal,
+};
+
+/// Builder of a [`Zarinpal`] client, created using [`Zarinpal::builder`].
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::prelude::*;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::builder("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
+///     .base_url("http://localhost:8080/".parse()?)
+///     .timeout(Duration::from_secs(10))
+///     .proxy(reqwest::Proxy::https("http://proxy.internal:3128")?)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ZarinpalBuilder {
+    merchant_id: MerchantId,
+    base_url: url::Url,
+    gateway_url: Option<GatewayUrl>,
+    request_defaults: Option<RequestDefaults>,
+    callback_url_policy: Option<CallbackUrlPolicy>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    retry_policy: Option<RetryPolicy>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    #[cfg(feature = "audit")]
+    audit_sink: Option<Arc<dyn crate::audit::AuditSink>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    access_token: Option<AccessToken>,
+    #[cfg(feature = "reqwest")]
+    timeout: Option<Duration>,
+    #[cfg(feature = "reqwest")]
+    proxy: Option<reqwest::Proxy>,
+    #[cfg(feature = "reqwest")]
+    default_headers: Option<reqwest::header::HeaderMap>,
+}
+
+impl ZarinpalBuilder {
+    pub(crate) fn new(merchant_id: impl Into<String>) -> Self {
+        Self {
+            merchant_id: MerchantId::new(merchant_id.into()),
+            base_url: Environment::Production.base_url(),
+            gateway_url: None,
+            request_defaults: None,
+            callback_url_policy: None,
+            transport: None,
+            retry_policy: None,
+            metrics: None,
+            rate_limiter: None,
+            #[cfg(feature = "audit")]
+            audit_sink: None,
+            event_handler: None,
+            access_token: None,
+            #[cfg(feature = "reqwest")]
+            timeout: None,
+            #[cfg(feature = "reqwest")]
+            proxy: None,
+            #[cfg(feature = "reqwest")]
+            default_headers: None,
+        }
+    }
+
+    /// Sends requests to a custom base url, like a staging proxy or a mock server.
+    pub fn base_url(mut self, base_url: url::Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Sends requests to base url of `environment`.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.base_url = environment.base_url();
+        self
+    }
+
+    /// Redirects payers to a custom gateway, see [`Zarinpal::with_gateway_url`].
+    pub fn gateway_url(mut self, gateway_url: GatewayUrl) -> Self {
+        self.gateway_url = Some(gateway_url);
+        self
+    }
+
+    /// Merges `defaults` into every payment request, see [`Zarinpal::with_request_defaults`].
+    pub fn request_defaults(mut self, defaults: RequestDefaults) -> Self {
+        self.request_defaults = Some(defaults);
+        self
+    }
+
+    /// Checks callback urls of payment requests using `policy`, see
+    /// [`Zarinpal::with_callback_url_policy`].
+    pub fn callback_url_policy(mut self, policy: CallbackUrlPolicy) -> Self {
+        self.callback_url_policy = Some(policy);
+        self
+    }
+
+    /// Uses a custom [`HttpTransport`] to send requests.
+    ///
+    /// [`ZarinpalBuilder::timeout`], [`ZarinpalBuilder::proxy`] and
+    /// [`ZarinpalBuilder::default_headers`] are ignored if this is set,
+    /// configure them on the transport instead.
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Retries transient failures using `policy`, see [`RetryPolicy`].
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Reports metrics of every sent request to `sink`, see [`MetricsSink`].
+    pub fn metrics(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.metrics = Some(Arc::new(sink));
+        self
+    }
+
+    /// Throttles requests using `limiter`, see [`RateLimiter`].
+    pub fn rate_limiter(mut self, limiter: impl Into<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = Some(limiter.into());
+        self
+    }
+
+    /// Sends a record of every api call to `sink`, see [`AuditSink`](crate::audit::AuditSink).
+    #[cfg(feature = "audit")]
+    pub fn audit_sink(mut self, sink: impl crate::audit::AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sends payment lifecycle events to `handler`, see [`EventHandler`].
+    pub fn event_handler(mut self, handler: impl EventHandler + 'static) -> Self {
+        self.event_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets access token of zarinpal dashboard, see [`Zarinpal::with_access_token`].
+    pub fn access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = Some(AccessToken::new(access_token.into()));
+        self
+    }
+
+    /// Timeout of each request (each attempt, if retried), from connecting until the response body is read.
+    #[cfg(feature = "reqwest")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends requests through a proxy.
+    #[cfg(feature = "reqwest")]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Headers that are sent with every request.
+    #[cfg(feature = "reqwest")]
+    pub fn default_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+        self.default_headers = Some(headers);
+        self
+    }
+
+    /// Uses a custom [`reqwest::Client`] as inner http client.
+    ///
+    /// Same as [`ZarinpalBuilder::transport`] with a [`crate::transport::ReqwestTransport`].
+    #[cfg(feature = "reqwest")]
+    pub fn client(self, client: reqwest::Client) -> Self {
+        self.transport(crate::transport::ReqwestTransport::new(client))
+    }
+
+    /// Builds the [`Zarinpal`] client.
+    ///
+    /// This fails if merchant id is not a valid uuid, or the http client can't be created.
+    pub fn build(self) -> ZarinResult<Zarinpal> {
+        let merchant_id = uuid::Uuid::parse_str(self.merchant_id.expose())
+            .map_err(Error::InvalidMerchantId)?
+            .to_string();
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            #[cfg(feature = "reqwest")]
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                if let Some(headers) = self.default_headers {
+                    builder = builder.default_headers(headers);
+                }
+                Arc::new(crate::transport::ReqwestTransport::new(builder.build()?))
+            }
+            #[cfg(not(feature = "reqwest"))]
+            None => {
+                return Err(Error::TransportError(
+                    "no http transport is set, and `reqwest` feature is disabled".into(),
+                ))
+            }
+        };
+
+        Ok(Zarinpal {
+            transport,
+            merchant_id: MerchantId::new(merchant_id),
+            base_url: self.base_url,
+            gateway_url: self.gateway_url,
+            wage_profiles: HashMap::new(),
+            request_defaults: self.request_defaults,
+            callback_url_policy: self.callback_url_policy,
+            request_hook: None,
+            retry_policy: self.retry_policy,
+            metrics: self.metrics,
+            rate_limiter: self.rate_limiter,
+            #[cfg(feature = "audit")]
+            audit_sink: self.audit_sink,
+            event_handler: self.event_handler,
+            access_token: self.access_token,
+            message_locale: Default::default(),
+            deserialization_mode: Default::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transport::tests::OfflineTransport, ZarinpalClient, TEST_UUID};
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let zarinpal = Zarinpal::builder(TEST_UUID)
+            .base_url("http://localhost:8080/".parse().unwrap())
+            .transport(OfflineTransport)
+            .build()
+            .unwrap();
+
+        assert_eq!(zarinpal.base_url().as_str(), "http://localhost:8080/");
+        assert_eq!(zarinpal.merchant_id(), TEST_UUID);
+
+        assert!(matches!(
+            Zarinpal::builder("not-a-uuid").build(),
+            Err(Error::InvalidMerchantId(_))
+        ));
+    }
+}
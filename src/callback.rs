@@ -0,0 +1,308 @@
+//! Parsing the query string zarinpal redirects the payer to `callback_url` with.
+//!
+//! Eg: `https://example.com/verify?Authority=A00000000000000000000000000217885159&Status=OK`
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    results::verify::{Verify, VerifyOutcome},
+};
+
+/// Default of [`CallbackUrlPolicy::max_length`].
+pub const DEFAULT_MAX_CALLBACK_URL_LENGTH: usize = 2048;
+
+/// Status of a payment reported in the callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub enum CallbackStatus {
+    /// The payer paid, the payment should be verified now.
+    #[serde(rename = "OK")]
+    Ok,
+
+    /// The payment is failed or canceled by the payer.
+    #[serde(rename = "NOK")]
+    Nok,
+}
+
+/// An error parsing a [`PaymentCallback`].
+#[cfg(feature = "web")]
+#[derive(Debug, thiserror::Error)]
+#[error("Malformed zarinpal callback query: {0}")]
+pub struct CallbackError(#[from] serde_urlencoded::de::Error);
+
+/// Query parameters of a zarinpal callback.
+///
+/// ```
+/// use zarinpal::callback::{CallbackStatus, PaymentCallback};
+///
+/// let callback =
+///     PaymentCallback::from_query("Authority=A00000000000000000000000000217885159&Status=OK")
+///         .unwrap();
+///
+/// assert_eq!(callback.authority(), "A00000000000000000000000000217885159");
+/// assert_eq!(callback.status(), CallbackStatus::Ok);
+/// ```
+///
+/// With `utoipa` feature, it's `utoipa::IntoParams` (as `Authority` and `Status` query
+/// parameters), so it can be listed in `params(...)` of `#[utoipa::path]` of callback endpoints.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(
+    feature = "utoipa",
+    derive(utoipa::ToSchema, utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct PaymentCallback {
+    /// Unique authority of the payment request.
+    #[serde(rename = "Authority")]
+    #[cfg_attr(
+        feature = "utoipa",
+        param(example = "A00000000000000000000000000217885159"),
+        schema(example = "A00000000000000000000000000217885159")
+    )]
+    authority: String,
+
+    /// Status of the payment.
+    #[serde(rename = "Status")]
+    #[cfg_attr(feature = "utoipa", param(inline))]
+    status: CallbackStatus,
+}
+
+impl PaymentCallback {
+    /// Creates a callback from its parts.
+    pub fn new(authority: impl Into<String>, status: CallbackStatus) -> Self {
+        Self {
+            authority: authority.into(),
+            status,
+        }
+    }
+
+    /// Parses a callback from a query string (without the leading `?`).
+    ///
+    /// Unknown parameters are ignored.
+    #[cfg(feature = "web")]
+    pub fn from_query(query: &str) -> Result<Self, CallbackError> {
+        Ok(serde_urlencoded::from_str(query)?)
+    }
+
+    /// Unique authority of the payment request.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Status of the payment.
+    pub fn status(&self) -> CallbackStatus {
+        self.status
+    }
+
+    /// Returns `true` if the payer reported a successful payment.
+    pub fn is_ok(&self) -> bool {
+        self.status == CallbackStatus::Ok
+    }
+}
+
+/// Outcome of [`crate::extensions::ZarinpalSendExtension::verify_callback`].
+#[derive(Debug, Clone)]
+pub enum CallbackOutcome {
+    /// The payment is verified for the first time, deliver the order.
+    Verified(Verify),
+
+    /// The payment was verified before.
+    AlreadyVerified(Verify),
+
+    /// The payer canceled (or failed) the payment, verify is not sent.
+    CanceledByUser,
+}
+
+impl CallbackOutcome {
+    /// Returns `true` if this is the first successful verification.
+    pub fn is_verified(&self) -> bool {
+        matches!(self, CallbackOutcome::Verified(_))
+    }
+
+    /// The verify result, if the payment is verified (now or before).
+    pub fn verify(&self) -> Option<&Verify> {
+        match self {
+            CallbackOutcome::Verified(verify) | CallbackOutcome::AlreadyVerified(verify) => {
+                Some(verify)
+            }
+            CallbackOutcome::CanceledByUser => None,
+        }
+    }
+}
+
+impl From<VerifyOutcome> for CallbackOutcome {
+    fn from(value: VerifyOutcome) -> Self {
+        match value {
+            VerifyOutcome::Verified(verify) => CallbackOutcome::Verified(verify),
+            VerifyOutcome::AlreadyVerified(verify) => CallbackOutcome::AlreadyVerified(verify),
+        }
+    }
+}
+
+/// Rules that callback urls of payment requests are checked with, before they're sent.
+///
+/// Callback urls must be absolute `http` or `https` urls, at most
+/// [`CallbackUrlPolicy::max_length`] long. If any domains are allowed, the host must be one of
+/// them (or their subdomains). Invalid urls fail with [`Error::InvalidCallbackUrl`].
+///
+/// ```
+/// use zarinpal::callback::CallbackUrlPolicy;
+///
+/// let policy = CallbackUrlPolicy::default().allow_domain("example.com");
+///
+/// assert!(policy.validate("https://shop.example.com/verify").is_ok());
+/// assert!(policy.validate("https://example.org/verify").is_err());
+/// assert!(policy.validate("/verify").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackUrlPolicy {
+    max_length: usize,
+    allowed_domains: Vec<String>,
+}
+
+impl Default for CallbackUrlPolicy {
+    /// Accepts any domain, with urls up to [`DEFAULT_MAX_CALLBACK_URL_LENGTH`] long.
+    fn default() -> Self {
+        Self {
+            max_length: DEFAULT_MAX_CALLBACK_URL_LENGTH,
+            allowed_domains: Vec::new(),
+        }
+    }
+}
+
+impl CallbackUrlPolicy {
+    /// Accepts urls up to `max_length` bytes long.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Accepts urls on `domain` and its subdomains. Any domain is accepted if none is allowed.
+    pub fn allow_domain(mut self, domain: impl Into<String>) -> Self {
+        self.allowed_domains
+            .push(domain.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Maximum length of callback urls, in bytes.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Domains that callback urls must be on, empty if any domain is accepted.
+    pub fn allowed_domains(&self) -> &[String] {
+        &self.allowed_domains
+    }
+
+    /// Checks `callback_url` against this policy.
+    pub fn validate(&self, callback_url: &str) -> ZarinResult<url::Url> {
+        let invalid = Error::InvalidCallbackUrl;
+
+        if callback_url.len() > self.max_length {
+            return Err(invalid(format!(
+                "callback_url must be at most {} characters.",
+                self.max_length
+            )));
+        }
+
+        let url = url::Url::parse(callback_url)
+            .map_err(|error| invalid(format!("callback_url must be an absolute url: {error}.")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid(
+                "callback_url must be an http or https url.".to_string(),
+            ));
+        }
+
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let allowed = self.allowed_domains.is_empty()
+            || self.allowed_domains.iter().any(|domain| {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|sub| sub.ends_with('.'))
+            });
+        if !allowed {
+            return Err(invalid(format!(
+                "callback_url domain {host} is not allowed."
+            )));
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "utoipa")]
+    #[test]
+    fn test_params() {
+        use utoipa::{openapi::path::ParameterIn, IntoParams};
+
+        let params = PaymentCallback::into_params(|| None);
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name, "Authority");
+        assert_eq!(params[1].name, "Status");
+        assert!(params
+            .iter()
+            .all(|param| param.parameter_in == ParameterIn::Query));
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn test_from_query() {
+        let callback = PaymentCallback::from_query(
+            "Authority=A00000000000000000000000000217885159&Status=NOK&utm=1",
+        )
+        .unwrap();
+        assert_eq!(callback.status(), CallbackStatus::Nok);
+        assert!(!callback.is_ok());
+
+        assert!(PaymentCallback::from_query("Authority=A0000&Status=MAYBE").is_err());
+        assert!(PaymentCallback::from_query("Status=OK").is_err());
+    }
+
+    #[test]
+    fn test_callback_url_policy() {
+        let rejected = |policy: &CallbackUrlPolicy, url: &str| {
+            assert!(
+                matches!(policy.validate(url), Err(Error::InvalidCallbackUrl(_))),
+                "{url}"
+            );
+        };
+
+        let policy = CallbackUrlPolicy::default();
+        assert!(policy
+            .validate("http://localhost:8080/verify?order=1")
+            .is_ok());
+        rejected(&policy, "example.com/verify");
+        rejected(&policy, "/verify");
+        rejected(&policy, "ftp://example.com/verify");
+        rejected(&policy, "mailto:payer@example.com");
+
+        let policy = CallbackUrlPolicy::default()
+            .with_max_length(30)
+            .allow_domain("Example.com");
+        assert!(policy.validate("https://EXAMPLE.com/v").is_ok());
+        assert!(policy.validate("https://pay.example.com/v").is_ok());
+        rejected(&policy, "https://badexample.com/v");
+        rejected(&policy, "https://example.com.evil.io/v");
+        rejected(&policy, "https://example.com/a-very-long-path");
+    }
+
+    #[tokio::test]
+    async fn test_verify_canceled_callback() {
+        use crate::{extensions::ZarinpalSendExtension, Zarinpal};
+
+        let zarinpal = Zarinpal::new_test().unwrap();
+        let callback =
+            PaymentCallback::new("A00000000000000000000000000217885159", CallbackStatus::Nok);
+
+        // Resolves without sending any request.
+        assert!(matches!(
+            zarinpal.verify_callback(callback, 10000).await.unwrap(),
+            CallbackOutcome::CanceledByUser
+        ));
+    }
+}
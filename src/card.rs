@@ -0,0 +1,340 @@
+//! Bank card numbers, see [`CardPan`], and the iranian banks issuing them, see [`Bank`].
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    mobile::to_ascii_digit,
+};
+
+/// An iranian bank, known by the first 6 digits (BIN) of the cards it issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Bank {
+    Melli,
+    Sepah,
+    Saderat,
+    Mellat,
+    Tejarat,
+    Keshavarzi,
+    Maskan,
+    Refah,
+    PostBank,
+    ToseeSaderat,
+    ToseeTaavon,
+    SanatMadan,
+    EghtesadNovin,
+    Parsian,
+    Pasargad,
+    Karafarin,
+    Saman,
+    Sina,
+    Sarmayeh,
+    Shahr,
+    Dey,
+    Ayandeh,
+    Iranzamin,
+    Gardeshgari,
+    Khavarmianeh,
+    Resalat,
+    MehrIran,
+}
+
+impl Bank {
+    /// Bank issuing cards starting with `pan`, which can be a full or masked card number (like
+    /// [`crate::results::verify::Verify::card_pan`]), as long as its first 6 digits are there.
+    ///
+    /// ```
+    /// use zarinpal::card::Bank;
+    ///
+    /// assert_eq!(Bank::from_pan("603799******1234"), Some(Bank::Melli));
+    /// assert_eq!(Bank::from_pan("123456******1234"), None);
+    /// ```
+    pub fn from_pan(pan: &str) -> Option<Self> {
+        let bank = match pan.get(..6)? {
+            "603799" => Bank::Melli,
+            "589210" => Bank::Sepah,
+            "603769" => Bank::Saderat,
+            "610433" | "991975" => Bank::Mellat,
+            "627353" | "585983" => Bank::Tejarat,
+            "603770" => Bank::Keshavarzi,
+            "628023" => Bank::Maskan,
+            "589463" => Bank::Refah,
+            "627760" => Bank::PostBank,
+            "627648" => Bank::ToseeSaderat,
+            "502908" => Bank::ToseeTaavon,
+            "627961" => Bank::SanatMadan,
+            "627412" => Bank::EghtesadNovin,
+            "622106" | "639194" => Bank::Parsian,
+            "502229" | "639347" => Bank::Pasargad,
+            "627488" | "502910" => Bank::Karafarin,
+            "621986" => Bank::Saman,
+            "639346" => Bank::Sina,
+            "639607" => Bank::Sarmayeh,
+            "502806" | "504706" => Bank::Shahr,
+            "502938" => Bank::Dey,
+            "636214" => Bank::Ayandeh,
+            "505785" => Bank::Iranzamin,
+            "505416" => Bank::Gardeshgari,
+            "585947" => Bank::Khavarmianeh,
+            "504172" => Bank::Resalat,
+            "606373" => Bank::MehrIran,
+            _ => return None,
+        };
+        Some(bank)
+    }
+
+    /// English name of the bank.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bank::Melli => "Bank Melli Iran",
+            Bank::Sepah => "Bank Sepah",
+            Bank::Saderat => "Bank Saderat Iran",
+            Bank::Mellat => "Bank Mellat",
+            Bank::Tejarat => "Tejarat Bank",
+            Bank::Keshavarzi => "Keshavarzi Bank",
+            Bank::Maskan => "Bank Maskan",
+            Bank::Refah => "Refah Kargaran Bank",
+            Bank::PostBank => "Post Bank of Iran",
+            Bank::ToseeSaderat => "Export Development Bank of Iran",
+            Bank::ToseeTaavon => "Tose'e Ta'avon Bank",
+            Bank::SanatMadan => "Bank of Industry and Mine",
+            Bank::EghtesadNovin => "EN Bank",
+            Bank::Parsian => "Parsian Bank",
+            Bank::Pasargad => "Bank Pasargad",
+            Bank::Karafarin => "Karafarin Bank",
+            Bank::Saman => "Saman Bank",
+            Bank::Sina => "Sina Bank",
+            Bank::Sarmayeh => "Sarmayeh Bank",
+            Bank::Shahr => "Shahr Bank",
+            Bank::Dey => "Dey Bank",
+            Bank::Ayandeh => "Ayandeh Bank",
+            Bank::Iranzamin => "Iran Zamin Bank",
+            Bank::Gardeshgari => "Gardeshgari Bank",
+            Bank::Khavarmianeh => "Middle East Bank",
+            Bank::Resalat => "Resalat Bank",
+            Bank::MehrIran => "Mehr Iran Bank",
+        }
+    }
+
+    /// Persian name of the bank.
+    pub fn persian_name(&self) -> &'static str {
+        match self {
+            Bank::Melli => "بانک ملی ایران",
+            Bank::Sepah => "بانک سپه",
+            Bank::Saderat => "بانک صادرات ایران",
+            Bank::Mellat => "بانک ملت",
+            Bank::Tejarat => "بانک تجارت",
+            Bank::Keshavarzi => "بانک کشاورزی",
+            Bank::Maskan => "بانک مسکن",
+            Bank::Refah => "بانک رفاه کارگران",
+            Bank::PostBank => "پست بانک ایران",
+            Bank::ToseeSaderat => "بانک توسعه صادرات",
+            Bank::ToseeTaavon => "بانک توسعه تعاون",
+            Bank::SanatMadan => "بانک صنعت و معدن",
+            Bank::EghtesadNovin => "بانک اقتصاد نوین",
+            Bank::Parsian => "بانک پارسیان",
+            Bank::Pasargad => "بانک پاسارگاد",
+            Bank::Karafarin => "بانک کارآفرین",
+            Bank::Saman => "بانک سامان",
+            Bank::Sina => "بانک سینا",
+            Bank::Sarmayeh => "بانک سرمایه",
+            Bank::Shahr => "بانک شهر",
+            Bank::Dey => "بانک دی",
+            Bank::Ayandeh => "بانک آینده",
+            Bank::Iranzamin => "بانک ایران زمین",
+            Bank::Gardeshgari => "بانک گردشگری",
+            Bank::Khavarmianeh => "بانک خاورمیانه",
+            Bank::Resalat => "بانک قرض الحسنه رسالت",
+            Bank::MehrIran => "بانک قرض الحسنه مهر ایران",
+        }
+    }
+}
+
+impl Display for Bank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A valid 16 digit bank card number, like `5022291083818920`.
+///
+/// Its checksum (Luhn) is validated when parsing, and spaces and dashes are ignored, so typos
+/// are caught before a request is sent.
+///
+/// `Display` output masks the middle digits like `502229******8920`, use [`CardPan::as_str`]
+/// for the full number. So does `Debug` output, unless `unredacted-debug` feature is enabled.
+///
+/// ```
+/// use zarinpal::card::{Bank, CardPan};
+///
+/// let pan: CardPan = "5022-2910-8381-8920".parse().unwrap();
+///
+/// assert_eq!(pan.as_str(), "5022291083818920");
+/// assert_eq!(pan.to_string(), "502229******8920");
+/// assert_eq!(pan.bank(), Some(Bank::Pasargad));
+/// assert!("5022291083818921".parse::<CardPan>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
+#[serde(try_from = "String", into = "String")]
+pub struct CardPan(String);
+
+impl CardPan {
+    /// Parses and validates `pan`, ignoring spaces and dashes.
+    ///
+    /// Fails with [`Error::InvalidCardPan`] if it's not 16 digits, or the checksum doesn't match.
+    pub fn new(pan: &str) -> ZarinResult<Self> {
+        let pan = pan
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .map(to_ascii_digit)
+            .collect::<Option<String>>()
+            .filter(|pan| pan.len() == 16)
+            .ok_or_else(|| Error::InvalidCardPan("card pan must be 16 digits".to_string()))?;
+
+        if !luhn(&pan) {
+            return Err(Error::InvalidCardPan(
+                "card pan checksum doesn't match".to_string(),
+            ));
+        }
+        Ok(Self(pan))
+    }
+
+    /// The full card number.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// First 6 digits of the card number, identifying its issuer.
+    pub fn bin(&self) -> &str {
+        &self.0[..6]
+    }
+
+    /// Bank that issued the card, if it's a known iranian one.
+    pub fn bank(&self) -> Option<Bank> {
+        Bank::from_pan(&self.0)
+    }
+
+    /// The card number with all but first 6 and last 4 digits masked, in the format of
+    /// [`crate::results::verify::Verify::card_pan`].
+    pub fn masked(&self) -> String {
+        crate::redact::mask(&self.0, 6, 4)
+    }
+}
+
+/// Returns `true` if the Luhn checksum of `digits` is valid.
+fn luhn(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| {
+            let digit = u32::from(digit - b'0');
+            match i % 2 {
+                0 => digit,
+                _ if digit > 4 => digit * 2 - 9,
+                _ => digit * 2,
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+impl FromStr for CardPan {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for CardPan {
+    type Error = Error;
+
+    fn try_from(pan: String) -> Result<Self, Self::Error> {
+        Self::new(&pan)
+    }
+}
+
+impl TryFrom<&str> for CardPan {
+    type Error = Error;
+
+    fn try_from(pan: &str) -> Result<Self, Self::Error> {
+        Self::new(pan)
+    }
+}
+
+impl From<CardPan> for String {
+    fn from(pan: CardPan) -> Self {
+        pan.0
+    }
+}
+
+impl AsRef<str> for CardPan {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CardPan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.masked())
+    }
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for CardPan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CardPan").field(&self.masked()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pan = CardPan::new("6037991234567893").unwrap();
+        assert_eq!(pan.bin(), "603799");
+        assert_eq!(pan.bank(), Some(Bank::Melli));
+        assert_eq!(CardPan::new("6037 9912 3456 7893").unwrap(), pan);
+        assert_eq!(CardPan::new("۶۰۳۷۹۹۱۲۳۴۵۶۷۸۹۳").unwrap(), pan);
+
+        for invalid in [
+            "",
+            "603799123456789",
+            "60379912345678930",
+            "6037991234567890",
+            "603799123456789x",
+        ] {
+            assert!(
+                matches!(CardPan::new(invalid), Err(Error::InvalidCardPan(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let pan: CardPan =
+            serde_json::from_value(serde_json::json!("5022-2910-8381-8920")).unwrap();
+        assert_eq!(
+            serde_json::to_value(&pan).unwrap(),
+            serde_json::json!("5022291083818920")
+        );
+        assert!(serde_json::from_value::<CardPan>(serde_json::json!("5022291083818921")).is_err());
+    }
+
+    #[test]
+    fn test_bank() {
+        assert_eq!(Bank::from_pan("610433******1234"), Some(Bank::Mellat));
+        assert_eq!(Bank::from_pan("603769"), Some(Bank::Saderat));
+        assert_eq!(Bank::from_pan("60376"), None);
+        assert_eq!(Bank::Saderat.to_string(), "Bank Saderat Iran");
+        assert_eq!(Bank::Mellat.persian_name(), "بانک ملت");
+    }
+}
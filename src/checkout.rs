@@ -0,0 +1,159 @@
+//! Html pages and forms that send payers to the payment gateway, for server-rendered apps.
+//!
+//! ```no_run
+//! use zarinpal::prelude::*;
+//! use zarinpal::checkout::CheckoutPage;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+//!     let zarinpal = Zarinpal::new("...")?;
+//!
+//!     let request = zarinpal
+//!         .request_payment(10000, "https://example.com/verify".parse()?, "...")
+//!         .build()
+//!         .await?;
+//!
+//!     // Respond with this html.
+//!     let html = CheckoutPage::for_request(&request)
+//!         .locale(MessageLocale::Persian)
+//!         .build()
+//!         .render();
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use typed_builder::TypedBuilder;
+
+use crate::{
+    html::escape,
+    results::{request::Request, result_code::MessageLocale},
+};
+
+/// A page (or just a form) that sends the payer to a gateway url.
+///
+/// The form submits itself using a tiny script if `auto_submit` is set (the default), and
+/// there's always a button for browsers without javascript.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CheckoutPage {
+    /// Gateway url of the payment, like [`Request::gateway_url`].
+    gateway_url: url::Url,
+
+    /// (Optional) Language of the page. Defaults to [`MessageLocale::English`].
+    #[builder(default)]
+    locale: MessageLocale,
+
+    /// (Optional) Submits the form as soon as the page is loaded. Defaults to `true`.
+    #[builder(default = true)]
+    auto_submit: bool,
+
+    /// (Optional) Message shown while the payer is being redirected, instead of the default one.
+    #[builder(default, setter(strip_option, into))]
+    message: Option<String>,
+}
+
+impl CheckoutPage {
+    /// Starts a page that sends the payer to the gateway of `request`.
+    pub fn for_request(request: &Request) -> CheckoutPageBuilder<((url::Url,), (), (), ())> {
+        Self::builder().gateway_url(request.gateway_url())
+    }
+
+    /// Message shown while the payer is being redirected.
+    pub fn message(&self) -> &str {
+        match (&self.message, self.locale) {
+            (Some(message), _) => message,
+            (None, MessageLocale::English) => "Redirecting to the bank gateway...",
+            (None, MessageLocale::Persian) => "در حال انتقال به درگاه بانک...",
+        }
+    }
+
+    fn button(&self) -> &'static str {
+        match self.locale {
+            MessageLocale::English => "Continue to payment",
+            MessageLocale::Persian => "ادامه پرداخت",
+        }
+    }
+
+    /// Renders just the form (and its script), to embed in your own page.
+    pub fn render_form(&self) -> String {
+        // StartPay takes the authority in path, so the form has no fields.
+        let mut html = format!(
+            concat!(
+                r#"<form id="zarinpal-checkout" method="get" action="{action}">"#,
+                r#"<p>{message}</p>"#,
+                r#"<button type="submit">{button}</button>"#,
+                "</form>"
+            ),
+            action = escape(self.gateway_url.as_str()),
+            message = escape(self.message()),
+            button = escape(self.button()),
+        );
+        if self.auto_submit {
+            html.push_str(
+                r#"<script>document.getElementById("zarinpal-checkout").submit();</script>"#,
+            );
+        }
+        html
+    }
+
+    /// Renders a whole html page, redirecting to the gateway.
+    pub fn render(&self) -> String {
+        let (lang, dir) = match self.locale {
+            MessageLocale::English => ("en", "ltr"),
+            MessageLocale::Persian => ("fa", "rtl"),
+        };
+
+        format!(
+            concat!(
+                "<!DOCTYPE html>",
+                r#"<html lang="{lang}" dir="{dir}">"#,
+                "<head>",
+                r#"<meta charset="utf-8">"#,
+                r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#,
+                "<title>{message}</title>",
+                "</head>",
+                "<body>{form}</body>",
+                "</html>"
+            ),
+            lang = lang,
+            dir = dir,
+            message = escape(self.message()),
+            form = self.render_form(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let page = CheckoutPage::builder()
+            .gateway_url(
+                "https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159"
+                    .parse()
+                    .unwrap(),
+            )
+            .build();
+
+        let html = page.render();
+        assert!(html.starts_with("<!DOCTYPE html><html lang=\"en\" dir=\"ltr\">"));
+        assert!(html.contains(
+            r#"action="https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159""#
+        ));
+        assert!(html.contains("<script>"));
+
+        let form = CheckoutPage::builder()
+            .gateway_url("https://example.com/pay?a=1&b=2".parse().unwrap())
+            .locale(MessageLocale::Persian)
+            .auto_submit(false)
+            .message("<پرداخت>")
+            .build()
+            .render_form();
+        assert!(form.contains(r#"action="https://example.com/pay?a=1&amp;b=2""#));
+        assert!(form.contains("<p>&lt;پرداخت&gt;</p>"));
+        assert!(form.contains("ادامه پرداخت"));
+        assert!(!form.contains("<script>"));
+    }
+}
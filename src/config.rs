@@ -0,0 +1,239 @@
+//! Creating a [`Zarinpal`] client from environment variables or config files.
+//!
+//! [`ZarinpalConfig`] is `Deserialize`, so it can be loaded from toml, yaml or any other format
+//! using `figment`, `config` or plain serde.
+//!
+//! ```toml
+//! [zarinpal]
+//! merchant_id = "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx"
+//! sandbox = true
+//! ```
+
+use std::fmt::Debug;
+
+use serde::Deserialize;
+
+use crate::{
+    builder::ZarinpalBuilder,
+    environment::{Environment, GatewayUrl},
+    error::{Error, ZarinResult},
+    Zarinpal,
+};
+
+/// Environment variable of merchant id, see [`ZarinpalConfig::from_env`].
+pub const MERCHANT_ID_VAR: &str = "ZARINPAL_MERCHANT_ID";
+
+/// Environment variable of base url, see [`ZarinpalConfig::from_env`].
+pub const BASE_URL_VAR: &str = "ZARINPAL_BASE_URL";
+
+/// Environment variable of sandbox flag, see [`ZarinpalConfig::from_env`].
+pub const SANDBOX_VAR: &str = "ZARINPAL_SANDBOX";
+
+/// Settings of a [`Zarinpal`] client.
+///
+/// ```no_run
+/// use zarinpal::config::ZarinpalConfig;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let config: ZarinpalConfig = serde_json::from_str(
+///     r#"{ "merchant_id": "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx", "sandbox": true }"#,
+/// )?;
+/// let zarinpal = config.build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct ZarinpalConfig {
+    /// Merchant id (uuid) of the client.
+    pub merchant_id: String,
+
+    /// (Optional) Custom base url, like a staging proxy or a mock server.
+    ///
+    /// This wins over [`ZarinpalConfig::sandbox`] if both are set.
+    #[serde(default)]
+    pub base_url: Option<url::Url>,
+
+    /// Sends requests to [`Environment::Sandbox`] if `true`, defaults to `false`.
+    #[serde(default)]
+    pub sandbox: bool,
+
+    /// (Optional) Custom payment gateway, see [`crate::Zarinpal::with_gateway_url`].
+    #[serde(default)]
+    pub gateway_url: Option<GatewayUrl>,
+}
+
+impl Debug for ZarinpalConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZarinpalConfig")
+            .field("merchant_id", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("sandbox", &self.sandbox)
+            .field("gateway_url", &self.gateway_url)
+            .finish()
+    }
+}
+
+impl ZarinpalConfig {
+    /// Creates a config of `merchant_id`, in production environment.
+    pub fn new(merchant_id: impl Into<String>) -> Self {
+        Self {
+            merchant_id: merchant_id.into(),
+            base_url: None,
+            sandbox: false,
+            gateway_url: None,
+        }
+    }
+
+    /// Reads the config from environment variables.
+    ///
+    /// - `ZARINPAL_MERCHANT_ID`: Merchant id, required.
+    /// - `ZARINPAL_BASE_URL`: (Optional) Custom base url.
+    /// - `ZARINPAL_SANDBOX`: (Optional) `true`/`1`/`yes` to use the sandbox, `false`/`0`/`no` or
+    ///   empty otherwise.
+    ///
+    /// This fails with [`Error::InvalidConfig`] if merchant id is missing, or other values can't
+    /// be parsed.
+    pub fn from_env() -> ZarinResult<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Reads the config using `var` to get values of environment variables.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> ZarinResult<Self> {
+        let merchant_id = var(MERCHANT_ID_VAR)
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| Error::InvalidConfig(format!("{MERCHANT_ID_VAR} is not set")))?;
+
+        let base_url = var(BASE_URL_VAR)
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| {
+                value.trim().parse().map_err(|error| {
+                    Error::InvalidConfig(format!("{BASE_URL_VAR} is not a valid url: {error}"))
+                })
+            })
+            .transpose()?;
+
+        let sandbox = match var(SANDBOX_VAR)
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "true" | "1" | "yes" => true,
+            "false" | "0" | "no" | "" => false,
+            other => {
+                return Err(Error::InvalidConfig(format!(
+                    "{SANDBOX_VAR} must be true or false, not {other:?}"
+                )))
+            }
+        };
+
+        Ok(Self {
+            merchant_id: merchant_id.trim().to_string(),
+            base_url,
+            sandbox,
+            gateway_url: None,
+        })
+    }
+
+    /// Base url of requests, according to [`ZarinpalConfig::base_url`] and
+    /// [`ZarinpalConfig::sandbox`].
+    pub fn resolved_base_url(&self) -> url::Url {
+        match &self.base_url {
+            Some(base_url) => base_url.clone(),
+            None if self.sandbox => Environment::Sandbox.base_url(),
+            None => Environment::Production.base_url(),
+        }
+    }
+
+    /// Creates a [`ZarinpalBuilder`] with these settings, to configure the client further.
+    pub fn builder(self) -> ZarinpalBuilder {
+        let base_url = self.resolved_base_url();
+        let builder = Zarinpal::builder(self.merchant_id).base_url(base_url);
+        match self.gateway_url {
+            Some(gateway_url) => builder.gateway_url(gateway_url),
+            None => builder,
+        }
+    }
+
+    /// Builds a [`Zarinpal`] client with these settings, see [`ZarinpalBuilder::build`].
+    pub fn build(self) -> ZarinResult<Zarinpal> {
+        self.builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::TEST_UUID;
+
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let config =
+            ZarinpalConfig::from_vars(vars(&[(MERCHANT_ID_VAR, TEST_UUID), (SANDBOX_VAR, "True")]))
+                .unwrap();
+        assert_eq!(config.merchant_id, TEST_UUID);
+        assert!(config.sandbox);
+        assert_eq!(config.resolved_base_url(), Environment::Sandbox.base_url());
+
+        let config = ZarinpalConfig::from_vars(vars(&[
+            (MERCHANT_ID_VAR, TEST_UUID),
+            (BASE_URL_VAR, "http://localhost:8080/"),
+            (SANDBOX_VAR, "1"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            config.resolved_base_url().as_str(),
+            "http://localhost:8080/"
+        );
+
+        assert!(matches!(
+            ZarinpalConfig::from_vars(vars(&[])),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            ZarinpalConfig::from_vars(vars(&[
+                (MERCHANT_ID_VAR, TEST_UUID),
+                (SANDBOX_VAR, "maybe")
+            ])),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            ZarinpalConfig::from_vars(vars(&[
+                (MERCHANT_ID_VAR, TEST_UUID),
+                (BASE_URL_VAR, "not a url")
+            ])),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let config: ZarinpalConfig = serde_json::from_value(serde_json::json!({
+            "merchant_id": TEST_UUID,
+            "base_url": "http://localhost:8080/"
+        }))
+        .unwrap();
+        assert!(!config.sandbox);
+        assert_eq!(
+            config.base_url.as_ref().map(url::Url::as_str),
+            Some("http://localhost:8080/")
+        );
+        assert!(!format!("{config:?}").contains(TEST_UUID));
+
+        assert_eq!(
+            ZarinpalConfig::new(TEST_UUID).resolved_base_url(),
+            Environment::Production.base_url()
+        );
+    }
+}
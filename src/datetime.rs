@@ -0,0 +1,43 @@
+//! Parsing dates returned by zarinpal, which are in Tehran local time.
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use chrono_tz::Asia::Tehran;
+
+/// Format of dates returned by zarinpal, like `2020-06-27 10:22:02`.
+pub const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parses a date returned by zarinpal as Tehran local time.
+///
+/// Tehran's historical daylight saving time is taken into account, so the offset is
+/// either `+03:30` or `+04:30`. Returns `None` if `raw` is not in [`DATE_FORMAT`] or
+/// doesn't exist in Tehran (skipped by a daylight saving transition).
+///
+/// ```
+/// use zarinpal::datetime::parse_tehran;
+///
+/// let date = parse_tehran("2023-07-01 17:33:25").unwrap();
+/// assert_eq!(date.to_rfc3339(), "2023-07-01T17:33:25+03:30");
+/// ```
+pub fn parse_tehran(raw: &str) -> Option<DateTime<FixedOffset>> {
+    let naive = NaiveDateTime::parse_from_str(raw.trim(), DATE_FORMAT).ok()?;
+    let local = Tehran.from_local_datetime(&naive).earliest()?;
+    Some(local.fixed_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tehran() {
+        // Daylight saving time was still observed in 2020.
+        let summer = parse_tehran("2020-07-01 17:33:25").unwrap();
+        assert_eq!(summer.to_rfc3339(), "2020-07-01T17:33:25+04:30");
+
+        let winter = parse_tehran("2020-12-01 08:00:00").unwrap();
+        assert_eq!(winter.to_rfc3339(), "2020-12-01T08:00:00+03:30");
+        assert!(summer < winter);
+
+        assert!(parse_tehran("01/07/2020").is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Zarinpal environments (production and sandbox).
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    results::request::GatewayKind,
+};
+
+/// The zarinpal environment that requests are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Environment {
+    /// Real payments, at `https://api.zarinpal.com/`.
+    #[default]
+    Production,
+
+    /// Test payments, at `https://sandbox.zarinpal.com/`.
+    ///
+    /// Any uuid is accepted as merchant id here, and no money is moved.
+    Sandbox,
+}
+
+impl Environment {
+    /// Base url of api requests in this environment.
+    pub fn base_url(&self) -> url::Url {
+        match self {
+            Environment::Production => "https://api.zarinpal.com/",
+            Environment::Sandbox => "https://sandbox.zarinpal.com/",
+        }
+        .parse()
+        .unwrap()
+    }
+
+    /// Base url of the payment gateway (`StartPay`) in this environment, that payers are
+    /// redirected to.
+    pub fn gateway_url(&self) -> GatewayUrl {
+        GatewayUrl(
+            match self {
+                Environment::Production => "https://www.zarinpal.com/pg/StartPay/",
+                Environment::Sandbox => "https://sandbox.zarinpal.com/pg/StartPay/",
+            }
+            .parse()
+            .unwrap(),
+        )
+    }
+
+    /// The environment of an api `base_url`, which is [`Environment::Production`] unless it's
+    /// the sandbox host.
+    pub fn from_base_url(base_url: &url::Url) -> Self {
+        if base_url.host_str() == Environment::Sandbox.base_url().host_str() {
+            Environment::Sandbox
+        } else {
+            Environment::Production
+        }
+    }
+}
+
+/// Base url of a payment gateway (like `https://www.zarinpal.com/pg/StartPay/`), that
+/// authorities are appended to.
+///
+/// It's always an `http` or `https` url, so payment urls can be built from it without failing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "url::Url", into = "url::Url")]
+pub struct GatewayUrl(url::Url);
+
+impl GatewayUrl {
+    /// Creates a gateway url, like a proxy of the `StartPay` page.
+    ///
+    /// Fails with [`Error::InvalidConfig`] if `url` is not an `http` or `https` url.
+    pub fn new(url: url::Url) -> ZarinResult<Self> {
+        if !matches!(url.scheme(), "http" | "https") || url.cannot_be_a_base() {
+            return Err(Error::InvalidConfig(format!(
+                "gateway url must be an http(s) url, not {url}"
+            )));
+        }
+        Ok(Self(url))
+    }
+
+    /// The url itself.
+    pub fn as_url(&self) -> &url::Url {
+        &self.0
+    }
+
+    /// Url of the gateway of `kind` for the payment of `authority`, like
+    /// `https://www.zarinpal.com/pg/StartPay/{authority}/ZarinGate`.
+    pub fn payment_url(&self, authority: &str, kind: &GatewayKind) -> url::Url {
+        let mut url = self.0.clone();
+        url.path_segments_mut()
+            .expect("http urls can be a base")
+            .pop_if_empty()
+            .push(authority)
+            .extend(kind.suffix());
+        url
+    }
+}
+
+impl Default for GatewayUrl {
+    /// Gateway of [`Environment::Production`].
+    fn default() -> Self {
+        Environment::Production.gateway_url()
+    }
+}
+
+impl TryFrom<url::Url> for GatewayUrl {
+    type Error = Error;
+
+    fn try_from(url: url::Url) -> Result<Self, Self::Error> {
+        Self::new(url)
+    }
+}
+
+impl From<GatewayUrl> for url::Url {
+    fn from(gateway: GatewayUrl) -> Self {
+        gateway.0
+    }
+}
+
+impl FromStr for GatewayUrl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = s
+            .parse()
+            .map_err(|error| Error::InvalidConfig(format!("invalid gateway url: {error}")))?;
+        Self::new(url)
+    }
+}
+
+impl std::fmt::Display for GatewayUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gateway_url() {
+        let gateway: GatewayUrl = "https://pay.example.com/start".parse().unwrap();
+        assert_eq!(
+            gateway.payment_url("A1", &GatewayKind::Sep).as_str(),
+            "https://pay.example.com/start/A1/Sep"
+        );
+        assert_eq!(
+            GatewayUrl::default()
+                .payment_url("A1", &GatewayKind::Default)
+                .as_str(),
+            "https://www.zarinpal.com/pg/StartPay/A1"
+        );
+
+        assert!(matches!(
+            "mailto:pay@example.com".parse::<GatewayUrl>(),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(
+            serde_json::from_value::<GatewayUrl>(serde_json::json!("ftp://example.com/")).is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_base_url() {
+        for environment in [Environment::Production, Environment::Sandbox] {
+            assert_eq!(
+                Environment::from_base_url(&environment.base_url()),
+                environment
+            );
+        }
+        assert_eq!(
+            Environment::from_base_url(&"http://localhost:8080/".parse().unwrap()),
+            Environment::Production
+        );
+    }
+}
@@ -1,16 +1,28 @@
 //! Contains a universal [`Error`] type and associated [`ZarinResult`] for the create.s
 
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
 
 use serde::{Deserialize, Deserializer};
 use thiserror::Error;
 
-use crate::results::result_code::ResultCode;
+#[cfg(feature = "graphql")]
+use crate::graphql::GraphqlError;
+use crate::{
+    methods::request::Currency,
+    results::result_code::{MessageLocale, ResultCode},
+};
 
 /// An error that ocurred while sending a request to the api.
+///
+/// With `utoipa` feature, its schema is the one of the `errors` object of api responses.
 #[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 pub struct ApiError {
     /// Error code returned from api.
+    #[cfg_attr(feature = "utoipa", schema(value_type = i64, example = -9))]
     code: ResultCode,
 
     /// Error message.
@@ -28,20 +40,40 @@ pub struct ApiError {
     ///     ]
     /// }
     /// ```
-    #[serde(deserialize_with = "deserialize_validations")]
-    validations: HashMap<String, Vec<String>>,
+    #[cfg_attr(
+        feature = "utoipa",
+        schema(value_type = Vec<std::collections::HashMap<String, String>>)
+    )]
+    validations: ValidationErrors,
+
+    /// Language of the [`Display`] output.
+    #[serde(skip)]
+    locale: MessageLocale,
 }
 
 impl std::error::Error for ApiError {}
 
 impl Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "Error code ({}) ocurred while communicating with zarinpal api: {}",
-            self.code, self.message
-        )?;
-        writeln!(f, "Here're detailed information:")?;
+        match self.locale {
+            MessageLocale::English => {
+                writeln!(
+                    f,
+                    "Error code ({}) ocurred while communicating with zarinpal api: {}",
+                    self.code, self.message
+                )?;
+                writeln!(f, "Here're detailed information:")?;
+            }
+            MessageLocale::Persian => {
+                writeln!(
+                    f,
+                    "خطای ({}) در ارتباط با زرین‌پال: {}",
+                    self.code.code(),
+                    self.code.message_fa()
+                )?;
+                writeln!(f, "جزئیات:")?;
+            }
+        }
         writeln!(f, "{:#?}", self.validations)
     }
 }
@@ -58,29 +90,236 @@ impl ApiError {
     }
 
     /// A set of validations that're failed and must be fixed before sending request.
-    pub fn validations(&self) -> &HashMap<String, Vec<String>> {
+    pub fn validations(&self) -> &ValidationErrors {
         &self.validations
     }
+
+    /// Failed validations of `field`, if any.
+    ///
+    /// ```
+    /// # fn handle(error: zarinpal::error::ApiError) {
+    /// use zarinpal::error::ValidationField;
+    ///
+    /// if let Some(messages) = error.validation_for(ValidationField::Amount) {
+    ///     println!("Invalid amount: {messages:?}");
+    /// }
+    /// # }
+    /// ```
+    pub fn validation_for(&self, field: impl Into<ValidationField>) -> Option<&[String]> {
+        self.validations.get(field)
+    }
+
+    /// Persian description of the error code, see [`ResultCode::message_fa`].
+    pub fn message_fa(&self) -> std::borrow::Cow<'static, str> {
+        self.code.message_fa()
+    }
+
+    /// Language of the [`Display`] output.
+    pub fn locale(&self) -> MessageLocale {
+        self.locale
+    }
+
+    /// Sets language of the [`Display`] output.
+    ///
+    /// Clients set this to their [`crate::ZarinpalClient::message_locale`].
+    pub fn with_locale(mut self, locale: MessageLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Creates an error with a code and its default message, and no validations.
+    #[cfg(feature = "testkit")]
+    pub(crate) fn from_code(code: ResultCode) -> Self {
+        Self {
+            code,
+            message: code.to_string(),
+            validations: ValidationErrors::default(),
+            locale: MessageLocale::default(),
+        }
+    }
+
+    /// Creates an error for a request that's rejected locally, before being sent to the api.
+    pub(crate) fn local(code: ResultCode, field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            code,
+            validations: ValidationErrors::from_iter([(field.into(), message.clone())]),
+            message,
+            locale: MessageLocale::default(),
+        }
+    }
+}
+
+/// A request field that's known to be validated by the api.
+///
+/// Fields this crate doesn't know about are kept in [`ValidationField::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValidationField {
+    /// `merchant_id`
+    MerchantId,
+    /// `amount`
+    Amount,
+    /// `callback_url`
+    CallbackUrl,
+    /// `description`
+    Description,
+    /// `authority`
+    Authority,
+    /// `currency`
+    Currency,
+    /// `mobile`
+    Mobile,
+    /// `email`
+    Email,
+    /// `order_id`
+    OrderId,
+    /// `card_pan`
+    CardPan,
+    /// `wages`
+    Wages,
+    /// `expire_in`
+    ExpireIn,
+    /// Any other field.
+    Other(String),
 }
 
-fn deserialize_validations<'de, D>(
-    deserializer: D,
-) -> Result<HashMap<String, Vec<String>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let opening = Vec::<HashMap<String, String>>::deserialize(deserializer)?;
-    let mut result = HashMap::<String, Vec<String>>::new();
+impl ValidationField {
+    /// Name of the field, as sent to the api.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ValidationField::MerchantId => "merchant_id",
+            ValidationField::Amount => "amount",
+            ValidationField::CallbackUrl => "callback_url",
+            ValidationField::Description => "description",
+            ValidationField::Authority => "authority",
+            ValidationField::Currency => "currency",
+            ValidationField::Mobile => "mobile",
+            ValidationField::Email => "email",
+            ValidationField::OrderId => "order_id",
+            ValidationField::CardPan => "card_pan",
+            ValidationField::Wages => "wages",
+            ValidationField::ExpireIn => "expire_in",
+            ValidationField::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for ValidationField {
+    fn from(value: &str) -> Self {
+        match value {
+            "merchant_id" => ValidationField::MerchantId,
+            "amount" => ValidationField::Amount,
+            "callback_url" => ValidationField::CallbackUrl,
+            "description" => ValidationField::Description,
+            "authority" => ValidationField::Authority,
+            "currency" => ValidationField::Currency,
+            "mobile" => ValidationField::Mobile,
+            "email" => ValidationField::Email,
+            "order_id" => ValidationField::OrderId,
+            "card_pan" => ValidationField::CardPan,
+            "wages" => ValidationField::Wages,
+            "expire_in" => ValidationField::ExpireIn,
+            other => ValidationField::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ValidationField {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
+impl Display for ValidationField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Failed validations of a request, the messages of each field.
+///
+/// The api sends these as a list of single entry objects, like:
+///
+/// ```json
+/// [
+///     { "merchant_id": "Merchant id is not a valid uuid." },
+///     { "amount": "The amount must be at least 1000." }
+/// ]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    fields: BTreeMap<ValidationField, Vec<String>>,
+}
+
+impl ValidationErrors {
+    /// Failed validations of `field`, if any.
+    pub fn get(&self, field: impl Into<ValidationField>) -> Option<&[String]> {
+        self.fields.get(&field.into()).map(Vec::as_slice)
+    }
+
+    /// Returns `true` if `field` has failed validations.
+    pub fn contains_key(&self, field: impl Into<ValidationField>) -> bool {
+        self.fields.contains_key(&field.into())
+    }
+
+    /// Fields with failed validations, and their messages.
+    pub fn iter(&self) -> impl Iterator<Item = (&ValidationField, &[String])> {
+        self.fields
+            .iter()
+            .map(|(field, messages)| (field, messages.as_slice()))
+    }
+
+    /// Number of fields with failed validations.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if there's no failed validation.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
 
-    for item in opening {
-        for (key, val) in item {
-            let entry = result.entry(key);
-            let vec = entry.or_insert_with(|| vec![]);
-            vec.push(val)
+impl FromIterator<(ValidationField, String)> for ValidationErrors {
+    fn from_iter<T: IntoIterator<Item = (ValidationField, String)>>(iter: T) -> Self {
+        let mut fields = BTreeMap::<ValidationField, Vec<String>>::new();
+        for (field, message) in iter {
+            fields.entry(field).or_default().push(message);
         }
+        Self { fields }
     }
+}
+
+impl<'de> Deserialize<'de> for ValidationErrors {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let opening = Vec::<HashMap<String, String>>::deserialize(deserializer)?;
+
+        Ok(opening
+            .into_iter()
+            .flatten()
+            .map(|(key, val)| (key.into(), val))
+            .collect())
+    }
+}
 
-    Ok(result)
+/// A problem with wages of a payment request, found before it's sent.
+///
+/// See [`crate::methods::request::validate_wages`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WageValidationError {
+    #[error("Amount of wage #{index} ({amount}) is less than the minimum of {minimum}")]
+    AmountTooSmall {
+        index: usize,
+        amount: u64,
+        minimum: u64,
+    },
+    #[error("Total amount of wages ({total}) exceeds the payment amount ({amount})")]
+    TotalExceedsAmount { total: u64, amount: u64 },
+    #[error("There are {count} wages, but at most {max} are allowed")]
+    TooManyWages { count: usize, max: usize },
 }
 
 /// Represents an error that ocurred inside this ([`zarinpal`]) crate.
@@ -90,16 +329,177 @@ where
 pub enum Error {
     #[error("Zarinpal api error: {0}")]
     ZarinpalApiError(ApiError),
+    #[cfg(feature = "reqwest")]
     #[error("Http client error: {0}")]
     HttpClientError(reqwest::Error),
+    #[error("Http transport error: {0}")]
+    TransportError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Merchant id is not a valid uuid: {0}")]
+    InvalidMerchantId(uuid::Error),
+    #[cfg(feature = "graphql")]
+    #[error("Zarinpal graphql api error: {}", join_graphql_errors(.0))]
+    GraphqlError(Vec<GraphqlError>),
+    #[error("Request didn't complete before its timeout or deadline")]
+    Timeout,
+    #[error("Can't {action} a payment session in {state} state")]
+    InvalidSessionTransition {
+        state: &'static str,
+        action: &'static str,
+    },
+    #[error("Callback of authority {actual} doesn't belong to the session of {expected}")]
+    SessionAuthorityMismatch { expected: String, actual: String },
+    #[error("Payment store error: {0}")]
+    StoreError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("No payment record is stored for authority {0}")]
+    UnknownAuthority(String),
+    #[error("Json error: {0}")]
+    JsonError(serde_json::Error),
+    #[error("Response has fields unknown to this crate: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    #[error(
+        "Unexpected response (status: {status:?}, content type: {content_type:?}): {body_snippet}"
+    )]
+    UnexpectedResponse {
+        /// Http status code, if the transport knows it.
+        status: Option<u16>,
+        /// Value of `Content-Type` header, if the transport knows it.
+        content_type: Option<String>,
+        /// Beginning of the response body, see [`Error::unexpected_response`].
+        body_snippet: String,
+    },
+    #[error("Invalid iban: {0}")]
+    InvalidIban(String),
+    #[error("Invalid mobile number: {0}")]
+    InvalidMobile(String),
+    #[error("Invalid card pan: {0}")]
+    InvalidCardPan(String),
+    #[error("Invalid callback url: {0}")]
+    InvalidCallbackUrl(String),
+    #[error("Invalid description: {0}")]
+    InvalidDescription(String),
+    #[error("Invalid expire_in: {0}")]
+    InvalidExpireIn(String),
+    #[error("Invalid installment plan: {0}")]
+    InvalidInstallmentPlan(String),
+    #[error("Invalid wages: {0}")]
+    InvalidWages(WageValidationError),
+    #[error("No wage profile named {0:?} is registered on the client")]
+    UnknownWageProfile(String),
+    #[error("No merchant is registered under {0:?}")]
+    UnknownMerchant(String),
+    #[error("This request needs an access token, but none is set on the client")]
+    MissingAccessToken,
+    #[error("This request has no zarinpal client attached, see `send_with`")]
+    MissingClient,
+    #[error("Invalid client configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Amount is too large")]
+    AmountOverflow,
+    #[error("No exchange rate is available from {from} to {to}")]
+    ExchangeRateUnavailable { from: Currency, to: Currency },
+    #[error("Can't convert {amount} {from} to {to}")]
+    CurrencyConversionError {
+        amount: u64,
+        from: Currency,
+        to: Currency,
+    },
 }
 
+impl Error {
+    /// The api error, if this is an [`Error::ZarinpalApiError`].
+    pub fn as_api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::ZarinpalApiError(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Result code of the api error, if this is an [`Error::ZarinpalApiError`].
+    pub fn result_code(&self) -> Option<ResultCode> {
+        self.as_api_error().map(ApiError::code)
+    }
+
+    /// Returns `true` if the request failed to reach the api or the response got lost on the
+    /// way, like connection errors.
+    ///
+    /// Such requests may or may not be processed by the api.
+    pub fn is_network(&self) -> bool {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Error::HttpClientError(error) => error.status().is_none() && !error.is_decode(),
+            Error::TransportError(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the same request may succeed later, using the classes of a default
+    /// [`RetryPolicy`](crate::retry::RetryPolicy): network errors, http `5xx` responses and
+    /// [`ResultCode::ToManyAttempts`].
+    ///
+    /// ```
+    /// use zarinpal::error::Error;
+    ///
+    /// assert!(Error::unexpected_response(Some(502), None, "Bad Gateway").is_retryable());
+    /// assert!(!Error::MissingAccessToken.is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        crate::retry::RetryPolicy::default().is_retryable(self)
+    }
+
+    /// Creates an [`Error::UnexpectedResponse`], keeping only the first 512 characters of `body`.
+    ///
+    /// Transports should return this when the response is not json, like an html maintenance
+    /// page or a challenge of a CDN.
+    pub fn unexpected_response(
+        status: Option<u16>,
+        content_type: Option<String>,
+        body: &str,
+    ) -> Self {
+        const SNIPPET_CHARS: usize = 512;
+
+        Error::UnexpectedResponse {
+            status,
+            content_type,
+            body_snippet: body.chars().take(SNIPPET_CHARS).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+fn join_graphql_errors(errors: &[GraphqlError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(feature = "reqwest")]
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
         Error::HttpClientError(value)
     }
 }
 
+#[cfg(feature = "graphql")]
+impl From<Vec<GraphqlError>> for Error {
+    fn from(value: Vec<GraphqlError>) -> Self {
+        Error::GraphqlError(value)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Error::JsonError(value)
+    }
+}
+
+impl From<WageValidationError> for Error {
+    fn from(value: WageValidationError) -> Self {
+        Error::InvalidWages(value)
+    }
+}
+
 impl From<ApiError> for Error {
     fn from(value: ApiError) -> Self {
         Error::ZarinpalApiError(value)
@@ -108,3 +508,78 @@ impl From<ApiError> for Error {
 
 /// Result type for this crate's [`Error`] type.
 pub type ZarinResult<T> = Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_validations() {
+        let error = serde_json::from_value::<ApiError>(serde_json::json!({
+            "code": -9,
+            "message": "The input params invalid, validation error.",
+            "validations": [
+                { "amount": "The amount must be at least 1000." },
+                { "amount": "The amount must be an integer." },
+                { "terminal": "The terminal is not valid." }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            error.validation_for(ValidationField::Amount).unwrap().len(),
+            2
+        );
+        assert_eq!(
+            error.validation_for("terminal"),
+            Some(["The terminal is not valid.".to_string()].as_slice())
+        );
+        assert!(error.validation_for("merchant_id").is_none());
+        assert_eq!(error.validations().len(), 2);
+    }
+
+    #[test]
+    fn test_classification() {
+        let error = Error::from(ApiError::local(ResultCode::ToManyAttempts, "", ""));
+        assert_eq!(error.result_code(), Some(ResultCode::ToManyAttempts));
+        assert!(error.as_api_error().is_some());
+        assert!(error.is_retryable());
+        assert!(!error.is_network());
+
+        let error = Error::TransportError("connection reset".into());
+        assert_eq!(error.result_code(), None);
+        assert!(error.is_network());
+        assert!(error.is_retryable());
+
+        assert!(!Error::Timeout.is_network());
+        assert!(!Error::unexpected_response(Some(200), None, "<html>").is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_localized_api_error() {
+        let zarinpal = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::error(-54, "Invalid authority.")),
+        )
+        .unwrap()
+        .with_message_locale(MessageLocale::Persian);
+
+        let Err(Error::ZarinpalApiError(error)) = zarinpal.inquiry_payment("A1").build().await
+        else {
+            panic!("expected an api error");
+        };
+        assert_eq!(error.locale(), MessageLocale::Persian);
+        assert_eq!(error.message(), "Invalid authority.");
+        assert!(error.to_string().contains("اتوریتی نامعتبر است."));
+        assert!(error
+            .with_locale(MessageLocale::English)
+            .to_string()
+            .contains("Invalid authority."));
+    }
+}
@@ -0,0 +1,80 @@
+//! Publishing payment events to Kafka, see [`KafkaPublisher`].
+
+use std::{fmt::Debug, sync::Mutex};
+
+use rdkafka::{
+    error::{KafkaError, KafkaResult},
+    producer::{BaseRecord, DefaultProducerContext, ThreadedProducer},
+    ClientConfig,
+};
+
+use super::publish::{EventMessage, EventPublisher};
+
+/// An [`EventPublisher`] producing events to Kafka, keyed by authority of the payment.
+///
+/// Messages are queued and delivered in the background by the producer. Errors of queueing
+/// (like a full queue) don't fail api calls, the first one is kept for
+/// [`KafkaPublisher::take_error`].
+///
+/// ```no_run
+/// use zarinpal::events::{kafka::KafkaPublisher, publish::PublishingEventHandler};
+/// use zarinpal::prelude::*;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let publisher = KafkaPublisher::from_brokers("localhost:9092")?;
+/// let zarinpal = Zarinpal::new("...")?.with_event_handler(PublishingEventHandler::new(publisher));
+/// # Ok(())
+/// # }
+/// ```
+pub struct KafkaPublisher {
+    producer: ThreadedProducer<DefaultProducerContext>,
+    error: Mutex<Option<KafkaError>>,
+}
+
+impl KafkaPublisher {
+    /// Creates a publisher using `producer`.
+    pub fn new(producer: ThreadedProducer<DefaultProducerContext>) -> Self {
+        Self {
+            producer,
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Creates a publisher with a producer connecting to `brokers`, like `localhost:9092`.
+    ///
+    /// Use [`KafkaPublisher::new`] to configure the producer further.
+    pub fn from_brokers(brokers: &str) -> KafkaResult<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()?;
+        Ok(Self::new(producer))
+    }
+
+    /// The underlying producer, to flush it before shutting down for example.
+    pub fn producer(&self) -> &ThreadedProducer<DefaultProducerContext> {
+        &self.producer
+    }
+
+    /// Returns the first error since the last call, if any.
+    pub fn take_error(&self) -> Option<KafkaError> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+impl EventPublisher for KafkaPublisher {
+    fn publish(&self, message: EventMessage) {
+        let record = BaseRecord::to(&message.topic)
+            .key(&message.key)
+            .payload(&message.payload);
+
+        if let Err((error, _)) = self.producer.send(record) {
+            self.error.lock().unwrap().get_or_insert(error);
+        }
+    }
+}
+
+impl Debug for KafkaPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaPublisher").finish_non_exhaustive()
+    }
+}
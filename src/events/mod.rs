@@ -0,0 +1,171 @@
+//! Payment lifecycle events, see [`EventHandler`].
+
+use std::{fmt::Debug, sync::Arc};
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod publish;
+
+use crate::{
+    error::ApiError,
+    results::{request::Request, verify::Verify},
+};
+
+/// Receives lifecycle events of payments sent through a client, a single place to send
+/// notifications, update inventory or emit domain events from.
+///
+/// Install it using [`crate::Zarinpal::with_event_handler`]. Events are emitted by
+/// [`crate::ZarinpalClient::send`] once a request is done (retries included), so they're
+/// emitted for requests sent by higher-level helpers (like
+/// [`crate::extensions::ZarinpalSendExtension::check_and_verify`]) as well. They're not
+/// emitted for [`crate::ZarinpalClient::send_raw`].
+///
+/// Handlers are called inline, so slow work (like sending emails) is better spawned.
+/// A `Vec` of handlers is a handler too, calling each of them in order.
+///
+/// ```no_run
+/// use zarinpal::{error::ApiError, events::EventHandler, prelude::*};
+///
+/// #[derive(Debug)]
+/// struct Notifier;
+///
+/// impl EventHandler for Notifier {
+///     fn on_payment_verified(&self, authority: &str, verify: &Verify) {
+///         if !verify.already_verified() {
+///             println!("{authority} is paid, ref id: {}", verify.ref_id());
+///         }
+///     }
+///
+///     fn on_verify_failed(&self, authority: &str, error: &ApiError) {
+///         println!("{authority} is not verified: {error}");
+///     }
+/// }
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_event_handler(Notifier);
+/// # Ok(())
+/// # }
+/// ```
+pub trait EventHandler: Debug + Send + Sync {
+    /// Called when a payment request is created, before the payer is sent to the gateway.
+    fn on_payment_requested(&self, _request: &Request) {}
+
+    /// Called when a payment of `authority` is verified.
+    ///
+    /// It's called for payments that were verified before too, see [`Verify::already_verified`].
+    fn on_payment_verified(&self, _authority: &str, _verify: &Verify) {}
+
+    /// Called when verifying a payment of `authority` is rejected, by the api or by local
+    /// validations. Failures without a result (like network errors) are not reported, since
+    /// the state of the payment is unknown then.
+    fn on_verify_failed(&self, _authority: &str, _error: &ApiError) {}
+}
+
+impl<T: EventHandler + ?Sized> EventHandler for Arc<T> {
+    fn on_payment_requested(&self, request: &Request) {
+        self.as_ref().on_payment_requested(request)
+    }
+
+    fn on_payment_verified(&self, authority: &str, verify: &Verify) {
+        self.as_ref().on_payment_verified(authority, verify)
+    }
+
+    fn on_verify_failed(&self, authority: &str, error: &ApiError) {
+        self.as_ref().on_verify_failed(authority, error)
+    }
+}
+
+impl<T: EventHandler> EventHandler for Vec<T> {
+    fn on_payment_requested(&self, request: &Request) {
+        self.iter()
+            .for_each(|handler| handler.on_payment_requested(request))
+    }
+
+    fn on_payment_verified(&self, authority: &str, verify: &Verify) {
+        self.iter()
+            .for_each(|handler| handler.on_payment_verified(authority, verify))
+    }
+
+    fn on_verify_failed(&self, authority: &str, error: &ApiError) {
+        self.iter()
+            .for_each(|handler| handler.on_verify_failed(authority, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone)]
+    struct RecordingHandler(Arc<Mutex<Vec<String>>>);
+
+    impl EventHandler for RecordingHandler {
+        fn on_payment_requested(&self, request: &Request) {
+            self.0.lock().unwrap().push(format!(
+                "requested {} {}",
+                request.authority(),
+                request.amount()
+            ));
+        }
+
+        fn on_payment_verified(&self, authority: &str, verify: &Verify) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("verified {authority} {}", verify.ref_id()));
+        }
+
+        fn on_verify_failed(&self, authority: &str, error: &ApiError) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("failed {authority} {:?}", error.code()));
+        }
+    }
+
+    /// Responds to payment requests, and to verifies of `A1` only.
+    fn payment_transport() -> CannedTransport {
+        CannedTransport::new(|request| {
+            Ok(match request.url.path() {
+                "/pg/v4/payment/request.json" => responses::request("A1"),
+                _ if request.body["authority"] == "A1" => responses::verify(100),
+                _ => responses::error(-51, "Session is not valid."),
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        let handler = RecordingHandler::default();
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, payment_transport())
+            .unwrap()
+            .with_event_handler(vec![handler.clone()]);
+
+        zarinpal
+            .request_payment(10000, "https://example.com/verify".parse().unwrap(), "Test")
+            .build()
+            .await
+            .unwrap();
+        zarinpal.verify_payment("A1", 10000).build().await.unwrap();
+        assert!(zarinpal.verify_payment("A2", 10000).build().await.is_err());
+
+        assert_eq!(
+            *handler.0.lock().unwrap(),
+            [
+                "requested A1 10000",
+                "verified A1 201",
+                "failed A2 InvalidSeasonNoActivePayment"
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Publishing payment events to NATS, see [`NatsPublisher`].
+
+use std::sync::{Arc, Mutex};
+
+use async_nats::{Client, PublishError};
+
+use super::publish::{EventMessage, EventPublisher};
+
+/// An [`EventPublisher`] publishing events to NATS, using topics of events as subjects.
+///
+/// Messages are published on a spawned task, so it must be used inside a tokio runtime.
+/// Errors don't fail api calls, the first one is kept for [`NatsPublisher::take_error`].
+///
+/// ```no_run
+/// use zarinpal::events::{nats::NatsPublisher, publish::PublishingEventHandler};
+/// use zarinpal::prelude::*;
+///
+/// # async fn connect() -> Result::<(), Box<dyn std::error::Error>> {
+/// let client = async_nats::connect("localhost:4222").await?;
+/// let zarinpal = Zarinpal::new("...")?
+///     .with_event_handler(PublishingEventHandler::new(NatsPublisher::new(client)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NatsPublisher {
+    client: Client,
+    error: Arc<Mutex<Option<PublishError>>>,
+}
+
+impl NatsPublisher {
+    /// Creates a publisher using `client`.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            error: Default::default(),
+        }
+    }
+
+    /// The underlying client, to flush it before shutting down for example.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Returns the first error since the last call, if any.
+    pub fn take_error(&self) -> Option<PublishError> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+impl EventPublisher for NatsPublisher {
+    fn publish(&self, message: EventMessage) {
+        let client = self.client.clone();
+        let error = self.error.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.publish(message.topic, message.payload.into()).await {
+                error.lock().unwrap().get_or_insert(e);
+            }
+        });
+    }
+}
@@ -0,0 +1,335 @@
+//! Publishing payment lifecycle events to a message bus as json, see
+//! [`PublishingEventHandler`].
+
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use super::EventHandler;
+use crate::{
+    error::ApiError,
+    redact::redact_json,
+    results::{request::Request, result_code::ResultCode, verify::Verify, RequestResult},
+};
+
+/// Kind of a [`PaymentEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// See [`EventHandler::on_payment_requested`].
+    PaymentRequested,
+
+    /// See [`EventHandler::on_payment_verified`].
+    PaymentVerified,
+
+    /// See [`EventHandler::on_verify_failed`].
+    VerifyFailed,
+}
+
+impl EventKind {
+    /// Name of the kind, like `payment_verified`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::PaymentRequested => "payment_requested",
+            EventKind::PaymentVerified => "payment_verified",
+            EventKind::VerifyFailed => "verify_failed",
+        }
+    }
+}
+
+/// A payment lifecycle event, as it's published.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaymentEvent {
+    /// Kind of the event.
+    pub event: EventKind,
+
+    /// When the event is emitted, in unix milliseconds.
+    pub timestamp_ms: u64,
+
+    /// Authority of the payment.
+    pub authority: String,
+
+    /// Result code of the request.
+    pub code: ResultCode,
+
+    /// Amount of the payment, if it's known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+
+    /// Currency of the payment, if it's known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+
+    /// Fee of the payment, if it's known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+
+    /// Reference id of a verified payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_id: Option<u64>,
+
+    /// Masked card number of a verified payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_pan: Option<String>,
+
+    /// Hash of card number of a verified payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_hash: Option<String>,
+
+    /// Error message of a failed verify, as returned by the api.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PaymentEvent {
+    fn new(event: EventKind, authority: &str, code: ResultCode) -> Self {
+        Self {
+            event,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+                .try_into()
+                .unwrap_or(u64::MAX),
+            authority: authority.to_string(),
+            code,
+            amount: None,
+            currency: None,
+            fee: None,
+            ref_id: None,
+            card_pan: None,
+            card_hash: None,
+            error: None,
+        }
+    }
+}
+
+/// A json message to be published, see [`EventPublisher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMessage {
+    /// Topic (or subject) to publish to.
+    pub topic: String,
+
+    /// Key of the message, the authority of the payment, so events of a payment stay in order
+    /// on partitioned buses.
+    pub key: String,
+
+    /// Json payload, a serialized [`PaymentEvent`].
+    pub payload: Vec<u8>,
+}
+
+/// A message bus that [`PublishingEventHandler`] publishes events to.
+///
+/// Publishing must not block (long), since event handlers are called inline. Implemented by
+/// `KafkaPublisher` (`kafka` feature) and `NatsPublisher` (`nats` feature), implement it to
+/// publish to other buses.
+pub trait EventPublisher: Debug + Send + Sync {
+    /// Publishes (or queues) `message`.
+    fn publish(&self, message: EventMessage);
+}
+
+/// How topics of events are named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicNaming {
+    /// A topic per kind of event, like `zarinpal.payment_verified` for `zarinpal` prefix.
+    Prefixed(String),
+
+    /// All events are published to this topic, told apart by their `event` field.
+    Single(String),
+}
+
+impl Default for TopicNaming {
+    fn default() -> Self {
+        TopicNaming::Prefixed("zarinpal".to_string())
+    }
+}
+
+impl TopicNaming {
+    /// Topic of events of `kind`.
+    pub fn topic(&self, kind: EventKind) -> String {
+        match self {
+            TopicNaming::Prefixed(prefix) => format!("{prefix}.{}", kind.as_str()),
+            TopicNaming::Single(topic) => topic.clone(),
+        }
+    }
+}
+
+/// An [`EventHandler`] publishing every event as a json [`PaymentEvent`] to an
+/// [`EventPublisher`].
+///
+/// Card numbers and hashes are masked using [`redact_json`], unless redaction is turned off
+/// using [`PublishingEventHandler::with_redaction`].
+///
+/// ```no_run
+/// use zarinpal::events::publish::{EventMessage, EventPublisher, PublishingEventHandler, TopicNaming};
+/// use zarinpal::prelude::*;
+///
+/// #[derive(Debug)]
+/// struct StdoutPublisher;
+///
+/// impl EventPublisher for StdoutPublisher {
+///     fn publish(&self, message: EventMessage) {
+///         println!("{}: {}", message.topic, String::from_utf8_lossy(&message.payload));
+///     }
+/// }
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_event_handler(
+///     PublishingEventHandler::new(StdoutPublisher)
+///         .with_topic_naming(TopicNaming::Prefixed("shop.payments".to_string())),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PublishingEventHandler<P> {
+    publisher: P,
+    topic_naming: TopicNaming,
+    redact: bool,
+}
+
+impl<P: EventPublisher> PublishingEventHandler<P> {
+    /// Creates a handler publishing to `publisher`, with default [`TopicNaming`] and redaction.
+    pub fn new(publisher: P) -> Self {
+        Self {
+            publisher,
+            topic_naming: TopicNaming::default(),
+            redact: true,
+        }
+    }
+
+    /// Names topics of events using `topic_naming`.
+    pub fn with_topic_naming(mut self, topic_naming: TopicNaming) -> Self {
+        self.topic_naming = topic_naming;
+        self
+    }
+
+    /// Masks sensitive values (like card numbers) of events if `redact` is `true`, the default.
+    pub fn with_redaction(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// The publisher that events are published to.
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+
+    /// Publishes `event`.
+    pub fn publish(&self, event: &PaymentEvent) {
+        let mut payload = serde_json::to_value(event).unwrap(); // Events can always be serialized.
+        if self.redact {
+            redact_json(&mut payload);
+        }
+
+        self.publisher.publish(EventMessage {
+            topic: self.topic_naming.topic(event.event),
+            key: event.authority.clone(),
+            payload: payload.to_string().into_bytes(),
+        });
+    }
+}
+
+impl<P: EventPublisher> EventHandler for PublishingEventHandler<P> {
+    fn on_payment_requested(&self, request: &Request) {
+        let mut event = PaymentEvent::new(
+            EventKind::PaymentRequested,
+            request.authority(),
+            request.code(),
+        );
+        event.amount = Some(request.amount());
+        event.currency = Some(request.currency().to_string());
+        event.fee = Some(request.fee());
+        self.publish(&event);
+    }
+
+    fn on_payment_verified(&self, authority: &str, verify: &Verify) {
+        let mut event = PaymentEvent::new(EventKind::PaymentVerified, authority, verify.code());
+        event.fee = Some(verify.fee());
+        event.ref_id = Some(verify.ref_id());
+        event.card_pan = Some(verify.card_pan().to_string());
+        event.card_hash = Some(verify.card_hash().to_string());
+        self.publish(&event);
+    }
+
+    fn on_verify_failed(&self, authority: &str, error: &ApiError) {
+        let mut event = PaymentEvent::new(EventKind::VerifyFailed, authority, error.code());
+        event.error = Some(error.message().to_string());
+        self.publish(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingPublisher(Mutex<Vec<EventMessage>>);
+
+    impl EventPublisher for RecordingPublisher {
+        fn publish(&self, message: EventMessage) {
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    fn verify() -> Verify {
+        serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Merchant",
+            "fee": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_publish() {
+        let handler = PublishingEventHandler::new(RecordingPublisher::default());
+
+        handler.on_payment_verified("A1", &verify());
+        let error = serde_json::from_value::<ApiError>(serde_json::json!({
+            "code": -11,
+            "message": "The authority is not valid.",
+            "validations": []
+        }))
+        .unwrap();
+        handler.on_verify_failed("A2", &error);
+
+        let messages = handler.publisher().0.lock().unwrap();
+        assert_eq!(messages[0].topic, "zarinpal.payment_verified");
+        assert_eq!(messages[0].key, "A1");
+
+        let payload: serde_json::Value = serde_json::from_slice(&messages[0].payload).unwrap();
+        assert_eq!(payload["event"], "payment_verified");
+        assert_eq!(payload["ref_id"], 201);
+        assert_eq!(payload["card_pan"], "5022********5995");
+        assert!(payload.get("error").is_none());
+
+        let payload: serde_json::Value = serde_json::from_slice(&messages[1].payload).unwrap();
+        assert_eq!(messages[1].topic, "zarinpal.verify_failed");
+        assert_eq!(payload["code"], -11);
+        assert_eq!(payload["error"], "The authority is not valid.");
+    }
+
+    #[test]
+    fn test_single_topic_without_redaction() {
+        let handler = PublishingEventHandler::new(RecordingPublisher::default())
+            .with_topic_naming(TopicNaming::Single("payments".to_string()))
+            .with_redaction(false);
+
+        handler.on_payment_verified("A1", &verify());
+
+        let messages = handler.publisher().0.lock().unwrap();
+        assert_eq!(messages[0].topic, "payments");
+        let payload: serde_json::Value = serde_json::from_slice(&messages[0].payload).unwrap();
+        assert_eq!(payload["card_pan"], "502229******5995");
+    }
+}
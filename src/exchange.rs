@@ -0,0 +1,272 @@
+//! Converting foreign-currency prices (USD, EUR, ...) to a currency supported by zarinpal.
+//!
+//! Implement [`RateProvider`] on top of your favorite exchange rate source,
+//! or use [`FixedRates`] for static rates.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::{
+    error::{Error, ZarinResult},
+    invoice::tax::Rounding,
+    methods::request::{Currency, Metadata, RequestPayment},
+    ZarinpalClient,
+};
+
+/// An exchange rate: one unit of `from` is worth `rate / 10^scale` units of `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangeRate {
+    from: Currency,
+    to: Currency,
+    rate: u64,
+    scale: u32,
+}
+
+impl ExchangeRate {
+    /// Creates a new exchange rate. One unit of `from` is worth `rate / 10^scale` units of `to`.
+    ///
+    /// Eg: `ExchangeRate::new("USD".into(), Currency::IRT, 585_000, 1)` means 1 USD is 58,500.0 Tomans.
+    pub fn new(from: Currency, to: Currency, rate: u64, scale: u32) -> Self {
+        Self {
+            from,
+            to,
+            rate,
+            scale,
+        }
+    }
+
+    /// Source currency.
+    pub fn from(&self) -> &Currency {
+        &self.from
+    }
+
+    /// Target currency.
+    pub fn to(&self) -> &Currency {
+        &self.to
+    }
+
+    /// Converts `amount / 10^amount_scale` units of [`ExchangeRate::from`]
+    /// to a whole amount of [`ExchangeRate::to`].
+    pub fn convert(&self, amount: u64, amount_scale: u32, rounding: Rounding) -> ZarinResult<u64> {
+        let denominator = 10u128
+            .checked_pow(self.scale + amount_scale)
+            .ok_or(Error::AmountOverflow)?;
+        rounding
+            .divide(u128::from(amount) * u128::from(self.rate), denominator, 1)
+            .try_into()
+            .map_err(|_| Error::AmountOverflow)
+    }
+}
+
+impl std::fmt::Display for ExchangeRate {
+    /// Formats like `58500.0 IRT/USD`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}/{}",
+            format_scaled(self.rate, self.scale),
+            self.to,
+            self.from
+        )
+    }
+}
+
+fn format_scaled(value: u64, scale: u32) -> String {
+    if scale == 0 {
+        return value.to_string();
+    }
+    let digits = format!("{value:0>width$}", width = scale as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+    format!("{integer}.{fraction}")
+}
+
+/// A source of exchange rates.
+#[async_trait::async_trait]
+pub trait RateProvider: Send + Sync {
+    /// Current exchange rate from `from` to `to`.
+    async fn rate(&self, from: &Currency, to: &Currency) -> ZarinResult<ExchangeRate>;
+}
+
+/// A [`RateProvider`] with static rates.
+#[derive(Debug, Clone, Default)]
+pub struct FixedRates {
+    rates: HashMap<(Currency, Currency), ExchangeRate>,
+}
+
+impl FixedRates {
+    /// Creates an empty set of rates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rate, replacing the previous one with the same currencies.
+    pub fn with_rate(mut self, rate: ExchangeRate) -> Self {
+        self.rates
+            .insert((rate.from.clone(), rate.to.clone()), rate);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl RateProvider for FixedRates {
+    async fn rate(&self, from: &Currency, to: &Currency) -> ZarinResult<ExchangeRate> {
+        self.rates
+            .get(&(from.clone(), to.clone()))
+            .cloned()
+            .ok_or_else(|| Error::ExchangeRateUnavailable {
+                from: from.clone(),
+                to: to.clone(),
+            })
+    }
+}
+
+/// A price in a foreign currency: `amount / 10^scale` units of `currency`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignPrice {
+    amount: u64,
+    scale: u32,
+    currency: Currency,
+}
+
+impl ForeignPrice {
+    /// Creates a new price. Eg: `ForeignPrice::new(1999, 2, "USD".into())` for 19.99 USD.
+    pub fn new(amount: u64, scale: u32, currency: Currency) -> Self {
+        Self {
+            amount,
+            scale,
+            currency,
+        }
+    }
+
+    /// Converts the price to `to` using a rate from `provider`.
+    pub async fn quote<P: RateProvider + ?Sized>(
+        &self,
+        provider: &P,
+        to: Currency,
+        rounding: Rounding,
+    ) -> ZarinResult<Quote> {
+        let rate = provider.rate(&self.currency, &to).await?;
+        let amount = rate.convert(self.amount, self.scale, rounding)?;
+
+        Ok(Quote {
+            price: self.clone(),
+            amount,
+            rate,
+        })
+    }
+}
+
+impl std::fmt::Display for ForeignPrice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            format_scaled(self.amount, self.scale),
+            self.currency
+        )
+    }
+}
+
+/// A [`ForeignPrice`] converted using an [`ExchangeRate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    price: ForeignPrice,
+    amount: u64,
+    rate: ExchangeRate,
+}
+
+impl Quote {
+    /// The original price.
+    pub fn price(&self) -> &ForeignPrice {
+        &self.price
+    }
+
+    /// Converted amount, in [`Quote::currency`].
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Currency of the converted amount.
+    pub fn currency(&self) -> &Currency {
+        &self.rate.to
+    }
+
+    /// The exchange rate used.
+    pub fn rate(&self) -> &ExchangeRate {
+        &self.rate
+    }
+
+    /// Records the original price and rate in `metadata`, as `original_price` and `exchange_rate`.
+    pub fn record(&self, metadata: &mut Metadata) {
+        metadata.insert_extra("original_price", self.price.to_string());
+        metadata.insert_extra("exchange_rate", self.rate.to_string());
+    }
+
+    /// Builds a [`RequestPayment`] for the converted amount, with the rate recorded in metadata.
+    pub fn request_payment<'z, Z: ZarinpalClient>(
+        &self,
+        zarinpal: &'z Z,
+        callback_url: impl Into<Cow<'z, str>>,
+        description: impl Into<Cow<'z, str>>,
+    ) -> RequestPayment<'z, &'z Z> {
+        let mut metadata = Metadata::default();
+        self.record(&mut metadata);
+
+        RequestPayment::builder()
+            .zarinpal(zarinpal)
+            .amount(self.amount)
+            .currency(self.currency().clone())
+            .callback_url(callback_url)
+            .description(description)
+            .metadata(metadata)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> FixedRates {
+        FixedRates::new().with_rate(ExchangeRate::new("USD".into(), Currency::IRT, 585_005, 1))
+    }
+
+    #[tokio::test]
+    async fn test_quote() {
+        let price = ForeignPrice::new(1999, 2, "USD".into());
+
+        let quote = price
+            .quote(&rates(), Currency::IRT, Rounding::Nearest)
+            .await
+            .unwrap();
+        assert_eq!(quote.amount(), 1_169_425);
+
+        let quote = price
+            .quote(&rates(), Currency::IRT, Rounding::Down)
+            .await
+            .unwrap();
+        assert_eq!(quote.amount(), 1_169_424);
+
+        let mut metadata = Metadata::default();
+        quote.record(&mut metadata);
+        assert_eq!(
+            serde_json::to_value(metadata).unwrap(),
+            serde_json::json!({
+                "original_price": "19.99 USD",
+                "exchange_rate": "58500.5 IRT/USD"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_rate() {
+        let price = ForeignPrice::new(1000, 2, "EUR".into());
+
+        assert!(matches!(
+            price
+                .quote(&rates(), Currency::IRT, Rounding::Nearest)
+                .await,
+            Err(Error::ExchangeRateUnavailable { .. })
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+//! CSV and JSON exports of results, like batches of [`Verify`] results, unverified payments and
+//! reconciliation reports, for finance teams and spreadsheets.
+//!
+//! Columns of each row type are always in the same order (see [`Export::COLUMNS`]), so
+//! exports of different days can be appended or compared.
+//!
+//! ```
+//! use zarinpal::export::{to_csv, CsvOptions};
+//! use zarinpal::prelude::*;
+//!
+//! let verified: Vec<Verify> = vec![];
+//!
+//! let csv = to_csv(&verified, CsvOptions::default().with_bom(true));
+//! assert!(csv.starts_with("\u{feff}ref_id,code,card_pan"));
+//! ```
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Value;
+
+use crate::results::verify::Verify;
+
+/// Byte order mark that tells Excel a CSV file is UTF-8, so persian text is shown correctly.
+pub const UTF8_BOM: char = '\u{feff}';
+
+/// A row type that can be exported.
+pub trait Export {
+    /// Names of the columns, in order.
+    const COLUMNS: &'static [&'static str];
+
+    /// Values of the columns, in the order of [`Export::COLUMNS`].
+    ///
+    /// `null` values are empty cells in CSV.
+    fn values(&self) -> Vec<Value>;
+}
+
+/// Options of CSV exports, see [`to_csv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    bom: bool,
+    delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            bom: false,
+            delimiter: ',',
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Starts the output with [`UTF8_BOM`], for Excel.
+    pub fn with_bom(mut self, bom: bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Separates cells with `delimiter` instead of `,`, like `;` for some locales of Excel.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+/// Renders `rows` as CSV, with a header row and `\r\n` line endings.
+///
+/// Cells that contain the delimiter, quotes or line breaks are quoted.
+pub fn to_csv<'r, R: Export + 'r>(
+    rows: impl IntoIterator<Item = &'r R>,
+    options: CsvOptions,
+) -> String {
+    let mut csv = String::new();
+    if options.bom {
+        csv.push(UTF8_BOM);
+    }
+
+    write_csv_line(
+        &mut csv,
+        R::COLUMNS.iter().map(|column| column.to_string()),
+        options.delimiter,
+    );
+    for row in rows {
+        let cells = row.values().into_iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(string) => string,
+            other => other.to_string(),
+        });
+        write_csv_line(&mut csv, cells, options.delimiter);
+    }
+    csv
+}
+
+fn write_csv_line(csv: &mut String, cells: impl Iterator<Item = String>, delimiter: char) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            csv.push(delimiter);
+        }
+        if cell.contains([delimiter, '"', '\r', '\n']) {
+            csv.push('"');
+            csv.push_str(&cell.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(&cell);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// Renders `rows` as a json array of objects, with keys in the order of [`Export::COLUMNS`].
+pub fn to_json<'r, R: Export + 'r>(rows: impl IntoIterator<Item = &'r R>) -> String {
+    let rows = JsonRows(
+        rows.into_iter().map(Export::values).collect::<Vec<_>>(),
+        R::COLUMNS,
+    );
+    serde_json::to_string(&rows).unwrap() // Json values can always be serialized.
+}
+
+/// Rows serialized as objects, keeping the order of columns.
+struct JsonRows(Vec<Vec<Value>>, &'static [&'static str]);
+
+impl Serialize for JsonRows {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Row<'a>(&'a [Value], &'static [&'static str]);
+
+        impl Serialize for Row<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.1.len()))?;
+                for (column, value) in self.1.iter().zip(self.0) {
+                    map.serialize_entry(column, value)?;
+                }
+                map.end()
+            }
+        }
+
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for row in &self.0 {
+            seq.serialize_element(&Row(row, self.1))?;
+        }
+        seq.end()
+    }
+}
+
+impl Export for Verify {
+    const COLUMNS: &'static [&'static str] = &[
+        "ref_id",
+        "code",
+        "card_pan",
+        "card_hash",
+        "fee_type",
+        "fee",
+        "shaparak_fee",
+        "order_id",
+    ];
+
+    fn values(&self) -> Vec<Value> {
+        use crate::results::RequestResult;
+
+        vec![
+            self.ref_id().into(),
+            self.code().code().into(),
+            self.card_pan().into(),
+            self.card_hash().into(),
+            self.fee_type().as_str().into(),
+            self.fee().into(),
+            self.shaparak_fee().into(),
+            self.order_id().into(),
+        ]
+    }
+}
+
+#[cfg(feature = "unverified")]
+mod unverified {
+    use serde_json::Value;
+
+    use super::{to_csv, to_json, CsvOptions, Export};
+    use crate::{
+        reconcile::ReconcileReport,
+        results::unverified::{Authorities, Unverified},
+    };
+
+    impl Export for Authorities {
+        const COLUMNS: &'static [&'static str] =
+            &["authority", "amount", "callback_url", "referer", "date"];
+
+        fn values(&self) -> Vec<Value> {
+            vec![
+                self.authority().into(),
+                self.amount().into(),
+                self.callback_url().into(),
+                self.referer().into(),
+                self.date().into(),
+            ]
+        }
+    }
+
+    impl Unverified {
+        /// Renders unverified payments as CSV, see [`super::to_csv`].
+        pub fn to_csv(&self, options: CsvOptions) -> String {
+            to_csv(self.authorities(), options)
+        }
+
+        /// Renders unverified payments as json, see [`super::to_json`].
+        pub fn to_json(&self) -> String {
+            to_json(self.authorities())
+        }
+    }
+
+    /// A row of a reconciliation report export.
+    struct ReconcileRow<'a> {
+        outcome: &'static str,
+        authority: &'a str,
+        expected_amount: Option<u64>,
+        actual_amount: Option<u64>,
+    }
+
+    impl Export for ReconcileRow<'_> {
+        const COLUMNS: &'static [&'static str] =
+            &["outcome", "authority", "expected_amount", "actual_amount"];
+
+        fn values(&self) -> Vec<Value> {
+            vec![
+                self.outcome.into(),
+                self.authority.into(),
+                self.expected_amount.into(),
+                self.actual_amount.into(),
+            ]
+        }
+    }
+
+    impl ReconcileReport {
+        /// One row per payment of the report, with `outcome` of `matched`, `missing_locally`,
+        /// `missing_remotely` or `amount_mismatch`, in that order.
+        fn rows(&self) -> Vec<ReconcileRow<'_>> {
+            let matched = self.matched().iter().map(|actual| ReconcileRow {
+                outcome: "matched",
+                authority: actual.authority(),
+                expected_amount: Some(actual.amount()),
+                actual_amount: Some(actual.amount()),
+            });
+            let missing_locally = self.missing_locally().iter().map(|actual| ReconcileRow {
+                outcome: "missing_locally",
+                authority: actual.authority(),
+                expected_amount: None,
+                actual_amount: Some(actual.amount()),
+            });
+            let missing_remotely = self.missing_remotely().iter().map(|expected| ReconcileRow {
+                outcome: "missing_remotely",
+                authority: expected.authority(),
+                expected_amount: Some(expected.amount()),
+                actual_amount: None,
+            });
+            let mismatches = self
+                .amount_mismatches()
+                .iter()
+                .map(|mismatch| ReconcileRow {
+                    outcome: "amount_mismatch",
+                    authority: mismatch.expected.authority(),
+                    expected_amount: Some(mismatch.expected.amount()),
+                    actual_amount: Some(mismatch.actual.amount()),
+                });
+
+            matched
+                .chain(missing_locally)
+                .chain(missing_remotely)
+                .chain(mismatches)
+                .collect()
+        }
+
+        /// Renders the report as CSV, one row per payment, see [`super::to_csv`].
+        ///
+        /// Columns are `outcome`, `authority`, `expected_amount` and `actual_amount`, where
+        /// outcome is one of `matched`, `missing_locally`, `missing_remotely` or
+        /// `amount_mismatch`.
+        pub fn to_csv(&self, options: CsvOptions) -> String {
+            to_csv(&self.rows(), options)
+        }
+
+        /// Renders the report as json, with the columns of [`ReconcileReport::to_csv`].
+        pub fn to_json(&self) -> String {
+            to_json(&self.rows())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Row(&'static str, Option<u64>);
+
+    impl Export for Row {
+        const COLUMNS: &'static [&'static str] = &["text", "amount"];
+
+        fn values(&self) -> Vec<Value> {
+            vec![self.0.into(), self.1.into()]
+        }
+    }
+
+    #[test]
+    fn test_csv() {
+        let rows = [
+            Row("سفارش ۱", Some(10000)),
+            Row("a, \"b\"", None),
+            Row("line\nbreak", Some(1)),
+        ];
+
+        assert_eq!(
+            to_csv(&rows, CsvOptions::default()),
+            "text,amount\r\nسفارش ۱,10000\r\n\"a, \"\"b\"\"\",\r\n\"line\nbreak\",1\r\n"
+        );
+        assert_eq!(
+            to_csv(
+                &rows[..1],
+                CsvOptions::default().with_bom(true).with_delimiter(';')
+            ),
+            "\u{feff}text;amount\r\nسفارش ۱;10000\r\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let rows = [Row("a", Some(1)), Row("b", None)];
+
+        assert_eq!(
+            to_json(&rows),
+            r#"[{"text":"a","amount":1},{"text":"b","amount":null}]"#
+        );
+    }
+
+    #[cfg(feature = "unverified")]
+    #[test]
+    fn test_reconcile_report() {
+        use crate::reconcile::{reconcile, ExpectedPayment};
+
+        let unverified: crate::results::unverified::Unverified =
+            serde_json::from_value(serde_json::json!({
+                "code": "100",
+                "message": "Success",
+                "authorities": [
+                    {
+                        "authority": "A1",
+                        "amount": 10000,
+                        "callback_url": "https://example.com/verify",
+                        "referer": "https://example.com",
+                        "date": "2024-06-27 17:33:25"
+                    },
+                    {
+                        "authority": "A2",
+                        "amount": 20000,
+                        "callback_url": "https://example.com/verify",
+                        "referer": "https://example.com",
+                        "date": "2024-06-27 17:40:00"
+                    }
+                ]
+            }))
+            .unwrap();
+
+        assert!(unverified
+            .to_csv(CsvOptions::default())
+            .starts_with("authority,amount,callback_url,referer,date\r\nA1,10000,"));
+
+        let report = reconcile(
+            [
+                ExpectedPayment::new("A1", 10000),
+                ExpectedPayment::new("A3", 5000),
+            ],
+            &unverified,
+        );
+        assert_eq!(
+            report.to_csv(CsvOptions::default()),
+            "outcome,authority,expected_amount,actual_amount\r\n\
+             matched,A1,10000,10000\r\n\
+             missing_locally,A2,,20000\r\n\
+             missing_remotely,A3,5000,\r\n"
+        );
+    }
+}
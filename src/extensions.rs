@@ -1,47 +1,78 @@
 //! Extension traits for [`Zarinpal`].
 
+use std::{borrow::Cow, future::Future};
+
+#[cfg(feature = "batch")]
+use futures_util::StreamExt;
+
+#[cfg(feature = "store")]
+use crate::error::Error;
 use crate::{
-    methods::{request::RequestPayment, unverified::UnverifiedRequests, verify::VerifyPayment},
+    amount::Amount,
+    callback::{CallbackOutcome, PaymentCallback},
+    error::ZarinResult,
+    methods::{inquiry::InquiryPayment, request::RequestPayment, verify::VerifyPayment},
+    results::{
+        inquiry::{CheckOutcome, PaymentStatus},
+        verify::VerifyOutcome,
+    },
     ZarinpalClient,
 };
 
+// Builders are returned with their required fields set, which shows in their types.
+#[allow(clippy::type_complexity)]
 pub trait ZarinpalSendExtension: ZarinpalClient + Sized {
     /// Request a payment through Zarinpal payments gateway.
-    fn request_payment<'z>(
-        &'z self,
-        amount: u64,
-        callback_url: reqwest::Url,
-        description: impl Into<String>,
+    fn request_payment<'a>(
+        &'a self,
+        amount: impl Into<Amount>,
+        callback_url: url::Url,
+        description: impl Into<Cow<'a, str>>,
     ) -> crate::methods::request::RequestPaymentBuilder<
-        '_,
-        Self,
+        'a,
+        &'a Self,
         (
             (),
             (),
-            (u64,),
-            (String,),
-            (String,),
+            (Amount,),
+            (Cow<'a, str>,),
+            (Cow<'a, str>,),
+            (),
+            (),
+            (),
             (),
             (),
-            (Option<&Self>,),
+            (),
+            (),
+            (),
+            (),
+            (Option<&'a Self>,),
         ),
     > {
         RequestPayment::builder()
             .zarinpal(self)
             .amount(amount)
-            .callback_url(callback_url)
+            .callback_url(String::from(callback_url))
             .description(description)
     }
 
     /// Verify a previously made payment requests through Zarinpal payments gateway.
-    fn verify_payment<'z>(
-        &'z self,
-        authority: impl Into<String>,
-        amount: u64,
+    fn verify_payment<'a>(
+        &'a self,
+        authority: impl Into<Cow<'a, str>>,
+        amount: impl Into<Amount>,
     ) -> crate::methods::verify::VerifyPaymentBuilder<
-        '_,
-        Self,
-        ((), (u64,), (String,), (Option<&Self>,)),
+        'a,
+        &'a Self,
+        (
+            (),
+            (Amount,),
+            (),
+            (Cow<'a, str>,),
+            (),
+            (),
+            (Option<&'a Self>,),
+        ),
     > {
         VerifyPayment::builder()
             .zarinpal(self)
@@ -49,12 +80,357 @@ pub trait ZarinpalSendExtension: ZarinpalClient + Sized {
             .authority(authority)
     }
 
+    /// Verify a payment, telling apart payments verified just now from the ones verified before.
+    ///
+    /// Unlike [`ZarinpalSendExtension::verify_payment`], code `101` can't be missed here:
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     match zarinpal
+    ///         .verify_payment_tolerant("A00000000000000000000000000217885159", 10000)
+    ///         .await?
+    ///     {
+    ///         VerifyOutcome::Verified(verify) => println!("Deliver order {}", verify.ref_id()),
+    ///         VerifyOutcome::AlreadyVerified(_) => println!("Already delivered"),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn verify_payment_tolerant(
+        &self,
+        authority: impl Into<String>,
+        amount: impl Into<Amount>,
+    ) -> impl Future<Output = ZarinResult<VerifyOutcome>> + Send
+    where
+        Self: Sync + Send,
+    {
+        let (authority, amount) = (authority.into(), amount.into());
+        async move {
+            self.verify_payment(authority, amount)
+                .build()
+                .outcome()
+                .await
+        }
+    }
+
     /// Returns a list of at most 100 recent unverified payment requests.
-    fn unverified_requests<'z>(
-        &'z self,
-    ) -> crate::methods::unverified::UnverifiedRequestsBuilder<'_, Self, ((), (Option<&Self>,))>
+    #[cfg(feature = "unverified")]
+    fn unverified_requests(
+        &self,
+    ) -> crate::methods::unverified::UnverifiedRequestsBuilder<&Self, ((), (), (), (Option<&Self>,))>
     {
-        UnverifiedRequests::builder().zarinpal(self)
+        crate::methods::unverified::UnverifiedRequests::builder().zarinpal(self)
+    }
+
+    /// Polls unverified requests every `interval`, yielding payments that newly appeared.
+    ///
+    /// See [`crate::watcher::UnverifiedWatcher`] for more options.
+    #[cfg(all(feature = "unverified", feature = "batch"))]
+    fn watch_unverified(
+        &self,
+        interval: std::time::Duration,
+    ) -> impl futures_util::Stream<Item = ZarinResult<crate::results::unverified::Authorities>> + Send + '_
+    where
+        Self: Sync + Send,
+    {
+        crate::watcher::UnverifiedWatcher::builder()
+            .interval(interval)
+            .build()
+            .watch(self)
+    }
+
+    /// Reverse a paid payment request, so it's not settled.
+    fn reverse_payment<'a>(
+        &'a self,
+        authority: impl Into<Cow<'a, str>>,
+    ) -> crate::methods::reverse::ReversePaymentBuilder<
+        'a,
+        &'a Self,
+        ((), (Cow<'a, str>,), (), (), (Option<&'a Self>,)),
+    > {
+        crate::methods::reverse::ReversePayment::builder()
+            .zarinpal(self)
+            .authority(authority)
+    }
+
+    /// Refund (part of) a verified payment by its session id.
+    ///
+    /// Needs an access token on the client, see [`crate::Zarinpal::with_access_token`].
+    #[cfg(feature = "graphql")]
+    fn refund_payment(
+        &self,
+        session_id: impl Into<String>,
+        amount: u64,
+    ) -> crate::methods::refund::RefundPaymentBuilder<
+        &Self,
+        ((String,), (u64,), (), (), (), (), (), (Option<&Self>,)),
+    > {
+        crate::methods::refund::RefundPayment::builder()
+            .zarinpal(self)
+            .session_id(session_id)
+            .amount(amount)
+    }
+
+    /// Inquire the current status of a payment request.
+    fn inquiry_payment<'a>(
+        &'a self,
+        authority: impl Into<Cow<'a, str>>,
+    ) -> crate::methods::inquiry::InquiryPaymentBuilder<
+        'a,
+        &'a Self,
+        ((), (Cow<'a, str>,), (), (), (Option<&'a Self>,)),
+    > {
+        InquiryPayment::builder()
+            .zarinpal(self)
+            .authority(authority)
+    }
+
+    /// Verifies the payment of a zarinpal callback, unless the payer canceled it.
+    ///
+    /// Verify is never sent when callback status is `NOK`.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     // Query string of the callback request.
+    ///     let callback = PaymentCallback::from_query(
+    ///         "Authority=A00000000000000000000000000217885159&Status=OK",
+    ///     )?;
+    ///
+    ///     match zarinpal.verify_callback(callback, 10000).await? {
+    ///         CallbackOutcome::Verified(verify) => println!("Paid: {}", verify.ref_id()),
+    ///         CallbackOutcome::AlreadyVerified(_) => println!("Already delivered"),
+    ///         CallbackOutcome::CanceledByUser => println!("Canceled"),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn verify_callback(
+        &self,
+        callback: PaymentCallback,
+        expected_amount: u64,
+    ) -> impl Future<Output = ZarinResult<CallbackOutcome>> + Send
+    where
+        Self: Sync + Send,
+    {
+        async move {
+            if !callback.is_ok() {
+                return Ok(CallbackOutcome::CanceledByUser);
+            }
+
+            Ok(self
+                .verify_payment(callback.authority(), expected_amount)
+                .build()
+                .outcome()
+                .await?
+                .into())
+        }
+    }
+
+    /// Verifies the payment of a zarinpal callback using the amount saved in `store`,
+    /// and records the outcome there.
+    ///
+    /// Fails with [`crate::error::Error::UnknownAuthority`] if there's no (pending) record of the callback.
+    ///
+    /// The record is marked failed only if the payer canceled the payment or zarinpal rejects the
+    /// session itself (see [`ResultCode::is_session_error`](crate::results::result_code::ResultCode::is_session_error)).
+    /// Other errors, like [`ToManyAttempts`](crate::results::result_code::ResultCode::ToManyAttempts)
+    /// or network ones, are returned and leave the record pending, so it can be verified again.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    /// use zarinpal::store::{MemoryStore, PaymentRecord, PaymentStore};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///     let store = MemoryStore::new();
+    ///
+    ///     let request = zarinpal
+    ///         .request_payment(10000, "https://example.com/verify".parse()?, "...")
+    ///         .build()
+    ///         .await?;
+    ///     store.save(PaymentRecord::from_request(&request)).await?;
+    ///
+    ///     // Later, in the callback handler.
+    ///     let callback = PaymentCallback::from_query(
+    ///         "Authority=A00000000000000000000000000217885159&Status=OK",
+    ///     )?;
+    ///     let outcome = zarinpal.verify_callback_stored(&store, callback).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "store")]
+    fn verify_callback_stored<S: crate::store::PaymentStore + ?Sized>(
+        &self,
+        store: &S,
+        callback: PaymentCallback,
+    ) -> impl Future<Output = ZarinResult<CallbackOutcome>> + Send
+    where
+        Self: Sync + Send,
+    {
+        async move {
+            let record = store
+                .load(callback.authority())
+                .await?
+                .ok_or_else(|| Error::UnknownAuthority(callback.authority().to_string()))?;
+
+            if !callback.is_ok() {
+                store.mark_failed(record.authority()).await?;
+                return Ok(CallbackOutcome::CanceledByUser);
+            }
+
+            let result = self
+                .verify_payment(record.authority(), record.amount())
+                .currency(record.currency().clone())
+                .build()
+                .outcome()
+                .await;
+
+            match result {
+                Ok(outcome) => {
+                    store
+                        .mark_verified(record.authority(), outcome.verify().ref_id())
+                        .await?;
+                    Ok(outcome.into())
+                }
+                Err(Error::ZarinpalApiError(error)) if error.code().is_session_error() => {
+                    store.mark_failed(record.authority()).await?;
+                    Err(error.into())
+                }
+                Err(error) => Err(error),
+            }
+        }
+    }
+
+    /// Verifies many payments concurrently, with at most `concurrency_limit` requests in flight.
+    ///
+    /// Results are paired with their authority, in the same order as `authorities`.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     let unverified = zarinpal.unverified_requests().build().await?;
+    ///     let results = zarinpal
+    ///         .verify_all(
+    ///             unverified
+    ///                 .authorities()
+    ///                 .iter()
+    ///                 .map(|unverified| (unverified.authority(), unverified.amount())),
+    ///             10,
+    ///         )
+    ///         .await;
+    ///
+    ///     for (authority, result) in results {
+    ///         println!("{authority}: {:?}", result.map(|verify| verify.ref_id()));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "batch")]
+    fn verify_all<A, M>(
+        &self,
+        authorities: impl IntoIterator<Item = (A, M)>,
+        concurrency_limit: usize,
+    ) -> impl Future<Output = Vec<(String, ZarinResult<crate::results::verify::Verify>)>> + Send
+    where
+        Self: Sync + Send,
+        A: Into<String>,
+        M: Into<Amount>,
+    {
+        let authorities = authorities
+            .into_iter()
+            .map(|(authority, amount)| (authority.into(), amount.into()))
+            .collect::<Vec<(String, Amount)>>();
+
+        async move {
+            futures_util::stream::iter(authorities)
+                .map(|(authority, amount)| async move {
+                    let result = self
+                        .verify_payment(authority.as_str(), amount)
+                        .build()
+                        .await;
+                    (authority, result)
+                })
+                .buffered(concurrency_limit.max(1))
+                .collect()
+                .await
+        }
+    }
+
+    /// Inquire a payment request first, and verify it only if it's actually paid.
+    ///
+    /// This avoids burning verify attempts on payments that are still pending, failed or expired.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     match zarinpal
+    ///         .check_and_verify("A00000000000000000000000000217885159", 10000)
+    ///         .await?
+    ///     {
+    ///         CheckOutcome::Verified(verify) => println!("Paid: {}", verify.ref_id()),
+    ///         CheckOutcome::Pending => println!("Still in bank, try later"),
+    ///         other => println!("Not paid: {other:?}"),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn check_and_verify(
+        &self,
+        authority: impl Into<String>,
+        amount: u64,
+    ) -> impl Future<Output = ZarinResult<CheckOutcome>> + Send
+    where
+        Self: Sync + Send,
+    {
+        let authority = authority.into();
+        async move {
+            let inquiry = self.inquiry_payment(authority.as_str()).build().await?;
+
+            Ok(match inquiry.status() {
+                PaymentStatus::Paid | PaymentStatus::Verified => {
+                    match self
+                        .verify_payment(authority, amount)
+                        .build()
+                        .outcome()
+                        .await?
+                    {
+                        VerifyOutcome::Verified(verify) => CheckOutcome::Verified(verify),
+                        VerifyOutcome::AlreadyVerified(verify) => {
+                            CheckOutcome::AlreadyVerified(verify)
+                        }
+                    }
+                }
+                PaymentStatus::InBank => CheckOutcome::Pending,
+                PaymentStatus::Failed => CheckOutcome::Failed,
+                PaymentStatus::Reversed => CheckOutcome::Reversed,
+                PaymentStatus::Expired => CheckOutcome::Expired,
+                PaymentStatus::Unknown => CheckOutcome::Unknown,
+            })
+        }
     }
 }
 
@@ -62,12 +438,28 @@ impl<T> ZarinpalSendExtension for T where T: ZarinpalClient {}
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::{
         methods::request::{Currency, Metadata},
         prelude::ZarinpalSendExtension,
-        Zarinpal,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
     };
 
+    /// Verifies everything slowly, except authority `A0`.
+    fn verifying_transport() -> CannedTransport {
+        CannedTransport::new(|request| {
+            Ok(if request.body["authority"] == "A0" {
+                responses::error(-51, "Session is not valid")
+            } else {
+                responses::verify(100)
+            })
+        })
+        .with_delay(Duration::from_millis(10))
+    }
+
+    #[cfg(feature = "unverified")]
     #[tokio::test]
     async fn test_1() {
         let zarinpal = Zarinpal::new_test().unwrap();
@@ -104,10 +496,119 @@ mod tests {
             )
             // Setting some optional field
             .currency(Currency::IRT)
-            .metadata(Metadata::builder().mobile("mobile").email("email").build())
+            .metadata(
+                Metadata::builder()
+                    .mobile("09121234567".parse().unwrap())
+                    .email("email")
+                    .build(),
+            )
             .build()
             .await;
 
         println!("{unverified:#?}")
     }
+
+    #[cfg(feature = "batch")]
+    #[tokio::test]
+    async fn test_verify_all() {
+        let transport = verifying_transport();
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport.clone()).unwrap();
+
+        let results = zarinpal
+            .verify_all((0..10).map(|i| (format!("A{i}"), 1000)), 3)
+            .await;
+
+        assert_eq!(results.len(), 10);
+        assert_eq!(results[1].0, "A1");
+        assert!(results[0].1.is_err());
+        assert!(results[1..].iter().all(|(_, result)| result.is_ok()));
+        assert!(transport.peak_in_flight() <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_tolerant() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, verifying_transport()).unwrap();
+
+        let outcome = zarinpal.verify_payment_tolerant("A1", 1000).await.unwrap();
+        assert!(outcome.is_verified());
+        assert_eq!(outcome.verify().ref_id(), 201);
+        assert!(zarinpal.verify_payment_tolerant("A0", 1000).await.is_err());
+    }
+
+    #[cfg(feature = "store")]
+    #[tokio::test]
+    async fn test_verify_callback_stored() {
+        use crate::{
+            callback::{CallbackOutcome, CallbackStatus, PaymentCallback},
+            error::Error,
+            results::result_code::ResultCode,
+            store::{MemoryStore, PaymentRecord, PaymentState, PaymentStore},
+        };
+
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, verifying_transport()).unwrap();
+        let store = MemoryStore::new();
+        store
+            .save(PaymentRecord::new("A1", 1000, Currency::IRT))
+            .await
+            .unwrap();
+        for authority in ["A0", "A2", "A4"] {
+            store
+                .save(PaymentRecord::new(authority, 1000, Currency::IRT))
+                .await
+                .unwrap();
+        }
+
+        let outcome = zarinpal
+            .verify_callback_stored(&store, PaymentCallback::new("A1", CallbackStatus::Ok))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, CallbackOutcome::Verified(_)));
+        assert_eq!(
+            store.load("A1").await.unwrap().unwrap().state(),
+            PaymentState::Verified { ref_id: 201 }
+        );
+
+        let outcome = zarinpal
+            .verify_callback_stored(&store, PaymentCallback::new("A2", CallbackStatus::Nok))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, CallbackOutcome::CanceledByUser));
+        assert_eq!(
+            store.load("A2").await.unwrap().unwrap().state(),
+            PaymentState::Failed
+        );
+
+        assert!(matches!(
+            zarinpal
+                .verify_callback_stored(&store, PaymentCallback::new("A3", CallbackStatus::Ok))
+                .await,
+            Err(Error::UnknownAuthority(_))
+        ));
+
+        // A rejected session can't be verified anymore.
+        assert!(zarinpal
+            .verify_callback_stored(&store, PaymentCallback::new("A0", CallbackStatus::Ok))
+            .await
+            .is_err());
+        assert_eq!(
+            store.load("A0").await.unwrap().unwrap().state(),
+            PaymentState::Failed
+        );
+
+        // But a busy api may verify it later.
+        let busy = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::error(-12, "Too many attempts")),
+        )
+        .unwrap();
+        let error = busy
+            .verify_callback_stored(&store, PaymentCallback::new("A4", CallbackStatus::Ok))
+            .await
+            .unwrap_err();
+        assert_eq!(error.result_code(), Some(ResultCode::ToManyAttempts));
+        assert_eq!(
+            store.load("A4").await.unwrap().unwrap().state(),
+            PaymentState::Pending
+        );
+    }
 }
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+use super::GraphqlQuery;
+
+/// Query profile of the merchant that owns the access token.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MeQuery;
+
+impl GraphqlQuery for MeQuery {
+    const QUERY: &'static str =
+        "query Me { Me { id first_name last_name cell_number email level } }";
+    const FIELD: &'static str = "Me";
+
+    type Result = Me;
+}
+
+/// Profile of an authenticated merchant.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Me {
+    /// Unique id of the user.
+    id: String,
+
+    /// First name.
+    #[serde(default)]
+    first_name: Option<String>,
+
+    /// Last name.
+    #[serde(default)]
+    last_name: Option<String>,
+
+    /// Mobile number.
+    #[serde(default)]
+    cell_number: Option<String>,
+
+    /// Email address.
+    #[serde(default)]
+    email: Option<String>,
+
+    /// User level, like `BLUE`, `SILVER` or `GOLD`.
+    #[serde(default)]
+    level: Option<String>,
+}
+
+impl Me {
+    /// Unique id of the user.
+    pub fn id(&self) -> &str {
+        self.id.as_ref()
+    }
+
+    /// First name.
+    pub fn first_name(&self) -> Option<&str> {
+        self.first_name.as_deref()
+    }
+
+    /// Last name.
+    pub fn last_name(&self) -> Option<&str> {
+        self.last_name.as_deref()
+    }
+
+    /// First and last name separated by a space.
+    pub fn full_name(&self) -> String {
+        [self.first_name(), self.last_name()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Mobile number.
+    pub fn cell_number(&self) -> Option<&str> {
+        self.cell_number.as_deref()
+    }
+
+    /// Email address.
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// User level, like `BLUE`, `SILVER` or `GOLD`.
+    pub fn level(&self) -> Option<&str> {
+        self.level.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::{extract_field, GraphqlResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_deserialization() {
+        let response = serde_json::from_value::<GraphqlResponse>(serde_json::json!({
+            "data": {
+                "Me": {
+                    "id": "123456",
+                    "first_name": "Ali",
+                    "last_name": "Alavi",
+                    "cell_number": "09121234567",
+                    "email": "info.test@gmail.com",
+                    "level": "SILVER"
+                }
+            }
+        }))
+        .unwrap();
+
+        let me = extract_field::<MeQuery>(response).unwrap();
+
+        assert_eq!(me.id(), "123456");
+        assert_eq!(me.full_name(), "Ali Alavi");
+        assert_eq!(me.cell_number(), Some("09121234567"));
+        assert_eq!(me.level(), Some("SILVER"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let response = serde_json::from_value::<GraphqlResponse>(serde_json::json!({
+            "data": null,
+            "errors": [
+                { "message": "Unauthenticated." }
+            ]
+        }))
+        .unwrap();
+
+        let error = extract_field::<MeQuery>(response).unwrap_err();
+        assert!(
+            matches!(error, crate::error::Error::GraphqlError(e) if e[0].message() == "Unauthenticated.")
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Client for Zarinpal's GraphQL api.
+//!
+//! Unlike payment gateway endpoints, GraphQL api is authenticated using an access token
+//! that you can create in your zarinpal dashboard, rather than a merchant id.
+
+pub mod me;
+pub mod reporting;
+pub mod settlement;
+pub mod terminal;
+
+use std::{fmt::Display, sync::Arc};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    error::ZarinResult,
+    secret::AccessToken,
+    transport::{HttpTransport, JsonRequest},
+};
+
+/// Default endpoint of zarinpal GraphQL api.
+pub const GRAPHQL_ENDPOINT: &str = "https://next.zarinpal.com/api/v4/graphql/";
+
+/// A query (or mutation) that can be sent to zarinpal GraphQL api.
+///
+/// The type itself is serialized as query variables.
+pub trait GraphqlQuery: Serialize {
+    /// The query document.
+    const QUERY: &'static str;
+
+    /// The root field of response `data` that [`GraphqlQuery::Result`] is read from.
+    const FIELD: &'static str;
+
+    /// The result type of the query.
+    type Result: DeserializeOwned;
+
+    /// Point in time this query must be completed by, if any.
+    ///
+    /// Called when the query is sent, so relative timeouts start from there.
+    fn deadline(&self) -> Option<std::time::Instant> {
+        None
+    }
+}
+
+/// An error reported by GraphQL api.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlError {
+    /// Error message.
+    message: String,
+}
+
+impl GraphqlError {
+    /// Error message.
+    pub fn message(&self) -> &str {
+        self.message.as_ref()
+    }
+}
+
+impl std::error::Error for GraphqlError {}
+
+impl Display for GraphqlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GraphqlRequest<'q, Q> {
+    query: &'static str,
+    variables: &'q Q,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlResponse {
+    #[serde(default)]
+    data: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+/// Access token authenticated client of zarinpal GraphQL api.
+///
+/// # Examples
+///
+/// ```no_run
+/// use zarinpal::graphql::GraphqlClient;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let graphql = GraphqlClient::new("access-token");
+///
+///     let me = graphql.me().await?;
+///     println!("Logged in as {}", me.full_name());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphqlClient {
+    transport: Arc<dyn HttpTransport>,
+    access_token: AccessToken,
+    endpoint: url::Url,
+}
+
+impl GraphqlClient {
+    /// Creates a new instance of [`GraphqlClient`] using an access token.
+    #[cfg(feature = "reqwest")]
+    pub fn new(access_token: impl Into<String>) -> Self {
+        Self::new_with_transport(access_token, crate::transport::ReqwestTransport::default())
+    }
+
+    /// Creates a new instance of [`GraphqlClient`] with custom [`reqwest::Client`]
+    /// as inner http client.
+    #[cfg(feature = "reqwest")]
+    pub fn new_with_client(access_token: impl Into<String>, client: reqwest::Client) -> Self {
+        Self::new_with_transport(
+            access_token,
+            crate::transport::ReqwestTransport::new(client),
+        )
+    }
+
+    /// Creates a new instance of [`GraphqlClient`] with a custom [`HttpTransport`].
+    pub fn new_with_transport(
+        access_token: impl Into<String>,
+        transport: impl HttpTransport + 'static,
+    ) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            access_token: AccessToken::new(access_token.into()),
+            endpoint: GRAPHQL_ENDPOINT.parse().unwrap(),
+        }
+    }
+
+    /// Http transport that is responsible for sending requests.
+    pub fn transport(&self) -> &dyn HttpTransport {
+        self.transport.as_ref()
+    }
+
+    /// The endpoint that queries are sent to.
+    pub fn endpoint(&self) -> &url::Url {
+        &self.endpoint
+    }
+
+    /// Sends a [`GraphqlQuery`] and returns its result.
+    pub async fn send<Q: GraphqlQuery>(&self, query: Q) -> ZarinResult<Q::Result> {
+        send_query(
+            self.transport(),
+            self.endpoint.clone(),
+            self.access_token.expose(),
+            &query,
+        )
+        .await
+    }
+
+    /// Returns profile of the merchant that owns the access token.
+    ///
+    /// Useful at startup to make sure the right credentials are configured.
+    pub async fn me(&self) -> ZarinResult<me::Me> {
+        self.send(me::MeQuery).await
+    }
+
+    /// Returns terminals owned by (or created under) the access token owner.
+    pub async fn terminals(&self) -> ZarinResult<Vec<terminal::Terminal>> {
+        self.send(terminal::TerminalsQuery).await
+    }
+
+    /// Creates a new (sub-)terminal.
+    ///
+    /// ```no_run
+    /// use zarinpal::graphql::{terminal::CreateTerminal, GraphqlClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let graphql = GraphqlClient::new("access-token");
+    ///
+    ///     let terminal = graphql
+    ///         .create_terminal(
+    ///             CreateTerminal::builder()
+    ///                 .name("My shop")
+    ///                 .domain("shop.example.com")
+    ///                 .build(),
+    ///         )
+    ///         .await?;
+    ///
+    ///     println!("{:?}", terminal.merchant_id());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn create_terminal(
+        &self,
+        terminal: terminal::CreateTerminal,
+    ) -> ZarinResult<terminal::Terminal> {
+        self.send(terminal).await
+    }
+
+    /// Edits an existing terminal.
+    pub async fn edit_terminal(
+        &self,
+        terminal: terminal::EditTerminal,
+    ) -> ZarinResult<terminal::Terminal> {
+        self.send(terminal).await
+    }
+
+    /// Lists sessions (transactions) of a terminal, see [`reporting::TransactionsQuery`].
+    ///
+    /// ```no_run
+    /// use zarinpal::graphql::{
+    ///     reporting::{SessionFilter, TransactionsQuery},
+    ///     GraphqlClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let graphql = GraphqlClient::new("access-token");
+    ///
+    ///     let settled = graphql
+    ///         .transactions(
+    ///             TransactionsQuery::builder()
+    ///                 .terminal_id("1234")
+    ///                 .filter(SessionFilter::Verified)
+    ///                 .created_from_date("2024-06-01 00:00:00")
+    ///                 .created_to_date("2024-06-30 23:59:59")
+    ///                 .build(),
+    ///         )
+    ///         .await?;
+    ///
+    ///     let total: u64 = settled.iter().map(|t| t.amount()).sum();
+    ///     println!("{} settled transactions, {total} in total", settled.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn transactions(
+        &self,
+        query: reporting::TransactionsQuery,
+    ) -> ZarinResult<Vec<reporting::Transaction>> {
+        self.send(query).await
+    }
+
+    /// Lists settlements (payout batches) of a terminal, see [`settlement::SettlementsQuery`]
+    /// and [`settlement::join_settlements`].
+    pub async fn settlements(
+        &self,
+        query: settlement::SettlementsQuery,
+    ) -> ZarinResult<Vec<settlement::Settlement>> {
+        self.send(query).await
+    }
+}
+
+/// Sends `query` to `endpoint`, authenticated by `access_token`.
+pub(crate) async fn send_query<Q: GraphqlQuery>(
+    transport: &dyn HttpTransport,
+    endpoint: url::Url,
+    access_token: &str,
+    query: &Q,
+) -> ZarinResult<Q::Result> {
+    let body = serde_json::to_value(GraphqlRequest {
+        query: Q::QUERY,
+        variables: query,
+    })?;
+
+    let response = transport
+        .post_json(JsonRequest::new(endpoint, body).bearer_auth(access_token))
+        .await?;
+
+    extract_field::<Q>(serde_json::from_value(response)?)
+}
+
+fn extract_field<Q: GraphqlQuery>(response: GraphqlResponse) -> ZarinResult<Q::Result> {
+    if !response.errors.is_empty() {
+        return Err(response.errors.into());
+    }
+
+    let value = response
+        .data
+        .and_then(|mut data| data.remove(Q::FIELD))
+        .unwrap_or_default();
+
+    Ok(serde_json::from_value(value)?)
+}
@@ -0,0 +1,345 @@
+//! Reporting over sessions (transactions) of a terminal, like listing settled transactions
+//! of a date range, that payment gateway endpoints can't answer.
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use super::GraphqlQuery;
+
+/// Fields of [`Transaction`] requested in every query.
+macro_rules! transaction_fields {
+    () => {
+        "id authority status amount fee description reference_id card_pan created_at"
+    };
+}
+
+/// Filters sessions of a terminal by their state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionFilter {
+    /// All sessions.
+    #[default]
+    All,
+
+    /// Paid sessions, verified or not.
+    Paid,
+
+    /// Paid and verified (settled) sessions.
+    Verified,
+
+    /// Sessions that are not paid yet.
+    Active,
+
+    /// Refunded sessions.
+    Refunded,
+
+    /// Removed sessions.
+    Trash,
+}
+
+/// Status of a session.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionStatus {
+    /// Waiting for the payer to pay.
+    Active,
+
+    /// Paid, but not verified yet.
+    Paid,
+
+    /// Paid and verified.
+    Verified,
+
+    /// Payment failed or was canceled by the payer.
+    Failed,
+
+    /// Paid and then refunded.
+    Refunded,
+
+    /// Paid but not verified in time, so reversed to the payer.
+    Reversed,
+
+    /// A status that's not known to this crate.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A session (transaction) of a terminal.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    /// Unique id of the session.
+    id: String,
+
+    /// Authority of the payment request.
+    #[serde(default)]
+    authority: Option<String>,
+
+    /// Current status of the session.
+    status: SessionStatus,
+
+    /// Amount of the payment.
+    #[serde(deserialize_with = "crate::results::__private::number_or_string")]
+    amount: u64,
+
+    /// Fee of the payment, if any.
+    #[serde(
+        default,
+        deserialize_with = "crate::results::__private::option_number_or_string"
+    )]
+    fee: Option<u64>,
+
+    /// Description of the payment.
+    #[serde(default)]
+    description: Option<String>,
+
+    /// Reference id of the payment, if it's paid.
+    #[serde(
+        default,
+        deserialize_with = "crate::results::__private::option_number_or_string"
+    )]
+    reference_id: Option<u64>,
+
+    /// Masked card number of the payer, if it's paid.
+    #[serde(default)]
+    card_pan: Option<String>,
+
+    /// Date and time of the session, like `2020-07-01 17:33:25`.
+    created_at: String,
+}
+
+impl Transaction {
+    /// Unique id of the session.
+    pub fn id(&self) -> &str {
+        self.id.as_ref()
+    }
+
+    /// Authority of the payment request.
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    /// Current status of the session.
+    pub fn status(&self) -> &SessionStatus {
+        &self.status
+    }
+
+    /// Amount of the payment.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Fee of the payment, if any.
+    pub fn fee(&self) -> Option<u64> {
+        self.fee
+    }
+
+    /// Description of the payment.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Reference id of the payment, if it's paid.
+    pub fn reference_id(&self) -> Option<u64> {
+        self.reference_id
+    }
+
+    /// Masked card number of the payer, if it's paid.
+    pub fn card_pan(&self) -> Option<&str> {
+        self.card_pan.as_deref()
+    }
+
+    /// Bank that issued the payer's card, if it's a known one.
+    pub fn card_bank(&self) -> Option<crate::card::Bank> {
+        crate::card::Bank::from_pan(self.card_pan.as_deref()?)
+    }
+
+    /// Date and time of the session, like `2020-07-01 17:33:25`.
+    pub fn created_at(&self) -> &str {
+        self.created_at.as_ref()
+    }
+
+    /// Date and time of the session, parsed as Tehran local time.
+    ///
+    /// Returns `None` if the date is malformed, see [`crate::datetime::parse_tehran`].
+    #[cfg(feature = "chrono")]
+    pub fn created_at_parsed(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::datetime::parse_tehran(&self.created_at)
+    }
+
+    /// Date and time of the session in Jalali calendar.
+    #[cfg(feature = "jalali")]
+    pub fn created_at_jalali(&self) -> Option<crate::jalali::JalaliDateTime> {
+        crate::jalali::JalaliDateTime::parse_gregorian(&self.created_at)
+    }
+}
+
+/// List sessions (transactions) of a terminal, optionally filtered.
+///
+/// ```
+/// use zarinpal::graphql::reporting::{SessionFilter, TransactionsQuery};
+///
+/// // Settled transactions of June 2024.
+/// let query = TransactionsQuery::builder()
+///     .terminal_id("1234")
+///     .filter(SessionFilter::Verified)
+///     .created_from_date("2024-06-01 00:00:00")
+///     .created_to_date("2024-06-30 23:59:59")
+///     .limit(100)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Serialize, TypedBuilder)]
+pub struct TransactionsQuery {
+    /// Id of the terminal, see [`crate::graphql::terminal::Terminal::id`].
+    #[builder(setter(into))]
+    terminal_id: String,
+
+    /// (Optional) Filter sessions by their state, defaults to [`SessionFilter::All`].
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<SessionFilter>,
+
+    /// (Optional) Only sessions with this reference id.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reference_id: Option<u64>,
+
+    /// (Optional) Only sessions with this amount.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount: Option<u64>,
+
+    /// (Optional) Only sessions paid by this card number.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card_pan: Option<String>,
+
+    /// (Optional) Only sessions of this payer mobile number.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mobile: Option<String>,
+
+    /// (Optional) Only sessions of this payer email.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+
+    /// (Optional) Only sessions created at or after this date, like `2024-06-01 00:00:00`.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_from_date: Option<String>,
+
+    /// (Optional) Only sessions created at or before this date, like `2024-06-30 23:59:59`.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_to_date: Option<String>,
+
+    /// (Optional) Number of sessions to skip, for pagination.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<u32>,
+
+    /// (Optional) Maximum number of sessions to return, for pagination.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+impl TransactionsQuery {
+    /// The same query for the page after this one, if [`TransactionsQueryBuilder::limit`] is set.
+    ///
+    /// Returns `None` if there's no limit, since then the whole list is returned at once.
+    pub fn next_page(&self) -> Option<Self> {
+        let limit = self.limit?;
+        Some(Self {
+            offset: Some(self.offset.unwrap_or_default() + limit),
+            ..self.clone()
+        })
+    }
+}
+
+impl GraphqlQuery for TransactionsQuery {
+    const QUERY: &'static str = concat!(
+        "query Session($terminal_id: ID!, $filter: FilterEnum, $reference_id: String, \
+         $amount: BigInteger, $card_pan: String, $mobile: CellNumber, $email: String, \
+         $created_from_date: DateTime, $created_to_date: DateTime, $offset: Int, $limit: Int) { \
+         Session(terminal_id: $terminal_id, filter: $filter, reference_id: $reference_id, \
+         amount: $amount, card_pan: $card_pan, mobile: $mobile, email: $email, \
+         created_from_date: $created_from_date, created_to_date: $created_to_date, \
+         offset: $offset, limit: $limit) { ",
+        transaction_fields!(),
+        " } }"
+    );
+    const FIELD: &'static str = "Session";
+
+    type Result = Vec<Transaction>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::{extract_field, GraphqlResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let raw_json = serde_json::json!({
+            "terminal_id": "1234",
+            "filter": "VERIFIED",
+            "created_from_date": "2024-06-01 00:00:00",
+            "limit": 50
+        });
+
+        let query = TransactionsQuery::builder()
+            .terminal_id("1234")
+            .filter(SessionFilter::Verified)
+            .created_from_date("2024-06-01 00:00:00")
+            .limit(50)
+            .build();
+
+        assert_eq!(raw_json, serde_json::to_value(&query).unwrap());
+
+        let next = serde_json::to_value(query.next_page().unwrap()).unwrap();
+        assert_eq!(next["offset"], 50);
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let response = serde_json::from_value::<GraphqlResponse>(serde_json::json!({
+            "data": {
+                "Session": [
+                    {
+                        "id": "1",
+                        "authority": "A00000000000000000000000000217885159",
+                        "status": "VERIFIED",
+                        "amount": "10000",
+                        "fee": 100,
+                        "description": "Order 1",
+                        "reference_id": "201",
+                        "card_pan": "502229******5995",
+                        "created_at": "2024-06-27 17:33:25"
+                    },
+                    {
+                        "id": "2",
+                        "status": "SOMETHING_NEW",
+                        "amount": 20000,
+                        "created_at": "2024-06-28 10:00:00"
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let transactions = extract_field::<TransactionsQuery>(response).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].status(), &SessionStatus::Verified);
+        assert_eq!(transactions[0].amount(), 10000);
+        assert_eq!(transactions[0].reference_id(), Some(201));
+        assert_eq!(
+            transactions[0].card_bank(),
+            Some(crate::card::Bank::Pasargad)
+        );
+        assert_eq!(transactions[1].status(), &SessionStatus::Unknown);
+        assert!(transactions[1].fee().is_none());
+    }
+}
@@ -0,0 +1,356 @@
+//! Settlement (تسویه) reports: payout batches that paid money of verified payments out to
+//! the merchant's iban, and joining them with verified payments for bookkeeping.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use super::GraphqlQuery;
+
+/// Fields of [`Settlement`] requested in every query.
+macro_rules! settlement_fields {
+    () => {
+        "id status amount fee iban reference_id reference_ids created_at settled_at"
+    };
+}
+
+/// Status of a settlement.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SettlementStatus {
+    /// Waiting for the next settlement cycle.
+    Pending,
+
+    /// Sent to the bank, not settled yet.
+    InProgress,
+
+    /// Money is deposited to the iban.
+    Done,
+
+    /// Settlement failed and the money is back in the terminal balance.
+    Failed,
+
+    /// A status that's not known to this crate.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A settlement (payout batch) of a terminal.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settlement {
+    /// Unique id of the settlement.
+    id: String,
+
+    /// Current status of the settlement.
+    status: SettlementStatus,
+
+    /// Amount paid out.
+    #[serde(deserialize_with = "crate::results::__private::number_or_string")]
+    amount: u64,
+
+    /// Fee of the settlement, if any.
+    #[serde(
+        default,
+        deserialize_with = "crate::results::__private::option_number_or_string"
+    )]
+    fee: Option<u64>,
+
+    /// Iban the money is paid out to, as reported by the api (may be masked).
+    iban: String,
+
+    /// Tracking number of the bank transfer, once it's sent.
+    #[serde(default)]
+    reference_id: Option<String>,
+
+    /// Reference ids of verified payments settled in this batch.
+    #[serde(default)]
+    reference_ids: Vec<u64>,
+
+    /// Date and time of the settlement, like `2020-07-01 17:33:25`.
+    created_at: String,
+
+    /// Date and time the money is deposited, if it's done.
+    #[serde(default)]
+    settled_at: Option<String>,
+}
+
+impl Settlement {
+    /// Unique id of the settlement.
+    pub fn id(&self) -> &str {
+        self.id.as_ref()
+    }
+
+    /// Current status of the settlement.
+    pub fn status(&self) -> &SettlementStatus {
+        &self.status
+    }
+
+    /// Amount paid out.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Fee of the settlement, if any.
+    pub fn fee(&self) -> Option<u64> {
+        self.fee
+    }
+
+    /// Iban the money is paid out to, as reported by the api (may be masked).
+    pub fn iban(&self) -> &str {
+        self.iban.as_ref()
+    }
+
+    /// Tracking number of the bank transfer, once it's sent.
+    pub fn reference_id(&self) -> Option<&str> {
+        self.reference_id.as_deref()
+    }
+
+    /// Reference ids of verified payments settled in this batch,
+    /// see [`crate::results::verify::Verify::ref_id`].
+    pub fn reference_ids(&self) -> &[u64] {
+        &self.reference_ids
+    }
+
+    /// Date and time of the settlement, like `2020-07-01 17:33:25`.
+    pub fn created_at(&self) -> &str {
+        self.created_at.as_ref()
+    }
+
+    /// Date and time the money is deposited, if it's done.
+    pub fn settled_at(&self) -> Option<&str> {
+        self.settled_at.as_deref()
+    }
+
+    /// Date and time of the settlement, parsed as Tehran local time.
+    ///
+    /// Returns `None` if the date is malformed, see [`crate::datetime::parse_tehran`].
+    #[cfg(feature = "chrono")]
+    pub fn created_at_parsed(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::datetime::parse_tehran(&self.created_at)
+    }
+
+    /// Date and time the money is deposited, parsed as Tehran local time.
+    #[cfg(feature = "chrono")]
+    pub fn settled_at_parsed(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::datetime::parse_tehran(self.settled_at.as_deref()?)
+    }
+
+    /// Date and time of the settlement in Jalali calendar.
+    #[cfg(feature = "jalali")]
+    pub fn created_at_jalali(&self) -> Option<crate::jalali::JalaliDateTime> {
+        crate::jalali::JalaliDateTime::parse_gregorian(&self.created_at)
+    }
+
+    /// Date and time the money is deposited in Jalali calendar.
+    #[cfg(feature = "jalali")]
+    pub fn settled_at_jalali(&self) -> Option<crate::jalali::JalaliDateTime> {
+        crate::jalali::JalaliDateTime::parse_gregorian(self.settled_at.as_deref()?)
+    }
+}
+
+/// List settlements of a terminal, optionally in a date range.
+///
+/// ```
+/// use zarinpal::graphql::settlement::SettlementsQuery;
+///
+/// let query = SettlementsQuery::builder()
+///     .terminal_id("1234")
+///     .created_from_date("2024-06-01 00:00:00")
+///     .created_to_date("2024-06-30 23:59:59")
+///     .build();
+/// ```
+#[derive(Debug, Clone, Serialize, TypedBuilder)]
+pub struct SettlementsQuery {
+    /// Id of the terminal, see [`crate::graphql::terminal::Terminal::id`].
+    #[builder(setter(into))]
+    terminal_id: String,
+
+    /// (Optional) Only settlements created at or after this date, like `2024-06-01 00:00:00`.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_from_date: Option<String>,
+
+    /// (Optional) Only settlements created at or before this date, like `2024-06-30 23:59:59`.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_to_date: Option<String>,
+
+    /// (Optional) Number of settlements to skip, for pagination.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<u32>,
+
+    /// (Optional) Maximum number of settlements to return, for pagination.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+impl SettlementsQuery {
+    /// The same query for the page after this one, if [`SettlementsQueryBuilder::limit`] is set.
+    ///
+    /// Returns `None` if there's no limit, since then the whole list is returned at once.
+    pub fn next_page(&self) -> Option<Self> {
+        let limit = self.limit?;
+        Some(Self {
+            offset: Some(self.offset.unwrap_or_default() + limit),
+            ..self.clone()
+        })
+    }
+}
+
+impl GraphqlQuery for SettlementsQuery {
+    const QUERY: &'static str = concat!(
+        "query Settlements($terminal_id: ID!, $created_from_date: DateTime, \
+         $created_to_date: DateTime, $offset: Int, $limit: Int) { \
+         Settlements(terminal_id: $terminal_id, created_from_date: $created_from_date, \
+         created_to_date: $created_to_date, offset: $offset, limit: $limit) { ",
+        settlement_fields!(),
+        " } }"
+    );
+    const FIELD: &'static str = "Settlements";
+
+    type Result = Vec<Settlement>;
+}
+
+/// A verified payment, and the settlement it's paid out in (if it is yet).
+///
+/// See [`join_settlements`].
+#[derive(Debug, Clone, Copy)]
+pub struct SettledPayment<'s> {
+    ref_id: u64,
+    settlement: Option<&'s Settlement>,
+}
+
+impl<'s> SettledPayment<'s> {
+    /// Reference id of the verified payment.
+    pub fn ref_id(&self) -> u64 {
+        self.ref_id
+    }
+
+    /// The settlement that paid this payment out, if any.
+    pub fn settlement(&self) -> Option<&'s Settlement> {
+        self.settlement
+    }
+
+    /// Returns `true` if the payment is in a [`SettlementStatus::Done`] settlement.
+    pub fn is_settled(&self) -> bool {
+        self.settlement
+            .is_some_and(|settlement| settlement.status == SettlementStatus::Done)
+    }
+}
+
+/// Finds the settlement of each verified payment (by its `ref_id`), in the order of `ref_ids`.
+///
+/// Payments that are in none of `settlements` are kept, without a settlement.
+///
+/// ```no_run
+/// use zarinpal::graphql::{
+///     settlement::{join_settlements, SettlementsQuery},
+///     GraphqlClient,
+/// };
+/// use zarinpal::prelude::*;
+///
+/// # async fn export(verified: Vec<Verify>) -> Result::<(), Box<dyn std::error::Error>> {
+/// let graphql = GraphqlClient::new("access-token");
+/// let settlements = graphql
+///     .settlements(SettlementsQuery::builder().terminal_id("1234").build())
+///     .await?;
+///
+/// for payment in join_settlements(&settlements, verified.iter().map(Verify::ref_id)) {
+///     match payment.settlement() {
+///         Some(settlement) => println!("{},{}", payment.ref_id(), settlement.id()),
+///         None => println!("{},", payment.ref_id()),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn join_settlements(
+    settlements: &[Settlement],
+    ref_ids: impl IntoIterator<Item = u64>,
+) -> Vec<SettledPayment<'_>> {
+    let by_ref_id: HashMap<u64, &Settlement> = settlements
+        .iter()
+        .flat_map(|settlement| {
+            settlement
+                .reference_ids
+                .iter()
+                .map(move |ref_id| (*ref_id, settlement))
+        })
+        .collect();
+
+    ref_ids
+        .into_iter()
+        .map(|ref_id| SettledPayment {
+            ref_id,
+            settlement: by_ref_id.get(&ref_id).copied(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::{extract_field, GraphqlResponse};
+
+    use super::*;
+
+    fn settlements() -> Vec<Settlement> {
+        let response = serde_json::from_value::<GraphqlResponse>(serde_json::json!({
+            "data": {
+                "Settlements": [
+                    {
+                        "id": "1",
+                        "status": "DONE",
+                        "amount": "19800",
+                        "fee": 200,
+                        "iban": "IR130570028780010957775103",
+                        "reference_id": "140306270001",
+                        "reference_ids": [201, 202],
+                        "created_at": "2024-06-27 10:00:00",
+                        "settled_at": "2024-06-27 12:30:00"
+                    },
+                    {
+                        "id": "2",
+                        "status": "SOMETHING_NEW",
+                        "amount": 5000,
+                        "iban": "IR13******************5103",
+                        "reference_ids": [203],
+                        "created_at": "2024-06-28 10:00:00"
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+
+        extract_field::<SettlementsQuery>(response).unwrap()
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let settlements = settlements();
+
+        assert_eq!(settlements.len(), 2);
+        assert_eq!(settlements[0].status(), &SettlementStatus::Done);
+        assert_eq!(settlements[0].amount(), 19800);
+        assert_eq!(settlements[0].reference_ids(), [201, 202]);
+        assert_eq!(settlements[1].status(), &SettlementStatus::Unknown);
+        assert!(settlements[1].settled_at().is_none());
+    }
+
+    #[test]
+    fn test_join_settlements() {
+        let settlements = settlements();
+
+        let joined = join_settlements(&settlements, [202, 203, 204]);
+
+        assert_eq!(joined.len(), 3);
+        assert_eq!(joined[0].settlement().map(Settlement::id), Some("1"));
+        assert!(joined[0].is_settled());
+        assert_eq!(joined[1].settlement().map(Settlement::id), Some("2"));
+        assert!(!joined[1].is_settled());
+        assert_eq!(joined[2].ref_id(), 204);
+        assert!(joined[2].settlement().is_none());
+    }
+}
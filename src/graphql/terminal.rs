@@ -0,0 +1,235 @@
+//! Terminal (and sub-terminal) management, mostly useful for resellers and agents
+//! that onboard merchants programmatically.
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use super::GraphqlQuery;
+
+/// Fields of [`Terminal`] requested in every query.
+macro_rules! terminal_fields {
+    () => {
+        "id name domain status merchant_id support_phone"
+    };
+}
+
+/// Status of a terminal.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TerminalStatus {
+    /// Terminal is active and can accept payments.
+    Active,
+
+    /// Terminal is waiting to be reviewed.
+    Pending,
+
+    /// Terminal is inactive.
+    Inactive,
+
+    /// Terminal is suspended.
+    Suspend,
+
+    /// A status that's not known to this crate.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A terminal (or sub-terminal) in zarinpal.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Terminal {
+    /// Unique id of the terminal.
+    id: String,
+
+    /// Name of the terminal.
+    name: String,
+
+    /// Domain (website) of the terminal.
+    domain: String,
+
+    /// Current status of the terminal.
+    status: TerminalStatus,
+
+    /// Merchant id of the terminal, to be used with [`crate::Zarinpal`].
+    #[serde(default)]
+    merchant_id: Option<String>,
+
+    /// Support phone number.
+    #[serde(default)]
+    support_phone: Option<String>,
+}
+
+impl Terminal {
+    /// Unique id of the terminal.
+    pub fn id(&self) -> &str {
+        self.id.as_ref()
+    }
+
+    /// Name of the terminal.
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Domain (website) of the terminal.
+    pub fn domain(&self) -> &str {
+        self.domain.as_ref()
+    }
+
+    /// Current status of the terminal.
+    pub fn status(&self) -> &TerminalStatus {
+        &self.status
+    }
+
+    /// Merchant id of the terminal, to be used with [`crate::Zarinpal`].
+    pub fn merchant_id(&self) -> Option<&str> {
+        self.merchant_id.as_deref()
+    }
+
+    /// Support phone number.
+    pub fn support_phone(&self) -> Option<&str> {
+        self.support_phone.as_deref()
+    }
+}
+
+/// List terminals owned by (or created under) the access token owner.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TerminalsQuery;
+
+impl GraphqlQuery for TerminalsQuery {
+    const QUERY: &'static str =
+        concat!("query Terminals { Terminals { ", terminal_fields!(), " } }");
+    const FIELD: &'static str = "Terminals";
+
+    type Result = Vec<Terminal>;
+}
+
+/// Create a new sub-terminal.
+#[derive(Debug, Clone, Serialize, TypedBuilder)]
+pub struct CreateTerminal {
+    /// Name of the terminal.
+    #[builder(setter(into))]
+    name: String,
+
+    /// Domain (website) of the terminal.
+    #[builder(setter(into))]
+    domain: String,
+
+    /// (Optional) Support phone number.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    support_phone: Option<String>,
+
+    /// (Optional) Id of the parent terminal, if this is a sub-terminal.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
+}
+
+impl GraphqlQuery for CreateTerminal {
+    const QUERY: &'static str = concat!(
+        "mutation TerminalAdd($name: String!, $domain: String!, $support_phone: CellNumber, $parent_id: ID) { \
+         TerminalAdd(name: $name, domain: $domain, support_phone: $support_phone, parent_id: $parent_id) { ",
+        terminal_fields!(),
+        " } }"
+    );
+    const FIELD: &'static str = "TerminalAdd";
+
+    type Result = Terminal;
+}
+
+/// Edit an existing terminal.
+#[derive(Debug, Clone, Serialize, TypedBuilder)]
+pub struct EditTerminal {
+    /// Unique id of the terminal.
+    #[builder(setter(into))]
+    id: String,
+
+    /// (Optional) New name of the terminal.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    /// (Optional) New domain of the terminal.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+
+    /// (Optional) New support phone number.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    support_phone: Option<String>,
+}
+
+impl GraphqlQuery for EditTerminal {
+    const QUERY: &'static str = concat!(
+        "mutation TerminalEdit($id: ID!, $name: String, $domain: String, $support_phone: CellNumber) { \
+         TerminalEdit(id: $id, name: $name, domain: $domain, support_phone: $support_phone) { ",
+        terminal_fields!(),
+        " } }"
+    );
+    const FIELD: &'static str = "TerminalEdit";
+
+    type Result = Terminal;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::{extract_field, GraphqlResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let raw_json = serde_json::json!({
+            "name": "My shop",
+            "domain": "shop.example.com",
+            "parent_id": "1234"
+        });
+
+        let from_model = serde_json::to_value(
+            CreateTerminal::builder()
+                .name("My shop")
+                .domain("shop.example.com")
+                .parent_id("1234")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(raw_json, from_model)
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let response = serde_json::from_value::<GraphqlResponse>(serde_json::json!({
+            "data": {
+                "Terminals": [
+                    {
+                        "id": "1234",
+                        "name": "My shop",
+                        "domain": "shop.example.com",
+                        "status": "ACTIVE",
+                        "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+                        "support_phone": null
+                    },
+                    {
+                        "id": "1235",
+                        "name": "My other shop",
+                        "domain": "other.example.com",
+                        "status": "SOMETHING_NEW"
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let terminals = extract_field::<TerminalsQuery>(response).unwrap();
+
+        assert_eq!(terminals.len(), 2);
+        assert_eq!(terminals[0].status(), &TerminalStatus::Active);
+        assert_eq!(
+            terminals[0].merchant_id(),
+            Some("1344b5d4-0048-11e8-94db-005056a205be")
+        );
+        assert_eq!(terminals[1].status(), &TerminalStatus::Unknown);
+        assert!(terminals[1].support_phone().is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! In-process deduplication of verify requests.
+//!
+//! Payers may hit the callback url twice, and retries may verify a payment that's being verified
+//! right now. [`VerifyGuard`] makes sure there's at most one verify request in flight for each
+//! authority, and answers duplicate calls with the cached [`Verify`] for a while.
+//!
+//! It only sees calls of its own process, `redis::RedisVerifyGuard` (`redis` feature) does the
+//! same between instances of an application.
+
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    amount::Amount, error::ZarinResult, extensions::ZarinpalSendExtension, results::verify::Verify,
+    ZarinpalClient,
+};
+
+/// A cached successful verify.
+#[derive(Debug)]
+struct Cached {
+    amount: Amount,
+    verified_at: Instant,
+    verify: Verify,
+}
+
+type Slot = Arc<tokio::sync::Mutex<Option<Cached>>>;
+
+/// Verifies payments, at most once at a time for each authority, caching successful results
+/// for `ttl`.
+///
+/// Calls for an authority that's being verified wait for the first one, and get its result if
+/// it succeeded. Failures are not cached, so the next call will try again. A cached result is
+/// only used if the amount is the same.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::prelude::*;
+/// use zarinpal::guard::VerifyGuard;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///     let guard = VerifyGuard::new(Duration::from_secs(10 * 60));
+///
+///     // Share the guard between callback handlers.
+///     let verify = guard
+///         .verify(&zarinpal, "A00000000000000000000000000217885159", 10000)
+///         .await?;
+///
+///     println!("{}", verify.ref_id());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct VerifyGuard {
+    ttl: Duration,
+    slots: Mutex<HashMap<String, Slot>>,
+}
+
+impl Default for VerifyGuard {
+    /// A guard caching results for 10 minutes.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10 * 60))
+    }
+}
+
+impl VerifyGuard {
+    /// Creates a guard caching successful results for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long successful results are cached.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Verifies the payment of `authority`, or returns the cached result of a previous call.
+    pub async fn verify<Z>(
+        &self,
+        zarinpal: &Z,
+        authority: impl Into<String>,
+        amount: impl Into<Amount>,
+    ) -> ZarinResult<Verify>
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        let authority = authority.into();
+        let amount = amount.into();
+        let slot = self.slot(&authority);
+
+        let mut cached = slot.lock().await;
+        if let Some(hit) = cached.as_ref() {
+            if hit.amount == amount && hit.verified_at.elapsed() < self.ttl {
+                return Ok(hit.verify.clone());
+            }
+        }
+
+        let verify = zarinpal
+            .verify_payment(authority, amount.clone())
+            .build()
+            .await?;
+
+        *cached = Some(Cached {
+            amount,
+            verified_at: Instant::now(),
+            verify: verify.clone(),
+        });
+        Ok(verify)
+    }
+
+    /// Forgets the cached result of `authority`, if any.
+    pub fn forget(&self, authority: &str) {
+        self.slots.lock().unwrap().remove(authority);
+    }
+
+    /// Returns the slot of `authority`, dropping expired (and unused) slots of others.
+    fn slot(&self, authority: &str) -> Slot {
+        let mut slots = self.slots.lock().unwrap();
+        slots.retain(|_, slot| {
+            // Others hold the slot while verifying, or waiting to verify, this one.
+            Arc::strong_count(slot) > 1
+                || slot.try_lock().is_ok_and(|cached| {
+                    cached
+                        .as_ref()
+                        .is_some_and(|hit| hit.verified_at.elapsed() < self.ttl)
+                })
+        });
+
+        slots.entry(authority.to_string()).or_default().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_duplicate_verifies() {
+        let transport =
+            CannedTransport::always(responses::verify(100)).with_delay(Duration::from_millis(50));
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport.clone()).unwrap();
+        let guard = VerifyGuard::default();
+
+        let (first, second) = tokio::join!(
+            guard.verify(&zarinpal, "A1", 1000),
+            guard.verify(&zarinpal, "A1", 1000),
+        );
+        assert_eq!(first.unwrap().ref_id(), 201);
+        assert_eq!(second.unwrap().ref_id(), 201);
+        assert_eq!(transport.request_count(), 1);
+
+        guard.verify(&zarinpal, "A2", 1000).await.unwrap();
+        guard.verify(&zarinpal, "A1", 2000).await.unwrap();
+        assert_eq!(transport.request_count(), 3);
+
+        guard.forget("A2");
+        guard.verify(&zarinpal, "A2", 1000).await.unwrap();
+        assert_eq!(transport.request_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_failed_first_verify() {
+        let transport =
+            CannedTransport::always(responses::verify(100)).with_delay(Duration::from_millis(50));
+        transport.fail_next(1);
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport.clone()).unwrap();
+        let guard = VerifyGuard::default();
+
+        // The second call verifies after the first one fails, while a third one arrives and
+        // drops unused slots.
+        let (first, second, third) = tokio::join!(
+            guard.verify(&zarinpal, "A1", 1000),
+            guard.verify(&zarinpal, "A1", 1000),
+            async {
+                tokio::time::sleep(Duration::from_millis(75)).await;
+                guard.verify(&zarinpal, "A2", 1000).await.unwrap();
+                guard.verify(&zarinpal, "A1", 1000).await
+            },
+        );
+        assert!(first.is_err());
+        assert_eq!(second.unwrap().ref_id(), 201);
+        assert_eq!(third.unwrap().ref_id(), 201);
+        assert_eq!(transport.request_count(), 3);
+
+        // A slot that's held by a waiter is kept.
+        let held = guard.slot("A3");
+        guard.slot("A4");
+        assert!(Arc::ptr_eq(&held, &guard.slot("A3")));
+    }
+}
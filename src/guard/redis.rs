@@ -0,0 +1,271 @@
+//! Deduplication of verify requests between instances, using Redis (requires `redis` feature).
+
+use std::time::Duration;
+
+use redis::{aio::ConnectionLike, AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    amount::Amount,
+    error::{Error, ZarinResult},
+    extensions::ZarinpalSendExtension,
+    results::verify::Verify,
+    ZarinpalClient,
+};
+
+/// How often a call waiting for another instance checks for its result.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Deletes a lock only if it's still held by us.
+const RELEASE_LOCK: &str = r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0"#;
+
+fn redis_error(error: redis::RedisError) -> Error {
+    Error::StoreError(Box::new(error))
+}
+
+/// A cached successful verify, as it's stored in Redis.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    /// See [`amount_key`].
+    amount: String,
+
+    verify: Verify,
+}
+
+/// Identifies an amount, cached results are only used for the same amount.
+fn amount_key(amount: &Amount) -> String {
+    match amount.currency() {
+        Some(currency) => format!("{} {currency}", amount.value()),
+        None => amount.value().to_string(),
+    }
+}
+
+/// Like [`super::VerifyGuard`], but shared between instances of an application through Redis.
+///
+/// At most one instance verifies an authority at a time (holding a lock that expires after
+/// [`RedisVerifyGuard::with_lock_ttl`]), others wait for its result. Successful results are
+/// cached for `ttl` under `{prefix}:{authority}`, failures are not cached. A cached result is
+/// only used if the amount is the same.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::guard::redis::RedisVerifyGuard;
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///     let connection = redis::Client::open("redis://127.0.0.1/")?
+///         .get_multiplexed_async_connection()
+///         .await?;
+///     let guard = RedisVerifyGuard::new(connection, Duration::from_secs(10 * 60));
+///
+///     let verify = guard
+///         .verify(&zarinpal, "A00000000000000000000000000217885159", 10000)
+///         .await?;
+///
+///     println!("{}", verify.ref_id());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedisVerifyGuard<C> {
+    connection: C,
+    ttl: Duration,
+    lock_ttl: Duration,
+    key_prefix: String,
+}
+
+impl<C> RedisVerifyGuard<C>
+where
+    C: ConnectionLike + Clone + Send + Sync,
+{
+    /// Creates a guard using `connection`, caching successful results for `ttl`.
+    ///
+    /// Keys are prefixed with `zarinpal:verify` and locks expire after 30 seconds by default.
+    pub fn new(connection: C, ttl: Duration) -> Self {
+        Self {
+            connection,
+            ttl,
+            lock_ttl: Duration::from_secs(30),
+            key_prefix: "zarinpal:verify".to_string(),
+        }
+    }
+
+    /// Prefixes keys with `key_prefix`, to share a Redis between applications.
+    pub fn with_key_prefix(mut self, key_prefix: impl Into<String>) -> Self {
+        self.key_prefix = key_prefix.into();
+        self
+    }
+
+    /// Releases locks of instances that didn't finish verifying after `lock_ttl`, like the ones
+    /// that crashed. It must be longer than a verify request may take.
+    pub fn with_lock_ttl(mut self, lock_ttl: Duration) -> Self {
+        self.lock_ttl = lock_ttl;
+        self
+    }
+
+    /// How long successful results are cached.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    fn key(&self, authority: &str) -> String {
+        format!("{}:{authority}", self.key_prefix)
+    }
+
+    fn lock_key(&self, authority: &str) -> String {
+        format!("{}:{authority}:lock", self.key_prefix)
+    }
+
+    /// Verifies the payment of `authority`, or returns the cached result of a previous call
+    /// of any instance.
+    ///
+    /// Fails with [`Error::StoreError`] if Redis fails.
+    pub async fn verify<Z>(
+        &self,
+        zarinpal: &Z,
+        authority: impl Into<String>,
+        amount: impl Into<Amount>,
+    ) -> ZarinResult<Verify>
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        let authority = authority.into();
+        let amount = amount.into();
+        let mut connection = self.connection.clone();
+
+        let token = uuid::Uuid::new_v4().to_string();
+        loop {
+            if let Some(verify) = self.cached(&authority, &amount).await? {
+                return Ok(verify);
+            }
+
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(self.lock_key(&authority))
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(self.lock_ttl.as_millis() as u64)
+                .query_async(&mut connection)
+                .await
+                .map_err(redis_error)?;
+            if acquired.is_some() {
+                break;
+            }
+
+            crate::timer::sleep(POLL_INTERVAL).await;
+        }
+
+        // Someone may have finished between checking the cache and taking the lock.
+        let result = match self.cached(&authority, &amount).await {
+            Ok(Some(verify)) => Ok(verify),
+            Ok(None) => self.verify_and_cache(zarinpal, &authority, amount).await,
+            Err(error) => Err(error),
+        };
+
+        // Failing to release the lock doesn't change the result, it expires after `lock_ttl`.
+        let _ = Script::new(RELEASE_LOCK)
+            .key(self.lock_key(&authority))
+            .arg(&token)
+            .invoke_async::<i64>(&mut connection)
+            .await;
+
+        result
+    }
+
+    /// Forgets the cached result of `authority`, if any.
+    pub async fn forget(&self, authority: &str) -> ZarinResult<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .del::<_, i64>(self.key(authority))
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn cached(&self, authority: &str, amount: &Amount) -> ZarinResult<Option<Verify>> {
+        let mut connection = self.connection.clone();
+        let cached: Option<String> = connection
+            .get(self.key(authority))
+            .await
+            .map_err(redis_error)?;
+
+        let Some(cached) = cached else {
+            return Ok(None);
+        };
+        let cached: Cached = serde_json::from_str(&cached).map_err(Error::JsonError)?;
+        if cached.amount != amount_key(amount) {
+            return Ok(None);
+        }
+        Ok(Some(cached.verify))
+    }
+
+    async fn verify_and_cache<Z>(
+        &self,
+        zarinpal: &Z,
+        authority: &str,
+        amount: Amount,
+    ) -> ZarinResult<Verify>
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        let amount_key = amount_key(&amount);
+        let verify = zarinpal.verify_payment(authority, amount).build().await?;
+
+        let cached = Cached {
+            amount: amount_key,
+            verify: verify.clone(),
+        };
+        let mut connection = self.connection.clone();
+        connection
+            .pset_ex::<_, _, ()>(
+                self.key(authority),
+                serde_json::to_string(&cached).map_err(Error::JsonError)?,
+                self.ttl.as_millis() as u64,
+            )
+            .await
+            .map_err(redis_error)?;
+
+        Ok(verify)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_verify() {
+        let verify: Verify = serde_json::from_value(serde_json::json!({
+            "code": 101,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Payer",
+            "fee": 100,
+            "order_id": "ORDER-1"
+        }))
+        .unwrap();
+
+        let cached = Cached {
+            amount: amount_key(&Amount::rials(1000)),
+            verify: verify.clone(),
+        };
+        let cached: Cached =
+            serde_json::from_str(&serde_json::to_string(&cached).unwrap()).unwrap();
+        assert_eq!(cached.verify, verify);
+        assert_eq!(cached.amount, "1000 IRR");
+
+        assert_ne!(
+            amount_key(&Amount::tomans(1000)),
+            amount_key(&Amount::rials(1000))
+        );
+    }
+}
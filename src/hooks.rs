@@ -0,0 +1,69 @@
+//! Hooks that can be installed on [`crate::Zarinpal`] to customize how requests are sent.
+
+use std::{fmt::Debug, sync::Arc};
+
+/// A hook that can adjust the serialized request json right before it's sent.
+///
+/// It's called with the path of the api method (like `pg/v4/payment/request.json`) and
+/// the request body. This is an escape hatch for api quirks that typed requests
+/// don't cover yet, like undocumented fields or renamed keys.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_request_hook(|path, body| {
+///     if path.ends_with("request.json") {
+///         body["undocumented_field"] = "value".into();
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RequestHook(Arc<RequestHookFn>);
+
+type RequestHookFn = dyn Fn(&str, &mut serde_json::Value) + Send + Sync;
+
+impl RequestHook {
+    /// Creates a new hook from a function.
+    pub fn new(hook: impl Fn(&str, &mut serde_json::Value) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    /// Calls the hook.
+    pub fn call(&self, path: &str, body: &mut serde_json::Value) {
+        (self.0)(path, body)
+    }
+}
+
+impl Debug for RequestHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RequestHook")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call() {
+        let hook = RequestHook::new(|path, body| {
+            if path == "pg/v4/payment/request.json" {
+                let description = body["description"].take();
+                body["desc"] = description;
+            }
+        });
+
+        let mut body = serde_json::json!({ "description": "Test" });
+        hook.call("pg/v4/payment/verify.json", &mut body);
+        assert_eq!(body, serde_json::json!({ "description": "Test" }));
+
+        hook.call("pg/v4/payment/request.json", &mut body);
+        assert_eq!(
+            body,
+            serde_json::json!({ "description": null, "desc": "Test" })
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Iranian (Shaparak) iban numbers, see [`Iban`].
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ZarinResult};
+
+/// A valid iranian iban number, like `IR130570028780010957775103`.
+///
+/// It's `IR` and 24 digits, with a valid mod-97 checksum. Spaces are ignored and letters are
+/// uppercased when parsing, so typos are caught before a request is sent, instead of surfacing
+/// as wage errors of the api.
+///
+/// `Display` output masks the middle digits, use [`Iban::as_str`] for the full number. So does
+/// `Debug` output, unless `unredacted-debug` feature is enabled.
+///
+/// ```
+/// use zarinpal::iban::Iban;
+///
+/// let iban: Iban = "ir13 0570 0287 8001 0957 7751 03".parse().unwrap();
+///
+/// assert_eq!(iban.as_str(), "IR130570028780010957775103");
+/// assert_eq!(iban.to_string(), "IR13******************5103");
+/// assert!("IR130570028780010957775104".parse::<Iban>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
+#[serde(try_from = "String", into = "String")]
+pub struct Iban(String);
+
+impl Iban {
+    /// Parses and validates `iban`, ignoring spaces.
+    ///
+    /// Fails with [`Error::InvalidIban`] if it's not `IR` and 24 digits, or the checksum doesn't
+    /// match.
+    pub fn new(iban: &str) -> ZarinResult<Self> {
+        let iban = iban
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+
+        if iban.len() != 26
+            || !iban.starts_with("IR")
+            || !iban[2..].bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(Error::InvalidIban(
+                "iban must be IR followed by 24 digits".to_string(),
+            ));
+        }
+        if checksum(&iban) != 1 {
+            return Err(Error::InvalidIban(
+                "iban checksum doesn't match".to_string(),
+            ));
+        }
+        Ok(Self(iban))
+    }
+
+    /// The full, normalized iban.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The iban with all but first and last 4 characters masked.
+    pub fn masked(&self) -> String {
+        crate::redact::mask(&self.0, 4, 4)
+    }
+}
+
+/// Mod-97 remainder of `iban`, which is 1 for valid ones.
+fn checksum(iban: &str) -> u32 {
+    // Move the first 4 characters to the end, and replace letters with numbers (A = 10).
+    iban[4..]
+        .chars()
+        .chain(iban[..4].chars())
+        .fold(0, |remainder, c| {
+            let value = c.to_digit(36).unwrap();
+            let shift = if value < 10 { 10 } else { 100 };
+            (remainder * shift + value) % 97
+        })
+}
+
+impl FromStr for Iban {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for Iban {
+    type Error = Error;
+
+    fn try_from(iban: String) -> Result<Self, Self::Error> {
+        Self::new(&iban)
+    }
+}
+
+impl TryFrom<&str> for Iban {
+    type Error = Error;
+
+    fn try_from(iban: &str) -> Result<Self, Self::Error> {
+        Self::new(iban)
+    }
+}
+
+impl From<Iban> for String {
+    fn from(iban: Iban) -> Self {
+        iban.0
+    }
+}
+
+impl AsRef<str> for Iban {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Iban {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.masked())
+    }
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for Iban {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Iban").field(&self.masked()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let iban = Iban::new("IR670170000000352965862009").unwrap();
+        assert_eq!(iban.as_str(), "IR670170000000352965862009");
+        assert_eq!(
+            "ir67 0170 0000 0035 2965 8620 09".parse::<Iban>().unwrap(),
+            iban
+        );
+
+        for invalid in [
+            "",
+            "IR67017000000035296586200",
+            "DE89370400440532013000",
+            "IR67017000000035296586200X",
+            "IR670170000000352965862008",
+        ] {
+            assert!(matches!(Iban::new(invalid), Err(Error::InvalidIban(_))));
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let iban: Iban =
+            serde_json::from_value(serde_json::json!("IR13 0570 0287 8001 0957 7751 03")).unwrap();
+        assert_eq!(
+            serde_json::to_value(&iban).unwrap(),
+            serde_json::json!("IR130570028780010957775103")
+        );
+        assert!(
+            serde_json::from_value::<Iban>(serde_json::json!("IR130570028780010957775104"))
+                .is_err()
+        );
+    }
+
+    #[cfg(not(feature = "unredacted-debug"))]
+    #[test]
+    fn test_masked() {
+        let iban = Iban::new("IR130570028780010957775103").unwrap();
+        assert_eq!(iban.to_string(), "IR13******************5103");
+        assert_eq!(format!("{iban:?}"), "Iban(\"IR13******************5103\")");
+    }
+}
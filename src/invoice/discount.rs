@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+/// How a [`Discount`] reduces the amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscountKind {
+    /// A percentage of the amount, capped at `100`.
+    Percentage(u8),
+
+    /// A fixed amount, in invoice currency.
+    Fixed(u64),
+}
+
+/// A discount (usually from a coupon) applied to an [`super::Invoice`].
+///
+/// ```
+/// use zarinpal::invoice::discount::{Discount, DiscountKind};
+///
+/// let discount = Discount::builder()
+///     .code("NOWRUZ")
+///     .kind(DiscountKind::Percentage(20))
+///     .max_discount(30000)
+///     .build();
+///
+/// assert_eq!(discount.apply(100000), 80000);
+/// assert_eq!(discount.apply(500000), 470000);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypedBuilder)]
+pub struct Discount {
+    /// Coupon code of the discount, recorded in payment metadata as `coupon`.
+    #[builder(setter(into))]
+    code: String,
+
+    /// How the discount reduces the amount.
+    kind: DiscountKind,
+
+    /// (Optional) Maximum amount the discount can reduce.
+    #[builder(default, setter(strip_option))]
+    max_discount: Option<u64>,
+
+    /// (Optional) Minimum payable amount after the discount.
+    #[builder(default, setter(strip_option))]
+    floor: Option<u64>,
+}
+
+impl Discount {
+    /// Coupon code of the discount.
+    pub fn code(&self) -> &str {
+        self.code.as_ref()
+    }
+
+    /// How the discount reduces the amount.
+    pub fn kind(&self) -> DiscountKind {
+        self.kind
+    }
+
+    /// The amount that this discount reduces from `amount`.
+    ///
+    /// Percentage discounts are rounded down, in favor of the merchant.
+    pub fn discount_for(&self, amount: u64) -> u64 {
+        let discount = match self.kind {
+            DiscountKind::Percentage(percent) => {
+                (u128::from(amount) * u128::from(percent.min(100)) / 100) as u64
+            }
+            DiscountKind::Fixed(fixed) => fixed,
+        };
+
+        let discount = self
+            .max_discount
+            .map_or(discount, |max| discount.min(max))
+            .min(amount);
+
+        match self.floor {
+            // Never discount below the floor, unless the amount is already below it.
+            Some(floor) => discount.min(amount.saturating_sub(floor)),
+            None => discount,
+        }
+    }
+
+    /// Applies the discount to `amount`.
+    pub fn apply(&self, amount: u64) -> u64 {
+        amount - self.discount_for(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentage() {
+        let discount = Discount::builder()
+            .code("OFF15")
+            .kind(DiscountKind::Percentage(15))
+            .build();
+
+        assert_eq!(discount.apply(10000), 8500);
+        assert_eq!(discount.discount_for(10001), 1500);
+        assert_eq!(discount.discount_for(u64::MAX), 2767011611056432742);
+    }
+
+    #[test]
+    fn test_fixed_with_floor() {
+        let discount = Discount::builder()
+            .code("GIFT")
+            .kind(DiscountKind::Fixed(50000))
+            .floor(10000)
+            .build();
+
+        assert_eq!(discount.apply(100000), 50000);
+        assert_eq!(discount.apply(40000), 10000);
+        assert_eq!(discount.apply(5000), 5000);
+    }
+
+    #[test]
+    fn test_over_discount() {
+        let discount = Discount::builder()
+            .code("FREE")
+            .kind(DiscountKind::Percentage(250))
+            .build();
+
+        assert_eq!(discount.apply(10000), 0);
+    }
+}
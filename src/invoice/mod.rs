@@ -0,0 +1,364 @@
+//! Invoices with line items, that compute the payment amount and description for you.
+
+pub mod discount;
+pub mod tax;
+
+use std::borrow::Cow;
+
+use typed_builder::TypedBuilder;
+
+use discount::Discount;
+use tax::{InvoiceBreakdown, Vat};
+
+use crate::{
+    error::{Error, ZarinResult},
+    methods::request::{Currency, Metadata, RequestPayment},
+    ZarinpalClient,
+};
+
+/// A single line of an [`Invoice`].
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct LineItem {
+    /// Name of the item.
+    #[builder(setter(into))]
+    name: String,
+
+    /// (Optional) Quantity of the item, defaults to `1`.
+    #[builder(default = 1)]
+    quantity: u32,
+
+    /// Price of a single item, in invoice currency.
+    unit_price: u64,
+}
+
+impl LineItem {
+    /// Name of the item.
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Quantity of the item.
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    /// Price of a single item, in invoice currency.
+    pub fn unit_price(&self) -> u64 {
+        self.unit_price
+    }
+
+    /// Total price of the line (`quantity * unit_price`), or `None` on overflow.
+    pub fn total(&self) -> Option<u64> {
+        self.unit_price.checked_mul(self.quantity.into())
+    }
+}
+
+/// An invoice made of [`LineItem`]s.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::invoice::{Invoice, LineItem};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let invoice = Invoice::builder()
+///         .number("1024")
+///         .currency(Currency::IRT)
+///         .items([
+///             LineItem::builder().name("Coffee").quantity(2).unit_price(60000).build(),
+///             LineItem::builder().name("Cake").unit_price(80000).build(),
+///         ])
+///         .build();
+///
+///     // Amount is 200000 and description is "Invoice #1024: 2 x Coffee, 1 x Cake".
+///     let request = invoice
+///         .request_payment(&zarinpal, "https://example.com/verify")?
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct Invoice {
+    /// Unique number of the invoice, also sent as `order_id`.
+    #[builder(setter(into))]
+    number: String,
+
+    /// (Optional) Title used in the description, defaults to `Invoice`.
+    #[builder(default, setter(strip_option, into))]
+    title: Option<String>,
+
+    /// (Optional) Currency of the prices.
+    #[builder(default, setter(strip_option))]
+    currency: Option<Currency>,
+
+    /// Line items of the invoice.
+    #[builder(default, setter(into))]
+    items: Vec<LineItem>,
+
+    /// (Optional) Discount applied to the invoice.
+    #[builder(default, setter(strip_option))]
+    discount: Option<Discount>,
+
+    /// (Optional) Value added tax of the invoice.
+    #[builder(default, setter(strip_option))]
+    vat: Option<Vat>,
+}
+
+impl Invoice {
+    /// Unique number of the invoice.
+    pub fn number(&self) -> &str {
+        self.number.as_ref()
+    }
+
+    /// Currency of the prices.
+    pub fn currency(&self) -> Currency {
+        self.currency.clone().unwrap_or_default()
+    }
+
+    /// Line items of the invoice.
+    pub fn items(&self) -> &[LineItem] {
+        self.items.as_ref()
+    }
+
+    /// Discount applied to the invoice.
+    pub fn discount(&self) -> Option<&Discount> {
+        self.discount.as_ref()
+    }
+
+    /// Value added tax of the invoice.
+    pub fn vat(&self) -> Option<&Vat> {
+        self.vat.as_ref()
+    }
+
+    /// Adds a line item to the invoice.
+    pub fn push(&mut self, item: LineItem) {
+        self.items.push(item)
+    }
+
+    /// Applies a discount to the invoice, replacing the previous one.
+    pub fn apply_discount(&mut self, discount: Discount) {
+        self.discount = Some(discount)
+    }
+
+    /// Sum of all line items, before discount.
+    ///
+    /// Fails with [`Error::AmountOverflow`] if the sum doesn't fit in an `u64`.
+    pub fn subtotal(&self) -> ZarinResult<u64> {
+        self.items.iter().try_fold(0u64, |total, item| {
+            item.total()
+                .and_then(|line| total.checked_add(line))
+                .ok_or(Error::AmountOverflow)
+        })
+    }
+
+    /// The amount reduced by [`Invoice::discount`].
+    pub fn discount_amount(&self) -> ZarinResult<u64> {
+        let subtotal = self.subtotal()?;
+        Ok(self
+            .discount
+            .as_ref()
+            .map_or(0, |discount| discount.discount_for(subtotal)))
+    }
+
+    /// Subtotal, discount, net, tax and gross figures of the invoice.
+    ///
+    /// Discount is applied before tax. If [`Vat::inclusive`], prices are considered
+    /// to already include the tax.
+    pub fn breakdown(&self) -> ZarinResult<InvoiceBreakdown> {
+        let subtotal = self.subtotal()?;
+        let discount = self.discount_amount()?;
+        let discounted = subtotal - discount;
+
+        let (net, tax, gross) = match &self.vat {
+            None => (discounted, 0, discounted),
+            Some(vat) if vat.inclusive() => {
+                let tax = vat.included_tax_for(discounted);
+                (discounted - tax, tax, discounted)
+            }
+            Some(vat) => {
+                let tax = vat.tax_for(discounted);
+                let gross = discounted.checked_add(tax).ok_or(Error::AmountOverflow)?;
+                (discounted, tax, gross)
+            }
+        };
+
+        Ok(InvoiceBreakdown {
+            subtotal,
+            discount,
+            net,
+            tax,
+            gross,
+        })
+    }
+
+    /// Total payable amount of the invoice, after discount and including tax.
+    ///
+    /// Fails with [`Error::AmountOverflow`] if the total doesn't fit in an `u64`.
+    pub fn total(&self) -> ZarinResult<u64> {
+        Ok(self.breakdown()?.gross)
+    }
+
+    /// A standardized description like `Invoice #1024: 2 x Coffee, 1 x Cake`.
+    pub fn description(&self) -> String {
+        let items = self
+            .items
+            .iter()
+            .map(|item| format!("{} x {}", item.quantity, item.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{} #{}: {}",
+            self.title.as_deref().unwrap_or("Invoice"),
+            self.number,
+            items
+        )
+    }
+
+    /// Metadata of the payment request, carrying invoice number as `order_id`
+    /// and the applied coupon code (if any) as `coupon`.
+    pub fn metadata(&self) -> Metadata {
+        let mut metadata = Metadata::builder().order_id(self.number.as_str()).build();
+        if let Some(discount) = &self.discount {
+            metadata.insert_extra("coupon", discount.code());
+        }
+        metadata
+    }
+
+    /// Builds a [`RequestPayment`] for this invoice.
+    pub fn request_payment<'z, Z: ZarinpalClient>(
+        &self,
+        zarinpal: &'z Z,
+        callback_url: impl Into<Cow<'z, str>>,
+    ) -> ZarinResult<RequestPayment<'z, &'z Z>> {
+        Ok(RequestPayment::builder()
+            .zarinpal(zarinpal)
+            .amount(self.total()?)
+            .currency(self.currency())
+            .callback_url(callback_url)
+            .description(self.description())
+            .metadata(self.metadata())
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Zarinpal;
+
+    use super::*;
+
+    fn invoice() -> Invoice {
+        Invoice::builder()
+            .number("1024")
+            .currency(Currency::IRT)
+            .items([
+                LineItem::builder()
+                    .name("Coffee")
+                    .quantity(2)
+                    .unit_price(60000)
+                    .build(),
+                LineItem::builder().name("Cake").unit_price(80000).build(),
+            ])
+            .build()
+    }
+
+    #[test]
+    fn test_total_and_description() {
+        let invoice = invoice();
+
+        assert_eq!(invoice.total().unwrap(), 200000);
+        assert_eq!(invoice.description(), "Invoice #1024: 2 x Coffee, 1 x Cake");
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut invoice = invoice();
+        invoice.push(
+            LineItem::builder()
+                .name("Gold")
+                .quantity(2)
+                .unit_price(u64::MAX)
+                .build(),
+        );
+
+        assert!(matches!(invoice.total(), Err(Error::AmountOverflow)));
+    }
+
+    #[test]
+    fn test_discount() {
+        let mut invoice = invoice();
+        invoice.apply_discount(
+            Discount::builder()
+                .code("NOWRUZ")
+                .kind(discount::DiscountKind::Percentage(10))
+                .build(),
+        );
+
+        assert_eq!(invoice.subtotal().unwrap(), 200000);
+        assert_eq!(invoice.discount_amount().unwrap(), 20000);
+        assert_eq!(invoice.total().unwrap(), 180000);
+        assert_eq!(
+            serde_json::to_value(invoice.metadata()).unwrap(),
+            serde_json::json!({ "order_id": "1024", "coupon": "NOWRUZ" })
+        );
+    }
+
+    #[test]
+    fn test_vat() {
+        let mut invoice = invoice();
+        invoice.apply_discount(
+            Discount::builder()
+                .code("NOWRUZ")
+                .kind(discount::DiscountKind::Fixed(20000))
+                .build(),
+        );
+        invoice.vat = Some(Vat::builder().basis_points(1000).build());
+
+        assert_eq!(
+            invoice.breakdown().unwrap(),
+            InvoiceBreakdown {
+                subtotal: 200000,
+                discount: 20000,
+                net: 180000,
+                tax: 18000,
+                gross: 198000,
+            }
+        );
+        assert_eq!(invoice.total().unwrap(), 198000);
+
+        invoice.vat = Some(Vat::builder().basis_points(1000).inclusive(true).build());
+        let breakdown = invoice.breakdown().unwrap();
+        assert_eq!(breakdown.gross, 180000);
+        assert_eq!(breakdown.tax, 16364);
+        assert_eq!(breakdown.net, 163636);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let raw_json = serde_json::json!({
+            "merchant_id": null,
+            "currency": "IRT",
+            "amount": 200000,
+            "callback_url": "http://yoursite.com/verify",
+            "description": "Invoice #1024: 2 x Coffee, 1 x Cake",
+            "metadata": {
+                "order_id": "1024"
+            }
+        });
+
+        let from_model = serde_json::to_value(
+            invoice()
+                .request_payment(&zarinpal, "http://yoursite.com/verify")
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(raw_json, from_model)
+    }
+}
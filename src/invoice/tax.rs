@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+/// How fractional amounts are rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Rounding {
+    /// Round towards zero.
+    Down,
+
+    /// Round away from zero.
+    Up,
+
+    /// Round to the nearest value, halves away from zero.
+    #[default]
+    Nearest,
+}
+
+impl Rounding {
+    /// Rounds `numerator / denominator` to a multiple of `step`.
+    pub(crate) fn divide(&self, numerator: u128, denominator: u128, step: u64) -> u128 {
+        let denominator = denominator * u128::from(step.max(1));
+        let quotient = match self {
+            Rounding::Down => numerator / denominator,
+            Rounding::Up => numerator.div_ceil(denominator),
+            Rounding::Nearest => (numerator + denominator / 2) / denominator,
+        };
+        quotient * u128::from(step.max(1))
+    }
+}
+
+/// Value added tax applied to an [`super::Invoice`].
+///
+/// ```
+/// use zarinpal::invoice::tax::{Rounding, Vat};
+///
+/// // 10% VAT, rounded up to whole Tomans (10 Rials).
+/// let vat = Vat::builder()
+///     .basis_points(1000)
+///     .rounding(Rounding::Up)
+///     .step(10)
+///     .build();
+///
+/// assert_eq!(vat.tax_for(12345), 1240);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypedBuilder)]
+pub struct Vat {
+    /// Tax rate in basis points (one hundredth of a percent), Eg: `1000` for 10%.
+    basis_points: u32,
+
+    /// (Optional) Whether line item prices already include the tax. Defaults to `false`.
+    #[builder(default)]
+    inclusive: bool,
+
+    /// (Optional) How the tax amount is rounded. Defaults to [`Rounding::Nearest`].
+    #[builder(default)]
+    rounding: Rounding,
+
+    /// (Optional) Tax amount is rounded to a multiple of this. Defaults to `1`.
+    #[builder(default = 1)]
+    step: u64,
+}
+
+impl Vat {
+    /// Tax rate in basis points (one hundredth of a percent).
+    pub fn basis_points(&self) -> u32 {
+        self.basis_points
+    }
+
+    /// Whether line item prices already include the tax.
+    pub fn inclusive(&self) -> bool {
+        self.inclusive
+    }
+
+    /// Tax amount of a `net` (tax excluded) amount.
+    pub fn tax_for(&self, net: u64) -> u64 {
+        self.rounding
+            .divide(
+                u128::from(net) * u128::from(self.basis_points),
+                10_000,
+                self.step,
+            )
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Tax amount included in a `gross` (tax included) amount.
+    pub fn included_tax_for(&self, gross: u64) -> u64 {
+        let tax = self.rounding.divide(
+            u128::from(gross) * u128::from(self.basis_points),
+            10_000 + u128::from(self.basis_points),
+            self.step,
+        );
+        (tax as u64).min(gross)
+    }
+}
+
+/// Gross, net and tax figures of an [`super::Invoice`], suitable for accounting exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvoiceBreakdown {
+    /// Sum of all line items.
+    pub subtotal: u64,
+
+    /// Amount reduced by discount.
+    pub discount: u64,
+
+    /// Payable amount, excluding tax.
+    pub net: u64,
+
+    /// Tax amount.
+    pub tax: u64,
+
+    /// Payable amount, including tax.
+    pub gross: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive() {
+        let vat = Vat::builder().basis_points(1000).build();
+
+        assert_eq!(vat.tax_for(10000), 1000);
+        assert_eq!(vat.tax_for(12345), 1235);
+        assert_eq!(
+            Vat::builder()
+                .basis_points(1000)
+                .rounding(Rounding::Down)
+                .build()
+                .tax_for(12345),
+            1234
+        );
+    }
+
+    #[test]
+    fn test_inclusive() {
+        let vat = Vat::builder().basis_points(1000).inclusive(true).build();
+
+        assert_eq!(vat.included_tax_for(11000), 1000);
+        assert_eq!(vat.included_tax_for(0), 0);
+    }
+
+    #[test]
+    fn test_step() {
+        let vat = Vat::builder().basis_points(900).step(10).build();
+
+        assert_eq!(vat.tax_for(12345), 1110);
+    }
+}
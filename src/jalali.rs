@@ -0,0 +1,199 @@
+//! Jalali (Shamsi) calendar dates, as displayed to Iranian users.
+
+use std::fmt::Display;
+
+/// A date in Jalali calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JalaliDate {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl JalaliDate {
+    /// Converts a Gregorian date to Jalali calendar.
+    ///
+    /// Returns `None` if the Gregorian date is invalid.
+    ///
+    /// ```
+    /// use zarinpal::jalali::JalaliDate;
+    ///
+    /// let date = JalaliDate::from_gregorian(2024, 6, 27).unwrap();
+    /// assert_eq!(date.to_string(), "1403/04/07");
+    /// ```
+    pub fn from_gregorian(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > gregorian_month_days(year, month) {
+            return None;
+        }
+
+        const MONTH_OFFSETS: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+        let gy = year as i64;
+        let gy2 = if month > 2 { gy + 1 } else { gy };
+        let mut days = 355666 + 365 * gy + (gy2 + 3) / 4 - (gy2 + 99) / 100
+            + (gy2 + 399) / 400
+            + day as i64
+            + MONTH_OFFSETS[month as usize - 1];
+
+        let mut jy = -1595 + 33 * (days / 12053);
+        days %= 12053;
+        jy += 4 * (days / 1461);
+        days %= 1461;
+        if days > 365 {
+            jy += (days - 1) / 365;
+            days = (days - 1) % 365;
+        }
+
+        let (jm, jd) = if days < 186 {
+            (1 + days / 31, 1 + days % 31)
+        } else {
+            (7 + (days - 186) / 30, 1 + (days - 186) % 30)
+        };
+
+        Some(Self {
+            year: jy as i32,
+            month: jm as u32,
+            day: jd as u32,
+        })
+    }
+
+    /// Jalali year, like `1403`.
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Jalali month, from `1` (Farvardin) to `12` (Esfand).
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// Day of the month, from `1`.
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+}
+
+impl Display for JalaliDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}/{:02}/{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A date and time in Jalali calendar, formatted like `1403/04/07 17:33:25`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JalaliDateTime {
+    date: JalaliDate,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+impl JalaliDateTime {
+    /// Parses a Gregorian date time returned by zarinpal, like `2024-06-27 17:33:25`
+    /// or `2024-06-27T17:33:25+03:30`, keeping its local time.
+    ///
+    /// ```
+    /// use zarinpal::jalali::JalaliDateTime;
+    ///
+    /// let date = JalaliDateTime::parse_gregorian("2024-06-27 17:33:25").unwrap();
+    /// assert_eq!(date.to_string(), "1403/04/07 17:33:25");
+    /// ```
+    pub fn parse_gregorian(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let (date, time) = raw.get(..10).zip(raw.get(11..19))?;
+        if !matches!(raw.as_bytes()[10], b' ' | b'T') {
+            return None;
+        }
+
+        let mut date = date.split('-').map(str::parse::<u32>);
+        let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+        let mut time = time.split(':').map(str::parse::<u32>);
+        let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+
+        Some(Self {
+            date: JalaliDate::from_gregorian(year as i32, month, day)?,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// The date part.
+    pub fn date(&self) -> JalaliDate {
+        self.date
+    }
+
+    /// Hour, from `0` to `23`.
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    /// Minute, from `0` to `59`.
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+
+    /// Second, from `0` to `59`.
+    pub fn second(&self) -> u32 {
+        self.second
+    }
+}
+
+impl Display for JalaliDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:02}:{:02}:{:02}",
+            self.date, self.hour, self.minute, self.second
+        )
+    }
+}
+
+fn gregorian_month_days(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_gregorian() {
+        for ((gy, gm, gd), jalali) in [
+            ((2024, 3, 20), "1403/01/01"),
+            ((2024, 3, 19), "1402/12/29"),
+            ((2025, 3, 20), "1403/12/30"),
+            ((2020, 7, 1), "1399/04/11"),
+            ((2000, 2, 29), "1378/12/10"),
+        ] {
+            assert_eq!(
+                JalaliDate::from_gregorian(gy, gm, gd).unwrap().to_string(),
+                jalali
+            );
+        }
+
+        assert!(JalaliDate::from_gregorian(2023, 2, 29).is_none());
+        assert!(JalaliDate::from_gregorian(2023, 13, 1).is_none());
+    }
+
+    #[test]
+    fn test_parse_gregorian() {
+        assert_eq!(
+            JalaliDateTime::parse_gregorian("2024-08-04T16:45:28+03:30")
+                .unwrap()
+                .to_string(),
+            "1403/05/14 16:45:28"
+        );
+        assert!(JalaliDateTime::parse_gregorian("2024-08-04").is_none());
+        assert!(JalaliDateTime::parse_gregorian("2024-08-04 25:00:00").is_none());
+    }
+}
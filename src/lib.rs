@@ -3,20 +3,103 @@
 //! - Request (To start a payment process)
 //! - Verify (To verify payments)
 //! - Unverified (To fetch unverified payments)
+//! - Inquiry (To check status of a payment)
+//! - Reverse (To reverse a paid payment before it's settled)
+//!
+//! As well as a [`graphql`] client for dashboard apis that need an access token.
 //!
 //! Supports `Wages`, `Currency`, `Card pan` and other ...
 
-use error::{ApiError, ZarinResult};
-use methods::ApiMethod;
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "audit")]
+use audit::AuditSink;
+use callback::CallbackUrlPolicy;
+use environment::{Environment, GatewayUrl};
+use error::ZarinResult;
+use events::EventHandler;
+use hooks::RequestHook;
+use methods::{
+    request::{RequestDefaults, Wage},
+    ApiMethod,
+};
+use metrics::MetricsSink;
+use rate_limit::RateLimiter;
+use results::{result_code::MessageLocale, DeserializationMode};
+use retry::RetryPolicy;
+use transport::{HttpTransport, JsonRequest};
 
+pub mod amount;
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod auth;
+pub mod builder;
+pub mod callback;
+pub mod card;
+#[cfg(feature = "web")]
+pub mod checkout;
+pub mod config;
+#[cfg(feature = "chrono")]
+pub mod datetime;
+pub mod environment;
 pub mod error;
+pub mod events;
+#[cfg(feature = "invoice")]
+pub mod exchange;
+#[cfg(feature = "export")]
+pub mod export;
 pub mod extensions;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "store")]
+pub mod guard;
+pub mod hooks;
+#[cfg(any(feature = "web", feature = "receipt-render"))]
+mod html;
+pub mod iban;
+#[cfg(feature = "invoice")]
+pub mod invoice;
+#[cfg(feature = "jalali")]
+pub mod jalali;
 pub mod methods;
+pub mod metrics;
+pub mod mobile;
+pub mod multi;
+#[cfg(feature = "orm")]
+pub mod orm;
+#[cfg(feature = "otel")]
+pub mod otel;
 pub mod prelude;
+#[cfg(feature = "qrcode")]
+pub mod qr;
+pub mod rate_limit;
+#[cfg(feature = "receipt")]
+pub mod receipt;
+#[cfg(feature = "unverified")]
+pub mod reconcile;
+pub mod redact;
 pub mod results;
+pub mod retry;
+mod secret;
+pub mod self_test;
+#[cfg(feature = "sentry")]
+pub mod sentry;
+#[cfg(feature = "store")]
+pub mod session;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "testkit")]
+pub mod testing;
+mod timer;
+pub mod transport;
+#[cfg(feature = "testkit")]
+pub mod vcr;
+pub mod watcher;
+#[cfg(feature = "web")]
+pub mod web;
 
 #[cfg(test)]
-const TEST_UUID: &'static str = "0f6deacb-a130-4d23-b4ae-b1121d2764fd";
+const TEST_UUID: &str = "0f6deacb-a130-4d23-b4ae-b1121d2764fd";
 
 /// [`ZarinpalClient`] is an interface to all zarinpal payment gateway api clients.
 /// This will be useful to implement extension methods on everything that implements this.
@@ -24,33 +107,391 @@ const TEST_UUID: &'static str = "0f6deacb-a130-4d23-b4ae-b1121d2764fd";
 /// You may want to use [`Zarinpal`] to send requests!
 #[async_trait::async_trait]
 pub trait ZarinpalClient {
-    /// Inner http client that is responsible for sending requests.
-    fn client(&self) -> &reqwest::Client;
+    /// Http transport that is responsible for sending requests.
+    fn transport(&self) -> &dyn HttpTransport;
 
     /// The merchant id passed to the client.
     fn merchant_id(&self) -> &str;
 
     /// The base url for all requests.
-    fn base_url(&self) -> &reqwest::Url;
+    fn base_url(&self) -> &url::Url;
+
+    /// Base url of the payment gateway that payers are redirected to.
+    ///
+    /// Defaults to the gateway of the environment of [`ZarinpalClient::base_url`].
+    fn gateway_url(&self) -> GatewayUrl {
+        Environment::from_base_url(self.base_url()).gateway_url()
+    }
+
+    /// A named set of wages that payment requests can refer to,
+    /// using [`methods::request::RequestPaymentBuilder::wage_profile`].
+    fn wage_profile(&self, _name: &str) -> Option<&[Wage]> {
+        None
+    }
+
+    /// Defaults merged into every payment request, see [`RequestDefaults`].
+    fn request_defaults(&self) -> Option<&RequestDefaults> {
+        None
+    }
+
+    /// Rules that callback urls of payment requests are checked with, see [`CallbackUrlPolicy`].
+    ///
+    /// A default policy is used if it's `None`.
+    fn callback_url_policy(&self) -> Option<&CallbackUrlPolicy> {
+        None
+    }
+
+    /// A hook to adjust request json right before it's sent, see [`RequestHook`].
+    fn request_hook(&self) -> Option<&RequestHook> {
+        None
+    }
+
+    /// Policy to retry transient failures with, see [`RetryPolicy`].
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        None
+    }
+
+    /// Sink that receives metrics of sent requests, see [`MetricsSink`].
+    fn metrics(&self) -> Option<&dyn MetricsSink> {
+        None
+    }
+
+    /// Limiter that throttles sent requests, see [`RateLimiter`].
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        None
+    }
+
+    /// Sink that receives a record of every api call, see [`AuditSink`].
+    #[cfg(feature = "audit")]
+    fn audit_sink(&self) -> Option<&dyn AuditSink> {
+        None
+    }
+
+    /// Handler of payment lifecycle events, see [`EventHandler`].
+    fn event_handler(&self) -> Option<&dyn EventHandler> {
+        None
+    }
+
+    /// Language of [`error::ApiError`] messages returned by this client.
+    fn message_locale(&self) -> MessageLocale {
+        MessageLocale::default()
+    }
+
+    /// How strictly responses are deserialized, see [`DeserializationMode`].
+    fn deserialization_mode(&self) -> DeserializationMode {
+        DeserializationMode::default()
+    }
+
+    /// Access token of zarinpal dashboard, used for requests that go through [`graphql`] api
+    /// (like [`methods::refund::RefundPayment`]).
+    fn access_token(&self) -> Option<&str> {
+        None
+    }
+
+    /// Sends a [`graphql::GraphqlQuery`] authenticated by [`ZarinpalClient::access_token`].
+    #[cfg(feature = "graphql")]
+    async fn send_graphql<Q: graphql::GraphqlQuery + Send + Sync>(
+        &self,
+        query: Q,
+    ) -> ZarinResult<Q::Result> {
+        let access_token = self
+            .access_token()
+            .ok_or(error::Error::MissingAccessToken)?;
+
+        let deadline = query.deadline();
+        let response = graphql::send_query(
+            self.transport(),
+            graphql::GRAPHQL_ENDPOINT.parse().unwrap(),
+            access_token,
+            &query,
+        );
+        methods::with_deadline(deadline, response).await
+    }
 
     async fn send<M: ApiMethod + Send + Sync>(&self, mut method: M) -> ZarinResult<M::Result> {
+        let deadline = method.deadline();
+        let request = json_request(self, &mut method)?;
+        let mut result = send_request(self, M::PATH, request, deadline).await;
+        if let Ok(result) = &mut result {
+            method.complete(self, result);
+        }
+
+        if let Some(handler) = self.event_handler() {
+            method.emit_events(self, handler, &result);
+        }
+
+        result
+    }
+
+    /// Sends a json `body` to an endpoint that has no typed [`ApiMethod`] (yet), and returns
+    /// `data` of the response as is.
+    ///
+    /// `merchant_id` is added to `body` (if it's an object without one) and api errors are
+    /// parsed the same way typed methods do, but no retry or timeout is applied.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     let data = zarinpal
+    ///         .send_raw(
+    ///             "/pg/v4/payment/inquiry.json",
+    ///             serde_json::json!({ "authority": "A00000000000000000000000000217885159" }),
+    ///         )
+    ///         .await?;
+    ///     println!("{}", data["status"]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn send_raw(
+        &self,
+        path: &str,
+        mut body: serde_json::Value,
+    ) -> ZarinResult<serde_json::Value> {
         let mut url = self.base_url().clone();
-        url.set_path(M::PATH);
+        url.set_path(path);
+
+        if let serde_json::Value::Object(map) = &mut body {
+            map.entry("merchant_id")
+                .or_insert_with(|| self.merchant_id().into());
+        }
+        if let Some(hook) = self.request_hook() {
+            hook.call(path, &mut body);
+        }
 
-        method.set_merchant_id_if_needed(self.merchant_id().clone());
+        let response = post_raw(
+            self.transport(),
+            JsonRequest::new(url, body),
+            self.message_locale(),
+            self.deserialization_mode(),
+            None,
+        )
+        .await?;
+        parse_response(response, self.message_locale(), self.deserialization_mode())
+    }
+}
 
-        let result = self
-            .client()
-            .post(url)
-            .json(&method)
-            .send()
-            .await?
-            .json::<crate::results::__private::ApiResult<M::Result>>()
-            .await;
+/// Sends `request` to `path` as [`ZarinpalClient::send`] does: rate limited, retried and timed out
+/// per client settings, and reported to metrics, audit and tracing sinks of `zarinpal`.
+///
+/// Used for calls that are already prepared, like the ones replayed by an outbox.
+pub(crate) async fn send_request<Z, R>(
+    zarinpal: &Z,
+    path: &str,
+    request: JsonRequest,
+    deadline: Option<std::time::Instant>,
+) -> ZarinResult<R>
+where
+    Z: ZarinpalClient + Sync + ?Sized,
+    R: results::RequestResult,
+{
+    #[cfg(feature = "audit")]
+    let mut last_response = None;
+    #[cfg(feature = "audit")]
+    let keep_response = zarinpal.audit_sink().map(|_| &mut last_response);
+    #[cfg(not(feature = "audit"))]
+    let keep_response = None;
 
-        result
-            .map(|f| Into::<Result<M::Result, ApiError>>::into(f))?
-            .map_err(|e| e.into())
+    let exchange = async {
+        let response = exchange(
+            zarinpal.transport(),
+            &request,
+            zarinpal.rate_limiter(),
+            zarinpal.retry_policy(),
+            zarinpal.message_locale(),
+            zarinpal.deserialization_mode(),
+            keep_response,
+        )
+        .await?;
+        parse_response(
+            response,
+            zarinpal.message_locale(),
+            zarinpal.deserialization_mode(),
+        )
+    };
+    if let Some(metrics) = zarinpal.metrics() {
+        metrics.on_request_start(path);
+    }
+    let started = std::time::Instant::now();
+    #[cfg(feature = "audit")]
+    let timestamp = std::time::SystemTime::now();
+
+    #[cfg(feature = "otel")]
+    let otel_context = otel::start_span(path, zarinpal.base_url(), &request.body);
+    #[cfg(feature = "otel")]
+    let exchange = opentelemetry::trace::FutureExt::with_context(exchange, otel_context.clone());
+
+    let result = methods::with_deadline(deadline, exchange).await;
+
+    #[cfg(feature = "otel")]
+    otel::end_span(&otel_context, &result);
+
+    if let Some(metrics) = zarinpal.metrics() {
+        metrics.on_request_end(path, metrics::result_code(&result), started.elapsed());
+    }
+
+    #[cfg(feature = "audit")]
+    if let Some(sink) = zarinpal.audit_sink() {
+        let merchant_id = request.body["merchant_id"]
+            .as_str()
+            .unwrap_or(zarinpal.merchant_id());
+        sink.record(&audit::AuditRecord::new(
+            timestamp,
+            path,
+            merchant_id,
+            request.body.clone(),
+            last_response,
+            started.elapsed(),
+            result.as_ref().map(results::RequestResult::code),
+        ));
+    }
+
+    #[cfg(feature = "sentry")]
+    {
+        sentry::add_breadcrumb(path, &request.body, &result, started.elapsed());
+        if let Err(error) = &result {
+            sentry::report_error(path, &request.body, error);
+        }
+    }
+
+    result
+}
+
+/// The json request that `zarinpal` sends for `method`, with credentials, client settings and
+/// request hook applied.
+fn json_request<Z: ZarinpalClient + ?Sized, M: ApiMethod>(
+    zarinpal: &Z,
+    method: &mut M,
+) -> ZarinResult<JsonRequest> {
+    method.set_merchant_id_if_needed(zarinpal.merchant_id());
+    method.prepare(zarinpal)?;
+
+    let body = serde_json::to_value(&*method)?;
+    prepared_request(zarinpal, M::PATH, M::AUTH, body)
+}
+
+/// The json request that `zarinpal` sends for an already prepared `body`, with credentials and
+/// request hook applied.
+pub(crate) fn prepared_request<Z: ZarinpalClient + ?Sized>(
+    zarinpal: &Z,
+    path: &str,
+    auth: auth::Auth,
+    mut body: serde_json::Value,
+) -> ZarinResult<JsonRequest> {
+    let mut url = zarinpal.base_url().clone();
+    url.set_path(path);
+
+    if let Some(hook) = zarinpal.request_hook() {
+        hook.call(path, &mut body);
+    }
+    auth.authenticate(zarinpal, JsonRequest::new(url, body))
+}
+
+/// Posts `request` until it succeeds or [`RetryPolicy`] gives up, and returns the raw response.
+///
+/// This is the part of [`ZarinpalClient::send`] that doesn't depend on the method, kept out of
+/// its generic body so it's compiled once instead of once per method and client.
+async fn exchange(
+    transport: &dyn HttpTransport,
+    request: &JsonRequest,
+    rate_limiter: Option<&RateLimiter>,
+    retry_policy: Option<&RetryPolicy>,
+    locale: MessageLocale,
+    mode: DeserializationMode,
+    mut keep_response: Option<&mut Option<serde_json::Value>>,
+) -> ZarinResult<serde_json::Value> {
+    let mut attempt = 1;
+    loop {
+        if let Some(limiter) = rate_limiter {
+            limiter.acquire().await;
+        }
+
+        let result = post_raw(
+            transport,
+            request.clone(),
+            locale,
+            mode,
+            keep_response.as_deref_mut(),
+        )
+        .await;
+
+        if let (Err(error), Some(limiter)) = (&result, rate_limiter) {
+            limiter.observe(error);
+        }
+
+        let delay = match (&result, retry_policy) {
+            (Err(error), Some(policy))
+                if attempt < policy.max_attempts() && policy.is_retryable(error) =>
+            {
+                policy.delay(attempt)
+            }
+            _ => return result,
+        };
+
+        timer::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Posts `request` to a payment gateway endpoint once, and returns the raw response if it's
+/// not an api error.
+///
+/// The raw response is kept in `keep_response` if it's set.
+async fn post_raw(
+    transport: &dyn HttpTransport,
+    request: JsonRequest,
+    locale: MessageLocale,
+    mode: DeserializationMode,
+    keep_response: Option<&mut Option<serde_json::Value>>,
+) -> ZarinResult<serde_json::Value> {
+    let response = transport.post_json(request).await?;
+    if let Some(kept) = keep_response {
+        *kept = Some(response.clone());
+    }
+    // Either `data` or `errors` of an api response is an object, the other one is an empty list.
+    if !(response["data"].is_object() || response["errors"].is_object()) {
+        return Err(error::Error::unexpected_response(
+            None,
+            None,
+            &response.to_string(),
+        ));
+    }
+
+    // Successful responses are parsed by `parse_response`, which knows their type.
+    if response["data"].is_object() {
+        return Ok(response);
+    }
+    if let Some(Err(error)) = results::__private::deserialize_response::<
+        results::__private::ApiResult<serde::de::IgnoredAny>,
+    >(response.clone(), mode)?
+    .into_result()
+    {
+        return Err(error.with_locale(locale).into());
+    }
+    Ok(response)
+}
+
+/// Reads the result of a raw response returned by [`post_raw`].
+fn parse_response<R: serde::de::DeserializeOwned>(
+    response: serde_json::Value,
+    locale: MessageLocale,
+    mode: DeserializationMode,
+) -> ZarinResult<R> {
+    match results::__private::deserialize_response::<results::__private::ApiResult<R>>(
+        response, mode,
+    )?
+    .into_result()
+    {
+        Some(result) => result.map_err(|error| error.with_locale(locale).into()),
+        None => Err(error::Error::unexpected_response(
+            None,
+            None,
+            "response has neither data nor errors",
+        )),
     }
 }
 
@@ -59,14 +500,14 @@ pub trait ZarinpalClient {
 /// # Examples
 ///
 /// Using `.send()` method to send api requests:
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
 /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
 ///     // Merchant id from zarinpal dashboard.
 ///     let merchant_id = "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx";
-///     // The new method fails if the `merchant_id` is invalid or can't create reqwest::Client.
+///     // The new method fails if the `merchant_id` is invalid.
 ///     let zarinpal = Zarinpal::new(merchant_id)?;
 ///
 ///     let unverified = UnverifiedRequests::builder()
@@ -80,7 +521,7 @@ pub trait ZarinpalClient {
 ///
 /// ### Example 2
 /// Let's verify 10 recent unverified payment requests.
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -99,28 +540,115 @@ pub trait ZarinpalClient {
 /// ```
 #[derive(Debug, Clone)]
 pub struct Zarinpal {
-    client: reqwest::Client,
+    transport: Arc<dyn HttpTransport>,
     // merchant_id_uuid: uuid::Uuid,
-    merchant_id: String,
-    base_url: reqwest::Url,
+    merchant_id: secret::MerchantId,
+    base_url: url::Url,
+    gateway_url: Option<GatewayUrl>,
+    wage_profiles: HashMap<String, Vec<Wage>>,
+    request_defaults: Option<RequestDefaults>,
+    callback_url_policy: Option<CallbackUrlPolicy>,
+    request_hook: Option<RequestHook>,
+    retry_policy: Option<RetryPolicy>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    #[cfg(feature = "audit")]
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    access_token: Option<secret::AccessToken>,
+    message_locale: MessageLocale,
+    deserialization_mode: DeserializationMode,
 }
 
 #[async_trait::async_trait]
 impl ZarinpalClient for Zarinpal {
-    fn client(&self) -> &reqwest::Client {
-        &self.client
+    fn transport(&self) -> &dyn HttpTransport {
+        self.transport.as_ref()
     }
 
     fn merchant_id(&self) -> &str {
-        &self.merchant_id
+        self.merchant_id.expose()
     }
 
-    fn base_url(&self) -> &reqwest::Url {
+    fn base_url(&self) -> &url::Url {
         &self.base_url
     }
+
+    fn gateway_url(&self) -> GatewayUrl {
+        match &self.gateway_url {
+            Some(gateway_url) => gateway_url.clone(),
+            None => Environment::from_base_url(&self.base_url).gateway_url(),
+        }
+    }
+
+    fn wage_profile(&self, name: &str) -> Option<&[Wage]> {
+        self.wage_profiles.get(name).map(Vec::as_slice)
+    }
+
+    fn request_defaults(&self) -> Option<&RequestDefaults> {
+        self.request_defaults.as_ref()
+    }
+
+    fn callback_url_policy(&self) -> Option<&CallbackUrlPolicy> {
+        self.callback_url_policy.as_ref()
+    }
+
+    fn request_hook(&self) -> Option<&RequestHook> {
+        self.request_hook.as_ref()
+    }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    fn metrics(&self) -> Option<&dyn MetricsSink> {
+        self.metrics.as_deref()
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    #[cfg(feature = "audit")]
+    fn audit_sink(&self) -> Option<&dyn AuditSink> {
+        self.audit_sink.as_deref()
+    }
+
+    fn event_handler(&self) -> Option<&dyn EventHandler> {
+        self.event_handler.as_deref()
+    }
+
+    fn access_token(&self) -> Option<&str> {
+        self.access_token.as_ref().map(secret::AccessToken::expose)
+    }
+
+    fn message_locale(&self) -> MessageLocale {
+        self.message_locale
+    }
+
+    fn deserialization_mode(&self) -> DeserializationMode {
+        self.deserialization_mode
+    }
 }
 
 impl Zarinpal {
+    /// Creates a [`builder::ZarinpalBuilder`] to configure base url, timeout, proxy,
+    /// default headers or the inner http client in one place.
+    pub fn builder(merchant_id: impl Into<String>) -> builder::ZarinpalBuilder {
+        builder::ZarinpalBuilder::new(merchant_id)
+    }
+
+    /// Creates a client from `ZARINPAL_MERCHANT_ID`, `ZARINPAL_BASE_URL` and `ZARINPAL_SANDBOX`
+    /// environment variables, see [`config::ZarinpalConfig::from_env`].
+    pub fn from_env() -> ZarinResult<Self> {
+        config::ZarinpalConfig::from_env()?.build()
+    }
+
+    /// Creates a client with settings of `config`, loaded from a config file for instance.
+    pub fn from_config(config: config::ZarinpalConfig) -> ZarinResult<Self> {
+        config.build()
+    }
+
     /// Creates a new instance of [`Zarinpal`] client.
     ///
     /// This method will fail if `merchant_id` is not a vail uuid.
@@ -128,14 +656,9 @@ impl Zarinpal {
     /// ## Note
     /// Almost all of zarinpal requests can carry `merchant_id` with themselves (as a field).
     /// merchant id here will be replaced with `merchant_id` field in requests if it's not present
+    #[cfg(feature = "reqwest")]
     pub fn new(merchant_id: &str) -> Result<Self, uuid::Error> {
-        let merchant_id_uuid = uuid::Uuid::parse_str(merchant_id)?;
-        Ok(Self {
-            client: reqwest::Client::new(),
-            merchant_id: merchant_id_uuid.to_string(),
-            // merchant_id_uuid,
-            base_url: "https://api.zarinpal.com/".parse().unwrap(),
-        })
+        Self::new_with_transport(merchant_id, transport::ReqwestTransport::default())
     }
 
     /// Creates a new instance of [`Zarinpal`] client with custom [`reqwest::Client`]
@@ -146,21 +669,189 @@ impl Zarinpal {
     /// ## Note
     /// Almost all of zarinpal requests can carry `merchant_id` with themselves (as a field).
     /// merchant id here will be replaced with `merchant_id` field in requests if it's not present
+    #[cfg(feature = "reqwest")]
     pub fn new_with_client(
         merchant_id: &str,
         client: reqwest::Client,
+    ) -> Result<Self, uuid::Error> {
+        Self::new_with_transport(merchant_id, transport::ReqwestTransport::new(client))
+    }
+
+    /// Creates a new instance of [`Zarinpal`] client with a custom [`HttpTransport`],
+    /// to use another http client (or a test double) instead of `reqwest`.
+    ///
+    /// This method will fail if `merchant_id` is not a vail uuid.
+    pub fn new_with_transport(
+        merchant_id: &str,
+        transport: impl HttpTransport + 'static,
     ) -> Result<Self, uuid::Error> {
         let merchant_id_uuid = uuid::Uuid::parse_str(merchant_id)?;
         Ok(Self {
-            client,
-            merchant_id: merchant_id_uuid.to_string(),
+            transport: Arc::new(transport),
+            merchant_id: secret::MerchantId::new(merchant_id_uuid.to_string()),
             // merchant_id_uuid,
-            base_url: "https://api.zarinpal.com/".parse().unwrap(),
+            base_url: Environment::Production.base_url(),
+            gateway_url: None,
+            wage_profiles: HashMap::new(),
+            request_defaults: None,
+            callback_url_policy: None,
+            request_hook: None,
+            retry_policy: None,
+            metrics: None,
+            rate_limiter: None,
+            #[cfg(feature = "audit")]
+            audit_sink: None,
+            event_handler: None,
+            access_token: None,
+            message_locale: MessageLocale::default(),
+            deserialization_mode: DeserializationMode::default(),
         })
     }
 
+    /// Registers a named set of wages (Eg: `"default-marketplace-split"`), so payment requests
+    /// can refer to it using [`methods::request::RequestPaymentBuilder::wage_profile`].
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?.with_wage_profile(
+    ///         "default-marketplace-split",
+    ///         [Wage::builder()
+    ///             .iban("IR130570028780010957775103".parse()?)
+    ///             .amount(10000)
+    ///             .description("Marketplace share")
+    ///             .build()],
+    ///     );
+    ///
+    ///     let request = zarinpal
+    ///         .request_payment(10000, "https://example.com/verify".parse()?, "...")
+    ///         .wage_profile("default-marketplace-split")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_wage_profile(
+        mut self,
+        name: impl Into<String>,
+        wages: impl Into<Vec<Wage>>,
+    ) -> Self {
+        self.wage_profiles.insert(name.into(), wages.into());
+        self
+    }
+
+    /// Merges `defaults` (currency, callback url and metadata) into every payment request,
+    /// unless they're set on the request, see [`RequestDefaults`].
+    pub fn with_request_defaults(mut self, defaults: RequestDefaults) -> Self {
+        self.request_defaults = Some(defaults);
+        self
+    }
+
+    /// Checks callback urls of payment requests using `policy`, Eg: to only allow your own
+    /// domains.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    /// use zarinpal::callback::CallbackUrlPolicy;
+    ///
+    /// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    /// let zarinpal = Zarinpal::new("...")?
+    ///     .with_callback_url_policy(CallbackUrlPolicy::default().allow_domain("example.com"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_callback_url_policy(mut self, policy: CallbackUrlPolicy) -> Self {
+        self.callback_url_policy = Some(policy);
+        self
+    }
+
+    /// Sends requests to `environment`, Eg: [`Environment::Sandbox`] for testing.
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.base_url = environment.base_url();
+        self
+    }
+
+    /// Redirects payers to a custom gateway, like a proxy of the `StartPay` page.
+    ///
+    /// By default it's the gateway of [`Zarinpal::with_environment`].
+    pub fn with_gateway_url(mut self, gateway_url: GatewayUrl) -> Self {
+        self.gateway_url = Some(gateway_url);
+        self
+    }
+
+    /// Retries transient failures (network errors, http `5xx` and
+    /// [`results::result_code::ResultCode::ToManyAttempts`]) using `policy`.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Reports metrics of every sent request to `sink`, see [`MetricsSink`].
+    pub fn with_metrics(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.metrics = Some(Arc::new(sink));
+        self
+    }
+
+    /// Throttles requests using `limiter`, see [`RateLimiter`].
+    ///
+    /// Pass an `Arc<RateLimiter>` to share a limiter between several clients.
+    pub fn with_rate_limiter(mut self, limiter: impl Into<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = Some(limiter.into());
+        self
+    }
+
+    /// Sends a record of every api call to `sink`, see [`AuditSink`].
+    #[cfg(feature = "audit")]
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sends payment lifecycle events to `handler`, see [`EventHandler`].
+    pub fn with_event_handler(mut self, handler: impl EventHandler + 'static) -> Self {
+        self.event_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets access token of zarinpal dashboard, needed for [`graphql`] based requests
+    /// like [`methods::refund::RefundPayment`], and methods that declare
+    /// [`auth::Auth::AccessToken`].
+    pub fn with_access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = Some(secret::AccessToken::new(access_token.into()));
+        self
+    }
+
+    /// Formats api errors in `locale`, Eg: [`MessageLocale::Persian`] to show them to users.
+    pub fn with_message_locale(mut self, locale: MessageLocale) -> Self {
+        self.message_locale = locale;
+        self
+    }
+
+    /// Deserializes responses in `mode`, Eg: `DeserializationMode::Strict` (`strict` feature) to
+    /// fail on fields unknown to this crate when testing against the sandbox.
+    pub fn with_deserialization_mode(mut self, mode: DeserializationMode) -> Self {
+        self.deserialization_mode = mode;
+        self
+    }
+
+    /// Installs a [`RequestHook`] that can adjust request json right before it's sent.
+    pub fn with_request_hook(
+        mut self,
+        hook: impl Fn(&str, &mut serde_json::Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.request_hook = Some(RequestHook::new(hook));
+        self
+    }
+
     #[cfg(test)]
     pub(crate) fn new_test() -> Result<Zarinpal, uuid::Error> {
-        Self::new(TEST_UUID)
+        #[cfg(feature = "reqwest")]
+        return Self::new(TEST_UUID);
+
+        #[cfg(not(feature = "reqwest"))]
+        Self::new_with_transport(TEST_UUID, transport::tests::OfflineTransport)
     }
 }
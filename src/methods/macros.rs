@@ -0,0 +1,252 @@
+/// Defines an [`ApiMethod`](crate::methods::ApiMethod) for an endpoint this crate has no type for,
+/// like private or enterprise ones.
+///
+/// The generated type works just like built-in ones (say
+/// [`InquiryPayment`](crate::methods::inquiry::InquiryPayment)): it has a builder, an optional
+/// `merchant_id` filled by the client, `timeout` and `deadline` options, and can be awaited
+/// directly. Fields are sent using their names, `None` ones are left out, and (like built-in
+/// ones) it's `Serialize` and `Deserialize`, so field types must be too.
+///
+/// Endpoints that need the access token of zarinpal dashboard can declare it like
+/// `struct SettlePayment("pg/v4/payment/settle.json", auth = AccessToken)`, see
+/// [`Auth`](crate::auth::Auth).
+///
+/// The result type must implement [`RequestResult`](crate::results::RequestResult).
+/// Field types must be paths, like `String` or `Option<Vec<u64>>`.
+///
+/// ```no_run
+/// use serde::Deserialize;
+/// use zarinpal::prelude::*;
+/// use zarinpal::results::RequestResult;
+///
+/// #[derive(Debug, Deserialize)]
+/// pub struct Settle {
+///     code: ResultCode,
+///     message: String,
+///     settled: bool,
+/// }
+///
+/// impl RequestResult for Settle {
+///     fn code(&self) -> ResultCode {
+///         self.code
+///     }
+///
+///     fn message(&self) -> &str {
+///         &self.message
+///     }
+/// }
+///
+/// zarinpal::define_api_method! {
+///     /// Settles a payment, available to enterprise merchants.
+///     pub struct SettlePayment("pg/v4/payment/settle.json") -> Settle {
+///         /// The unique authority of the payment.
+///         #[builder(setter(into))]
+///         authority: String,
+///
+///         /// (Optional) Settlement note.
+///         #[builder(default, setter(strip_option, into))]
+///         note: Option<String>,
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let settle = SettlePayment::builder()
+///         .authority("A00000000000000000000000000217885159")
+///         .zarinpal(&zarinpal)
+///         .build()
+///         .await?;
+///
+///     println!("{}", settle.settled);
+///
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_api_method {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident ($path:literal $(, auth = $auth:ident)?) -> $result:ty {
+            $(
+                $(#[$field_attr:meta])*
+                $field:ident : $($field_ty:ident)::+ $(<$($field_generic:ty),+>)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, $crate::methods::__private::typed_builder::TypedBuilder)]
+        #[builder(crate_module_path = $crate::methods::__private::typed_builder)]
+        $vis struct $name<C = $crate::methods::Detached> {
+            /// (Optional) Merchant id of whoever makes the request.
+            ///
+            /// If you leave this field as `None`, the client will set it.
+            #[builder(default, setter(strip_option, into))]
+            merchant_id: ::core::option::Option<::std::string::String>,
+
+            $(
+                $(#[$field_attr])*
+                $field: $($field_ty)::+ $(<$($field_generic),+>)?,
+            )*
+
+            /// (Optional) Time limit of this request, counted from when it's sent.
+            #[builder(default, setter(strip_option))]
+            timeout: ::core::option::Option<::std::time::Duration>,
+
+            /// (Optional) Point in time this request must be completed by.
+            #[builder(default, setter(strip_option))]
+            deadline: ::core::option::Option<::std::time::Instant>,
+
+            /// The zarinpal client to send this request with, `Detached` if there's none yet.
+            #[builder(default, setter(strip_option))]
+            zarinpal: ::core::option::Option<C>,
+        }
+
+        impl<C> $crate::methods::__private::serde::Serialize for $name<C> {
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::methods::__private::serde::Serializer,
+            {
+                use $crate::methods::__private::serde::{ser::Error as _, ser::SerializeMap as _};
+
+                let mut map = serializer.serialize_map(::core::option::Option::None)?;
+                if let ::core::option::Option::Some(merchant_id) = &self.merchant_id {
+                    map.serialize_entry("merchant_id", merchant_id)?;
+                }
+                $(
+                    let value = $crate::methods::__private::serde_json::to_value(&self.$field)
+                        .map_err(S::Error::custom)?;
+                    if !value.is_null() {
+                        map.serialize_entry(::core::stringify!($field), &value)?;
+                    }
+                )*
+                map.end()
+            }
+        }
+
+        impl<'de, C> $crate::methods::__private::serde::Deserialize<'de> for $name<C> {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: $crate::methods::__private::serde::Deserializer<'de>,
+            {
+                use $crate::methods::__private::{serde::de::Error as _, serde_json};
+
+                let mut map = <serde_json::Map<::std::string::String, serde_json::Value>
+                    as $crate::methods::__private::serde::Deserialize>::deserialize(deserializer)?;
+                let mut take = |name: &str| map.remove(name).unwrap_or_default();
+
+                ::core::result::Result::Ok($name {
+                    merchant_id: serde_json::from_value(take("merchant_id"))
+                        .map_err(D::Error::custom)?,
+                    $(
+                        $field: serde_json::from_value(take(::core::stringify!($field)))
+                            .map_err(D::Error::custom)?,
+                    )*
+                    timeout: ::core::option::Option::None,
+                    deadline: ::core::option::Option::None,
+                    zarinpal: ::core::option::Option::None,
+                })
+            }
+        }
+
+        impl<'z, Z: $crate::ZarinpalClient + Sync + Send> ::core::future::IntoFuture
+            for $name<&'z Z>
+        {
+            type Output = $crate::error::ZarinResult<$result>;
+            type IntoFuture = ::core::pin::Pin<
+                ::std::boxed::Box<dyn ::core::future::Future<Output = Self::Output> + Send + 'z>,
+            >;
+
+            fn into_future(mut self) -> Self::IntoFuture {
+                match self.zarinpal.take() {
+                    ::core::option::Option::Some(zarinpal) => {
+                        $crate::ZarinpalClient::send(zarinpal, self)
+                    }
+                    ::core::option::Option::None => ::std::boxed::Box::pin(::core::future::ready(
+                        ::core::result::Result::Err($crate::error::Error::MissingClient),
+                    )),
+                }
+            }
+        }
+
+        impl<C> $name<C> {
+            /// Attaches `zarinpal` to this request, so it can be awaited.
+            pub fn send_with<Z: $crate::ZarinpalClient>(self, zarinpal: &Z) -> $name<&Z> {
+                $name {
+                    merchant_id: self.merchant_id,
+                    $($field: self.$field,)*
+                    timeout: self.timeout,
+                    deadline: self.deadline,
+                    zarinpal: ::core::option::Option::Some(zarinpal),
+                }
+            }
+        }
+
+        impl<C> $crate::methods::ApiMethod for $name<C> {
+            const PATH: &'static str = $path;
+            $(const AUTH: $crate::auth::Auth = $crate::auth::Auth::$auth;)?
+
+            type Result = $result;
+
+            fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<::std::string::String>) {
+                if self.merchant_id.is_none() {
+                    self.merchant_id = ::core::option::Option::Some(merchant_id.into())
+                }
+            }
+
+            fn deadline(&self) -> ::core::option::Option<::std::time::Instant> {
+                $crate::methods::__private::deadline(self.timeout, self.deadline)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        results::{inquiry::Inquiry, RequestResult},
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    define_api_method! {
+        /// An inquiry, defined by the macro.
+        pub struct CustomInquiry("pg/v4/payment/inquiry.json") -> Inquiry {
+            /// The unique authority of the payment.
+            #[builder(setter(into))]
+            authority: String,
+
+            /// Not sent if `None`.
+            #[builder(default, setter(strip_option))]
+            note: Option<String>,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_defined_method() {
+        // Responds with a paid inquiry, if the request is the expected one.
+        let transport = CannedTransport::new(|request| {
+            assert_eq!(request.url.path(), "/pg/v4/payment/inquiry.json");
+            assert_eq!(
+                request.body,
+                serde_json::json!({ "merchant_id": TEST_UUID, "authority": "A1" })
+            );
+            Ok(responses::inquiry("PAID"))
+        });
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+
+        let inquiry = CustomInquiry::builder()
+            .authority("A1")
+            .zarinpal(&zarinpal)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(inquiry.message(), "Success");
+
+        let detached: CustomInquiry = CustomInquiry::builder().authority("A1").build();
+        let detached: CustomInquiry =
+            serde_json::from_value(serde_json::to_value(detached).unwrap()).unwrap();
+        assert!(detached.send_with(&zarinpal).await.is_ok());
+    }
+}
@@ -1,17 +1,167 @@
 //! This module contains requests type.
 
+pub mod inquiry;
+mod macros;
+#[cfg(feature = "graphql")]
+pub mod refund;
 pub mod request;
+pub mod reverse;
+#[cfg(feature = "unverified")]
 pub mod unverified;
 pub mod verify;
 
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
 use serde::Serialize;
 
-use crate::results::RequestResult;
+use crate::{
+    auth::Auth,
+    error::{Error, ZarinResult},
+    events::EventHandler,
+    results::RequestResult,
+    ZarinpalClient,
+};
+
+/// Client slot of a request that has no client attached.
+///
+/// Method types are generic over their client, `RequestPayment` (detached) is plain data that
+/// can be stored, queued or sent over channels, and `RequestPayment<&Zarinpal>` can be awaited.
+/// Attach a client using `send_with`, or pass the request to [`ZarinpalClient::send`].
+///
+/// Text fields like descriptions and authorities are [`Cow`](std::borrow::Cow)s, so a request
+/// borrows the `&str`s it's built from for its lifetime `'a`. Build it from owned `String`s to
+/// get a `RequestPayment<'static>` that outlives them, like one moved to another task.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let inquiry: InquiryPayment = InquiryPayment::builder()
+///         .authority("A00000000000000000000000000217885159")
+///         .build();
+///
+///     let mut queue = std::collections::VecDeque::new();
+///     queue.push_back(inquiry);
+///
+///     while let Some(inquiry) = queue.pop_front() {
+///         println!("{:?}", inquiry.send_with(&zarinpal).await?.status());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Detached;
 
 pub trait ApiMethod: Serialize {
     const PATH: &'static str;
 
+    /// How this method is authenticated, see [`Auth`].
+    const AUTH: Auth = Auth::MerchantId;
+
     type Result: RequestResult;
 
     fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<String>);
+
+    /// Called right before sending the request, to fill in anything that comes from the client
+    /// (like [`ZarinpalClient::wage_profile`]).
+    fn prepare<Z: ZarinpalClient + ?Sized>(&mut self, _zarinpal: &Z) -> ZarinResult<()> {
+        Ok(())
+    }
+
+    /// Fills in parts of a successful `result` that are not in the api response, but known to
+    /// this (prepared) method or `zarinpal`.
+    ///
+    /// Called by [`ZarinpalClient::send`] before events are emitted, so results are the same
+    /// however the method is sent.
+    fn complete<Z: ZarinpalClient + ?Sized>(&self, _zarinpal: &Z, _result: &mut Self::Result) {}
+
+    /// Reports the `result` of sending this method to `handler`, see [`EventHandler`].
+    ///
+    /// Called by [`ZarinpalClient::send`] once the request is done, retries included.
+    fn emit_events<Z: ZarinpalClient + ?Sized>(
+        &self,
+        _zarinpal: &Z,
+        _handler: &dyn EventHandler,
+        _result: &ZarinResult<Self::Result>,
+    ) {
+    }
+
+    /// Point in time this request must be completed by, if any.
+    ///
+    /// Called when the request is sent, so relative timeouts start from there.
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Renders the request that `zarinpal` would send for this method as a curl command, with
+    /// merchant id, card pan and other sensitive values masked, see
+    /// [`crate::transport::JsonRequest::to_curl`].
+    ///
+    /// The request is prepared the same way it is when sent, so it fails the same way for
+    /// invalid requests. Nothing is sent.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    /// let zarinpal = Zarinpal::new("...")?;
+    ///
+    /// let inquiry: InquiryPayment = InquiryPayment::builder()
+    ///     .authority("A00000000000000000000000000217885159")
+    ///     .build();
+    ///
+    /// println!("{}", inquiry.to_curl(&zarinpal)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn to_curl<Z: ZarinpalClient + ?Sized>(&self, zarinpal: &Z) -> ZarinResult<String>
+    where
+        Self: Clone,
+    {
+        Ok(crate::json_request(zarinpal, &mut self.clone())?.to_curl())
+    }
+}
+
+/// Items used by [`crate::define_api_method`], not a public api.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    pub use serde_json;
+    pub use typed_builder;
+
+    pub fn deadline(
+        timeout: Option<std::time::Duration>,
+        deadline: Option<std::time::Instant>,
+    ) -> Option<std::time::Instant> {
+        super::deadline(timeout, deadline)
+    }
+}
+
+/// The earliest of `deadline` and `timeout` from now.
+pub(crate) fn deadline(timeout: Option<Duration>, deadline: Option<Instant>) -> Option<Instant> {
+    let from_timeout = timeout.map(|timeout| Instant::now() + timeout);
+    match (from_timeout, deadline) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Runs `future`, failing with [`Error::Timeout`] if it's not done before `deadline`.
+pub(crate) async fn with_deadline<T>(
+    deadline: Option<Instant>,
+    future: impl Future<Output = ZarinResult<T>>,
+) -> ZarinResult<T> {
+    match deadline {
+        Some(deadline) => crate::timer::timeout_at(deadline, future)
+            .await
+            .unwrap_or(Err(Error::Timeout)),
+        None => future.await,
+    }
 }
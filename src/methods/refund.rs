@@ -0,0 +1,232 @@
+use std::{
+    future::{Future, IntoFuture},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use typed_builder::{Optional, TypedBuilder};
+
+use crate::{
+    error::{Error, ZarinResult},
+    graphql::GraphqlQuery,
+    results::refund::Refund,
+    ZarinpalClient,
+};
+
+use super::Detached;
+
+/// How the refunded money is sent back to the payer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RefundMethod {
+    /// Through the next paya settlement cycle.
+    #[default]
+    Paya,
+
+    /// Instantly, to the card that paid (extra fees may apply).
+    Card,
+}
+
+/// Why a payment is refunded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RefundReason {
+    /// The payer asked for it.
+    #[default]
+    CustomerRequest,
+
+    /// The payment is made twice.
+    DuplicateTransaction,
+
+    /// The payment looks fraudulent.
+    SuspiciousTransaction,
+
+    /// Any other reason.
+    Other,
+}
+
+/// Refund (part of) a verified payment.
+///
+/// Unlike other payment methods, refunds go through zarinpal GraphQL api and need
+/// an access token, see [`crate::Zarinpal::with_access_token`].
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?.with_access_token("...");
+///
+///     let refund = zarinpal
+///         .refund_payment("385404539", 10000)
+///         .description("Out of stock")
+///         .build()
+///         .await?;
+///
+///     println!("{}", refund.id());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+pub struct RefundPayment<C = Detached> {
+    /// Session id of the verified payment, as shown in zarinpal dashboard.
+    #[builder(setter(into))]
+    session_id: String,
+
+    /// Amount to refund, up to the payment amount.
+    amount: u64,
+
+    /// (Optional) Description of the refund.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// (Optional) How the money is sent back. Defaults to [`RefundMethod::Paya`].
+    #[builder(default)]
+    method: RefundMethod,
+
+    /// (Optional) Why the payment is refunded. Defaults to [`RefundReason::CustomerRequest`].
+    #[builder(default)]
+    reason: RefundReason,
+
+    /// (Optional) Time limit of this request, counted from when it's sent.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    timeout: Option<Duration>,
+
+    /// (Optional) Point in time this request must be completed by.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    deadline: Option<Instant>,
+
+    /// The zarinpal client to send this request with, [`Detached`] if there's none yet.
+    ///
+    /// See [`RefundPayment::send_with`].
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    zarinpal: Option<C>,
+}
+
+impl<'z, Z: ZarinpalClient + Sync + Send> IntoFuture for RefundPayment<&'z Z> {
+    type Output = ZarinResult<Refund>;
+    type IntoFuture = ::core::pin::Pin<Box<dyn Future<Output = Self::Output> + Send + 'z>>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
+        match self.zarinpal.take() {
+            Some(zarinpal) => zarinpal.send_graphql(self),
+            // Built without calling `.zarinpal(...)`.
+            None => Box::pin(std::future::ready(Err(Error::MissingClient))),
+        }
+    }
+}
+
+/// Awaits the builder directly, without calling `build()` first.
+impl<'z, Z, De, Me, R, T, D, C> IntoFuture
+    for RefundPaymentBuilder<&'z Z, ((String,), (u64,), De, Me, R, T, D, C)>
+where
+    Z: ZarinpalClient + Sync + Send,
+    De: Optional<Option<String>>,
+    Me: Optional<RefundMethod>,
+    R: Optional<RefundReason>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    C: Optional<Option<&'z Z>>,
+{
+    type Output = ZarinResult<Refund>;
+    type IntoFuture = <RefundPayment<&'z Z> as IntoFuture>::IntoFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.build().into_future()
+    }
+}
+
+impl<C> RefundPayment<C> {
+    /// Attaches `zarinpal` to this request, so it can be awaited.
+    ///
+    /// Useful for requests that're built (or stored) without a client.
+    pub fn send_with<Z: ZarinpalClient>(self, zarinpal: &Z) -> RefundPayment<&Z> {
+        RefundPayment {
+            session_id: self.session_id,
+            amount: self.amount,
+            description: self.description,
+            method: self.method,
+            reason: self.reason,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            zarinpal: Some(zarinpal),
+        }
+    }
+}
+
+impl<C> GraphqlQuery for RefundPayment<C> {
+    const QUERY: &'static str = "mutation AddRefund($session_id: ID!, $amount: BigInteger!, \
+        $description: String, $method: InstantPayoutActionTypeEnum, $reason: RefundReasonEnum) { \
+        resource: AddRefund(session_id: $session_id, amount: $amount, description: $description, \
+        method: $method, reason: $reason) { \
+        terminal_id id amount timeline { refund_amount refund_time refund_status } } }";
+    const FIELD: &'static str = "resource";
+
+    type Result = Refund;
+
+    fn deadline(&self) -> Option<Instant> {
+        super::deadline(self.timeout, self.deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Zarinpal;
+
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let from_model = serde_json::to_value(
+            RefundPayment::builder()
+                .session_id("385404539")
+                .amount(20000)
+                .description("Out of stock")
+                .reason(RefundReason::DuplicateTransaction)
+                .zarinpal(&zarinpal)
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            from_model,
+            serde_json::json!({
+                "session_id": "385404539",
+                "amount": 20000,
+                "description": "Out of stock",
+                "method": "PAYA",
+                "reason": "DUPLICATE_TRANSACTION"
+            })
+        )
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        use crate::transport::tests::CannedTransport;
+
+        let transport = CannedTransport::always(serde_json::json!({ "data": {} }))
+            .with_delay(Duration::from_millis(200));
+        let zarinpal = Zarinpal::new_with_transport(crate::TEST_UUID, transport)
+            .unwrap()
+            .with_access_token("token");
+
+        let result = RefundPayment::builder()
+            .session_id("385404539")
+            .amount(20000)
+            .timeout(Duration::from_millis(20))
+            .zarinpal(&zarinpal)
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)));
+    }
+}
@@ -1,26 +1,135 @@
-use std::future::{Future, IntoFuture};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    future::{Future, IntoFuture},
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
 
 use serde::{Deserialize, Serialize};
-use typed_builder::TypedBuilder;
+use typed_builder::{Optional, TypedBuilder};
 
-use crate::{error::ZarinResult, results::request::Request, ZarinpalClient};
+use crate::{
+    amount::Amount,
+    callback::CallbackUrlPolicy,
+    card::CardPan,
+    error::{ApiError, Error, WageValidationError, ZarinResult},
+    events::EventHandler,
+    iban::Iban,
+    mobile::Mobile,
+    results::{request::Request, result_code::ResultCode},
+    ZarinpalClient,
+};
 
-use super::ApiMethod;
+use super::{ApiMethod, Detached};
 
-#[derive(Debug, Clone, Serialize, Default)]
+/// Accepted range of [`RequestPaymentBuilder::expire_in`], in seconds.
+pub const EXPIRE_IN_RANGE: RangeInclusive<u64> = 1800..=3600;
+
+/// Maximum number of wages in a payment request.
+pub const MAX_WAGES: usize = 5;
+
+/// Minimum amount of each wage, in Rials (converted to the payment currency when validated).
+pub const MIN_WAGE_RIALS: u64 = 10_000;
+
+/// Maximum length of [`RequestPayment::description`], in characters.
+pub const MAX_DESCRIPTION_LENGTH: usize = 500;
+
+/// Currency of a payment.
+///
+/// Unknown currency codes are kept in [`Currency::Other`], so that new currencies
+/// introduced by zarinpal won't break anything.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum Currency {
+    /// Iranian Rial.
     #[default]
     IRR,
+
+    /// Iranian Toman.
     IRT,
+
+    /// A currency code that's not known to this crate.
+    Other(String),
+}
+
+impl Currency {
+    /// Raw currency code as used by the api.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Currency::IRR => "IRR",
+            Currency::IRT => "IRT",
+            Currency::Other(code) => code,
+        }
+    }
+
+    /// Converts an `amount` in this currency to the `to` currency.
+    ///
+    /// Returns `None` if either currency is [`Currency::Other`], the conversion overflows
+    /// or the amount can't be represented exactly (Eg: `15` Rials in Tomans).
+    pub fn convert(&self, amount: u64, to: &Currency) -> Option<u64> {
+        match (self, to) {
+            (Currency::IRR, Currency::IRR) | (Currency::IRT, Currency::IRT) => Some(amount),
+            (Currency::IRT, Currency::IRR) => amount.checked_mul(10),
+            (Currency::IRR, Currency::IRT) if amount.is_multiple_of(10) => Some(amount / 10),
+            (Currency::Other(from), Currency::Other(to)) if from == to => Some(amount),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Currency {
+    fn from(value: &str) -> Self {
+        match value {
+            "IRR" => Currency::IRR,
+            "IRT" => Currency::IRT,
+            other => Currency::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for Currency {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "IRR" | "IRT" => value.as_str().into(),
+            _ => Currency::Other(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(String::deserialize(deserializer)?.into())
+    }
 }
 
 /// Metadata of a payment request.
-#[derive(Debug, Clone, Serialize, TypedBuilder, Default)]
+///
+/// `Debug` output masks contact info and card pan, unless `unredacted-debug` feature is enabled.
+#[derive(Clone, Serialize, Deserialize, TypedBuilder, Default)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
 pub struct Metadata {
-    /// Mobile number of payer. (Can be useful for zarinpal to save card info)
-    #[builder(default, setter(strip_option, into))]
+    /// Mobile number of payer, see [`Mobile`]. (Can be useful for zarinpal to save card info)
+    #[builder(default, setter(strip_option))]
     #[serde(skip_serializing_if = "Option::is_none")]
-    mobile: Option<String>,
+    mobile: Option<Mobile>,
 
     /// Email address of the payer.
     #[builder(default, setter(strip_option, into))]
@@ -32,33 +141,154 @@ pub struct Metadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     order_id: Option<String>,
 
-    /// Card pan to accept payment only from this card.
+    /// Card pan to accept payment only from this card, see [`CardPan`].
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card_pan: Option<CardPan>,
+
+    /// National code of the payer, to accept payment only from cards owned by them.
     #[builder(default, setter(strip_option, into))]
     #[serde(skip_serializing_if = "Option::is_none")]
-    card_pan: Option<String>,
+    national_code: Option<String>,
+
+    /// Any other metadata keys, serialized next to the known ones.
+    ///
+    /// Use [`Metadata::extra`] to send keys that aren't supported yet.
+    #[builder(default, setter(skip))]
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use crate::redact::{mask, redacted_map};
+
+        let masked = |value: &Option<String>| value.as_deref().map(|value| mask(value, 4, 4));
+        f.debug_struct("Metadata")
+            .field("mobile", &self.mobile)
+            .field("email", &masked(&self.email))
+            .field("order_id", &self.order_id)
+            .field("card_pan", &self.card_pan)
+            .field("national_code", &masked(&self.national_code))
+            .field("extra", &redacted_map(&self.extra))
+            .finish()
+    }
+}
+
+impl Metadata {
+    /// Mobile number of the payer.
+    pub fn mobile(&self) -> Option<&Mobile> {
+        self.mobile.as_ref()
+    }
+
+    /// Email address of the payer.
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Order id.
+    pub fn order_id(&self) -> Option<&str> {
+        self.order_id.as_deref()
+    }
+
+    /// Card pan that the payment is only accepted from.
+    pub fn card_pan(&self) -> Option<&CardPan> {
+        self.card_pan.as_ref()
+    }
+
+    /// National code of the payer.
+    pub fn national_code(&self) -> Option<&str> {
+        self.national_code.as_deref()
+    }
+
+    /// Adds a metadata key that's not supported by this crate (yet),
+    /// so new metadata can be sent without waiting for a release.
+    ///
+    /// ```
+    /// use zarinpal::prelude::*;
+    ///
+    /// let metadata = Metadata::builder()
+    ///     .mobile("09121234567".parse().unwrap())
+    ///     .build()
+    ///     .extra("utm_source", "newsletter");
+    ///
+    /// assert_eq!(metadata.extra_fields()["utm_source"], "newsletter");
+    /// ```
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.insert_extra(key, value);
+        self
+    }
+
+    /// Metadata keys that aren't supported by this crate.
+    pub fn extra_fields(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+
+    /// Fills fields that are not set (and extra keys that are missing) from `defaults`.
+    pub(crate) fn merge_defaults(&mut self, defaults: &Metadata) {
+        fn or_default<T: Clone>(field: &mut Option<T>, default: &Option<T>) {
+            if field.is_none() {
+                field.clone_from(default);
+            }
+        }
+
+        or_default(&mut self.mobile, &defaults.mobile);
+        or_default(&mut self.email, &defaults.email);
+        or_default(&mut self.order_id, &defaults.order_id);
+        or_default(&mut self.card_pan, &defaults.card_pan);
+        or_default(&mut self.national_code, &defaults.national_code);
+        for (key, value) in &defaults.extra {
+            self.extra
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    pub(crate) fn insert_extra(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) {
+        self.extra.insert(key.into(), value.into());
+    }
 }
 
 /// Info about a wage in payment request.
-#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+///
+/// `Debug` output masks middle digits of the iban, unless `unredacted-debug` feature is enabled.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, TypedBuilder)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
 pub struct Wage {
-    /// Shaparak iban number of the participant.
-    #[builder(setter(into))]
-    iban: String,
+    /// Shaparak iban number of the participant, see [`Iban`].
+    iban: Iban,
 
-    /// The amount for this participant.
-    amount: u64,
+    /// The amount for this participant, see [`Amount`].
+    #[builder(setter(into))]
+    amount: Amount,
 
     /// Description.
     #[builder(setter(into))]
     description: String,
 }
 
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for Wage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Wage")
+            .field("iban", &self.iban)
+            .field("amount", &self.amount)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
 /// Request a new payment.
 ///
 /// This type implements [`IntoFuture`], which means you can call `.await` directly
 /// on it when built.
 ///
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -67,7 +297,7 @@ pub struct Wage {
 ///
 ///     let built = RequestPayment::builder()
 ///         .amount(10000)
-///         .callback_url("example.com")
+///         .callback_url("https://example.com/verify")
 ///         .description("...")
 ///         // Takes a reference to your client.
 ///         .zarinpal(&zarinpal)
@@ -82,7 +312,7 @@ pub struct Wage {
 /// But you may want to use an extension method to make your life brighter.
 ///
 /// _The example below is as the same of above._
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -90,7 +320,7 @@ pub struct Wage {
 ///     let zarinpal = Zarinpal::new("...")?;
 ///
 ///     let built = zarinpal
-///         .request_payment(10000, "example.com".parse()?, "...")
+///         .request_payment(10000, "https://example.com/verify".parse()?, "...")
 ///         .build();
 ///
 ///     let result = built.await?;
@@ -98,8 +328,8 @@ pub struct Wage {
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, TypedBuilder)]
-pub struct RequestPayment<'z, Z: ZarinpalClient> {
+#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+pub struct RequestPayment<'a, C = Detached> {
     /// (Optional) Merchant id of whoever makes the payment request.
     ///
     /// If you leave this field as `None`, [`ZarinpalClient`] will set it.
@@ -111,19 +341,30 @@ pub struct RequestPayment<'z, Z: ZarinpalClient> {
     #[serde(skip_serializing_if = "Option::is_none")]
     currency: Option<Currency>,
 
-    /// Payment amount.
-    amount: u64,
+    /// Payment amount, see [`Amount`].
+    ///
+    /// If [`RequestPayment::currency`] is not set, the payment is requested in the currency of the amount.
+    #[builder(setter(into))]
+    amount: Amount,
 
     /// Callback url of the payment.
-    #[builder(setter(into))]
-    callback_url: String,
+    ///
+    /// It's checked before being sent, see [`ZarinpalClient::callback_url_policy`]. If it's
+    /// left out, the default of the client is used, see [`RequestDefaults::callback_url`].
+    #[builder(default, setter(into))]
+    #[serde(default)]
+    callback_url: Cow<'a, str>,
 
     /// Description.
+    ///
+    /// Like [`RequestPayment::callback_url`], it may borrow a `&str` to avoid allocating
+    /// a `String` for each request.
     #[builder(setter(into))]
-    description: String,
+    description: Cow<'a, str>,
 
-    /// (Optional) Metadata of the payment. (contains: `mobile`, `email`, `order_id` and `card_pan`).
+    /// (Optional) Metadata of the payment. (contains: `mobile`, `email`, `order_id`, `card_pan`, `national_code` and any extra keys).
     #[builder(default)]
+    #[serde(default)]
     metadata: Metadata,
 
     /// (Optional) Wages information.
@@ -131,38 +372,834 @@ pub struct RequestPayment<'z, Z: ZarinpalClient> {
     #[serde(skip_serializing_if = "Option::is_none")]
     wages: Option<Vec<Wage>>,
 
-    /// The zarinpal client to send this request with.
+    /// (Optional) Name of a wage profile registered on the client, see [`ZarinpalClient::wage_profile`].
+    ///
+    /// Ignored if [`RequestPayment::wages`] is set explicitly.
+    #[builder(default, setter(strip_option, into))]
     #[serde(skip_serializing)]
-    #[builder(setter(strip_option))]
-    zarinpal: Option<&'z Z>,
+    wage_profile: Option<String>,
+
+    /// (Optional) How long the payment authority stays valid, within [`EXPIRE_IN_RANGE`] seconds.
+    ///
+    /// Fails with [`Error::InvalidExpireIn`] before being sent, if it's out of the range.
+    #[builder(default, setter(strip_option))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_expire_in",
+        deserialize_with = "deserialize_expire_in"
+    )]
+    expire_in: Option<Duration>,
+
+    /// (Optional) Referrer id of zarinpal referral program.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    referrer_id: Option<String>,
+
+    /// (Optional) Sends wages as they are, without [`validate_wages`]. Defaults to `false`.
+    #[builder(default)]
+    #[serde(skip)]
+    skip_wage_validation: bool,
+
+    /// (Optional) Truncates descriptions longer than [`MAX_DESCRIPTION_LENGTH`] using
+    /// [`truncate_description`], instead of failing. Defaults to `false`.
+    #[builder(default)]
+    #[serde(skip)]
+    truncate_long_description: bool,
+
+    /// (Optional) Time limit of this request, counted from when it's sent.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    timeout: Option<Duration>,
+
+    /// (Optional) Point in time this request must be completed by.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    deadline: Option<Instant>,
+
+    /// The zarinpal client to send this request with, [`Detached`] if there's none yet.
+    ///
+    /// See [`RequestPayment::send_with`].
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    zarinpal: Option<C>,
 }
 
-impl<'z, Z: ZarinpalClient + Sync + Send> IntoFuture for RequestPayment<'z, Z> {
+impl<'a: 'z, 'z, Z: ZarinpalClient + Sync + Send> IntoFuture for RequestPayment<'a, &'z Z> {
     type Output = ZarinResult<Request>;
     type IntoFuture = ::core::pin::Pin<Box<dyn Future<Output = Self::Output> + Send + 'z>>;
 
     fn into_future(mut self) -> Self::IntoFuture {
-        let zarinpal = std::mem::take(&mut self.zarinpal).unwrap(); // Can't be none if object is built!
-        Box::pin(zarinpal.send(self))
+        match self.zarinpal.take() {
+            Some(zarinpal) => zarinpal.send(self),
+            // Built without calling `.zarinpal(...)`.
+            None => Box::pin(std::future::ready(Err(Error::MissingClient))),
+        }
     }
 }
 
-impl<'z, Z: ZarinpalClient> ApiMethod for RequestPayment<'z, Z> {
+fn deserialize_expire_in<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+}
+
+/// Awaits the builder directly, without calling `build()` first.
+impl<'a: 'z, 'z, Z, M, Cu, Cb, Me, W, Wp, E, R, S, Tr, T, D, C> IntoFuture
+    for RequestPaymentBuilder<
+        'a,
+        &'z Z,
+        (
+            M,
+            Cu,
+            (Amount,),
+            Cb,
+            (Cow<'a, str>,),
+            Me,
+            W,
+            Wp,
+            E,
+            R,
+            S,
+            Tr,
+            T,
+            D,
+            C,
+        ),
+    >
+where
+    Z: ZarinpalClient + Sync + Send,
+    M: Optional<Option<String>>,
+    Cu: Optional<Option<Currency>>,
+    Cb: Optional<Cow<'a, str>>,
+    Me: Optional<Metadata>,
+    W: Optional<Option<Vec<Wage>>>,
+    Wp: Optional<Option<String>>,
+    E: Optional<Option<Duration>>,
+    R: Optional<Option<String>>,
+    S: Optional<bool>,
+    Tr: Optional<bool>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    C: Optional<Option<&'z Z>>,
+{
+    type Output = ZarinResult<Request>;
+    type IntoFuture = <RequestPayment<'a, &'z Z> as IntoFuture>::IntoFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        match self.try_build() {
+            Ok(request) => request.into_future(),
+            Err(error) => Box::pin(std::future::ready(Err(error))),
+        }
+    }
+}
+
+impl<'a, C, M, Cu, Cb, Me, W, Wp, E, R, S, Tr, T, D, Cl>
+    RequestPaymentBuilder<
+        'a,
+        C,
+        (
+            M,
+            Cu,
+            (Amount,),
+            Cb,
+            (Cow<'a, str>,),
+            Me,
+            W,
+            Wp,
+            E,
+            R,
+            S,
+            Tr,
+            T,
+            D,
+            Cl,
+        ),
+    >
+where
+    M: Optional<Option<String>>,
+    Cu: Optional<Option<Currency>>,
+    Cb: Optional<Cow<'a, str>>,
+    Me: Optional<Metadata>,
+    W: Optional<Option<Vec<Wage>>>,
+    Wp: Optional<Option<String>>,
+    E: Optional<Option<Duration>>,
+    R: Optional<Option<String>>,
+    S: Optional<bool>,
+    Tr: Optional<bool>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    Cl: Optional<Option<C>>,
+{
+    /// Builds the request, checking its description with [`validate_description`].
+    ///
+    /// Long descriptions are truncated first if
+    /// [`RequestPaymentBuilder::truncate_long_description`] is set. Awaiting the builder directly
+    /// builds it this way.
+    ///
+    /// ```
+    /// use zarinpal::{error::Error, methods::request::RequestPayment};
+    ///
+    /// let request: Result<RequestPayment, _> = RequestPayment::builder()
+    ///     .amount(10000)
+    ///     .description(" ")
+    ///     .try_build();
+    ///
+    /// assert!(matches!(request, Err(Error::InvalidDescription(_))));
+    /// ```
+    pub fn try_build(self) -> ZarinResult<RequestPayment<'a, C>> {
+        let mut request = self.build();
+        request.check_description()?;
+        Ok(request)
+    }
+}
+
+impl<'a, C> RequestPayment<'a, C> {
+    /// Attaches `zarinpal` to this request, so it can be awaited.
+    ///
+    /// Useful for requests that're built (or stored) without a client.
+    pub fn send_with<Z: ZarinpalClient>(self, zarinpal: &Z) -> RequestPayment<'a, &Z> {
+        RequestPayment {
+            merchant_id: self.merchant_id,
+            currency: self.currency,
+            amount: self.amount,
+            callback_url: self.callback_url,
+            description: self.description,
+            metadata: self.metadata,
+            wages: self.wages,
+            wage_profile: self.wage_profile,
+            expire_in: self.expire_in,
+            referrer_id: self.referrer_id,
+            skip_wage_validation: self.skip_wage_validation,
+            truncate_long_description: self.truncate_long_description,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            zarinpal: Some(zarinpal),
+        }
+    }
+
+    /// Description of the payment.
+    pub fn description(&self) -> &str {
+        self.description.as_ref()
+    }
+
+    /// Truncates the description if it's asked to, and checks it with [`validate_description`].
+    fn check_description(&mut self) -> ZarinResult<()> {
+        if self.truncate_long_description {
+            let end = truncate_description(&self.description, MAX_DESCRIPTION_LENGTH).len();
+            match &mut self.description {
+                Cow::Borrowed(description) => *description = &description[..end],
+                Cow::Owned(description) => description.truncate(end),
+            }
+        }
+        validate_description(&self.description)
+    }
+
+    /// Metadata of the payment.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+impl<C> ApiMethod for RequestPayment<'_, C> {
     const PATH: &'static str = "pg/v4/payment/request.json";
 
     type Result = Request;
 
     fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<String>) {
-        match self.merchant_id {
-            None => self.merchant_id = Some(merchant_id.into()),
-            _ => (),
+        if self.merchant_id.is_none() {
+            self.merchant_id = Some(merchant_id.into())
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        super::deadline(self.timeout, self.deadline)
+    }
+
+    fn complete<Z: ZarinpalClient + ?Sized>(&self, zarinpal: &Z, request: &mut Request) {
+        // Prepared by now, so typed amounts are already resolved.
+        if let Ok((amount, currency)) = self.requested() {
+            request.set_requested(amount, currency);
+        }
+        request.set_gateway(zarinpal.gateway_url());
+    }
+
+    fn emit_events<Z: ZarinpalClient + ?Sized>(
+        &self,
+        _zarinpal: &Z,
+        handler: &dyn EventHandler,
+        result: &ZarinResult<Request>,
+    ) {
+        if let Ok(request) = result {
+            handler.on_payment_requested(request);
+        }
+    }
+
+    fn prepare<Z: ZarinpalClient + ?Sized>(&mut self, zarinpal: &Z) -> ZarinResult<()> {
+        if let Some(defaults) = zarinpal.request_defaults() {
+            self.apply_defaults(defaults)?;
+        }
+
+        match zarinpal.callback_url_policy() {
+            Some(policy) => policy.validate(&self.callback_url)?,
+            None => CallbackUrlPolicy::default().validate(&self.callback_url)?,
+        };
+
+        self.check_description()?;
+
+        if let Some(expire_in) = self.expire_in {
+            if !EXPIRE_IN_RANGE.contains(&expire_in.as_secs()) {
+                return Err(Error::InvalidExpireIn(format!(
+                    "expire_in must be between {} and {} seconds.",
+                    EXPIRE_IN_RANGE.start(),
+                    EXPIRE_IN_RANGE.end()
+                )));
+            }
+        }
+
+        if self.wages.is_none() {
+            if let Some(name) = &self.wage_profile {
+                let wages = zarinpal
+                    .wage_profile(name)
+                    .ok_or_else(|| Error::UnknownWageProfile(name.clone()))?;
+                self.wages = Some(wages.to_vec());
+            }
+        }
+
+        // Typed amounts are sent in the payment currency.
+        let (amount, currency) = self.requested()?;
+        if let Some(wages) = &mut self.wages {
+            for wage in wages {
+                wage.amount = wage.amount.resolve(&currency)?.into();
+            }
+        }
+        if let (Some(wages), false) = (&self.wages, self.skip_wage_validation) {
+            validate_wages(wages, amount, &currency)?;
+        }
+        if self.amount.currency().is_some() {
+            self.currency = Some(currency);
+        }
+        self.amount = amount.into();
+        Ok(())
+    }
+}
+
+/// Checks `wages` of a payment of `amount` in `currency`, before it's sent.
+///
+/// - There are at most [`MAX_WAGES`] wages.
+/// - Each wage is at least [`MIN_WAGE_RIALS`], in `currency`. This is skipped for
+///   [`Currency::Other`].
+/// - Total of wages doesn't exceed `amount`.
+///
+/// Requests are validated this way before being sent, unless
+/// [`RequestPaymentBuilder::skip_wage_validation`] is set.
+pub fn validate_wages(wages: &[Wage], amount: u64, currency: &Currency) -> ZarinResult<()> {
+    if wages.len() > MAX_WAGES {
+        return Err(WageValidationError::TooManyWages {
+            count: wages.len(),
+            max: MAX_WAGES,
+        }
+        .into());
+    }
+
+    let minimum = Currency::IRR.convert(MIN_WAGE_RIALS, currency);
+    let mut total = 0u64;
+    for (index, wage) in wages.iter().enumerate() {
+        let wage_amount = wage.amount.resolve(currency)?;
+        if let Some(minimum) = minimum.filter(|minimum| wage_amount < *minimum) {
+            return Err(WageValidationError::AmountTooSmall {
+                index,
+                amount: wage_amount,
+                minimum,
+            }
+            .into());
+        }
+        total = total.saturating_add(wage_amount);
+    }
+
+    if total > amount {
+        return Err(WageValidationError::TotalExceedsAmount { total, amount }.into());
+    }
+    Ok(())
+}
+
+/// Checks `description` of a payment request, before it's sent.
+///
+/// It must have some text (not only whitespace), no control characters except line breaks and
+/// tabs, and be at most [`MAX_DESCRIPTION_LENGTH`] characters long. Invalid descriptions fail
+/// with [`Error::InvalidDescription`].
+///
+/// ```
+/// use zarinpal::methods::request::{validate_description, MAX_DESCRIPTION_LENGTH};
+///
+/// assert!(validate_description("خرید اشتراک ماهانه").is_ok());
+/// assert!(validate_description(" ").is_err());
+/// assert!(validate_description(&"a".repeat(MAX_DESCRIPTION_LENGTH + 1)).is_err());
+/// ```
+pub fn validate_description(description: &str) -> ZarinResult<()> {
+    let invalid = Error::InvalidDescription;
+
+    if description.trim().is_empty() {
+        return Err(invalid("description must not be empty.".to_string()));
+    }
+    if description
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        return Err(invalid(
+            "description must not contain control characters.".to_string(),
+        ));
+    }
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        return Err(invalid(format!(
+            "description must be at most {MAX_DESCRIPTION_LENGTH} characters."
+        )));
+    }
+    Ok(())
+}
+
+/// Longest prefix of `description` that's at most `max_chars` characters, without splitting
+/// grapheme clusters (like a letter and its diacritics in persian text).
+///
+/// Without `unicode-segmentation` feature, it's cut at character boundaries instead.
+///
+/// ```
+/// use zarinpal::methods::request::truncate_description;
+///
+/// assert_eq!(truncate_description("پرداخت", 3), "پرد");
+/// // The diacritic (Fatha) isn't separated from its letter.
+/// # #[cfg(feature = "unicode-segmentation")]
+/// assert_eq!(truncate_description("مَن", 1), "");
+/// assert_eq!(truncate_description("short", 100), "short");
+/// ```
+#[cfg(feature = "unicode-segmentation")]
+pub fn truncate_description(description: &str, max_chars: usize) -> &str {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let mut chars = 0;
+    let mut end = 0;
+    for (start, grapheme) in description.grapheme_indices(true) {
+        chars += grapheme.chars().count();
+        if chars > max_chars {
+            break;
+        }
+        end = start + grapheme.len();
+    }
+    &description[..end]
+}
+
+/// Longest prefix of `description` that's at most `max_chars` characters.
+#[cfg(not(feature = "unicode-segmentation"))]
+pub fn truncate_description(description: &str, max_chars: usize) -> &str {
+    match description.char_indices().nth(max_chars) {
+        Some((end, _)) => &description[..end],
+        None => description,
+    }
+}
+
+fn serialize_expire_in<S>(expire_in: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match expire_in {
+        Some(expire_in) => serializer.serialize_u64(expire_in.as_secs()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl<'a, C> RequestPayment<'a, C> {
+    /// Fills currency, callback url and metadata that are not set, using `defaults`.
+    fn apply_defaults(&mut self, defaults: &RequestDefaults) -> ZarinResult<()> {
+        if self.currency.is_none() && self.amount.currency().is_none() {
+            self.currency = defaults.currency.clone();
+        }
+        self.metadata.merge_defaults(&defaults.metadata);
+        if self.callback_url.is_empty() {
+            if let Some(callback_url) = &defaults.callback_url {
+                self.callback_url = RequestDefaults::render_callback_url(
+                    callback_url,
+                    self.metadata.order_id.as_deref(),
+                )?
+                .into();
+            }
+        }
+        Ok(())
+    }
+
+    /// Amount and currency that the payment is requested with.
+    fn requested(&self) -> ZarinResult<(u64, Currency)> {
+        let currency = self
+            .currency
+            .clone()
+            .or_else(|| self.amount.currency().cloned())
+            .unwrap_or_default();
+        Ok((self.amount.resolve(&currency)?, currency))
+    }
+
+    /// Creates a reusable [`RequestTemplate`] out of this request, leaving the amount
+    /// and the client out.
+    pub fn template(&self) -> RequestTemplate {
+        RequestTemplate {
+            merchant_id: self.merchant_id.clone(),
+            currency: self.currency.clone(),
+            callback_url: self.callback_url.to_string(),
+            description: self.description.to_string(),
+            metadata: self.metadata.clone(),
+            wages: self.wages.clone(),
+            wage_shares: Vec::new(),
+            wage_profile: self.wage_profile.clone(),
+            expire_in: self.expire_in,
+            referrer_id: self.referrer_id.clone(),
         }
     }
 }
 
+/// Defaults of a client, merged into every [`RequestPayment`] it sends unless they're set on
+/// the request, see [`crate::Zarinpal::with_request_defaults`].
+///
+/// With a default callback url, checkout code only needs the amount and description.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?.with_request_defaults(
+///         RequestDefaults::builder()
+///             .currency(Currency::IRT)
+///             .callback_url("https://example.com/orders/{order_id}/verify")
+///             .metadata(Metadata::builder().email("support@example.com").build())
+///             .build(),
+///     );
+///
+///     let request = RequestPayment::builder()
+///         .amount(10000)
+///         .description("Order #1024")
+///         .metadata(Metadata::builder().order_id("1024").build())
+///         .zarinpal(&zarinpal)
+///         .build()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct RequestDefaults {
+    /// (Optional) Currency of payments whose amount is untyped and currency is not set.
+    #[builder(default, setter(strip_option))]
+    currency: Option<Currency>,
+
+    /// (Optional) Callback url of payments that don't set one.
+    ///
+    /// `{order_id}` in it is replaced with the (url encoded) order id in metadata of the
+    /// payment, which is required then.
+    #[builder(default, setter(strip_option, into))]
+    callback_url: Option<String>,
+
+    /// (Optional) Metadata merged into metadata of payments, field by field.
+    #[builder(default)]
+    metadata: Metadata,
+}
+
+impl RequestDefaults {
+    /// Default currency.
+    pub fn currency(&self) -> Option<&Currency> {
+        self.currency.as_ref()
+    }
+
+    /// Default callback url (template).
+    pub fn callback_url(&self) -> Option<&str> {
+        self.callback_url.as_deref()
+    }
+
+    /// Default metadata.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Replaces `{order_id}` placeholder of `template` with the url encoded `order_id`.
+    fn render_callback_url(template: &str, order_id: Option<&str>) -> ZarinResult<String> {
+        let order_id = order_id.map(|order_id| {
+            url::form_urlencoded::byte_serialize(order_id.as_bytes()).collect::<String>()
+        });
+        fill_order_id("callback_url", template, order_id.as_deref())
+    }
+}
+
+/// Replaces `{order_id}` placeholder of `text`, failing if it's there but `order_id` is `None`.
+fn fill_order_id(field: &str, text: &str, order_id: Option<&str>) -> ZarinResult<String> {
+    if !text.contains("{order_id}") {
+        return Ok(text.to_string());
+    }
+
+    let order_id = order_id.ok_or_else(|| {
+        ApiError::local(
+            ResultCode::Validation,
+            field,
+            format!("{field} needs an order_id in metadata."),
+        )
+    })?;
+    Ok(text.replace("{order_id}", order_id))
+}
+
+/// A reusable, client-free [`RequestPayment`] with everything fixed but the amount and order id.
+///
+/// Instantiate it per order using [`RequestTemplate::request`] (or [`RequestTemplate::with_amount`]).
+///
+/// `{order_id}` and `{amount}` placeholders in the description and callback url are filled per
+/// payment (the order id is url encoded in the callback url), and wages can be given as
+/// [`WageShare`]s of the amount.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::methods::request::WageShare;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let template = RequestTemplate::builder()
+///         .callback_url("https://example.com/orders/{order_id}/verify")
+///         .description("Subscription renewal #{order_id}")
+///         .currency(Currency::IRT)
+///         .wage_shares([WageShare::builder()
+///             .iban("IR130570028780010957775103".parse()?)
+///             .basis_points(1000) // 10%
+///             .description("Reseller share")
+///             .build()])
+///         .build();
+///
+///     let request = template.request(10000, "1024").send_with(&zarinpal).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RequestTemplate {
+    /// (Optional) Merchant id of whoever makes the payment request.
+    #[builder(default, setter(strip_option, into))]
+    merchant_id: Option<String>,
+
+    /// (Optional) Currency for the payments.
+    #[builder(default, setter(strip_option))]
+    currency: Option<Currency>,
+
+    /// Callback url of the payments, may contain `{order_id}` and `{amount}`.
+    ///
+    /// If it's left out, the default of the client is used, see [`RequestDefaults::callback_url`].
+    #[builder(default, setter(into))]
+    callback_url: String,
+
+    /// Description, may contain `{order_id}` and `{amount}`.
+    #[builder(setter(into))]
+    description: String,
+
+    /// (Optional) Metadata of the payments.
+    #[builder(default)]
+    metadata: Metadata,
+
+    /// (Optional) Wages information.
+    #[builder(default, setter(strip_option, into))]
+    wages: Option<Vec<Wage>>,
+
+    /// (Optional) Wages that are a share of each payment's amount, sent after [`RequestTemplate::wages`].
+    #[builder(default, setter(into))]
+    wage_shares: Vec<WageShare>,
+
+    /// (Optional) Name of a wage profile registered on the client.
+    #[builder(default, setter(strip_option, into))]
+    wage_profile: Option<String>,
+
+    /// (Optional) How long the payment authorities stay valid.
+    #[builder(default, setter(strip_option))]
+    expire_in: Option<Duration>,
+
+    /// (Optional) Referrer id of zarinpal referral program.
+    #[builder(default, setter(strip_option, into))]
+    referrer_id: Option<String>,
+}
+
+impl RequestTemplate {
+    /// Instantiates the template for a payment of `amount`.
+    pub fn with_amount(&self, amount: impl Into<Amount>) -> TemplatedPayment {
+        TemplatedPayment {
+            template: self.clone(),
+            amount: amount.into(),
+        }
+    }
+
+    /// Instantiates the template for a payment of `amount` for order `order_id`.
+    pub fn request(
+        &self,
+        amount: impl Into<Amount>,
+        order_id: impl Into<String>,
+    ) -> TemplatedPayment {
+        self.with_amount(amount).order_id(order_id)
+    }
+
+    /// Callback url of the payments.
+    pub fn callback_url(&self) -> &str {
+        self.callback_url.as_ref()
+    }
+
+    /// Description.
+    pub fn description(&self) -> &str {
+        self.description.as_ref()
+    }
+
+    /// Wages that are a share of each payment's amount.
+    pub fn wage_shares(&self) -> &[WageShare] {
+        &self.wage_shares
+    }
+}
+
+/// A wage that's a share of the payment amount, for [`RequestTemplate::wage_shares`].
+///
+/// The wage amount is rounded down, in the currency of the payment amount.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct WageShare {
+    /// Shaparak iban number of the participant, see [`Iban`].
+    iban: Iban,
+
+    /// Share of the participant in basis points (hundredths of a percent), `1000` is 10%.
+    basis_points: u32,
+
+    /// Description.
+    #[builder(setter(into))]
+    description: String,
+}
+
+impl WageShare {
+    /// Share of the participant in basis points.
+    pub fn basis_points(&self) -> u32 {
+        self.basis_points
+    }
+
+    /// The wage of this share of `amount`.
+    pub fn wage_of(&self, amount: &Amount) -> Wage {
+        let value = (u128::from(amount.value()) * u128::from(self.basis_points) / 10_000) as u64;
+        let amount = match amount.currency() {
+            Some(currency) => Amount::new(value, currency.clone()),
+            None => value.into(),
+        };
+
+        Wage {
+            iban: self.iban.clone(),
+            amount,
+            description: self.description.clone(),
+        }
+    }
+}
+
+/// A [`RequestTemplate`] instantiated with an amount, see [`RequestTemplate::with_amount`].
+///
+/// Fields of the template can still be overridden before sending.
+#[derive(Debug, Clone)]
+pub struct TemplatedPayment {
+    template: RequestTemplate,
+    amount: Amount,
+}
+
+impl TemplatedPayment {
+    /// Overrides callback url of the template.
+    pub fn callback_url(mut self, callback_url: impl Into<String>) -> Self {
+        self.template.callback_url = callback_url.into();
+        self
+    }
+
+    /// Overrides description of the template.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.template.description = description.into();
+        self
+    }
+
+    /// Overrides currency of the template.
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.template.currency = Some(currency);
+        self
+    }
+
+    /// Overrides metadata of the template.
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.template.metadata = metadata;
+        self
+    }
+
+    /// Sets order id in metadata of the template.
+    pub fn order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.template.metadata.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Builds a [`RequestPayment`] to be sent by `zarinpal`, filling placeholders and wage
+    /// shares of the template.
+    ///
+    /// Fails with a validation error if there's an `{order_id}` placeholder but no order id,
+    /// see [`TemplatedPayment::order_id`].
+    pub fn build<Z: ZarinpalClient>(
+        self,
+        zarinpal: &Z,
+    ) -> ZarinResult<RequestPayment<'static, &Z>> {
+        let template = self.template;
+
+        let amount = self.amount.value().to_string();
+        let order_id = template.metadata.order_id.as_deref();
+        let callback_url = RequestDefaults::render_callback_url(
+            &template.callback_url.replace("{amount}", &amount),
+            order_id,
+        )?;
+        let description = fill_order_id(
+            "description",
+            &template.description.replace("{amount}", &amount),
+            order_id,
+        )?;
+
+        let mut wages = template.wages;
+        if !template.wage_shares.is_empty() {
+            wages.get_or_insert_with(Vec::new).extend(
+                template
+                    .wage_shares
+                    .iter()
+                    .map(|share| share.wage_of(&self.amount)),
+            );
+        }
+
+        Ok(RequestPayment {
+            merchant_id: template.merchant_id,
+            currency: template.currency,
+            amount: self.amount,
+            callback_url: callback_url.into(),
+            description: description.into(),
+            metadata: template.metadata,
+            wages,
+            wage_profile: template.wage_profile,
+            expire_in: template.expire_in,
+            referrer_id: template.referrer_id,
+            skip_wage_validation: false,
+            truncate_long_description: false,
+            timeout: None,
+            deadline: None,
+            zarinpal: Some(zarinpal),
+        })
+    }
+
+    /// Sends the payment request using `zarinpal`.
+    pub async fn send_with<Z: ZarinpalClient + Sync + Send>(
+        self,
+        zarinpal: &Z,
+    ) -> ZarinResult<Request> {
+        self.build(zarinpal)?.await
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::Zarinpal;
+    use crate::{extensions::ZarinpalSendExtension, Zarinpal};
 
     use super::*;
 
@@ -182,14 +1219,14 @@ mod tests {
         });
 
         let from_model = serde_json::to_value(
-            &RequestPayment::builder()
+            RequestPayment::builder()
                 .merchant_id("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
                 .amount(1000)
                 .callback_url("http://alireza.work/verify")
                 .description("Transaction description.")
                 .metadata(
                     Metadata::builder()
-                        .mobile("09106869409")
+                        .mobile("09106869409".parse().unwrap())
                         .email("info.test@gmail.com")
                         .build(),
                 )
@@ -218,7 +1255,7 @@ mod tests {
         });
 
         let from_model = serde_json::to_value(
-            &RequestPayment::builder()
+            RequestPayment::builder()
                 .merchant_id("1344b5d4-0048-11e8-94db-005056a205be")
                 .amount(10000)
                 .callback_url("http://yoursite.com/verify")
@@ -226,7 +1263,7 @@ mod tests {
                 .description("افزایش اعتبار کاربر شماره ۱۱۳۴۶۲۹")
                 .metadata(
                     Metadata::builder()
-                        .mobile("09121234567")
+                        .mobile("09121234567".parse().unwrap())
                         .email("info.test@gmail.com")
                         .build(),
                 )
@@ -239,6 +1276,34 @@ mod tests {
         assert_eq!(raw_json, from_model)
     }
 
+    #[test]
+    fn test_currency_convert() {
+        assert_eq!(Currency::IRT.convert(1000, &Currency::IRR), Some(10000));
+        assert_eq!(Currency::IRR.convert(10000, &Currency::IRT), Some(1000));
+        assert_eq!(Currency::IRR.convert(10005, &Currency::IRT), None);
+        assert_eq!(Currency::IRR.convert(10005, &Currency::IRR), Some(10005));
+        assert_eq!(Currency::IRT.convert(u64::MAX, &Currency::IRR), None);
+        assert_eq!(
+            Currency::Other("USD".to_string()).convert(10, &Currency::IRR),
+            None
+        );
+    }
+
+    #[test]
+    fn test_currency_serde() {
+        for (currency, raw) in [
+            (Currency::IRR, "IRR"),
+            (Currency::IRT, "IRT"),
+            (Currency::Other("USD".to_string()), "USD"),
+        ] {
+            assert_eq!(serde_json::to_value(&currency).unwrap(), raw);
+            assert_eq!(
+                serde_json::from_value::<Currency>(serde_json::json!(raw)).unwrap(),
+                currency
+            );
+        }
+    }
+
     #[test]
     fn test_serialization_with_wages() {
         let zarinpal = Zarinpal::new_test().unwrap();
@@ -249,7 +1314,7 @@ mod tests {
           "callback_url": "http://yoursite.com/verify",
           "description": "Transaction description.",
           "metadata": {
-            "mobile": "091212334567",
+            "mobile": "09121234567",
             "email": "info.test@gmail.com"
           },
           "wages": [
@@ -267,25 +1332,25 @@ mod tests {
         });
 
         let from_model = serde_json::to_value(
-            &RequestPayment::builder()
+            RequestPayment::builder()
                 .merchant_id("1344b5d4-0048-11e8-94db-005056a205be")
                 .amount(20000)
                 .callback_url("http://yoursite.com/verify")
                 .description("Transaction description.")
                 .metadata(
                     Metadata::builder()
-                        .mobile("091212334567")
+                        .mobile("09121234567".parse().unwrap())
                         .email("info.test@gmail.com")
                         .build(),
                 )
                 .wages([
                     Wage::builder()
-                        .iban("IR130570028780010957775103")
+                        .iban("IR130570028780010957775103".parse().unwrap())
                         .amount(1000)
                         .description("تسهیم سود فروش از محصول به مسعود امینی")
                         .build(),
                     Wage::builder()
-                        .iban("IR670170000000352965862009")
+                        .iban("IR670170000000352965862009".parse().unwrap())
                         .amount(5000)
                         .description("تسهیم سود فروش از محصول به یوسفی")
                         .build(),
@@ -299,6 +1364,58 @@ mod tests {
         assert_eq!(raw_json, from_model)
     }
 
+    #[test]
+    fn test_wage_profile() {
+        let split = vec![Wage::builder()
+            .iban("IR130570028780010957775103".parse().unwrap())
+            .amount(10000)
+            .description("Marketplace share")
+            .build()];
+        let zarinpal = Zarinpal::new_test()
+            .unwrap()
+            .with_wage_profile("default-marketplace-split", split);
+
+        let mut from_profile = RequestPayment::builder()
+            .amount(100000)
+            .callback_url("http://yoursite.com/verify")
+            .description("Transaction description.")
+            .wage_profile("default-marketplace-split")
+            .zarinpal(&zarinpal)
+            .build();
+        from_profile.prepare(&zarinpal).unwrap();
+        assert_eq!(
+            from_profile.wages.as_ref().unwrap()[0].amount.value(),
+            10000
+        );
+
+        let mut overridden = RequestPayment::builder()
+            .amount(100000)
+            .callback_url("http://yoursite.com/verify")
+            .description("Transaction description.")
+            .wages([Wage::builder()
+                .iban("IR670170000000352965862009".parse().unwrap())
+                .amount(50000)
+                .description("Override")
+                .build()])
+            .wage_profile("default-marketplace-split")
+            .zarinpal(&zarinpal)
+            .build();
+        overridden.prepare(&zarinpal).unwrap();
+        assert_eq!(overridden.wages.as_ref().unwrap()[0].amount.value(), 50000);
+
+        let mut unknown = RequestPayment::builder()
+            .amount(10000)
+            .callback_url("http://yoursite.com/verify")
+            .description("Transaction description.")
+            .wage_profile("missing")
+            .zarinpal(&zarinpal)
+            .build();
+        assert!(matches!(
+            unknown.prepare(&zarinpal),
+            Err(Error::UnknownWageProfile(name)) if name == "missing"
+        ));
+    }
+
     #[test]
     fn test_serialization_with_card_pan() {
         let zarinpal = Zarinpal::new_test().unwrap();
@@ -316,16 +1433,16 @@ mod tests {
         });
 
         let from_model = serde_json::to_value(
-            &RequestPayment::builder()
+            RequestPayment::builder()
                 .merchant_id("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
                 .amount(10000)
                 .callback_url("http://yoursite.com/verify")
                 .description("پرداخت تست ۱۱۰")
                 .metadata(
                     Metadata::builder()
-                        .mobile("09121234567")
+                        .mobile("09121234567".parse().unwrap())
                         .email("info.test@gmail.com")
-                        .card_pan("5022291083818920")
+                        .card_pan("5022291083818920".parse().unwrap())
                         .build(),
                 )
                 .zarinpal(&zarinpal)
@@ -336,4 +1453,511 @@ mod tests {
         // DO NOT test using string representing, since field ordering are different.
         assert_eq!(raw_json, from_model)
     }
+
+    #[test]
+    fn test_template() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let template = RequestPayment::builder()
+            .merchant_id("1344b5d4-0048-11e8-94db-005056a205be")
+            .amount(0)
+            .callback_url("http://yoursite.com/verify")
+            .description("Wallet charge")
+            .currency(Currency::IRT)
+            .zarinpal(&zarinpal)
+            .build()
+            .template();
+
+        let from_template = serde_json::to_value(
+            template
+                .with_amount(10000)
+                .order_id("1024")
+                .description("Wallet charge #1024")
+                .build(&zarinpal)
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            from_template,
+            serde_json::json!({
+              "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+              "currency": "IRT",
+              "amount": 10000,
+              "callback_url": "http://yoursite.com/verify",
+              "description": "Wallet charge #1024",
+              "metadata": {"order_id": "1024"}
+            })
+        );
+
+        // The template itself is left untouched.
+        assert_eq!(template.description(), "Wallet charge");
+    }
+
+    #[test]
+    fn test_template_request() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let template = RequestTemplate::builder()
+            .callback_url("https://example.com/orders/{order_id}/verify")
+            .description("Renewal #{order_id} ({amount} Tomans)")
+            .currency(Currency::IRT)
+            .wage_shares([WageShare::builder()
+                .iban("IR130570028780010957775103".parse().unwrap())
+                .basis_points(1250)
+                .description("Reseller share")
+                .build()])
+            .build();
+
+        let from_template = serde_json::to_value(
+            template
+                .request(Amount::tomans(10001), "A/1")
+                .build(&zarinpal)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            from_template,
+            serde_json::json!({
+              "merchant_id": null,
+              "currency": "IRT",
+              "amount": 10001,
+              "callback_url": "https://example.com/orders/A%2F1/verify",
+              "description": "Renewal #A/1 (10001 Tomans)",
+              "metadata": {"order_id": "A/1"},
+              "wages": [{
+                "iban": "IR130570028780010957775103",
+                "amount": 1250,
+                "description": "Reseller share"
+              }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_fills_requested() {
+        use crate::{
+            environment::Environment,
+            transport::tests::{responses, CannedTransport},
+            TEST_UUID,
+        };
+
+        let zarinpal = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::request("A1")),
+        )
+        .unwrap()
+        .with_environment(Environment::Sandbox);
+        let payment: RequestPayment = RequestPayment::builder()
+            .amount(Amount::tomans(1000))
+            .callback_url("https://example.com/verify")
+            .description("Order")
+            .build();
+
+        // The same however the payment is sent.
+        let sent = zarinpal.send(payment.clone()).await.unwrap();
+        let awaited = payment.clone().send_with(&zarinpal).await.unwrap();
+        assert_eq!(sent, awaited);
+        assert_eq!(sent.amount(), 1000);
+        assert_eq!(sent.currency(), &Currency::IRT);
+        assert_eq!(
+            sent.gateway_url(),
+            Environment::Sandbox
+                .gateway_url()
+                .payment_url("A1", &crate::results::request::GatewayKind::Default)
+        );
+    }
+
+    #[test]
+    fn test_template_without_order_id() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let template = RequestTemplate::builder()
+            .callback_url("https://example.com/orders/{order_id}/verify")
+            .description("Renewal")
+            .build();
+        let error = template.with_amount(10000).build(&zarinpal).unwrap_err();
+        assert!(error
+            .as_api_error()
+            .unwrap()
+            .validations()
+            .contains_key("callback_url"));
+
+        let template = RequestTemplate::builder()
+            .callback_url("https://example.com/verify")
+            .description("Renewal #{order_id}")
+            .build();
+        let error = template.with_amount(10000).build(&zarinpal).unwrap_err();
+        assert!(error
+            .as_api_error()
+            .unwrap()
+            .validations()
+            .contains_key("description"));
+        assert!(template.request(10000, "1024").build(&zarinpal).is_ok());
+    }
+
+    #[test]
+    fn test_to_curl() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+        let request: RequestPayment = RequestPayment::builder()
+            .amount(10000)
+            .callback_url("https://example.com/verify")
+            .description("Payer's order")
+            .metadata(
+                Metadata::builder()
+                    .card_pan("5022291083818920".parse().unwrap())
+                    .build(),
+            )
+            .build();
+
+        let curl = request.to_curl(&zarinpal).unwrap();
+        assert!(curl.starts_with(
+            "curl -X POST 'https://api.zarinpal.com/pg/v4/payment/request.json' \\\n"
+        ));
+        assert!(curl.contains(r#""description":"Payer'\''s order""#));
+        assert!(curl.contains("5022********8920"));
+        assert!(!curl.contains("5022291083818920"));
+        assert!(!curl.contains(zarinpal.merchant_id()));
+
+        // Invalid requests fail the same way they would when sent.
+        let request: RequestPayment = RequestPayment::builder()
+            .amount(10000)
+            .callback_url("/verify")
+            .description("Order")
+            .build();
+        assert!(request.to_curl(&zarinpal).is_err());
+    }
+
+    #[test]
+    fn test_expire_in() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let mut request = RequestPayment::builder()
+            .merchant_id("1344b5d4-0048-11e8-94db-005056a205be")
+            .amount(10000)
+            .callback_url("http://yoursite.com/verify")
+            .description("Wallet charge")
+            .expire_in(Duration::from_secs(1800))
+            .zarinpal(&zarinpal)
+            .build();
+
+        assert!(request.prepare(&zarinpal).is_ok());
+        assert_eq!(serde_json::to_value(&request).unwrap()["expire_in"], 1800);
+
+        request.expire_in = Some(Duration::from_secs(60));
+        assert!(matches!(
+            request.prepare(&zarinpal),
+            Err(Error::InvalidExpireIn(_))
+        ));
+    }
+
+    #[test]
+    fn test_typed_amount() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let mut request = RequestPayment::builder()
+            .amount(Amount::tomans(2000))
+            .callback_url("http://yoursite.com/verify")
+            .description("Wallet charge")
+            .wages([Wage::builder()
+                .iban("IR130570028780010957775103".parse().unwrap())
+                .amount(Amount::rials(15000))
+                .description("Share")
+                .build()])
+            .zarinpal(&zarinpal)
+            .build();
+        request.prepare(&zarinpal).unwrap();
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["currency"], "IRT");
+        assert_eq!(json["amount"], 2000);
+        assert_eq!(json["wages"][0]["amount"], 1500);
+
+        let mut request = RequestPayment::builder()
+            .currency(Currency::IRT)
+            .amount(Amount::rials(10005))
+            .callback_url("http://yoursite.com/verify")
+            .description("Wallet charge")
+            .zarinpal(&zarinpal)
+            .build();
+        assert!(matches!(
+            request.prepare(&zarinpal),
+            Err(Error::CurrencyConversionError { amount: 10005, .. })
+        ));
+    }
+
+    #[test]
+    fn test_wage_validation() {
+        let wage = |iban: &str, amount: u64| {
+            Wage::builder()
+                .iban(iban.parse().unwrap())
+                .amount(amount)
+                .description("Share")
+                .build()
+        };
+        let valid = "IR130570028780010957775103";
+
+        assert!(validate_wages(&[wage(valid, 10000)], 20000, &Currency::IRR).is_ok());
+        assert!(validate_wages(
+            &[wage("ir13 0570 0287 8001 0957 7751 03", 1000)],
+            2000,
+            &Currency::IRT
+        )
+        .is_ok());
+
+        let invalid = |wages: &[Wage], amount: u64, currency: &Currency| match validate_wages(
+            wages, amount, currency,
+        ) {
+            Err(Error::InvalidWages(error)) => error,
+            other => panic!("expected invalid wages, got {other:?}"),
+        };
+        assert_eq!(
+            invalid(
+                &[wage(valid, 10000), wage(valid, 999)],
+                20000,
+                &Currency::IRT
+            ),
+            WageValidationError::AmountTooSmall {
+                index: 1,
+                amount: 999,
+                minimum: 1000
+            }
+        );
+        assert_eq!(
+            invalid(
+                &[wage(valid, 10000), wage(valid, 15000)],
+                20000,
+                &Currency::IRR
+            ),
+            WageValidationError::TotalExceedsAmount {
+                total: 25000,
+                amount: 20000
+            }
+        );
+        assert_eq!(
+            invalid(&vec![wage(valid, 10000); 6], 100000, &Currency::IRR),
+            WageValidationError::TooManyWages { count: 6, max: 5 }
+        );
+
+        let zarinpal = Zarinpal::new_test().unwrap();
+        let request = || {
+            RequestPayment::builder()
+                .amount(10000)
+                .callback_url("http://yoursite.com/verify")
+                .description("Transaction description.")
+                .wages([wage(valid, 1000)])
+        };
+        assert!(matches!(
+            request().zarinpal(&zarinpal).build().prepare(&zarinpal),
+            Err(Error::InvalidWages(_))
+        ));
+        assert!(request()
+            .skip_wage_validation(true)
+            .zarinpal(&zarinpal)
+            .build()
+            .prepare(&zarinpal)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_callback_url_validation() {
+        let request = |callback_url: &'static str| -> RequestPayment<'static> {
+            RequestPayment::builder()
+                .amount(10000)
+                .callback_url(callback_url)
+                .description("Transaction description.")
+                .build()
+        };
+        let invalid = |result: ZarinResult<()>| matches!(result, Err(Error::InvalidCallbackUrl(_)));
+
+        let zarinpal = Zarinpal::new_test().unwrap();
+        assert!(request("https://shop.com/verify")
+            .prepare(&zarinpal)
+            .is_ok());
+        assert!(invalid(request("shop.com/verify").prepare(&zarinpal)));
+
+        let zarinpal = zarinpal
+            .with_callback_url_policy(CallbackUrlPolicy::default().allow_domain("example.com"));
+        assert!(invalid(
+            request("https://shop.com/verify").prepare(&zarinpal)
+        ));
+        assert!(request("https://example.com/verify")
+            .prepare(&zarinpal)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_description_validation() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+        let long = "پرداخت ".repeat(100);
+        fn request(description: &str) -> RequestPayment<'_> {
+            RequestPayment::builder()
+                .amount(10000)
+                .callback_url("https://example.com/verify")
+                .description(description)
+                .build()
+        }
+
+        assert!(matches!(
+            request(&long).prepare(&zarinpal),
+            Err(Error::InvalidDescription(_))
+        ));
+        assert!(request("Bad\u{0}description").prepare(&zarinpal).is_err());
+        assert!(request("Two\nlines").prepare(&zarinpal).is_ok());
+
+        // Checked when the request is built.
+        let builder = || {
+            RequestPayment::builder()
+                .amount(10000)
+                .callback_url("https://example.com/verify")
+                .description(long.as_str())
+        };
+        assert!(matches!(
+            builder().try_build(),
+            Err(Error::InvalidDescription(_))
+        ));
+
+        let truncated = builder()
+            .truncate_long_description(true)
+            .zarinpal(&zarinpal)
+            .try_build()
+            .unwrap();
+        assert_eq!(
+            truncated.description.chars().count(),
+            MAX_DESCRIPTION_LENGTH
+        );
+        assert!(long.starts_with(&*truncated.description));
+        // Borrowed descriptions are truncated without copying them.
+        assert!(matches!(truncated.description, Cow::Borrowed(_)));
+
+        // Combining marks stay with their letter.
+        #[cfg(feature = "unicode-segmentation")]
+        assert_eq!(truncate_description("e\u{301}e\u{301}", 3), "e\u{301}");
+        #[cfg(not(feature = "unicode-segmentation"))]
+        assert_eq!(truncate_description("e\u{301}e\u{301}", 3), "e\u{301}e");
+    }
+
+    #[test]
+    fn test_request_defaults() {
+        let zarinpal = Zarinpal::new_test().unwrap().with_request_defaults(
+            RequestDefaults::builder()
+                .currency(Currency::IRT)
+                .callback_url("https://example.com/orders/{order_id}/verify")
+                .metadata(
+                    Metadata::builder()
+                        .email("support@example.com")
+                        .order_id("default")
+                        .build(),
+                )
+                .build(),
+        );
+
+        let mut request: RequestPayment = RequestPayment::builder()
+            .amount(10000)
+            .description("Order")
+            .metadata(Metadata::builder().order_id("10/24").build())
+            .build();
+        request.prepare(&zarinpal).unwrap();
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            body["callback_url"],
+            "https://example.com/orders/10%2F24/verify"
+        );
+        assert_eq!(body["currency"], "IRT");
+        assert_eq!(
+            body["metadata"],
+            serde_json::json!({ "email": "support@example.com", "order_id": "10/24" })
+        );
+
+        // Set fields are kept.
+        let mut request: RequestPayment = RequestPayment::builder()
+            .amount(Amount::rials(10000))
+            .callback_url("https://shop.com/verify")
+            .description("Order")
+            .build();
+        request.prepare(&zarinpal).unwrap();
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["callback_url"], "https://shop.com/verify");
+        assert_eq!(body["currency"], "IRR");
+        assert_eq!(body["metadata"]["order_id"], "default");
+
+        let zarinpal = Zarinpal::new_test().unwrap().with_request_defaults(
+            RequestDefaults::builder()
+                .callback_url("https://example.com/orders/{order_id}/verify")
+                .build(),
+        );
+        let mut request: RequestPayment = RequestPayment::builder()
+            .amount(10000)
+            .description("Order")
+            .build();
+        assert!(request.prepare(&zarinpal).is_err());
+    }
+
+    #[test]
+    fn test_serialization_with_referrer_id() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let raw_json = serde_json::json!({
+          "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+          "amount": 10000,
+          "callback_url": "http://yoursite.com/verify",
+          "description": "Wallet charge",
+          "metadata": {},
+          "referrer_id": "ZP.QF2N"
+        });
+
+        let from_model = serde_json::to_value(
+            zarinpal
+                .request_payment(
+                    10000,
+                    "http://yoursite.com/verify".parse().unwrap(),
+                    "Wallet charge",
+                )
+                .merchant_id("1344b5d4-0048-11e8-94db-005056a205be")
+                .referrer_id("ZP.QF2N")
+                .build(),
+        )
+        .unwrap();
+
+        // DO NOT test using string representing, since field ordering are different.
+        assert_eq!(raw_json, from_model)
+    }
+
+    #[test]
+    fn test_metadata_extra() {
+        let metadata = Metadata::builder()
+            .mobile("09121234567".parse().unwrap())
+            .national_code("0012345678")
+            .build()
+            .extra("utm_source", "newsletter")
+            .extra("items", 3);
+
+        assert_eq!(
+            serde_json::to_value(&metadata).unwrap(),
+            serde_json::json!({
+                "mobile": "09121234567",
+                "national_code": "0012345678",
+                "utm_source": "newsletter",
+                "items": 3
+            })
+        );
+    }
+
+    #[cfg(not(feature = "unredacted-debug"))]
+    #[test]
+    fn test_metadata_redacted_debug() {
+        let metadata = Metadata::builder()
+            .mobile("09121234567".parse().unwrap())
+            .card_pan("5022291083818920".parse().unwrap())
+            .order_id("1024")
+            .build()
+            .extra("email", "payer@example.com");
+
+        let debug = format!("{metadata:?}");
+        assert!(debug.contains("0912***4567"));
+        assert!(debug.contains("502229******8920"));
+        assert!(debug.contains("1024"));
+        assert!(!debug.contains("09121234567"));
+        assert!(!debug.contains("payer@example.com"));
+    }
 }
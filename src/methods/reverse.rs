@@ -0,0 +1,166 @@
+use std::{
+    borrow::Cow,
+    future::{Future, IntoFuture},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use typed_builder::{Optional, TypedBuilder};
+
+use crate::{
+    error::{Error, ZarinResult},
+    results::reverse::Reverse,
+    ZarinpalClient,
+};
+
+use super::{ApiMethod, Detached};
+
+/// Reverse a paid payment request, so it's not settled and the money goes back to the payer.
+///
+/// Only payments that are paid within the last 30 minutes can be reversed,
+/// and the terminal must have ip restriction enabled.
+///
+/// This type implements [`IntoFuture`], which means you can call `.await` directly
+/// on it when built.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let reverse = zarinpal
+///         .reverse_payment("A00000000000000000000000000217885159")
+///         .build()
+///         .await?;
+///
+///     println!("{}", reverse.message());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+pub struct ReversePayment<'a, C = Detached> {
+    /// (Optional) Merchant id of whoever makes the payment request.
+    ///
+    /// If you leave this field as `None`, [`ZarinpalClient`] will set it.
+    #[builder(default, setter(strip_option, into))]
+    merchant_id: Option<String>,
+
+    /// The unique authority of the payment to reverse.
+    #[builder(setter(into))]
+    authority: Cow<'a, str>,
+
+    /// (Optional) Time limit of this request, counted from when it's sent.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    timeout: Option<Duration>,
+
+    /// (Optional) Point in time this request must be completed by.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    deadline: Option<Instant>,
+
+    /// The zarinpal client to send this request with, [`Detached`] if there's none yet.
+    ///
+    /// See [`ReversePayment::send_with`].
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    zarinpal: Option<C>,
+}
+
+impl<'a: 'z, 'z, Z: ZarinpalClient + Sync + Send> IntoFuture for ReversePayment<'a, &'z Z> {
+    type Output = ZarinResult<Reverse>;
+    type IntoFuture = ::core::pin::Pin<Box<dyn Future<Output = Self::Output> + Send + 'z>>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
+        match self.zarinpal.take() {
+            Some(zarinpal) => zarinpal.send(self),
+            // Built without calling `.zarinpal(...)`.
+            None => Box::pin(std::future::ready(Err(Error::MissingClient))),
+        }
+    }
+}
+
+/// Awaits the builder directly, without calling `build()` first.
+impl<'a: 'z, 'z, Z, M, T, D, C> IntoFuture
+    for ReversePaymentBuilder<'a, &'z Z, (M, (Cow<'a, str>,), T, D, C)>
+where
+    Z: ZarinpalClient + Sync + Send,
+    M: Optional<Option<String>>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    C: Optional<Option<&'z Z>>,
+{
+    type Output = ZarinResult<Reverse>;
+    type IntoFuture = <ReversePayment<'a, &'z Z> as IntoFuture>::IntoFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.build().into_future()
+    }
+}
+
+impl<'a, C> ReversePayment<'a, C> {
+    /// Attaches `zarinpal` to this request, so it can be awaited.
+    ///
+    /// Useful for requests that're built (or stored) without a client.
+    pub fn send_with<Z: ZarinpalClient>(self, zarinpal: &Z) -> ReversePayment<'a, &Z> {
+        ReversePayment {
+            merchant_id: self.merchant_id,
+            authority: self.authority,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            zarinpal: Some(zarinpal),
+        }
+    }
+}
+
+impl<C> ApiMethod for ReversePayment<'_, C> {
+    const PATH: &'static str = "pg/v4/payment/reverse.json";
+
+    type Result = Reverse;
+
+    fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<String>) {
+        if self.merchant_id.is_none() {
+            self.merchant_id = Some(merchant_id.into())
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        super::deadline(self.timeout, self.deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Zarinpal;
+
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let zarinpal = Zarinpal::new_test().unwrap();
+
+        let raw_json = serde_json::json!({
+            "merchant_id": "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx",
+            "authority": "A00000000000000000000000000217885159"
+        });
+
+        let from_model = serde_json::to_value(
+            ReversePayment::builder()
+                .merchant_id("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
+                .authority("A00000000000000000000000000217885159")
+                .zarinpal(&zarinpal)
+                .build(),
+        )
+        .unwrap();
+
+        // DO NOT test using string representing, since field ordering are different.
+        assert_eq!(raw_json, from_model)
+    }
+}
@@ -1,18 +1,25 @@
-use std::future::{Future, IntoFuture};
+use std::{
+    future::{Future, IntoFuture},
+    time::{Duration, Instant},
+};
 
-use serde::Serialize;
-use typed_builder::TypedBuilder;
+use serde::{Deserialize, Serialize};
+use typed_builder::{Optional, TypedBuilder};
 
-use crate::{error::ZarinResult, results::unverified::Unverified, ZarinpalClient};
+use crate::{
+    error::{Error, ZarinResult},
+    results::unverified::Unverified,
+    ZarinpalClient,
+};
 
-use super::ApiMethod;
+use super::{ApiMethod, Detached};
 
 /// Get 100 recent unverified payment requests..
 ///
 /// This type implements [`IntoFuture`], which means you can call `.await` directly
 /// on it when built.
 ///
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -33,7 +40,7 @@ use super::ApiMethod;
 /// But you may want to use an extension method to make your life brighter.
 ///
 /// _The example below is as the same of above._
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -46,39 +53,92 @@ use super::ApiMethod;
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, TypedBuilder)]
-pub struct UnverifiedRequests<'z, Z: ZarinpalClient> {
+#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+pub struct UnverifiedRequests<C = Detached> {
     /// (Optional) Merchant id of whoever makes the payment request.
     ///
     /// If you leave this field as `None`, [`ZarinpalClient`] will set it.
     #[builder(default, setter(strip_option, into))]
     merchant_id: Option<String>,
 
-    /// The zarinpal client to send this request with.
-    #[serde(skip_serializing)]
-    #[builder(setter(strip_option))]
-    zarinpal: Option<&'z Z>,
+    /// (Optional) Time limit of this request, counted from when it's sent.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    timeout: Option<Duration>,
+
+    /// (Optional) Point in time this request must be completed by.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    deadline: Option<Instant>,
+
+    /// The zarinpal client to send this request with, [`Detached`] if there's none yet.
+    ///
+    /// See [`UnverifiedRequests::send_with`].
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    zarinpal: Option<C>,
 }
 
-impl<'z, Z: ZarinpalClient + Sync + Send> IntoFuture for UnverifiedRequests<'z, Z> {
+impl<'z, Z: ZarinpalClient + Sync + Send> IntoFuture for UnverifiedRequests<&'z Z> {
     type Output = ZarinResult<Unverified>;
     type IntoFuture = ::core::pin::Pin<Box<dyn Future<Output = Self::Output> + Send + 'z>>;
 
     fn into_future(mut self) -> Self::IntoFuture {
-        let zarinpal = std::mem::take(&mut self.zarinpal).unwrap(); // Can't be none if object is built!
-        Box::pin(zarinpal.send(self))
+        match self.zarinpal.take() {
+            Some(zarinpal) => zarinpal.send(self),
+            // Built without calling `.zarinpal(...)`.
+            None => Box::pin(std::future::ready(Err(Error::MissingClient))),
+        }
+    }
+}
+
+/// Awaits the builder directly, without calling `build()` first.
+impl<'z, Z, M, T, D, C> IntoFuture for UnverifiedRequestsBuilder<&'z Z, (M, T, D, C)>
+where
+    Z: ZarinpalClient + Sync + Send,
+    M: Optional<Option<String>>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    C: Optional<Option<&'z Z>>,
+{
+    type Output = ZarinResult<Unverified>;
+    type IntoFuture = <UnverifiedRequests<&'z Z> as IntoFuture>::IntoFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.build().into_future()
+    }
+}
+
+impl<C> UnverifiedRequests<C> {
+    /// Attaches `zarinpal` to this request, so it can be awaited.
+    ///
+    /// Useful for requests that're built (or stored) without a client.
+    pub fn send_with<Z: ZarinpalClient>(self, zarinpal: &Z) -> UnverifiedRequests<&Z> {
+        UnverifiedRequests {
+            merchant_id: self.merchant_id,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            zarinpal: Some(zarinpal),
+        }
     }
 }
 
-impl<'z, Z: ZarinpalClient> ApiMethod for UnverifiedRequests<'z, Z> {
+impl<C> ApiMethod for UnverifiedRequests<C> {
     const PATH: &'static str = "pg/v4/payment/unVerified.json";
 
     type Result = Unverified;
 
     fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<String>) {
-        match self.merchant_id {
-            None => self.merchant_id = Some(merchant_id.into()),
-            _ => (),
+        if self.merchant_id.is_none() {
+            self.merchant_id = Some(merchant_id.into())
         }
     }
+
+    fn deadline(&self) -> Option<Instant> {
+        super::deadline(self.timeout, self.deadline)
+    }
 }
@@ -1,18 +1,29 @@
-use std::future::{Future, IntoFuture};
+use std::{
+    borrow::Cow,
+    future::{Future, IntoFuture},
+    time::{Duration, Instant},
+};
 
-use serde::Serialize;
-use typed_builder::TypedBuilder;
+use serde::{Deserialize, Serialize};
+use typed_builder::{Optional, TypedBuilder};
 
-use crate::{error::ZarinResult, results::verify::Verify, ZarinpalClient};
+use crate::{
+    amount::Amount,
+    error::{Error, ZarinResult},
+    events::EventHandler,
+    methods::request::Currency,
+    results::verify::{Verify, VerifyOutcome},
+    ZarinpalClient,
+};
 
-use super::ApiMethod;
+use super::{ApiMethod, Detached};
 
 /// Verify a payment request.
 ///
 /// This type implements [`IntoFuture`], which means you can call `.await` directly
 /// on it when built.
 ///
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
@@ -35,69 +46,193 @@ use super::ApiMethod;
 /// But you may want to use an extension method to make your life brighter.
 ///
 /// _The example below is as the same of above._
-/// ```
+/// ```no_run
 /// use zarinpal::prelude::*;
 ///
 /// #[tokio::main]
 /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
 ///     let zarinpal = Zarinpal::new("...")?;
 ///
-///     let built = zarinpal
+///     // Builders can be awaited too, without calling `build()`.
+///     let result = zarinpal
 ///         .verify_payment("A00000000000000000000000000217885159", 10000)
-///         .build();
+///         .await?;
 ///
-///     let result = built.await?;
-///     
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, TypedBuilder)]
-pub struct VerifyPayment<'z, Z: ZarinpalClient> {
+#[derive(Debug, Clone, Serialize, Deserialize, TypedBuilder)]
+pub struct VerifyPayment<'a, C = Detached> {
     /// (Optional) Merchant id of whoever makes the payment request.
     ///
     /// If you leave this field as `None`, [`ZarinpalClient`] will set it.
     #[builder(default, setter(strip_option, into))]
     merchant_id: Option<String>,
 
-    /// Payment amount.
-    amount: u64,
+    /// Payment amount, see [`Amount`].
+    ///
+    /// Untyped amounts are sent as is, typed ones are converted to [`VerifyPayment::currency`].
+    #[builder(setter(into))]
+    amount: Amount,
+
+    /// (Optional) Currency that the payment was requested in.
+    ///
+    /// Defaults to the currency of the amount.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    currency: Option<Currency>,
 
     /// The unique authority of the payment.
+    ///
+    /// Takes a `&str` or a `String`, borrowed ones are not copied.
     #[builder(setter(into))]
-    authority: String,
+    authority: Cow<'a, str>,
+
+    /// (Optional) Time limit of this request, counted from when it's sent.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    timeout: Option<Duration>,
+
+    /// (Optional) Point in time this request must be completed by.
+    ///
+    /// Fails with [`crate::error::Error::Timeout`] if exceeded, retries included.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    deadline: Option<Instant>,
 
-    /// The zarinpal client to send this request with.
-    #[serde(skip_serializing)]
-    #[builder(setter(strip_option))]
-    zarinpal: Option<&'z Z>,
+    /// The zarinpal client to send this request with, [`Detached`] if there's none yet.
+    ///
+    /// Awaiting a request that has no client fails with [`Error::MissingClient`],
+    /// see [`VerifyPayment::send_with`].
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    zarinpal: Option<C>,
 }
 
-impl<'z, Z: ZarinpalClient + Sync + Send> IntoFuture for VerifyPayment<'z, Z> {
+impl<'a: 'z, 'z, Z: ZarinpalClient + Sync + Send> IntoFuture for VerifyPayment<'a, &'z Z> {
     type Output = ZarinResult<Verify>;
     type IntoFuture = ::core::pin::Pin<Box<dyn Future<Output = Self::Output> + Send + 'z>>;
 
     fn into_future(mut self) -> Self::IntoFuture {
-        let zarinpal = std::mem::take(&mut self.zarinpal).unwrap(); // Can't be none if object is built!
-        Box::pin(zarinpal.send(self))
+        match self.zarinpal.take() {
+            Some(zarinpal) => zarinpal.send(self),
+            // Built without calling `.zarinpal(...)`.
+            None => Box::pin(std::future::ready(Err(Error::MissingClient))),
+        }
+    }
+}
+
+/// Awaits the builder directly, without calling `build()` first.
+impl<'a: 'z, 'z, Z, M, Cu, T, D, C> IntoFuture
+    for VerifyPaymentBuilder<'a, &'z Z, (M, (Amount,), Cu, (Cow<'a, str>,), T, D, C)>
+where
+    Z: ZarinpalClient + Sync + Send,
+    M: Optional<Option<String>>,
+    Cu: Optional<Option<Currency>>,
+    T: Optional<Option<Duration>>,
+    D: Optional<Option<Instant>>,
+    C: Optional<Option<&'z Z>>,
+{
+    type Output = ZarinResult<Verify>;
+    type IntoFuture = <VerifyPayment<'a, &'z Z> as IntoFuture>::IntoFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.build().into_future()
     }
 }
 
-impl<'z, Z: ZarinpalClient> ApiMethod for VerifyPayment<'z, Z> {
+impl<'a, C> VerifyPayment<'a, C> {
+    /// Attaches `zarinpal` to this request, so it can be awaited.
+    ///
+    /// Useful for requests that're built (or stored) without a client.
+    pub fn send_with<Z: ZarinpalClient>(self, zarinpal: &Z) -> VerifyPayment<'a, &Z> {
+        VerifyPayment {
+            merchant_id: self.merchant_id,
+            amount: self.amount,
+            currency: self.currency,
+            authority: self.authority,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            zarinpal: Some(zarinpal),
+        }
+    }
+}
+
+impl<Z: ZarinpalClient + Sync + Send> VerifyPayment<'_, &Z> {
+    /// Sends the request and returns a [`VerifyOutcome`] instead of a flat [`Verify`],
+    /// so you can't forget to check whether the payment was verified before.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     match zarinpal
+    ///         .verify_payment("A00000000000000000000000000217885159", 10000)
+    ///         .build()
+    ///         .outcome()
+    ///         .await?
+    ///     {
+    ///         VerifyOutcome::Verified(verify) => println!("Deliver order {}", verify.ref_id()),
+    ///         VerifyOutcome::AlreadyVerified(_) => println!("Already delivered"),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn outcome(self) -> ZarinResult<VerifyOutcome> {
+        Ok(self.await?.into())
+    }
+}
+
+impl<C> ApiMethod for VerifyPayment<'_, C> {
     const PATH: &'static str = "pg/v4/payment/verify.json";
 
     type Result = Verify;
 
     fn set_merchant_id_if_needed(&mut self, merchant_id: impl Into<String>) {
-        match self.merchant_id {
-            None => self.merchant_id = Some(merchant_id.into()),
-            _ => (),
+        if self.merchant_id.is_none() {
+            self.merchant_id = Some(merchant_id.into())
         }
     }
+
+    fn deadline(&self) -> Option<Instant> {
+        super::deadline(self.timeout, self.deadline)
+    }
+
+    fn emit_events<Z: ZarinpalClient + ?Sized>(
+        &self,
+        _zarinpal: &Z,
+        handler: &dyn EventHandler,
+        result: &ZarinResult<Verify>,
+    ) {
+        match result {
+            Ok(verify) => handler.on_payment_verified(&self.authority, verify),
+            Err(Error::ZarinpalApiError(error)) => handler.on_verify_failed(&self.authority, error),
+            Err(_) => {}
+        }
+    }
+
+    fn prepare<Z: ZarinpalClient + ?Sized>(&mut self, _zarinpal: &Z) -> ZarinResult<()> {
+        if let Some(currency) = &self.currency {
+            self.amount = self.amount.resolve(currency)?.into();
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Zarinpal;
+    use crate::{
+        error::Error,
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
 
     use super::*;
 
@@ -112,7 +247,7 @@ mod tests {
         });
 
         let from_model = serde_json::to_value(
-            &VerifyPayment::builder()
+            VerifyPayment::builder()
                 .merchant_id("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
                 .amount(1000)
                 .authority("A00000000000000000000000000217885159")
@@ -125,20 +260,47 @@ mod tests {
         assert_eq!(raw_json, from_model)
     }
 
-    use std::future::Future;
-    use std::pin::Pin;
+    /// Never answers in time.
+    fn slow_transport() -> CannedTransport {
+        CannedTransport::always(responses::verify(100)).with_delay(Duration::from_secs(60))
+    }
 
-    struct MyFuture<'a, T>(&'a T);
+    #[tokio::test]
+    async fn test_timeout() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, slow_transport()).unwrap();
 
-    impl<'a, T: Sync> IntoFuture for MyFuture<'a, T> {
-        type Output = ();
-        type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+        let result = zarinpal
+            .verify_payment("A00000000000000000000000000217885159", 1000)
+            .timeout(Duration::from_millis(10))
+            .build()
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)), "{result:?}");
 
-        fn into_future(self) -> Self::IntoFuture {
-            Box::pin(async move {
-                let _t = self.0;
-                ()
-            })
-        }
+        let result = zarinpal
+            .verify_payment("A00000000000000000000000000217885159", 1000)
+            .deadline(Instant::now() + Duration::from_millis(10))
+            .build()
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn test_missing_client() {
+        let verify: VerifyPayment<&Zarinpal> = VerifyPayment::builder()
+            .amount(1000)
+            .authority("A00000000000000000000000000217885159")
+            .build();
+        assert!(matches!(verify.await, Err(Error::MissingClient)));
+    }
+
+    #[tokio::test]
+    async fn test_await_builder() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, slow_transport()).unwrap();
+
+        let result = zarinpal
+            .verify_payment("A00000000000000000000000000217885159", 1000)
+            .timeout(Duration::from_millis(10))
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)), "{result:?}");
     }
 }
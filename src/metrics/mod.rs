@@ -0,0 +1,95 @@
+//! Metrics of requests sent to zarinpal, see [`MetricsSink`].
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+use std::{fmt::Debug, time::Duration};
+
+use crate::{error::Error, results::result_code::ResultCode};
+
+/// Receives metrics of every request that's sent to the payment gateway.
+///
+/// Install it using [`crate::Zarinpal::with_metrics`], or use
+/// [`prometheus::PrometheusMetrics`] (`prometheus` feature).
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::{metrics::MetricsSink, prelude::*};
+///
+/// #[derive(Debug)]
+/// struct LogMetrics;
+///
+/// impl MetricsSink for LogMetrics {
+///     fn on_request_end(&self, path: &str, code: Option<ResultCode>, latency: Duration) {
+///         println!("{path}: {code:?} in {latency:?}");
+///     }
+/// }
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_metrics(LogMetrics);
+/// # Ok(())
+/// # }
+/// ```
+pub trait MetricsSink: Debug + Send + Sync {
+    /// Called right before a request to `path` is sent.
+    fn on_request_start(&self, _path: &str) {}
+
+    /// Called when a request to `path` is done, retries included.
+    ///
+    /// `code` is the result code returned by api, or `None` if the request failed
+    /// without one (like network errors and timeouts).
+    fn on_request_end(&self, path: &str, code: Option<ResultCode>, latency: Duration);
+}
+
+/// Result code of a finished request, as reported to [`MetricsSink::on_request_end`].
+pub(crate) fn result_code<R: crate::results::RequestResult>(
+    result: &Result<R, Error>,
+) -> Option<ResultCode> {
+    match result {
+        Ok(result) => Some(result.code()),
+        Err(error) => error.result_code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        extensions::ZarinpalSendExtension, transport::tests::OfflineTransport, Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone)]
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    impl MetricsSink for RecordingSink {
+        fn on_request_start(&self, path: &str) {
+            self.0.lock().unwrap().push(format!("start {path}"));
+        }
+
+        fn on_request_end(&self, path: &str, code: Option<ResultCode>, _latency: Duration) {
+            self.0.lock().unwrap().push(format!("end {path} {code:?}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sink() {
+        let sink = RecordingSink::default();
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, OfflineTransport)
+            .unwrap()
+            .with_metrics(sink.clone());
+
+        let _ = zarinpal.inquiry_payment("A0").build().await;
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            [
+                "start pg/v4/payment/inquiry.json",
+                "end pg/v4/payment/inquiry.json None"
+            ]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! [`MetricsSink`] that records into [`prometheus`](::prometheus) metrics.
+
+use std::time::Duration;
+
+use ::prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry};
+
+use crate::results::result_code::ResultCode;
+
+use super::MetricsSink;
+
+/// Records requests into prometheus metrics, labeled by api path:
+///
+/// - `zarinpal_requests_total`: finished requests, also labeled by result `code`
+///   (`"none"` for requests that failed without one).
+/// - `zarinpal_requests_in_flight`: requests that are being sent.
+/// - `zarinpal_request_duration_seconds`: latency histogram of requests.
+///
+/// ```no_run
+/// use zarinpal::{metrics::prometheus::PrometheusMetrics, prelude::*};
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let registry = prometheus::Registry::new();
+/// let zarinpal = Zarinpal::new("...")?.with_metrics(PrometheusMetrics::register(&registry)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrometheusMetrics {
+    requests: IntCounterVec,
+    in_flight: IntGaugeVec,
+    latency: HistogramVec,
+}
+
+impl PrometheusMetrics {
+    /// Creates the metrics, without registering them.
+    pub fn new() -> ::prometheus::Result<Self> {
+        Ok(Self {
+            requests: IntCounterVec::new(
+                Opts::new("zarinpal_requests_total", "Requests sent to zarinpal."),
+                &["path", "code"],
+            )?,
+            in_flight: IntGaugeVec::new(
+                Opts::new(
+                    "zarinpal_requests_in_flight",
+                    "Requests to zarinpal that are being sent.",
+                ),
+                &["path"],
+            )?,
+            latency: HistogramVec::new(
+                HistogramOpts::new(
+                    "zarinpal_request_duration_seconds",
+                    "Latency of requests sent to zarinpal.",
+                ),
+                &["path"],
+            )?,
+        })
+    }
+
+    /// Creates the metrics and registers them in `registry`.
+    pub fn register(registry: &Registry) -> ::prometheus::Result<Self> {
+        let metrics = Self::new()?;
+        registry.register(Box::new(metrics.requests.clone()))?;
+        registry.register(Box::new(metrics.in_flight.clone()))?;
+        registry.register(Box::new(metrics.latency.clone()))?;
+        Ok(metrics)
+    }
+}
+
+impl MetricsSink for PrometheusMetrics {
+    fn on_request_start(&self, path: &str) {
+        self.in_flight.with_label_values(&[path]).inc();
+    }
+
+    fn on_request_end(&self, path: &str, code: Option<ResultCode>, latency: Duration) {
+        let code = code.map_or_else(|| "none".to_string(), |code| i64::from(code).to_string());
+
+        self.in_flight.with_label_values(&[path]).dec();
+        self.requests.with_label_values(&[path, &code]).inc();
+        self.latency
+            .with_label_values(&[path])
+            .observe(latency.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let registry = Registry::new();
+        let metrics = PrometheusMetrics::register(&registry).unwrap();
+
+        metrics.on_request_start("pg/v4/payment/verify.json");
+        metrics.on_request_end(
+            "pg/v4/payment/verify.json",
+            Some(ResultCode::Verified),
+            Duration::from_millis(120),
+        );
+        metrics.on_request_start("pg/v4/payment/verify.json");
+        metrics.on_request_end("pg/v4/payment/verify.json", None, Duration::from_secs(1));
+
+        let verify = ["pg/v4/payment/verify.json"];
+        assert_eq!(
+            metrics
+                .requests
+                .with_label_values(&["pg/v4/payment/verify.json", "101"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            metrics
+                .requests
+                .with_label_values(&["pg/v4/payment/verify.json", "none"])
+                .get(),
+            1
+        );
+        assert_eq!(metrics.in_flight.with_label_values(&verify).get(), 0);
+        assert_eq!(
+            metrics
+                .latency
+                .with_label_values(&verify)
+                .get_sample_count(),
+            2
+        );
+        assert_eq!(registry.gather().len(), 3);
+    }
+}
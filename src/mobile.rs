@@ -0,0 +1,183 @@
+//! Iranian mobile numbers, see [`Mobile`].
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ZarinResult};
+
+/// An iranian mobile number, normalized to the `09xxxxxxxxx` format zarinpal expects.
+///
+/// Accepts `09121234567`, `9121234567`, `989121234567`, `+989121234567` and `00989121234567`,
+/// ignoring spaces, dashes and parentheses. Persian and Arabic digits are accepted too.
+///
+/// `Debug` output masks the middle digits, unless `unredacted-debug` feature is enabled.
+///
+/// ```
+/// use zarinpal::mobile::Mobile;
+///
+/// let mobile: Mobile = "+98 912 123 4567".parse().unwrap();
+///
+/// assert_eq!(mobile.as_str(), "09121234567");
+/// assert!("0912123456".parse::<Mobile>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
+#[serde(try_from = "String", into = "String")]
+pub struct Mobile(String);
+
+impl Mobile {
+    /// Parses and normalizes `mobile`.
+    ///
+    /// Fails with [`Error::InvalidMobile`] if it's not an iranian mobile number.
+    pub fn new(mobile: &str) -> ZarinResult<Self> {
+        // The number itself is left out, to keep it out of logs.
+        let invalid = || Error::InvalidMobile("not an iranian mobile number".to_string());
+
+        let (plus, rest) = match mobile.trim_start().strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, mobile),
+        };
+        let digits = rest
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+            .map(|c| to_ascii_digit(c).ok_or_else(invalid))
+            .collect::<ZarinResult<String>>()?;
+
+        let national = if plus {
+            digits.strip_prefix("98")
+        } else if let Some(national) = digits.strip_prefix("0098") {
+            Some(national)
+        } else if digits.len() == 12 {
+            digits.strip_prefix("98")
+        } else if digits.len() == 11 {
+            digits.strip_prefix('0')
+        } else {
+            Some(digits.as_str())
+        }
+        .ok_or_else(invalid)?;
+
+        if national.len() != 10 || !national.starts_with('9') {
+            return Err(invalid());
+        }
+        Ok(Self(format!("0{national}")))
+    }
+
+    /// The normalized number, like `09121234567`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Value of an ascii, persian or arabic digit, as an ascii digit.
+pub(crate) fn to_ascii_digit(c: char) -> Option<char> {
+    let value = match c {
+        '0'..='9' => return Some(c),
+        '۰'..='۹' => c as u32 - '۰' as u32,
+        '٠'..='٩' => c as u32 - '٠' as u32,
+        _ => return None,
+    };
+    char::from_digit(value, 10)
+}
+
+impl FromStr for Mobile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for Mobile {
+    type Error = Error;
+
+    fn try_from(mobile: String) -> Result<Self, Self::Error> {
+        Self::new(&mobile)
+    }
+}
+
+impl TryFrom<&str> for Mobile {
+    type Error = Error;
+
+    fn try_from(mobile: &str) -> Result<Self, Self::Error> {
+        Self::new(mobile)
+    }
+}
+
+impl From<Mobile> for String {
+    fn from(mobile: Mobile) -> Self {
+        mobile.0
+    }
+}
+
+impl AsRef<str> for Mobile {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Mobile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for Mobile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Mobile")
+            .field(&crate::redact::mask(&self.0, 4, 4))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        for mobile in [
+            "09121234567",
+            "9121234567",
+            "989121234567",
+            "+989121234567",
+            "+98 (912) 123-4567",
+            "00989121234567",
+            "۰۹۱۲۱۲۳۴۵۶۷",
+            "٠٩١٢١٢٣٤٥٦٧",
+        ] {
+            assert_eq!(
+                Mobile::new(mobile).unwrap().as_str(),
+                "09121234567",
+                "{mobile}"
+            );
+        }
+
+        for invalid in [
+            "",
+            "mobile",
+            "0912123456",
+            "091212345678",
+            "02112345678",
+            "+19121234567",
+            "+0989121234567",
+            "0912123456x",
+        ] {
+            assert!(
+                matches!(Mobile::new(invalid), Err(Error::InvalidMobile(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let mobile: Mobile = serde_json::from_value(serde_json::json!("+989121234567")).unwrap();
+        assert_eq!(
+            serde_json::to_value(&mobile).unwrap(),
+            serde_json::json!("09121234567")
+        );
+        assert!(serde_json::from_value::<Mobile>(serde_json::json!("12345")).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! One client for many merchants, like terminals of marketplace tenants.
+//!
+//! [`MultiZarinpal`] keeps merchant ids of tenants next to a single [`Zarinpal`] client, so they
+//! all share its http client (and connection pool), base url, retry policy and other settings.
+//! [`MultiZarinpal::for_merchant`] returns a cheap handle that sends requests as one of them.
+
+use std::collections::HashMap;
+
+use crate::{
+    callback::CallbackUrlPolicy,
+    environment::GatewayUrl,
+    error::{Error, ZarinResult},
+    events::EventHandler,
+    hooks::RequestHook,
+    methods::request::{RequestDefaults, Wage},
+    metrics::MetricsSink,
+    rate_limit::RateLimiter,
+    results::{result_code::MessageLocale, DeserializationMode},
+    retry::RetryPolicy,
+    secret::MerchantId,
+    transport::HttpTransport,
+    Zarinpal, ZarinpalClient,
+};
+
+/// A registry of merchant ids sharing one [`Zarinpal`] client.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::multi::MultiZarinpal;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = MultiZarinpal::new(Zarinpal::new("...")?)
+///         .with_merchant("shop-a", "...")?
+///         .with_merchant("shop-b", "...")?;
+///
+///     let request = zarinpal
+///         .for_merchant("shop-a")?
+///         .request_payment(10000, "https://shop-a.example.com/callback".parse()?, "...")
+///         .build()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiZarinpal {
+    client: Zarinpal,
+    merchants: HashMap<String, MerchantId>,
+}
+
+impl MultiZarinpal {
+    /// Creates a registry whose merchants send requests using settings of `client`.
+    ///
+    /// Merchant id of `client` itself is not registered, add it using
+    /// [`MultiZarinpal::with_merchant`] if it's needed.
+    pub fn new(client: Zarinpal) -> Self {
+        Self {
+            client,
+            merchants: HashMap::new(),
+        }
+    }
+
+    /// Registers `merchant_id` under `key` (Eg: a tenant name), replacing the previous one.
+    ///
+    /// This fails if `merchant_id` is not a valid uuid.
+    pub fn with_merchant(
+        mut self,
+        key: impl Into<String>,
+        merchant_id: &str,
+    ) -> Result<Self, uuid::Error> {
+        self.insert(key, merchant_id)?;
+        Ok(self)
+    }
+
+    /// Registers `merchant_id` under `key`, replacing the previous one.
+    ///
+    /// This fails if `merchant_id` is not a valid uuid.
+    pub fn insert(&mut self, key: impl Into<String>, merchant_id: &str) -> Result<(), uuid::Error> {
+        let merchant_id = uuid::Uuid::parse_str(merchant_id)?.to_string();
+        self.merchants
+            .insert(key.into(), MerchantId::new(merchant_id));
+        Ok(())
+    }
+
+    /// Unregisters the merchant of `key`, returns `true` if there was one.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.merchants.remove(key).is_some()
+    }
+
+    /// Keys of registered merchants, in no particular order.
+    pub fn merchants(&self) -> impl Iterator<Item = &str> {
+        self.merchants.keys().map(String::as_str)
+    }
+
+    /// The shared client.
+    pub fn client(&self) -> &Zarinpal {
+        &self.client
+    }
+
+    /// A client sending requests as the merchant of `key`.
+    ///
+    /// This fails with [`Error::UnknownMerchant`] if no merchant is registered under `key`.
+    pub fn for_merchant(&self, key: &str) -> ZarinResult<MerchantZarinpal<'_>> {
+        let merchant_id = self
+            .merchants
+            .get(key)
+            .ok_or_else(|| Error::UnknownMerchant(key.to_string()))?;
+
+        Ok(MerchantZarinpal {
+            client: &self.client,
+            merchant_id,
+        })
+    }
+}
+
+/// A [`ZarinpalClient`] sending requests as one merchant of a [`MultiZarinpal`].
+///
+/// Everything but merchant id comes from the shared client.
+#[derive(Debug, Clone, Copy)]
+pub struct MerchantZarinpal<'a> {
+    client: &'a Zarinpal,
+    merchant_id: &'a MerchantId,
+}
+
+#[async_trait::async_trait]
+impl ZarinpalClient for MerchantZarinpal<'_> {
+    fn transport(&self) -> &dyn HttpTransport {
+        self.client.transport()
+    }
+
+    fn merchant_id(&self) -> &str {
+        self.merchant_id.expose()
+    }
+
+    fn base_url(&self) -> &url::Url {
+        self.client.base_url()
+    }
+
+    fn gateway_url(&self) -> GatewayUrl {
+        self.client.gateway_url()
+    }
+
+    fn wage_profile(&self, name: &str) -> Option<&[Wage]> {
+        self.client.wage_profile(name)
+    }
+
+    fn request_defaults(&self) -> Option<&RequestDefaults> {
+        self.client.request_defaults()
+    }
+
+    fn callback_url_policy(&self) -> Option<&CallbackUrlPolicy> {
+        self.client.callback_url_policy()
+    }
+
+    fn request_hook(&self) -> Option<&RequestHook> {
+        self.client.request_hook()
+    }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.client.retry_policy()
+    }
+
+    fn metrics(&self) -> Option<&dyn MetricsSink> {
+        self.client.metrics()
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.client.rate_limiter()
+    }
+
+    #[cfg(feature = "audit")]
+    fn audit_sink(&self) -> Option<&dyn crate::audit::AuditSink> {
+        self.client.audit_sink()
+    }
+
+    fn message_locale(&self) -> MessageLocale {
+        self.client.message_locale()
+    }
+
+    fn deserialization_mode(&self) -> DeserializationMode {
+        self.client.deserialization_mode()
+    }
+
+    fn event_handler(&self) -> Option<&dyn EventHandler> {
+        self.client.event_handler()
+    }
+
+    fn access_token(&self) -> Option<&str> {
+        self.client.access_token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        TEST_UUID,
+    };
+
+    use super::*;
+
+    const OTHER_UUID: &str = "6a8b2c47-03f4-4a3e-9a53-7d0f7c8e1b21";
+
+    #[tokio::test]
+    async fn test_merchants_share_client() {
+        let transport = CannedTransport::always(responses::inquiry("PAID"));
+        let zarinpal =
+            MultiZarinpal::new(Zarinpal::new_with_transport(TEST_UUID, transport.clone()).unwrap())
+                .with_merchant("a", OTHER_UUID)
+                .unwrap()
+                .with_merchant("b", TEST_UUID)
+                .unwrap();
+        assert!(!format!("{zarinpal:?}").contains(OTHER_UUID));
+
+        for key in ["a", "b"] {
+            zarinpal
+                .for_merchant(key)
+                .unwrap()
+                .inquiry_payment("A1")
+                .build()
+                .await
+                .unwrap();
+        }
+        let merchant_ids = transport
+            .requests()
+            .into_iter()
+            .map(|request| request.body["merchant_id"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(merchant_ids, [OTHER_UUID, TEST_UUID]);
+
+        assert!(matches!(
+            zarinpal.for_merchant("c"),
+            Err(Error::UnknownMerchant(key)) if key == "c"
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+//! Entity of unverified [`Authorities`](crate::results::unverified::Authorities).
+
+use sea_orm::entity::prelude::*;
+
+/// An unverified payment in `zarinpal_authorities` table.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "zarinpal_authorities")]
+pub struct Model {
+    /// Unique authority of the payment request.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub authority: String,
+
+    /// Payment amount.
+    pub amount: i64,
+
+    /// Callback url of the payment.
+    pub callback_url: String,
+
+    /// Refer url.
+    pub referer: String,
+
+    /// Date and time of the request in a format like: `2020-06-27 10:22:02`.
+    pub date: String,
+}
+
+/// Relations of authorities, there's none.
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(feature = "unverified")]
+mod unverified {
+    use super::Model;
+    use crate::results::unverified::Authorities;
+
+    impl From<&Authorities> for Model {
+        fn from(authorities: &Authorities) -> Self {
+            Self {
+                authority: authorities.authority.clone(),
+                amount: authorities.amount as i64,
+                callback_url: authorities.callback_url.clone(),
+                referer: authorities.referer.clone(),
+                date: authorities.date.clone(),
+            }
+        }
+    }
+
+    impl From<Model> for Authorities {
+        fn from(model: Model) -> Self {
+            Self {
+                authority: model.authority,
+                amount: model.amount as u64,
+                callback_url: model.callback_url,
+                referer: model.referer,
+                date: model.date,
+                extra: Default::default(),
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! [SeaORM](sea_orm) entities of payment tables (requires `orm` feature).
+//!
+//! - [`payment`]: payments tracked by a [`crate::store::PaymentStore`], in the same
+//!   `zarinpal_payments` table (and schema) that `sqlx` stores use.
+//! - [`verification`]: [`crate::results::verify::Verify`] results, one per authority.
+//! - [`authority`]: unverified [`crate::results::unverified::Authorities`].
+//!
+//! Models convert from and to types of this crate, so results can be stored as is:
+//!
+//! ```no_run
+//! use sea_orm::{ActiveModelTrait, DatabaseConnection, IntoActiveModel};
+//! use zarinpal::{orm::verification, prelude::*};
+//!
+//! # async fn store(db: &DatabaseConnection, authority: &str, verify: &Verify) -> Result::<(), Box<dyn std::error::Error>> {
+//! verification::Model::from_verify(authority, verify)
+//!     .into_active_model()
+//!     .insert(db)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Amounts and reference ids are `BIGINT`s and times are milliseconds since unix epoch, like in
+//! [`crate::store`]. Wages and fields that are not modeled by this crate are not stored.
+
+pub mod authority;
+pub mod payment;
+pub mod verification;
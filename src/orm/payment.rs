@@ -0,0 +1,121 @@
+//! Entity of payments tracked by a [`crate::store::PaymentStore`].
+
+use sea_orm::entity::prelude::*;
+
+/// A payment in `zarinpal_payments` table, see [`crate::store::PaymentRecord`].
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "zarinpal_payments")]
+pub struct Model {
+    /// Unique authority of the payment request.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub authority: String,
+
+    /// Payment amount, in [`Model::currency`].
+    pub amount: i64,
+
+    /// Currency code used in the payment request, like `IRR`.
+    pub currency: String,
+
+    /// Order id that this payment belongs to.
+    pub order_id: Option<String>,
+
+    /// State of the payment: `pending`, `verified`, `failed` or `expired`.
+    pub state: String,
+
+    /// Reference id of a verified payment.
+    pub ref_id: Option<i64>,
+
+    /// When the payment was requested, in milliseconds since unix epoch.
+    pub created_at: i64,
+
+    /// When the record was archived, in milliseconds since unix epoch.
+    pub archived_at: Option<i64>,
+}
+
+/// Relations of payments.
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Verification of the payment, if it's verified.
+    #[sea_orm(has_one = "super::verification::Entity")]
+    Verification,
+}
+
+impl Related<super::verification::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Verification.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(feature = "store")]
+mod store {
+    use super::Model;
+    use crate::{
+        error::Error,
+        store::{
+            db::{decode_state, encode_state, from_millis, to_millis},
+            PaymentRecord,
+        },
+    };
+
+    impl From<&PaymentRecord> for Model {
+        fn from(record: &PaymentRecord) -> Self {
+            let (state, ref_id) = encode_state(record.state);
+            Self {
+                authority: record.authority.clone(),
+                amount: record.amount as i64,
+                currency: record.currency.to_string(),
+                order_id: record.order_id.clone(),
+                state: state.to_string(),
+                ref_id,
+                created_at: to_millis(record.created_at),
+                archived_at: record.archived_at.map(to_millis),
+            }
+        }
+    }
+
+    impl TryFrom<Model> for PaymentRecord {
+        type Error = Error;
+
+        /// Fails with [`Error::StoreError`] if the state is not valid.
+        fn try_from(model: Model) -> Result<Self, Self::Error> {
+            Ok(Self {
+                state: decode_state(&model.state, model.ref_id)?,
+                authority: model.authority,
+                amount: model.amount as u64,
+                currency: model.currency.into(),
+                order_id: model.order_id,
+                created_at: from_millis(model.created_at),
+                archived_at: model.archived_at.map(from_millis),
+            })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "store"))]
+mod tests {
+    use super::*;
+    use crate::store::{PaymentRecord, PaymentState};
+
+    #[test]
+    fn test_record_conversion() {
+        let record = PaymentRecord::new("A1", 10000, Default::default()).with_order_id("ORDER-1");
+
+        let mut model = Model::from(&record);
+        assert_eq!(model.state, "pending");
+        assert_eq!(
+            PaymentRecord::try_from(model.clone()).unwrap().authority(),
+            "A1"
+        );
+
+        model.state = "verified".to_string();
+        model.ref_id = Some(201);
+        let loaded = PaymentRecord::try_from(model.clone()).unwrap();
+        assert_eq!(loaded.state(), PaymentState::Verified { ref_id: 201 });
+        assert_eq!(loaded.order_id(), Some("ORDER-1"));
+
+        model.state = "unknown".to_string();
+        assert!(PaymentRecord::try_from(model).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Entity of [`Verify`] results.
+
+use sea_orm::entity::prelude::*;
+
+use crate::results::verify::Verify;
+
+/// A verified payment in `zarinpal_verifications` table.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "zarinpal_verifications")]
+pub struct Model {
+    /// Authority of the verified payment.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub authority: String,
+
+    /// Result code of the verification, `100` or `101` (verified before).
+    pub code: i32,
+
+    /// Result message of the verification.
+    pub message: String,
+
+    /// SHA256 hash of card number.
+    pub card_hash: String,
+
+    /// Masked card number in a format like `60379986****5434`.
+    pub card_pan: String,
+
+    /// Reference id of the payment.
+    #[sea_orm(unique)]
+    pub ref_id: i64,
+
+    /// Fee type, `Payer` or `Merchant`.
+    pub fee_type: String,
+
+    /// Fee amount.
+    pub fee: i64,
+
+    /// Fee of shaparak (the interbank network) for this transaction.
+    pub shaparak_fee: Option<i64>,
+
+    /// Order id you've entered while sending payment request.
+    pub order_id: Option<String>,
+}
+
+impl Model {
+    /// Creates a model of a verification of `authority`.
+    pub fn from_verify(authority: impl Into<String>, verify: &Verify) -> Self {
+        Self {
+            authority: authority.into(),
+            code: verify.code.code() as i32,
+            message: verify.message.clone(),
+            card_hash: verify.card_hash.clone(),
+            card_pan: verify.card_pan.clone(),
+            ref_id: verify.ref_id as i64,
+            fee_type: verify.fee_type.as_str().to_string(),
+            fee: verify.fee as i64,
+            shaparak_fee: verify.shaparak_fee.map(|fee| fee as i64),
+            order_id: verify.order_id.clone(),
+        }
+    }
+}
+
+impl From<Model> for Verify {
+    fn from(model: Model) -> Self {
+        Self {
+            code: i64::from(model.code).into(),
+            message: model.message,
+            card_hash: model.card_hash,
+            card_pan: model.card_pan,
+            ref_id: model.ref_id as u64,
+            fee_type: model.fee_type.into(),
+            fee: model.fee as u64,
+            wages: None,
+            shaparak_fee: model.shaparak_fee.map(|fee| fee as u64),
+            order_id: model.order_id,
+            extra: Default::default(),
+        }
+    }
+}
+
+/// Relations of verifications.
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// The payment that's verified.
+    #[sea_orm(
+        belongs_to = "super::payment::Entity",
+        from = "Column::Authority",
+        to = "super::payment::Column::Authority"
+    )]
+    Payment,
+}
+
+impl Related<super::payment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Payment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_conversion() {
+        let verify: Verify = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Payer",
+            "fee": 100,
+            "shaparak_fee": 120
+        }))
+        .unwrap();
+
+        let model = Model::from_verify("A1", &verify);
+        assert_eq!(model.code, 100);
+        assert_eq!(model.fee_type, "Payer");
+
+        let loaded = Verify::from(model);
+        assert_eq!(loaded.ref_id(), 201);
+        assert!(loaded.fee_type().is_payer());
+        assert_eq!(loaded.shaparak_fee(), Some(120));
+        assert_eq!(loaded.order_id(), None);
+    }
+}
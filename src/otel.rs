@@ -0,0 +1,118 @@
+//! OpenTelemetry spans of api calls (requires `otel` feature).
+//!
+//! Each call of [`crate::ZarinpalClient::send`] (retries included) is a client span named like
+//! `POST /pg/v4/payment/verify.json`, created using the global tracer provider as a child of
+//! the current context, so it shows up next to the handler that made the call. The context of
+//! the span is current while the call is running, so spans of the transport nest under it.
+//!
+//! Spans carry these attributes:
+//! - `peer.service`: always `zarinpal`.
+//! - `http.route` and `http.request.method`: the api path, and `POST`.
+//! - `server.address`: host of [`crate::ZarinpalClient::base_url`].
+//! - `zarinpal.authority_hash`: hex encoded sha256 of the authority of the request, if it has
+//!   one (like verify and inquiry). Authorities are hashed so they can be correlated with logs
+//!   without being exposed.
+//! - `zarinpal.result_code`: result code of the response, if the api returned one.
+//!
+//! Failed calls have an error status, with the error message as description.
+
+use opentelemetry::{
+    global,
+    trace::{SpanBuilder, SpanKind, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{error::ZarinResult, results::RequestResult};
+
+/// Name of the tracer that spans are created with.
+pub const TRACER_NAME: &str = "zarinpal";
+
+fn span_builder(path: &str, base_url: &url::Url, body: &serde_json::Value) -> SpanBuilder {
+    let mut attributes = vec![
+        KeyValue::new("peer.service", "zarinpal"),
+        KeyValue::new("http.route", path.to_string()),
+        KeyValue::new("http.request.method", "POST"),
+    ];
+    if let Some(host) = base_url.host_str() {
+        attributes.push(KeyValue::new("server.address", host.to_string()));
+    }
+    if let Some(authority) = body["authority"].as_str() {
+        let hash: String = Sha256::digest(authority.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        attributes.push(KeyValue::new("zarinpal.authority_hash", hash));
+    }
+
+    SpanBuilder::from_name(format!("POST {path}"))
+        .with_kind(SpanKind::Client)
+        .with_attributes(attributes)
+}
+
+/// Starts a span of a call to `path`, returns the current context with the span in it.
+pub(crate) fn start_span(path: &str, base_url: &url::Url, body: &serde_json::Value) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer.build_with_context(span_builder(path, base_url, body), &Context::current());
+    Context::current_with_span(span)
+}
+
+/// Records `result` on the span of `context`, and ends it.
+pub(crate) fn end_span<T: RequestResult>(context: &Context, result: &ZarinResult<T>) {
+    let span = context.span();
+    match result {
+        Ok(result) => {
+            span.set_attribute(KeyValue::new("zarinpal.result_code", result.code().code()));
+        }
+        Err(error) => {
+            if let Some(code) = error.result_code() {
+                span.set_attribute(KeyValue::new("zarinpal.result_code", code.code()));
+            }
+            span.set_status(Status::error(error.to_string()));
+        }
+    }
+    span.end();
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_span_attributes() {
+        let builder = span_builder(
+            "/pg/v4/payment/verify.json",
+            &"https://payment.zarinpal.com".parse().unwrap(),
+            &serde_json::json!({
+                "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+                "authority": "A00000000000000000000000000217885159",
+                "amount": 10000
+            }),
+        );
+
+        assert_eq!(builder.name, "POST /pg/v4/payment/verify.json");
+        assert_eq!(builder.span_kind, Some(SpanKind::Client));
+
+        let attributes = builder.attributes.unwrap();
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.key.as_str() == key)
+                .map(|attribute| attribute.value.clone())
+        };
+        assert_eq!(attribute("peer.service"), Some(Value::from("zarinpal")));
+        assert_eq!(
+            attribute("server.address"),
+            Some(Value::from("payment.zarinpal.com"))
+        );
+        match attribute("zarinpal.authority_hash") {
+            Some(Value::String(hash)) => {
+                assert_eq!(hash.as_str().len(), 64);
+                assert!(!hash.as_str().contains("A000"));
+            }
+            other => panic!("unexpected authority hash: {other:?}"),
+        }
+    }
+}
@@ -6,20 +6,44 @@
 //! ```
 
 pub use crate::{
+    amount::Amount,
+    callback::{CallbackOutcome, CallbackStatus, PaymentCallback},
+    card::CardPan,
+    environment::Environment,
     error::ZarinResult,
     extensions::ZarinpalSendExtension,
+    iban::Iban,
     methods::{
-        request::{Currency, Metadata, RequestPayment, Wage},
-        unverified::UnverifiedRequests,
+        inquiry::InquiryPayment,
+        request::{Currency, Metadata, RequestDefaults, RequestPayment, RequestTemplate, Wage},
+        reverse::ReversePayment,
         verify::VerifyPayment,
-        ApiMethod,
+        ApiMethod, Detached,
     },
+    mobile::Mobile,
     results::{
-        request::Request,
-        result_code::ResultCode,
-        unverified::{Authorities, Unverified},
-        verify::Verify,
+        inquiry::{CheckOutcome, Inquiry, PaymentStatus},
+        request::{GatewayKind, Request},
+        result_code::{MessageLocale, ResultCode},
+        reverse::Reverse,
+        verify::{Verify, VerifyOutcome},
         ApiResult, RequestResult,
     },
     Zarinpal, ZarinpalClient,
 };
+
+#[cfg(feature = "graphql")]
+pub use crate::{
+    graphql::{me::Me, GraphqlClient},
+    methods::refund::{RefundMethod, RefundPayment, RefundReason},
+    results::refund::Refund,
+};
+
+#[cfg(feature = "unverified")]
+pub use crate::{
+    methods::unverified::UnverifiedRequests,
+    results::unverified::{Authorities, Unverified},
+};
+
+#[cfg(all(feature = "unverified", feature = "batch"))]
+pub use crate::results::unverified::VerifyReport;
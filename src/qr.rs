@@ -0,0 +1,150 @@
+//! QR codes of payment links, like [`crate::results::request::Request::gateway_qr`]
+//! (requires `qrcode` feature).
+//!
+//! Codes are encoded by the [`qrcode`](https://docs.rs/qrcode) crate with medium (~15%) error
+//! correction, and can be rendered as svg or png, with a quiet zone of 4 modules.
+//!
+//! ```
+//! use zarinpal::qr::QrCode;
+//!
+//! let qr = QrCode::encode(b"https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159")
+//!     .unwrap();
+//!
+//! let svg = qr.to_svg();
+//! let png = qr.to_png(8);
+//! # assert!(svg.contains("<svg"));
+//! # assert!(png.starts_with(b"\x89PNG"));
+//! ```
+
+use qrcode::{
+    render::{svg, Renderer},
+    Color, EcLevel, Version,
+};
+
+/// Width of the light border around the code, in modules.
+const QUIET_ZONE: usize = 4;
+
+/// Data is too long to fit in a QR code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Data is too long for a QR code")]
+pub struct DataTooLong;
+
+/// A QR code, as a square of dark and light modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    modules: Vec<Color>,
+}
+
+impl QrCode {
+    /// Encodes `data` in the smallest QR code that fits it.
+    ///
+    /// This fails if `data` is longer than 2331 bytes.
+    pub fn encode(data: &[u8]) -> Result<Self, DataTooLong> {
+        let code = qrcode::QrCode::with_error_correction_level(data, EcLevel::M)
+            .map_err(|_| DataTooLong)?;
+        let (Version::Normal(version) | Version::Micro(version)) = code.version();
+
+        Ok(Self {
+            version: version as usize,
+            size: code.width(),
+            modules: code.into_colors(),
+        })
+    }
+
+    /// Version of the code, from `1` to `40`.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Number of modules in each side, without the quiet zone.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the module at column `x` and row `y` is dark.
+    ///
+    /// Modules outside of the code (in the quiet zone) are light.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x] == Color::Dark
+    }
+
+    /// Renders the code as an svg image, one unit for each module.
+    pub fn to_svg(&self) -> String {
+        Renderer::<svg::Color>::new(&self.modules, self.size, QUIET_ZONE as u32)
+            .module_dimensions(1, 1)
+            .build()
+    }
+
+    /// Renders the code as a grayscale png image, `scale` pixels for each module.
+    pub fn to_png(&self, scale: usize) -> Vec<u8> {
+        let scale = scale.max(1);
+        let side = (self.size + QUIET_ZONE * 2) * scale;
+
+        let mut pixels = Vec::with_capacity(side * side);
+        for py in 0..side {
+            for px in 0..side {
+                let x = (px / scale).wrapping_sub(QUIET_ZONE);
+                let y = (py / scale).wrapping_sub(QUIET_ZONE);
+                pixels.push(if self.is_dark(x, y) { 0 } else { 255 });
+            }
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .expect("Pixels match the size of the image");
+        png
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        for data in [
+            &b"https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159"[..],
+            b"",
+            &[b'x'; 300],
+            &[0xAB; 2331],
+        ] {
+            let qr = QrCode::encode(data).unwrap();
+            assert_eq!(qr.size(), qr.version() * 4 + 17);
+        }
+
+        assert_eq!(QrCode::encode(&[0xAB; 2332]), Err(DataTooLong));
+    }
+
+    #[test]
+    fn test_known_versions() {
+        // Capacities of medium level, in bytes.
+        assert_eq!(QrCode::encode(&[0xAB; 14]).unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[0xAB; 15]).unwrap().version(), 2);
+        assert_eq!(QrCode::encode(&[0xAB; 84]).unwrap().version(), 5);
+        assert_eq!(QrCode::encode(&[0xAB; 85]).unwrap().version(), 6);
+        assert_eq!(QrCode::encode(&[0xAB; 213]).unwrap().version(), 10);
+    }
+
+    #[test]
+    fn test_render() {
+        let qr = QrCode::encode(b"zarinpal").unwrap();
+        assert!(qr.is_dark(0, 0));
+        assert!(!qr.is_dark(qr.size(), 0));
+
+        let svg = qr.to_svg();
+        assert!(svg.contains("<svg") && svg.ends_with("</svg>"));
+        assert!(svg.contains(r#"viewBox="0 0 29 29""#));
+
+        let png = qr.to_png(2);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(&png[16..24], &[0, 0, 0, 58, 0, 0, 0, 58]);
+        assert!(png.ends_with(&[0xAE, 0x42, 0x60, 0x82]));
+    }
+}
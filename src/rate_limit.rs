@@ -0,0 +1,224 @@
+//! Client-side throttling of requests, see [`RateLimiter`].
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Error, ZarinResult},
+    results::result_code::ResultCode,
+};
+
+/// Tokens of a [`RateLimiter`], refilled as time passes.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// A token bucket limiting requests of a client to a steady rate, with bursts.
+///
+/// Each attempt of a request takes a token, waiting for one if the bucket is empty. When the api
+/// answers with [`ResultCode::ToManyAttempts`] (`-12`), no request is sent for a while (see
+/// [`RateLimiter::with_cool_down`]).
+///
+/// Install it using [`crate::Zarinpal::with_rate_limiter`] or
+/// [`crate::builder::ZarinpalBuilder::rate_limiter`]. Clones of a client share its limiter, so
+/// concurrent tasks (like an unverified poller and callback handlers) are throttled together.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::prelude::*;
+/// use zarinpal::rate_limit::RateLimiter;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_rate_limiter(
+///     RateLimiter::new(5., 10)?.with_cool_down(Duration::from_secs(30)),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: u32,
+    cool_down: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// Creates a full bucket of `burst` tokens, refilled by `requests_per_second`.
+    ///
+    /// Cool-down after [`ResultCode::ToManyAttempts`] defaults to 10 seconds.
+    ///
+    /// Fails with [`Error::InvalidConfig`] if `burst` is zero, or `requests_per_second` is not
+    /// positive (or so small that the time between two requests can't be represented).
+    pub fn new(requests_per_second: f64, burst: u32) -> ZarinResult<Self> {
+        // Time between two requests, which is negative or NaN for rates that are not positive.
+        if Duration::try_from_secs_f64(requests_per_second.recip()).is_err() {
+            return Err(Error::InvalidConfig(format!(
+                "requests_per_second must be positive, got {requests_per_second}"
+            )));
+        }
+        if burst == 0 {
+            return Err(Error::InvalidConfig("burst can't be zero".to_string()));
+        }
+
+        Ok(Self {
+            requests_per_second,
+            burst,
+            cool_down: Duration::from_secs(10),
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                refilled_at: Instant::now(),
+                paused_until: None,
+            }),
+        })
+    }
+
+    /// Sets how long requests are paused after [`ResultCode::ToManyAttempts`].
+    pub fn with_cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// Steady rate of requests.
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Maximum number of requests that can be sent at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// How long requests are paused after [`ResultCode::ToManyAttempts`].
+    pub fn cool_down(&self) -> Duration {
+        self.cool_down
+    }
+
+    /// Takes a token if there's one, otherwise returns how long to wait before trying again.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut bucket = self.bucket.lock().unwrap();
+
+        if let Some(paused_until) = bucket.paused_until {
+            if paused_until > now {
+                return Err(paused_until - now);
+            }
+            bucket.paused_until = None;
+            bucket.refilled_at = paused_until;
+        }
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second)
+            .min(self.burst as f64);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1. - bucket.tokens) / self.requests_per_second,
+            ))
+        }
+    }
+
+    /// Waits until a token is available, and takes it.
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            crate::timer::sleep(wait).await;
+        }
+    }
+
+    /// Empties the bucket and pauses requests for [`RateLimiter::cool_down`].
+    pub fn pause(&self) {
+        let mut bucket = self.bucket.lock().unwrap();
+        let until = Instant::now() + self.cool_down;
+        bucket.tokens = 0.;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |paused| paused.max(until)),
+        );
+    }
+
+    /// Pauses requests if `error` is a [`ResultCode::ToManyAttempts`] api error.
+    pub(crate) fn observe(&self, error: &Error) {
+        if error.result_code() == Some(ResultCode::ToManyAttempts) {
+            self.pause()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ApiError,
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let limiter = RateLimiter::new(20., 2).unwrap();
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_ok());
+
+        let wait = limiter.try_acquire().unwrap_err();
+        assert!(wait <= Duration::from_millis(50), "{wait:?}");
+
+        std::thread::sleep(wait);
+        assert!(limiter.try_acquire().is_ok());
+
+        limiter.observe(&ApiError::local(ResultCode::InvalidAuthority, "", "").into());
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(limiter.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cool_down() {
+        let zarinpal = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::error(-12, "Too many attempts")),
+        )
+        .unwrap()
+        .with_rate_limiter(
+            RateLimiter::new(100., 10)
+                .unwrap()
+                .with_cool_down(Duration::from_secs(60)),
+        );
+
+        assert!(zarinpal.inquiry_payment("A1").build().await.is_err());
+
+        let wait = zarinpal
+            .rate_limiter
+            .as_ref()
+            .unwrap()
+            .try_acquire()
+            .unwrap_err();
+        assert!(wait > Duration::from_secs(50), "{wait:?}");
+    }
+
+    #[test]
+    fn test_invalid_limits() {
+        for (requests_per_second, burst) in [(0., 1), (-1., 1), (f64::NAN, 1), (1e-300, 1), (1., 0)]
+        {
+            assert!(matches!(
+                RateLimiter::new(requests_per_second, burst),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+
+        let limiter = RateLimiter::new(1e-9, 1).unwrap();
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().unwrap_err() > Duration::from_secs(999_999_999));
+    }
+}
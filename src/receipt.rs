@@ -0,0 +1,485 @@
+//! Customer-facing receipts of verified payments, see [`Receipt`].
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+#[cfg(feature = "receipt-render")]
+use crate::html::escape;
+use crate::{
+    methods::request::{Currency, RequestPayment},
+    results::{request::Request, verify::Verify},
+};
+
+/// Receipt of a verified payment, put together from the [`RequestPayment`] that started it,
+/// its [`Request`] result and its [`Verify`] result.
+///
+/// It's serializable, to be stored or sent to a frontend, and its `Display` output is a plain
+/// text receipt that can be shown to the payer.
+///
+/// ```
+/// use zarinpal::receipt::Receipt;
+/// use zarinpal::prelude::*;
+///
+/// let receipt = Receipt::builder()
+///     .authority("A00000000000000000000000000217885159")
+///     .ref_id(201)
+///     .amount(1250000)
+///     .card_pan("502229******5995")
+///     .description("Order #1234")
+///     .merchant_name("My shop")
+///     .build();
+///
+/// assert_eq!(
+///     receipt.to_string(),
+///     "Payment receipt\n\
+///      Merchant: My shop\n\
+///      Amount: 1,250,000 IRR\n\
+///      Description: Order #1234\n\
+///      Reference id: 201\n\
+///      Authority: A00000000000000000000000000217885159\n\
+///      Card: 502229******5995 (Bank Pasargad)\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypedBuilder)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Receipt {
+    /// Unique authority of the payment request.
+    #[builder(setter(into))]
+    authority: String,
+
+    /// Reference id of the verified payment.
+    ref_id: u64,
+
+    /// Amount of the payment, in [`Receipt::currency`].
+    amount: u64,
+
+    /// (Optional) Currency of the payment. Defaults to [`Currency::IRR`].
+    #[builder(default)]
+    #[cfg_attr(feature = "utoipa", schema(value_type = String, example = "IRR"))]
+    currency: Currency,
+
+    /// (Optional) Fee of the payment.
+    #[builder(default)]
+    fee: u64,
+
+    /// Masked card number that paid, like `502229******5995`.
+    #[builder(setter(into))]
+    card_pan: String,
+
+    /// (Optional) Description of the payment.
+    #[builder(default, setter(into))]
+    description: String,
+
+    /// (Optional) Order id of the payment.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order_id: Option<String>,
+
+    /// (Optional) Name of the merchant (shop) to show on the receipt.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    merchant_name: Option<String>,
+
+    /// (Optional) Date and time of the payment, like `2024-06-27 17:33:25`.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    paid_at: Option<String>,
+}
+
+impl Receipt {
+    /// Puts a receipt together from a `payment` request, its `request` result and its `verify`
+    /// result.
+    ///
+    /// Amount and currency are the ones the payment is requested with (see
+    /// [`Request::amount`]), and order id is the one echoed back by verify, or else the one
+    /// in metadata of `payment`. Use [`Receipt::with_merchant_name`] and
+    /// [`Receipt::with_paid_at`] to fill in the rest.
+    ///
+    /// ```no_run
+    /// use zarinpal::receipt::Receipt;
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     let payment: RequestPayment = RequestPayment::builder()
+    ///         .amount(10000)
+    ///         .callback_url("https://example.com/verify")
+    ///         .description("Order #1234")
+    ///         .build();
+    ///
+    ///     let request = payment.clone().send_with(&zarinpal).await?;
+    ///     // ... the payer pays, and comes back to the callback url.
+    ///     let verify = request.verify(&zarinpal).await?;
+    ///
+    ///     let receipt = Receipt::new(&payment, &request, &verify).with_merchant_name("My shop");
+    ///     println!("{receipt}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new<C>(payment: &RequestPayment<C>, request: &Request, verify: &Verify) -> Self {
+        Self {
+            authority: request.authority().to_string(),
+            ref_id: verify.ref_id(),
+            amount: request.amount(),
+            currency: request.currency().clone(),
+            fee: verify.fee(),
+            card_pan: verify.card_pan().to_string(),
+            description: payment.description().to_string(),
+            order_id: verify
+                .order_id()
+                .or_else(|| payment.metadata().order_id())
+                .map(str::to_string),
+            merchant_name: None,
+            paid_at: None,
+        }
+    }
+
+    /// Sets name of the merchant (shop) to show on the receipt.
+    pub fn with_merchant_name(mut self, merchant_name: impl Into<String>) -> Self {
+        self.merchant_name = Some(merchant_name.into());
+        self
+    }
+
+    /// Sets date and time of the payment, like `2024-06-27 17:33:25`.
+    pub fn with_paid_at(mut self, paid_at: impl Into<String>) -> Self {
+        self.paid_at = Some(paid_at.into());
+        self
+    }
+
+    /// Unique authority of the payment request.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Reference id of the verified payment.
+    pub fn ref_id(&self) -> u64 {
+        self.ref_id
+    }
+
+    /// Amount of the payment, in [`Receipt::currency`].
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Currency of the payment.
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Fee of the payment.
+    pub fn fee(&self) -> u64 {
+        self.fee
+    }
+
+    /// Masked card number that paid, like `502229******5995`.
+    pub fn card_pan(&self) -> &str {
+        self.card_pan.as_ref()
+    }
+
+    /// Bank that issued the card, if it's a known iranian one.
+    pub fn card_bank(&self) -> Option<crate::card::Bank> {
+        crate::card::Bank::from_pan(&self.card_pan)
+    }
+
+    /// Description of the payment.
+    pub fn description(&self) -> &str {
+        self.description.as_ref()
+    }
+
+    /// Order id of the payment.
+    pub fn order_id(&self) -> Option<&str> {
+        self.order_id.as_deref()
+    }
+
+    /// Name of the merchant (shop) to show on the receipt.
+    pub fn merchant_name(&self) -> Option<&str> {
+        self.merchant_name.as_deref()
+    }
+
+    /// Date and time of the payment, like `2024-06-27 17:33:25`.
+    pub fn paid_at(&self) -> Option<&str> {
+        self.paid_at.as_deref()
+    }
+}
+
+/// Formats `amount` with thousands separated by `,`, like `1,250,000`.
+pub(crate) fn group_thousands(amount: u64) -> String {
+    let digits = amount.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+impl Display for Receipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Payment receipt")?;
+        if let Some(merchant_name) = &self.merchant_name {
+            writeln!(f, "Merchant: {merchant_name}")?;
+        }
+        writeln!(
+            f,
+            "Amount: {} {}",
+            group_thousands(self.amount),
+            self.currency
+        )?;
+        if !self.description.is_empty() {
+            writeln!(f, "Description: {}", self.description)?;
+        }
+        if let Some(order_id) = &self.order_id {
+            writeln!(f, "Order id: {order_id}")?;
+        }
+        writeln!(f, "Reference id: {}", self.ref_id)?;
+        writeln!(f, "Authority: {}", self.authority)?;
+        match self.card_bank() {
+            Some(bank) => writeln!(f, "Card: {} ({bank})", self.card_pan)?,
+            None => writeln!(f, "Card: {}", self.card_pan)?,
+        }
+        if let Some(paid_at) = &self.paid_at {
+            writeln!(f, "Date: {paid_at}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "receipt-render")]
+impl Receipt {
+    /// Renders the receipt as a standalone html page in `locale`, to be emailed to the payer
+    /// after verification.
+    ///
+    /// Persian receipts are laid out right to left, with persian digits and labels (and
+    /// Jalali dates, if `jalali` feature is enabled). Values like authority and card number
+    /// are kept left to right either way. For a PDF, pass the page to an html to PDF
+    /// converter (like weasyprint), which handles persian fonts and shaping.
+    ///
+    /// ```
+    /// use zarinpal::receipt::Receipt;
+    /// use zarinpal::prelude::*;
+    ///
+    /// let receipt = Receipt::builder()
+    ///     .authority("A00000000000000000000000000217885159")
+    ///     .ref_id(201)
+    ///     .amount(1250000)
+    ///     .card_pan("502229******5995")
+    ///     .build();
+    ///
+    /// let html = receipt.to_html(MessageLocale::Persian);
+    /// assert!(html.contains(r#"<html lang="fa" dir="rtl">"#));
+    /// assert!(html.contains("۱٬۲۵۰٬۰۰۰ ریال"));
+    /// ```
+    pub fn to_html(&self, locale: crate::results::result_code::MessageLocale) -> String {
+        use crate::results::result_code::MessageLocale;
+
+        let persian = locale == MessageLocale::Persian;
+        let label = |english: &'static str, persian_label: &'static str| {
+            if persian {
+                persian_label
+            } else {
+                english
+            }
+        };
+        let number = |grouped: String| {
+            if persian {
+                to_persian_digits(&grouped)
+            } else {
+                grouped
+            }
+        };
+
+        let currency = match (&self.currency, persian) {
+            (Currency::IRR, true) => "ریال",
+            (Currency::IRT, true) => "تومان",
+            (currency, _) => currency.as_str(),
+        };
+        let card = match (self.card_bank(), persian) {
+            (Some(bank), true) => format!("{} ({})", self.card_pan, bank.persian_name()),
+            (Some(bank), false) => format!("{} ({bank})", self.card_pan),
+            (None, _) => self.card_pan.clone(),
+        };
+
+        // (label, value, whether value is always left to right)
+        let mut rows = vec![(
+            label("Amount", "مبلغ"),
+            format!("{} {currency}", number(group_thousands(self.amount))),
+            false,
+        )];
+        if !self.description.is_empty() {
+            rows.push((
+                label("Description", "توضیحات"),
+                self.description.clone(),
+                false,
+            ));
+        }
+        if let Some(order_id) = &self.order_id {
+            rows.push((label("Order id", "شماره سفارش"), order_id.clone(), true));
+        }
+        rows.push((
+            label("Reference id", "کد پیگیری"),
+            self.ref_id.to_string(),
+            true,
+        ));
+        rows.push((
+            label("Authority", "شناسه پرداخت"),
+            self.authority.clone(),
+            true,
+        ));
+        rows.push((label("Card", "کارت"), card, !persian));
+        if let Some(paid_at) = &self.paid_at {
+            #[cfg(feature = "jalali")]
+            let paid_at = match (
+                persian,
+                crate::jalali::JalaliDateTime::parse_gregorian(paid_at),
+            ) {
+                (true, Some(jalali)) => to_persian_digits(&jalali.to_string()),
+                _ => paid_at.clone(),
+            };
+            #[cfg(not(feature = "jalali"))]
+            let paid_at = paid_at.clone();
+            rows.push((label("Date", "تاریخ"), paid_at, !persian));
+        }
+
+        let title = label("Payment receipt", "رسید پرداخت");
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"{}\" dir=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n<style>\n\
+             body {{ font-family: Vazirmatn, Tahoma, sans-serif; margin: 2em; }}\n\
+             table {{ border-collapse: collapse; }}\n\
+             th, td {{ padding: 0.4em 0.8em; text-align: start; border-bottom: 1px solid #ddd; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            if persian { "fa" } else { "en" },
+            if persian { "rtl" } else { "ltr" },
+        );
+        if let Some(merchant_name) = &self.merchant_name {
+            html.push_str(&format!("<h2>{}</h2>\n", escape(merchant_name)));
+        }
+        html.push_str("<table>\n");
+        for (label, value, ltr) in rows {
+            let value = escape(&value);
+            if ltr {
+                html.push_str(&format!(
+                    "<tr><th>{label}</th><td><bdi dir=\"ltr\">{value}</bdi></td></tr>\n"
+                ));
+            } else {
+                html.push_str(&format!("<tr><th>{label}</th><td>{value}</td></tr>\n"));
+            }
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+/// Replaces ascii digits of `text` with persian ones, and `,` with the persian thousands
+/// separator.
+#[cfg(feature = "receipt-render")]
+fn to_persian_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '0'..='9' => char::from_u32('۰' as u32 + (c as u32 - '0' as u32)).unwrap(),
+            ',' => '٬',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::request::Metadata;
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1250000), "1,250,000");
+    }
+
+    #[test]
+    fn test_new() {
+        let payment: RequestPayment = RequestPayment::builder()
+            .amount(10000)
+            .description("Order #1234")
+            .metadata(Metadata::builder().order_id("1234").build())
+            .build();
+        let mut request: Request = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A00000000000000000000000000217885159",
+            "fee_type": "Merchant",
+            "fee": 100
+        }))
+        .unwrap();
+        request.set_requested(10000, Currency::IRT);
+        let verify: Verify = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Merchant",
+            "fee": 0
+        }))
+        .unwrap();
+
+        let receipt = Receipt::new(&payment, &request, &verify).with_paid_at("2024-06-27 17:33:25");
+
+        assert_eq!(receipt.amount(), 10000);
+        assert_eq!(receipt.currency(), &Currency::IRT);
+        assert_eq!(receipt.order_id(), Some("1234"));
+        assert_eq!(
+            receipt.to_string(),
+            "Payment receipt\n\
+             Amount: 10,000 IRT\n\
+             Description: Order #1234\n\
+             Order id: 1234\n\
+             Reference id: 201\n\
+             Authority: A00000000000000000000000000217885159\n\
+             Card: 502229******5995 (Bank Pasargad)\n\
+             Date: 2024-06-27 17:33:25\n"
+        );
+
+        let json = serde_json::to_value(&receipt).unwrap();
+        assert_eq!(json["currency"], "IRT");
+        assert_eq!(serde_json::from_value::<Receipt>(json).unwrap(), receipt);
+    }
+
+    #[cfg(feature = "receipt-render")]
+    #[test]
+    fn test_to_html() {
+        use crate::results::result_code::MessageLocale;
+
+        let receipt = Receipt::builder()
+            .authority("A00000000000000000000000000217885159")
+            .ref_id(201)
+            .amount(10000)
+            .currency(Currency::IRT)
+            .card_pan("502229******5995")
+            .description("<Order> #1234")
+            .merchant_name("My shop")
+            .build();
+
+        let html = receipt.to_html(MessageLocale::English);
+        assert!(html.contains(r#"<html lang="en" dir="ltr">"#));
+        assert!(html.contains("<h2>My shop</h2>"));
+        assert!(html.contains("<tr><th>Amount</th><td>10,000 IRT</td></tr>"));
+        assert!(html.contains("<td>&lt;Order&gt; #1234</td>"));
+        assert!(html.contains(
+            r#"<tr><th>Card</th><td><bdi dir="ltr">502229******5995 (Bank Pasargad)</bdi></td></tr>"#
+        ));
+
+        let html = receipt.to_html(MessageLocale::Persian);
+        assert!(html.contains(r#"<html lang="fa" dir="rtl">"#));
+        assert!(html.contains("<tr><th>مبلغ</th><td>۱۰٬۰۰۰ تومان</td></tr>"));
+        assert!(html.contains(r#"<tr><th>کد پیگیری</th><td><bdi dir="ltr">201</bdi></td></tr>"#));
+        assert!(html.contains("<td>502229******5995 (بانک پاسارگاد)</td>"));
+    }
+}
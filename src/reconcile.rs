@@ -0,0 +1,219 @@
+//! Reconciling a local ledger of payments with zarinpal's unverified payments.
+//!
+//! Unverified payments are the ones that are paid, but not verified yet. Comparing them with
+//! payments you're expecting (pending in your ledger) reveals payments you don't know about,
+//! payments that are never paid and amounts that don't match.
+
+use std::collections::HashMap;
+
+use crate::{
+    error::ZarinResult,
+    extensions::ZarinpalSendExtension,
+    results::unverified::{Authorities, Unverified},
+    ZarinpalClient,
+};
+
+/// A payment that's expected to be paid, according to the local ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedPayment {
+    authority: String,
+    amount: u64,
+}
+
+impl ExpectedPayment {
+    /// Creates an expected payment, `amount` must be in the currency it's requested with.
+    pub fn new(authority: impl Into<String>, amount: u64) -> Self {
+        Self {
+            authority: authority.into(),
+            amount,
+        }
+    }
+
+    /// Unique authority of the payment.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Expected amount of the payment.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<crate::store::PaymentRecord> for ExpectedPayment {
+    fn from(record: crate::store::PaymentRecord) -> Self {
+        Self::new(record.authority(), record.amount())
+    }
+}
+
+/// A payment with different amounts in the ledger and in zarinpal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountMismatch {
+    /// Expected payment, from the ledger.
+    pub expected: ExpectedPayment,
+
+    /// Unverified payment, from zarinpal.
+    pub actual: Authorities,
+}
+
+/// Result of [`reconcile`].
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileReport {
+    matched: Vec<Authorities>,
+    missing_locally: Vec<Authorities>,
+    missing_remotely: Vec<ExpectedPayment>,
+    amount_mismatches: Vec<AmountMismatch>,
+}
+
+impl ReconcileReport {
+    /// Paid payments that are expected with the same amount, these can be verified.
+    pub fn matched(&self) -> &[Authorities] {
+        self.matched.as_ref()
+    }
+
+    /// Paid payments that are not in the ledger.
+    pub fn missing_locally(&self) -> &[Authorities] {
+        self.missing_locally.as_ref()
+    }
+
+    /// Expected payments that are not paid (yet), or are already verified elsewhere.
+    pub fn missing_remotely(&self) -> &[ExpectedPayment] {
+        self.missing_remotely.as_ref()
+    }
+
+    /// Paid payments that are expected with a different amount.
+    pub fn amount_mismatches(&self) -> &[AmountMismatch] {
+        self.amount_mismatches.as_ref()
+    }
+
+    /// Returns `true` if nothing is missing and all amounts match.
+    pub fn is_clean(&self) -> bool {
+        self.missing_locally.is_empty()
+            && self.missing_remotely.is_empty()
+            && self.amount_mismatches.is_empty()
+    }
+}
+
+/// Compares expected payments of a ledger with unverified payments returned by zarinpal.
+///
+/// Items of each list in the report keep the order of their source.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::reconcile::{reconcile, ExpectedPayment};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let unverified = zarinpal.unverified_requests().build().await?;
+///     let report = reconcile(
+///         [ExpectedPayment::new("A00000000000000000000000000217885159", 10000)],
+///         &unverified,
+///     );
+///
+///     for unknown in report.missing_locally() {
+///         println!("Unknown payment: {}", unknown.authority());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn reconcile(
+    expected: impl IntoIterator<Item = ExpectedPayment>,
+    unverified: &Unverified,
+) -> ReconcileReport {
+    let mut expected_by_authority = HashMap::new();
+    let mut expected_order = Vec::new();
+    for payment in expected {
+        expected_order.push(payment.authority.clone());
+        expected_by_authority.insert(payment.authority.clone(), payment);
+    }
+
+    let mut report = ReconcileReport::default();
+    for actual in unverified.authorities() {
+        match expected_by_authority.remove(actual.authority()) {
+            Some(expected) if expected.amount == actual.amount() => {
+                report.matched.push(actual.clone())
+            }
+            Some(expected) => report.amount_mismatches.push(AmountMismatch {
+                expected,
+                actual: actual.clone(),
+            }),
+            None => report.missing_locally.push(actual.clone()),
+        }
+    }
+
+    report.missing_remotely = expected_order
+        .iter()
+        .filter_map(|authority| expected_by_authority.remove(authority))
+        .collect();
+    report
+}
+
+/// Reconciles pending payments of `store` with unverified payments of `zarinpal`.
+#[cfg(feature = "store")]
+pub async fn reconcile_store<Z, S>(zarinpal: &Z, store: &S) -> ZarinResult<ReconcileReport>
+where
+    Z: ZarinpalClient + Sync + Send,
+    S: crate::store::PaymentStore + ?Sized,
+{
+    let pending = store.load_pending().await?;
+    reconcile_with(zarinpal, pending.into_iter().map(Into::into)).await
+}
+
+/// Reconciles `expected` payments with unverified payments of `zarinpal`.
+pub async fn reconcile_with<Z>(
+    zarinpal: &Z,
+    expected: impl IntoIterator<Item = ExpectedPayment>,
+) -> ZarinResult<ReconcileReport>
+where
+    Z: ZarinpalClient + Sync + Send,
+{
+    let unverified = zarinpal.unverified_requests().build().await?;
+    Ok(reconcile(expected, &unverified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconcile() {
+        let authorities = [("A1", 1000), ("A2", 2000), ("A3", 3000)].map(|(authority, amount)| {
+            serde_json::json!({
+                "authority": authority,
+                "amount": amount,
+                "callback_url": "https://example.com/",
+                "referer": "https://example.com/",
+                "date": "2024-06-27 17:33:25"
+            })
+        });
+        let unverified = serde_json::from_value::<Unverified>(serde_json::json!({
+            "code": "100",
+            "message": "Success",
+            "authorities": authorities
+        }))
+        .unwrap();
+
+        let report = reconcile(
+            [
+                ExpectedPayment::new("A4", 4000),
+                ExpectedPayment::new("A2", 2500),
+                ExpectedPayment::new("A1", 1000),
+            ],
+            &unverified,
+        );
+
+        assert_eq!(report.matched()[0].authority(), "A1");
+        assert_eq!(report.missing_locally()[0].authority(), "A3");
+        assert_eq!(
+            report.missing_remotely(),
+            [ExpectedPayment::new("A4", 4000)]
+        );
+        assert_eq!(report.amount_mismatches()[0].expected.amount(), 2500);
+        assert_eq!(report.amount_mismatches()[0].actual.amount(), 2000);
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,106 @@
+//! Masking sensitive values (merchant ids, card numbers, contact info, ...) before they're logged
+//! or sent to third party services.
+
+/// Json keys whose values are masked by [`redact_json`].
+pub const SENSITIVE_KEYS: &[&str] = &[
+    "merchant_id",
+    "card_pan",
+    "card_hash",
+    "mobile",
+    "email",
+    "iban",
+    "national_code",
+    "access_token",
+];
+
+/// Masks all characters of `value` except `keep_start` first and `keep_end` last ones.
+///
+/// ```
+/// use zarinpal::redact::mask;
+///
+/// assert_eq!(mask("5022291083818920", 6, 4), "502229******8920");
+/// assert_eq!(mask("short", 6, 4), "*****");
+/// ```
+pub fn mask(value: &str, keep_start: usize, keep_end: usize) -> String {
+    let count = value.chars().count();
+    if count <= keep_start + keep_end {
+        return "*".repeat(count);
+    }
+
+    value
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < keep_start || i >= count - keep_end {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+/// Masks values of [`SENSITIVE_KEYS`] anywhere in a json value, in place.
+pub fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    serde_json::Value::String(string) if SENSITIVE_KEYS.contains(&key.as_str()) => {
+                        *string = mask(string, 4, 4)
+                    }
+                    _ => redact_json(value),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => (),
+    }
+}
+
+/// Masks [`SENSITIVE_KEYS`] of a json map for `Debug` output, see [`redact_json`].
+#[cfg(not(feature = "unredacted-debug"))]
+pub(crate) fn redacted_map(
+    map: &std::collections::HashMap<String, serde_json::Value>,
+) -> serde_json::Value {
+    let mut value = serde_json::Value::Object(map.clone().into_iter().collect());
+    redact_json(&mut value);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_json() {
+        let mut value = serde_json::json!({
+            "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+            "amount": 1000,
+            "metadata": {
+                "mobile": "09121234567",
+                "order_id": "1024"
+            },
+            "wages": [
+                { "iban": "IR130570028780010957775103", "amount": 1000 }
+            ]
+        });
+
+        redact_json(&mut value);
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "merchant_id": "1344****************************05be",
+                "amount": 1000,
+                "metadata": {
+                    "mobile": "0912***4567",
+                    "order_id": "1024"
+                },
+                "wages": [
+                    { "iban": "IR13******************5103", "amount": 1000 }
+                ]
+            })
+        );
+    }
+}
@@ -1,10 +1,82 @@
+#[cfg(feature = "strict")]
+use std::cell::Cell;
 use std::fmt;
 use std::marker::PhantomData;
+use std::str::FromStr;
 
-use serde::de::{self, Visitor};
+use serde::de::{self, DeserializeOwned, Visitor};
 use serde::{Deserialize, Deserializer};
 
-use crate::error::ApiError;
+use crate::error::{ApiError, ZarinResult};
+
+use super::DeserializationMode;
+
+#[cfg(feature = "strict")]
+thread_local! {
+    /// Whether the response being deserialized (on this thread) is in strict mode.
+    static STRICT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Deserializes a response `value` in `mode`, see [`DeserializationMode`].
+pub(crate) fn deserialize_response<T: DeserializeOwned>(
+    value: serde_json::Value,
+    mode: DeserializationMode,
+) -> ZarinResult<T> {
+    match mode {
+        DeserializationMode::Lenient => Ok(serde_json::from_value(value)?),
+        #[cfg(feature = "strict")]
+        DeserializationMode::Strict => {
+            let mut unknown_fields = Vec::new();
+
+            let was_strict = STRICT.replace(true);
+            let result =
+                serde_ignored::deserialize(value, |path| unknown_fields.push(path.to_string()));
+            STRICT.set(was_strict);
+
+            let result = result?;
+            if unknown_fields.is_empty() {
+                Ok(result)
+            } else {
+                Err(crate::error::Error::UnknownFields(unknown_fields))
+            }
+        }
+    }
+}
+
+/// Returns `true` if a response is being deserialized in [`DeserializationMode::Strict`].
+pub(crate) fn is_strict() -> bool {
+    #[cfg(feature = "strict")]
+    return STRICT.get();
+
+    #[cfg(not(feature = "strict"))]
+    false
+}
+
+/// An enum with a catch-all variant for values unknown to this crate.
+pub(crate) trait MaybeUnknown {
+    fn is_unknown(&self) -> bool;
+}
+
+/// Deserializes an enum that may be unknown, failing if it is in strict mode.
+///
+/// Use it with `#[serde(deserialize_with = "...")]` on fields like fee types.
+pub(crate) fn known<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + MaybeUnknown,
+{
+    let value = T::deserialize(deserializer)?;
+    if value.is_unknown() && is_strict() {
+        return Err(de::Error::custom(format!(
+            "unknown {} value",
+            std::any::type_name::<T>()
+                .rsplit("::")
+                .next()
+                .unwrap_or_default()
+        )));
+    }
+    Ok(value)
+}
 
 struct MapOrSeq<T>(PhantomData<fn() -> T>);
 
@@ -36,18 +108,13 @@ where
 }
 
 /// [`T`] Here must be an mapping (struct).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub(crate) enum WiredOption<T> {
     Some(T),
+    #[default]
     None,
 }
 
-impl<T> Default for WiredOption<T> {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl<T> From<WiredOption<T>> for Option<T> {
     fn from(value: WiredOption<T>) -> Self {
         match value {
@@ -80,6 +147,45 @@ where
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString<T> {
+    Number(T),
+    String(String),
+}
+
+/// Deserializes a number that may be sent as either a json number or a numeric string.
+///
+/// Use it with `#[serde(deserialize_with = "...")]` on amounts, fees and alike, since
+/// the api is not consistent about them between endpoints and versions.
+pub(crate) fn number_or_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    match NumberOrString::<T>::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(string) => string.trim().parse().map_err(de::Error::custom),
+    }
+}
+
+/// Like [`number_or_string`], for optional fields (use with `#[serde(default)]` too).
+pub(crate) fn option_number_or_string<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    match Option::<NumberOrString<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(number)) => Ok(Some(number)),
+        Some(NumberOrString::String(string)) => {
+            string.trim().parse().map(Some).map_err(de::Error::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +226,98 @@ mod tests {
         println!("{result:#?}");
         assert!(result.data.is_some())
     }
+
+    #[derive(Debug, Deserialize)]
+    struct Amounts {
+        #[serde(deserialize_with = "number_or_string")]
+        amount: u64,
+        #[serde(deserialize_with = "number_or_string")]
+        fee: u64,
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    fn test_deserialization_modes() {
+        use crate::{
+            error::Error,
+            results::{inquiry::Inquiry, RequestResult},
+        };
+
+        let known = serde_json::json!({
+            "data": { "code": 100, "message": "Success", "status": "PAID" },
+            "errors": []
+        });
+        let extra_field = serde_json::json!({
+            "data": { "code": 100, "message": "Success", "status": "PAID", "order_id": "1" },
+            "errors": []
+        });
+        let unknown_status = serde_json::json!({
+            "data": { "code": 100, "message": "Success", "status": "REFUNDED" },
+            "errors": []
+        });
+        let unknown_code = serde_json::json!({
+            "data": { "code": 102, "message": "Success", "status": "PAID" },
+            "errors": []
+        });
+
+        for value in [&known, &extra_field, &unknown_status, &unknown_code] {
+            assert!(deserialize_response::<ApiResult<Inquiry>>(
+                value.clone(),
+                DeserializationMode::Lenient
+            )
+            .is_ok());
+        }
+
+        let inquiry =
+            deserialize_response::<ApiResult<Inquiry>>(known, DeserializationMode::Strict)
+                .unwrap()
+                .into_result()
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            inquiry.code(),
+            crate::results::result_code::ResultCode::Success
+        );
+
+        assert!(matches!(
+            deserialize_response::<ApiResult<Inquiry>>(extra_field, DeserializationMode::Strict),
+            Err(Error::UnknownFields(fields)) if fields == ["data.order_id"]
+        ));
+        assert!(matches!(
+            deserialize_response::<ApiResult<Inquiry>>(unknown_status, DeserializationMode::Strict),
+            Err(Error::JsonError(_))
+        ));
+        assert!(matches!(
+            deserialize_response::<ApiResult<Inquiry>>(unknown_code, DeserializationMode::Strict),
+            Err(Error::JsonError(_))
+        ));
+        assert!(!is_strict());
+    }
+
+    #[test]
+    fn test_number_or_string() {
+        let from_numbers = serde_json::from_value::<Amounts>(serde_json::json!({
+            "amount": 1000,
+            "fee": 10
+        }))
+        .unwrap();
+        assert_eq!(from_numbers.amount, 1000);
+        assert_eq!(from_numbers.fee, 10);
+
+        let from_strings = serde_json::from_value::<Amounts>(serde_json::json!({
+            "amount": "1000",
+            "fee": "10"
+        }))
+        .unwrap();
+        assert_eq!(from_strings.amount, 1000);
+        assert_eq!(from_strings.fee, 10);
+
+        assert!(serde_json::from_value::<Amounts>(serde_json::json!({
+            "amount": "ten",
+            "fee": 10
+        }))
+        .is_err());
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -129,3 +327,16 @@ pub(crate) struct ApiResult<R> {
     #[serde(default = "WiredOption::default")]
     pub(crate) errors: WiredOption<ApiError>,
 }
+
+impl<R> ApiResult<R> {
+    /// Data of a successful response, or the errors.
+    ///
+    /// `None` if the response has neither, eg: `{"data": [], "errors": []}`.
+    pub(crate) fn into_result(self) -> Option<Result<R, ApiError>> {
+        match (self.data, self.errors) {
+            (WiredOption::Some(data), _) => Some(Ok(data)),
+            (WiredOption::None, WiredOption::Some(errors)) => Some(Err(errors)),
+            (WiredOption::None, WiredOption::None) => None,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use super::{result_code::ResultCode, verify::Verify, RequestResult};
+
+/// Current status of a payment request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaymentStatus {
+    /// The payment is paid and verified.
+    Verified,
+
+    /// The payment is paid, but not verified yet.
+    Paid,
+
+    /// The payer is still in the bank gateway.
+    InBank,
+
+    /// The payment is failed (or canceled by the payer).
+    Failed,
+
+    /// The payment is reversed.
+    Reversed,
+
+    /// The payment session is expired.
+    Expired,
+
+    /// A status that's not known to this crate.
+    #[serde(other)]
+    Unknown,
+}
+
+impl super::__private::MaybeUnknown for PaymentStatus {
+    fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown)
+    }
+}
+
+/// The result type of a successful [`crate::methods::inquiry::InquiryPayment`] request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inquiry {
+    code: ResultCode,
+    message: String,
+
+    /// Current status of the payment request.
+    #[serde(deserialize_with = "super::__private::known")]
+    status: PaymentStatus,
+}
+
+impl PaymentStatus {
+    /// If the payment reached a final status (paid, verified, failed, reversed or expired).
+    pub fn is_settled(&self) -> bool {
+        !matches!(self, PaymentStatus::InBank | PaymentStatus::Unknown)
+    }
+}
+
+#[cfg(feature = "testkit")]
+impl Inquiry {
+    /// A mocked result of an inquiry request.
+    pub fn mock(status: PaymentStatus) -> Self {
+        Self {
+            code: ResultCode::Success,
+            message: "Success".to_string(),
+            status,
+        }
+    }
+}
+
+impl Inquiry {
+    /// Current status of the payment request.
+    pub fn status(&self) -> PaymentStatus {
+        self.status
+    }
+}
+
+impl RequestResult for Inquiry {
+    fn code(&self) -> ResultCode {
+        self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Outcome of [`crate::extensions::ZarinpalSendExtension::check_and_verify`].
+///
+/// A verify request is only sent if the payment is actually paid.
+#[derive(Debug, Clone)]
+pub enum CheckOutcome {
+    /// The payment is verified for the first time.
+    Verified(Verify),
+
+    /// The payment was verified before.
+    AlreadyVerified(Verify),
+
+    /// The payer is still in the bank gateway, try again later.
+    Pending,
+
+    /// The payment is failed or canceled.
+    Failed,
+
+    /// The payment is reversed.
+    Reversed,
+
+    /// The payment session is expired.
+    Expired,
+
+    /// Inquiry returned a status that's not known to this crate.
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialization() {
+        let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Inquiry>>(
+            serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Success",
+                    "status": "IN_BANK"
+                },
+                "errors": []
+            }),
+        )
+        .unwrap();
+
+        let data: Option<Inquiry> = from_json.data.into();
+        let data = data.unwrap();
+
+        assert_eq!(data.code, ResultCode::Success);
+        assert_eq!(data.status, PaymentStatus::InBank);
+    }
+
+    #[test]
+    fn test_deserialization_unknown_status() {
+        let data = serde_json::from_value::<Inquiry>(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "status": "SOMETHING_NEW"
+        }))
+        .unwrap();
+
+        assert_eq!(data.status(), PaymentStatus::Unknown);
+    }
+}
@@ -1,8 +1,15 @@
 //! Contains result types of the requests.
 
 pub mod __private;
+pub mod inquiry;
+#[cfg(feature = "graphql")]
+pub mod refund;
 pub mod request;
 pub mod result_code;
+pub mod reverse;
+#[cfg(any(feature = "sqlx-postgres", feature = "sqlx-sqlite"))]
+pub mod sqlx;
+#[cfg(feature = "unverified")]
 pub mod unverified;
 pub mod verify;
 
@@ -12,7 +19,7 @@ use result_code::ResultCode;
 
 use crate::error::ApiError;
 
-pub trait RequestResult: DeserializeOwned {
+pub trait RequestResult: DeserializeOwned + 'static {
     /// **Error code returned from api.**
     ///
     /// _In this case (Successful request) it must either be `100` (Success) or `100` (Already verified,
@@ -28,14 +35,15 @@ pub trait RequestResult: DeserializeOwned {
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
-impl<R: RequestResult> From<__private::ApiResult<R>> for ApiResult<R> {
-    fn from(value: __private::ApiResult<R>) -> Self {
-        match value.data {
-            __private::WiredOption::Some(data) => Ok(data),
-            __private::WiredOption::None => match value.errors {
-                __private::WiredOption::Some(errors) => Err(errors),
-                __private::WiredOption::None => unreachable!(),
-            },
-        }
-    }
+/// How strictly api responses are deserialized, see [`crate::Zarinpal::with_deserialization_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DeserializationMode {
+    /// Rejects fields and enum values (fee types, payment statuses and result codes) unknown to
+    /// this crate, to detect changes of the api early. Eg: in CI, against the sandbox.
+    #[cfg(feature = "strict")]
+    Strict,
+
+    /// Ignores unknown fields and maps unknown enum values to their `Unknown` variants.
+    #[default]
+    Lenient,
 }
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Progress of a [`Refund`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefundTimeline {
+    /// Refunded amount.
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    refund_amount: u64,
+
+    /// When the refund was registered.
+    refund_time: String,
+
+    /// Status of the refund, like `PENDING` or `DONE`.
+    refund_status: String,
+}
+
+impl RefundTimeline {
+    /// Refunded amount.
+    pub fn refund_amount(&self) -> u64 {
+        self.refund_amount
+    }
+
+    /// When the refund was registered.
+    pub fn refund_time(&self) -> &str {
+        self.refund_time.as_ref()
+    }
+
+    /// When the refund was registered, in Jalali calendar.
+    #[cfg(feature = "jalali")]
+    pub fn refund_time_jalali(&self) -> Option<crate::jalali::JalaliDateTime> {
+        crate::jalali::JalaliDateTime::parse_gregorian(&self.refund_time)
+    }
+
+    /// Status of the refund, like `PENDING` or `DONE`.
+    pub fn refund_status(&self) -> &str {
+        self.refund_status.as_ref()
+    }
+}
+
+/// The result type of a successful [`crate::methods::refund::RefundPayment`] request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Refund {
+    /// Unique id of the refund.
+    id: String,
+
+    /// Id of the terminal that the payment belongs to.
+    terminal_id: String,
+
+    /// Amount of the refunded payment.
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    amount: u64,
+
+    /// Progress of the refund.
+    timeline: RefundTimeline,
+}
+
+impl Refund {
+    /// Unique id of the refund.
+    pub fn id(&self) -> &str {
+        self.id.as_ref()
+    }
+
+    /// Id of the terminal that the payment belongs to.
+    pub fn terminal_id(&self) -> &str {
+        self.terminal_id.as_ref()
+    }
+
+    /// Amount of the refunded payment.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Progress of the refund.
+    pub fn timeline(&self) -> &RefundTimeline {
+        &self.timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialization() {
+        let refund: Refund = serde_json::from_value(serde_json::json!({
+            "terminal_id": "238",
+            "id": "1651254143",
+            "amount": "20000",
+            "timeline": {
+                "refund_amount": 20000,
+                "refund_time": "2024-08-04T16:45:28+03:30",
+                "refund_status": "PENDING"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(refund.amount(), 20000);
+        assert_eq!(refund.timeline().refund_status(), "PENDING");
+    }
+}
@@ -1,31 +1,203 @@
-use serde::Deserialize;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    environment::GatewayUrl,
+    error::{Error, ZarinResult},
+    methods::{request::Currency, verify::VerifyPayment},
+    ZarinpalClient,
+};
 
 use super::{result_code::ResultCode, verify::FeeType, RequestResult};
 
+/// Gateway that a payer is sent to, see [`Request::gateway_url_with`].
+///
+/// Other than the default zarinpal gateway, payers can be routed directly to a bank's gateway.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum GatewayKind {
+    /// The zarinpal gateway, where payers choose how to pay.
+    #[default]
+    Default,
+
+    /// Zarin Gate, routing payers directly to a bank gateway (must be enabled for the terminal).
+    ZarinGate,
+
+    /// Saderat bank gateway.
+    Sad,
+
+    /// Saman (SEP) bank gateway.
+    Sep,
+
+    /// Any other gateway, by its url suffix (Eg: `"Asan"`).
+    Other(String),
+}
+
+impl GatewayKind {
+    /// Suffix of the gateway url, `None` for the default gateway.
+    pub fn suffix(&self) -> Option<&str> {
+        match self {
+            GatewayKind::Default => None,
+            GatewayKind::ZarinGate => Some("ZarinGate"),
+            GatewayKind::Sad => Some("Sad"),
+            GatewayKind::Sep => Some("Sep"),
+            GatewayKind::Other(suffix) => Some(suffix),
+        }
+    }
+}
+
 /// The result type of a successful [`crate::methods::request::RequestPayment`] request.
-#[derive(Debug, Clone, Deserialize)]
+///
+/// Amount, currency and gateway are not part of the api response, they're filled in by the
+/// client. They're serialized (and deserialized if present) so stored results keep them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Request {
-    code: ResultCode,
-    message: String,
+    pub(crate) code: ResultCode,
+    pub(crate) message: String,
 
     /// Unique authority of the payment request.
-    authority: String,
+    pub(crate) authority: String,
 
     /// Fee type. Indicates if the [`FeeType::Merchant`] is responsible for payment fee or [`FeeType::Payer`].
-    fee_type: FeeType,
+    #[serde(deserialize_with = "super::__private::known")]
+    pub(crate) fee_type: FeeType,
 
     /// Fee amount.
-    fee: u64,
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    pub(crate) fee: u64,
+
+    /// Amount of the payment request, in [`Request::currency`].
+    #[serde(default)]
+    pub(crate) amount: u64,
+
+    /// Currency used in the payment request.
+    #[serde(default)]
+    pub(crate) currency: Currency,
+
+    /// Payment gateway of the client that sent the payment request.
+    #[serde(default)]
+    pub(crate) gateway: GatewayUrl,
+
+    /// Fields of the response that are not modeled by this crate (yet), requires
+    /// `capture-extra` feature to be captured.
+    #[cfg_attr(feature = "capture-extra", serde(flatten))]
+    #[cfg_attr(not(feature = "capture-extra"), serde(skip))]
+    pub(crate) extra: HashMap<String, serde_json::Value>,
+}
+
+#[cfg(feature = "testkit")]
+impl Request {
+    /// A mocked result of a successful payment request.
+    pub fn mock_success(authority: impl Into<String>) -> Self {
+        Self {
+            code: ResultCode::Success,
+            message: "Success".to_string(),
+            authority: authority.into(),
+            fee_type: FeeType::Merchant,
+            fee: 0,
+            amount: 0,
+            currency: Currency::default(),
+            gateway: GatewayUrl::default(),
+            extra: Default::default(),
+        }
+    }
 }
 
 impl Request {
     /// Returns a url to the zarinpal payment gateway for this payment request (`authority` attached.)
     ///
     /// _This is the url that user should be redirected to, after a successful payment request._
-    pub fn gateway_url(&self) -> reqwest::Url {
-        format!("https://www.zarinpal.com/pg/StartPay/{}", self.authority())
-            .parse()
-            .unwrap()
+    ///
+    /// It's on the gateway of the client that sent the request (see
+    /// [`crate::ZarinpalClient::gateway_url`]), like the sandbox one if the request is sent to
+    /// the sandbox.
+    pub fn gateway_url(&self) -> url::Url {
+        self.gateway_url_with(&GatewayKind::Default)
+    }
+
+    /// Returns a url to the gateway of `kind` for this payment request, like
+    /// `https://www.zarinpal.com/pg/StartPay/{authority}/ZarinGate`.
+    pub fn gateway_url_with(&self, kind: &GatewayKind) -> url::Url {
+        self.gateway.payment_url(self.authority(), kind)
+    }
+
+    /// A `302 Found` response redirecting the payer to [`Request::gateway_url`] (requires `http`
+    /// feature).
+    #[cfg(feature = "http")]
+    pub fn gateway_redirect<B: Default>(&self) -> http::Response<B> {
+        self.gateway_redirect_with(&GatewayKind::Default)
+    }
+
+    /// A `302 Found` response redirecting the payer to the gateway of `kind`, see
+    /// [`Request::gateway_url_with`] (requires `http` feature).
+    #[cfg(feature = "http")]
+    pub fn gateway_redirect_with<B: Default>(&self, kind: &GatewayKind) -> http::Response<B> {
+        let location = http::HeaderValue::from_str(self.gateway_url_with(kind).as_str())
+            .expect("urls are valid header values");
+
+        let mut response = http::Response::new(B::default());
+        *response.status_mut() = http::StatusCode::FOUND;
+        response
+            .headers_mut()
+            .insert(http::header::LOCATION, location);
+        response
+    }
+
+    /// Returns a QR code of [`Request::gateway_url`], so payers can scan it to pay (requires
+    /// `qrcode` feature).
+    ///
+    /// Render it using [`crate::qr::QrCode::to_svg`] or [`crate::qr::QrCode::to_png`].
+    #[cfg(feature = "qrcode")]
+    pub fn gateway_qr(&self) -> Result<crate::qr::QrCode, crate::qr::DataTooLong> {
+        crate::qr::QrCode::encode(self.gateway_url().as_str().as_bytes())
+    }
+
+    /// Verify this payment request using the amount and currency it was requested with.
+    ///
+    /// This way the verify amount always matches the requested one,
+    /// avoiding [`ResultCode::InvalidSeasonUnmatchedAmounts`] errors.
+    pub fn verify<'z, Z: ZarinpalClient>(&self, zarinpal: &'z Z) -> VerifyPayment<'_, &'z Z> {
+        VerifyPayment::builder()
+            .zarinpal(zarinpal)
+            .authority(self.authority())
+            .amount(self.amount)
+            .build()
+    }
+
+    /// Verify this payment request using an `amount` in `currency`, which is converted
+    /// to the currency that the payment was requested with.
+    ///
+    /// Fails with [`Error::CurrencyConversionError`] if the amount can't be converted.
+    pub fn verify_amount<'z, Z: ZarinpalClient>(
+        &self,
+        zarinpal: &'z Z,
+        amount: u64,
+        currency: &Currency,
+    ) -> ZarinResult<VerifyPayment<'_, &'z Z>> {
+        let converted = currency.convert(amount, &self.currency).ok_or_else(|| {
+            Error::CurrencyConversionError {
+                amount,
+                from: currency.clone(),
+                to: self.currency.clone(),
+            }
+        })?;
+
+        Ok(VerifyPayment::builder()
+            .zarinpal(zarinpal)
+            .authority(self.authority())
+            .amount(converted)
+            .build())
+    }
+
+    /// Sets amount and currency the payment was requested with.
+    pub(crate) fn set_requested(&mut self, amount: u64, currency: Currency) {
+        self.amount = amount;
+        self.currency = currency;
+    }
+
+    /// Sets gateway of the client that sent the request.
+    pub(crate) fn set_gateway(&mut self, gateway: GatewayUrl) {
+        self.gateway = gateway;
     }
 }
 
@@ -36,14 +208,35 @@ impl Request {
     }
 
     /// Fee type. Indicates if the [`FeeType::Merchant`] is responsible for payment fee or [`FeeType::Payer`].
-    pub fn fee_type(&self) -> FeeType {
-        self.fee_type
+    pub fn fee_type(&self) -> &FeeType {
+        &self.fee_type
     }
 
     /// Fee amount.
     pub fn fee(&self) -> u64 {
         self.fee
     }
+
+    /// Amount of the payment request, in [`Request::currency`].
+    ///
+    /// _Only available on results of requests sent by this crate._
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Currency used in the payment request.
+    ///
+    /// _Only available on results of requests sent by this crate._
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Fields of the response that are not modeled by this crate (yet).
+    ///
+    /// Always empty, unless `capture-extra` feature is enabled.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
 }
 
 impl RequestResult for Request {
@@ -69,6 +262,10 @@ mod tests {
             authority: "A00000000000000000000000000217885159".to_string(),
             fee_type: FeeType::Merchant,
             fee: 100,
+            amount: 0,
+            currency: Currency::IRR,
+            gateway: GatewayUrl::default(),
+            extra: Default::default(),
         };
 
         let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Request>>(
@@ -94,4 +291,105 @@ mod tests {
         assert_eq!(data.fee, inner_model.fee);
         assert_eq!(data.fee_type, inner_model.fee_type);
     }
+
+    #[test]
+    fn test_serialization() {
+        let mut request = serde_json::from_value::<Request>(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A00000000000000000000000000217885159",
+            "fee_type": "Merchant",
+            "fee": 100
+        }))
+        .unwrap();
+        request.set_requested(1000, Currency::IRT);
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["amount"], 1000);
+        assert_eq!(json["currency"], "IRT");
+        assert_eq!(json["gateway"], "https://www.zarinpal.com/pg/StartPay/");
+
+        assert_eq!(serde_json::from_value::<Request>(json).unwrap(), request);
+    }
+
+    #[test]
+    fn test_verify_amount_conversion() {
+        let zarinpal = crate::Zarinpal::new_test().unwrap();
+        let mut request = Request {
+            code: ResultCode::Success,
+            message: "Success".to_string(),
+            authority: "A00000000000000000000000000217885159".to_string(),
+            fee_type: FeeType::Merchant,
+            fee: 100,
+            amount: 0,
+            currency: Currency::IRR,
+            gateway: GatewayUrl::default(),
+            extra: Default::default(),
+        };
+        request.set_requested(10000, Currency::IRT);
+
+        let verify = serde_json::to_value(request.verify(&zarinpal)).unwrap();
+        assert_eq!(verify["amount"], 10000);
+
+        let verify = serde_json::to_value(
+            request
+                .verify_amount(&zarinpal, 100000, &Currency::IRR)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(verify["amount"], 10000);
+
+        assert!(request
+            .verify_amount(&zarinpal, 100005, &Currency::IRR)
+            .is_err());
+    }
+
+    #[test]
+    fn test_gateway_url() {
+        let mut request: Request = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A00000000000000000000000000217885159",
+            "fee_type": "Merchant",
+            "fee": 100
+        }))
+        .unwrap();
+
+        assert_eq!(
+            request.gateway_url().as_str(),
+            "https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159"
+        );
+        assert_eq!(
+            request.gateway_url_with(&GatewayKind::ZarinGate).as_str(),
+            "https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159/ZarinGate"
+        );
+
+        request.set_gateway(crate::environment::Environment::Sandbox.gateway_url());
+        assert_eq!(
+            request
+                .gateway_url_with(&GatewayKind::Other("Asan".to_string()))
+                .as_str(),
+            "https://sandbox.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159/Asan"
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_gateway_redirect() {
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A1",
+            "fee_type": "Merchant",
+            "fee": 0
+        }))
+        .unwrap();
+        let response: http::Response<()> = request.gateway_redirect();
+
+        assert_eq!(response.status(), http::StatusCode::FOUND);
+        assert_eq!(
+            response.headers()[http::header::LOCATION],
+            "https://www.zarinpal.com/pg/StartPay/A1"
+        );
+    }
 }
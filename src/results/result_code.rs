@@ -1,6 +1,19 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// Language of messages that describe a [`ResultCode`] or an [`ApiError`](crate::error::ApiError).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MessageLocale {
+    /// English messages, the default.
+    #[default]
+    English,
+
+    /// Persian messages, as in zarinpal docs.
+    Persian,
+}
+
 /// The result code of a request made to the api.
 #[derive(Debug, Clone, Copy, Error, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ResultCode {
@@ -18,6 +31,10 @@ pub enum ResultCode {
     TerminalLevelToLow,
     #[error("Terminal user level is not valid : ( please contact our support team).")]
     TerminalBlueLevelRestriction,
+    #[error("The referrer address does not match the registered domain.")]
+    InvalidReferrer,
+    #[error("Terminal user transactions are banned.")]
+    TerminalTransactionsBanned,
     #[error("Success.")]
     Success,
     #[error("Terminal do not allow to accept floating wages.")]
@@ -42,6 +59,8 @@ pub enum ResultCode {
     ErrorInWages,
     #[error("Invalid extra params, expire_in is not valid.")]
     InvalidExpireInValue,
+    #[error("Maximum amount is 100,000,000 tomans.")]
+    AmountTooHigh,
     #[error("Session is not valid, amounts values is not the same.")]
     InvalidSeasonUnmatchedAmounts,
     #[error("Session is not valid, session is not active paid try.")]
@@ -52,93 +71,347 @@ pub enum ResultCode {
     InvalidSeasonInvalidMerchantId,
     #[error("Invalid authority.")]
     InvalidAuthority,
+    #[error("Manual payment request not found.")]
+    ManualPaymentNotFound,
     #[error("Already verified.")]
     Verified,
+    #[error("Session can not be reversed with bank.")]
+    ReverseNotPossible,
+    #[error("Session is not in success status.")]
+    ReverseSessionNotSuccessful,
+    #[error("You need to enable ip restriction of the terminal to reverse payments.")]
+    ReverseIpRestrictionRequired,
+    #[error("Maximum time for reverse this session is expired.")]
+    ReverseTimeExpired,
+    #[error("Refund amount is more than the remaining amount of the session.")]
+    RefundAmountTooHigh,
+    #[error("Terminal doesn't have access to refunds.")]
+    RefundNotAllowed,
     #[error("Unknown error code: {0}")]
     Unknown(i64),
 }
 
+impl ResultCode {
+    /// Persian description of this code, as in zarinpal docs.
+    pub fn message_fa(&self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
+            Self::Validation => "خطای اعتبار سنجی.",
+            Self::InvalidTerminalInfo => "آی‌پی یا مرچنت کد پذیرنده صحیح نیست.",
+            Self::InactiveTerminal => "مرچنت کد فعال نیست، لطفا با تیم پشتیبانی تماس بگیرید.",
+            Self::ToManyAttempts => "تلاش بیش از حد در یک بازه زمانی کوتاه.",
+            Self::SuspendTerminal => {
+                "ترمینال شما به حالت تعلیق در آمده، با تیم پشتیبانی تماس بگیرید."
+            }
+            Self::TerminalLevelToLow => "سطح تایید پذیرنده پایین‌تر از سطح نقره‌ای است.",
+            Self::TerminalBlueLevelRestriction => "محدودیت پذیرنده در سطح آبی.",
+            Self::InvalidReferrer => "آدرس ارجاع‌دهنده با دامنه ثبت شده مطابقت ندارد.",
+            Self::TerminalTransactionsBanned => "امکان ایجاد تراکنش برای این ترمینال وجود ندارد.",
+            Self::Success => "عملیات موفق.",
+            Self::FloatingWagesNotAllowed => "اجازه دسترسی به تسویه اشتراکی شناور ندارید.",
+            Self::TerminalCantAcceptWages => "حساب بانکی تسویه را به پنل اضافه کنید.",
+            Self::TotalFloatingWagesHigherThanMaxAmount => {
+                "مبلغ تسهیم شناور از مبلغ کل تراکنش بیشتر است."
+            }
+            Self::InvalidWagesFloating => "درصدهای وارد شده برای تسهیم درست نیست.",
+            Self::TotalFixedWagesHigherThanMaxAmount => {
+                "مبلغ تسهیم ثابت از مبلغ کل تراکنش بیشتر است."
+            }
+            Self::TooManyFloutingWagesPartition => {
+                "تعداد افراد دریافت کننده تسهیم بیش از حد مجاز است."
+            }
+            Self::FloatingWagesAmountTooLow => "حداقل مبلغ جهت تسهیم باید ۱۰,۰۰۰ ریال باشد.",
+            Self::OneOrMoreIBansAreInactive => {
+                "یک یا چند شماره شبای وارد شده برای تسهیم از سمت بانک غیر فعال است."
+            }
+            Self::IBanNotSetInShaparak => "شبای تسهیم در شاپرک تعریف نشده است.",
+            Self::ErrorInWages => "خطایی در تسهیم رخ داده است، به امور مشتریان زرین‌پال اطلاع دهید.",
+            Self::InvalidExpireInValue => "پارامترهای اضافی نامعتبر، expire_in معتبر نیست.",
+            Self::AmountTooHigh => "حداکثر مبلغ پرداختی ۱۰۰ میلیون تومان است.",
+            Self::InvalidSeasonUnmatchedAmounts => {
+                "مبلغ پرداخت شده با مبلغ ارسالی در متد وریفای متفاوت است."
+            }
+            Self::InvalidSeasonNoActivePayment => "پرداخت ناموفق.",
+            Self::InvalidSeason => "خطای غیر منتظره‌ای رخ داده است، با پشتیبانی تماس بگیرید.",
+            Self::InvalidSeasonInvalidMerchantId => "پرداخت متعلق به این مرچنت کد نیست.",
+            Self::InvalidAuthority => "اتوریتی نامعتبر است.",
+            Self::ManualPaymentNotFound => "درخواست پرداخت دستی یافت نشد.",
+            Self::Verified => "تراکنش قبلا وریفای شده است.",
+            Self::ReverseNotPossible => "امکان ریورس کردن تراکنش با بانک وجود ندارد.",
+            Self::ReverseSessionNotSuccessful => "تراکنش موفق نیست یا قبلا ریورس شده است.",
+            Self::ReverseIpRestrictionRequired => {
+                "برای ریورس تراکنش، محدودیت آی‌پی ترمینال را فعال کنید."
+            }
+            Self::ReverseTimeExpired => "حداکثر زمان ریورس کردن این تراکنش منقضی شده است.",
+            Self::RefundAmountTooHigh => "مبلغ استرداد از مبلغ باقی‌مانده تراکنش بیشتر است.",
+            Self::RefundNotAllowed => "ترمینال دسترسی به استرداد ندارد.",
+            Self::Unknown(code) => return Cow::Owned(format!("کد خطای ناشناخته: {code}")),
+        })
+    }
+
+    /// Description of this code in `locale`, the english one is the same as [`Display`](std::fmt::Display).
+    pub fn message_in(&self, locale: MessageLocale) -> Cow<'static, str> {
+        match locale {
+            MessageLocale::English => Cow::Owned(self.to_string()),
+            MessageLocale::Persian => self.message_fa(),
+        }
+    }
+
+    /// The integer representing this code in api responses, like `-54` for
+    /// [`InvalidAuthority`](ResultCode::InvalidAuthority).
+    pub fn code(&self) -> i64 {
+        match self {
+            ResultCode::Unknown(code) => *code,
+            known => CODES
+                .iter()
+                .find(|(_, result_code)| result_code == known)
+                .map(|(code, _)| *code)
+                .expect("all known result codes are in the table"),
+        }
+    }
+
+    /// Returns `true` for successful results, [`Success`] and [`Verified`].
+    ///
+    /// [`Success`]: ResultCode::Success
+    /// [`Verified`]: ResultCode::Verified
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success | Self::Verified)
+    }
+
+    /// Returns `true` if the error is about the terminal (merchant) itself, like an invalid
+    /// merchant id or a suspended terminal. These need a fix in the panel or configuration,
+    /// not a retry.
+    #[must_use]
+    pub fn is_terminal_config_error(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidTerminalInfo
+                | Self::InactiveTerminal
+                | Self::SuspendTerminal
+                | Self::TerminalLevelToLow
+                | Self::TerminalBlueLevelRestriction
+                | Self::InvalidReferrer
+                | Self::TerminalTransactionsBanned
+        )
+    }
+
+    /// Returns `true` if the error is about wages of the payment (codes `-30` to `-39`).
+    #[must_use]
+    pub fn is_wage_error(&self) -> bool {
+        matches!(
+            self,
+            Self::FloatingWagesNotAllowed
+                | Self::TerminalCantAcceptWages
+                | Self::TotalFloatingWagesHigherThanMaxAmount
+                | Self::InvalidWagesFloating
+                | Self::TotalFixedWagesHigherThanMaxAmount
+                | Self::TooManyFloutingWagesPartition
+                | Self::FloatingWagesAmountTooLow
+                | Self::OneOrMoreIBansAreInactive
+                | Self::IBanNotSetInShaparak
+                | Self::ErrorInWages
+        )
+    }
+
+    /// Returns `true` if the error is about the payment session or its authority
+    /// (codes `-50` to `-55`).
+    #[must_use]
+    pub fn is_session_error(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidSeasonUnmatchedAmounts
+                | Self::InvalidSeasonNoActivePayment
+                | Self::InvalidSeason
+                | Self::InvalidSeasonInvalidMerchantId
+                | Self::InvalidAuthority
+                | Self::ManualPaymentNotFound
+        )
+    }
+
+    /// Returns `true` if the error is about reversing a payment (codes `-60` to `-63`).
+    #[must_use]
+    pub fn is_reverse_error(&self) -> bool {
+        matches!(
+            self,
+            Self::ReverseNotPossible
+                | Self::ReverseSessionNotSuccessful
+                | Self::ReverseIpRestrictionRequired
+                | Self::ReverseTimeExpired
+        )
+    }
+
+    /// Returns `true` if the error is about refunding a payment.
+    #[must_use]
+    pub fn is_refund_error(&self) -> bool {
+        matches!(self, Self::RefundAmountTooHigh | Self::RefundNotAllowed)
+    }
+
+    /// Returns `true` if the same request may succeed later, that's only
+    /// [`ToManyAttempts`](ResultCode::ToManyAttempts).
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::ToManyAttempts)
+    }
+}
+
 impl Serialize for ResultCode {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_i64((*self).into())
+        serializer.serialize_i64(self.code())
     }
 }
 
+/// Codes are accepted as either a json number or a numeric string (like `"100"`), since
+/// some endpoints send them as strings.
 impl<'de> Deserialize<'de> for ResultCode {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        Ok(i64::deserialize(deserializer)?.into())
+        let code = ResultCode::from(super::__private::number_or_string::<_, i64>(deserializer)?);
+        if let ResultCode::Unknown(code) = code {
+            if super::__private::is_strict() {
+                return Err(serde::de::Error::custom(format!(
+                    "unknown result code {code}"
+                )));
+            }
+        }
+        Ok(code)
     }
 }
 
+/// Wire integer of each known result code, used for conversions in both directions.
+const CODES: &[(i64, ResultCode)] = &[
+    (-9, ResultCode::Validation),
+    (-10, ResultCode::InvalidTerminalInfo),
+    (-11, ResultCode::InactiveTerminal),
+    (-12, ResultCode::ToManyAttempts),
+    (-15, ResultCode::SuspendTerminal),
+    (-16, ResultCode::TerminalLevelToLow),
+    (-17, ResultCode::TerminalBlueLevelRestriction),
+    (-18, ResultCode::InvalidReferrer),
+    (-19, ResultCode::TerminalTransactionsBanned),
+    (100, ResultCode::Success),
+    (-30, ResultCode::FloatingWagesNotAllowed),
+    (-31, ResultCode::TerminalCantAcceptWages),
+    (-32, ResultCode::TotalFloatingWagesHigherThanMaxAmount),
+    (-33, ResultCode::InvalidWagesFloating),
+    (-34, ResultCode::TotalFixedWagesHigherThanMaxAmount),
+    (-35, ResultCode::TooManyFloutingWagesPartition),
+    (-36, ResultCode::FloatingWagesAmountTooLow),
+    (-37, ResultCode::OneOrMoreIBansAreInactive),
+    (-38, ResultCode::IBanNotSetInShaparak),
+    (-39, ResultCode::ErrorInWages),
+    (-40, ResultCode::InvalidExpireInValue),
+    (-41, ResultCode::AmountTooHigh),
+    (-50, ResultCode::InvalidSeasonUnmatchedAmounts),
+    (-51, ResultCode::InvalidSeasonNoActivePayment),
+    (-52, ResultCode::InvalidSeason),
+    (-53, ResultCode::InvalidSeasonInvalidMerchantId),
+    (-54, ResultCode::InvalidAuthority),
+    (-55, ResultCode::ManualPaymentNotFound),
+    (101, ResultCode::Verified),
+    (-60, ResultCode::ReverseNotPossible),
+    (-61, ResultCode::ReverseSessionNotSuccessful),
+    (-62, ResultCode::ReverseIpRestrictionRequired),
+    (-63, ResultCode::ReverseTimeExpired),
+    (-74, ResultCode::RefundAmountTooHigh),
+    (-80, ResultCode::RefundNotAllowed),
+];
+
 impl From<i64> for ResultCode {
     fn from(value: i64) -> Self {
-        use ResultCode::*;
-        match value {
-            -9 => Validation,
-            -10 => InvalidTerminalInfo,
-            -11 => InactiveTerminal,
-            -12 => ToManyAttempts,
-            -15 => SuspendTerminal,
-            -16 => TerminalLevelToLow,
-            -17 => TerminalBlueLevelRestriction,
-            100 => Success,
-            -30 => FloatingWagesNotAllowed,
-            -31 => TerminalCantAcceptWages,
-            -32 => TotalFloatingWagesHigherThanMaxAmount,
-            -33 => InvalidWagesFloating,
-            -34 => TotalFixedWagesHigherThanMaxAmount,
-            -35 => TooManyFloutingWagesPartition,
-            -36 => FloatingWagesAmountTooLow,
-            -37 => OneOrMoreIBansAreInactive,
-            -38 => IBanNotSetInShaparak,
-            -39 => ErrorInWages,
-            -40 => InvalidExpireInValue,
-            -50 => InvalidSeasonUnmatchedAmounts,
-            -51 => InvalidSeasonNoActivePayment,
-            -52 => InvalidSeason,
-            -53 => InvalidSeasonInvalidMerchantId,
-            -54 => InvalidAuthority,
-            101 => Verified,
-            e => Unknown(e),
-        }
+        CODES
+            .iter()
+            .find(|(code, _)| *code == value)
+            .map_or(ResultCode::Unknown(value), |(_, result_code)| *result_code)
     }
 }
 
 impl From<ResultCode> for i64 {
     fn from(value: ResultCode) -> Self {
-        match value {
-            ResultCode::Validation => -9,
-            ResultCode::InvalidTerminalInfo => -10,
-            ResultCode::InactiveTerminal => -11,
-            ResultCode::ToManyAttempts => -12,
-            ResultCode::SuspendTerminal => -15,
-            ResultCode::TerminalLevelToLow => -16,
-            ResultCode::TerminalBlueLevelRestriction => -17,
-            ResultCode::Success => 100,
-            ResultCode::FloatingWagesNotAllowed => 30,
-            ResultCode::TerminalCantAcceptWages => 31,
-            ResultCode::TotalFloatingWagesHigherThanMaxAmount => 32,
-            ResultCode::InvalidWagesFloating => 33,
-            ResultCode::TotalFixedWagesHigherThanMaxAmount => 34,
-            ResultCode::TooManyFloutingWagesPartition => 35,
-            ResultCode::FloatingWagesAmountTooLow => 36,
-            ResultCode::OneOrMoreIBansAreInactive => 37,
-            ResultCode::IBanNotSetInShaparak => 38,
-            ResultCode::ErrorInWages => 39,
-            ResultCode::InvalidExpireInValue => 40,
-            ResultCode::InvalidSeasonUnmatchedAmounts => 50,
-            ResultCode::InvalidSeasonNoActivePayment => 51,
-            ResultCode::InvalidSeason => 52,
-            ResultCode::InvalidSeasonInvalidMerchantId => 53,
-            ResultCode::InvalidAuthority => 54,
-            ResultCode::Verified => 101,
-            ResultCode::Unknown(e) => e,
+        value.code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories() {
+        assert!(ResultCode::Verified.is_success());
+        assert!(!ResultCode::Validation.is_success());
+        assert!(ResultCode::from(-18).is_terminal_config_error());
+        assert!(ResultCode::from(-36).is_wage_error());
+        assert!(ResultCode::from(-55).is_session_error());
+        assert!(ResultCode::from(-62).is_reverse_error());
+        assert!(ResultCode::from(-80).is_refund_error());
+        assert!(ResultCode::from(-12).is_retryable());
+        assert!(!ResultCode::from(-54).is_retryable());
+
+        assert_eq!(ResultCode::from(-41), ResultCode::AmountTooHigh);
+        assert_eq!(i64::from(ResultCode::RefundAmountTooHigh), -74);
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            ResultCode::InvalidAuthority.message_in(MessageLocale::English),
+            "Invalid authority."
+        );
+        assert_eq!(
+            ResultCode::InvalidAuthority.message_in(MessageLocale::Persian),
+            "اتوریتی نامعتبر است."
+        );
+        assert_eq!(
+            ResultCode::Unknown(-99).message_fa(),
+            "کد خطای ناشناخته: -99"
+        );
+    }
+
+    #[test]
+    fn test_known_codes_round_trip() {
+        for (code, result_code) in CODES {
+            assert_eq!(ResultCode::from(*code), *result_code);
+            assert_eq!(result_code.code(), *code);
+
+            let json = serde_json::to_value(result_code).unwrap();
+            assert_eq!(json, serde_json::json!(code));
+            assert_eq!(
+                serde_json::from_value::<ResultCode>(json).unwrap(),
+                *result_code
+            );
+        }
+
+        assert_eq!(ResultCode::InvalidAuthority.code(), -54);
+        assert_eq!(ResultCode::FloatingWagesNotAllowed.code(), -30);
+    }
+
+    #[test]
+    fn test_string_codes() {
+        assert_eq!(
+            serde_json::from_value::<ResultCode>(serde_json::json!("100")).unwrap(),
+            ResultCode::Success
+        );
+        assert_eq!(
+            serde_json::from_value::<ResultCode>(serde_json::json!(" -54 ")).unwrap(),
+            ResultCode::InvalidAuthority
+        );
+        assert!(serde_json::from_value::<ResultCode>(serde_json::json!("OK")).is_err());
+        assert!(serde_json::from_value::<ResultCode>(serde_json::json!(true)).is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_any_code_round_trips(code: i64) {
+            let result_code = ResultCode::from(code);
+            proptest::prop_assert_eq!(result_code.code(), code);
+
+            let json = serde_json::to_value(result_code).unwrap();
+            proptest::prop_assert_eq!(serde_json::from_value::<ResultCode>(json).unwrap(), result_code);
         }
     }
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use super::{result_code::ResultCode, RequestResult};
+
+/// The result type of a successful [`crate::methods::reverse::ReversePayment`] request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reverse {
+    code: ResultCode,
+    message: String,
+}
+
+#[cfg(feature = "testkit")]
+impl Reverse {
+    /// A mocked result of a successful reverse request.
+    pub fn mock_success() -> Self {
+        Self {
+            code: ResultCode::Success,
+            message: "Reversed".to_string(),
+        }
+    }
+}
+
+impl RequestResult for Reverse {
+    fn code(&self) -> ResultCode {
+        self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::results::{__private, ApiResult};
+
+    use super::*;
+
+    #[test]
+    fn test_deserialization() {
+        let result: ApiResult<Reverse> =
+            serde_json::from_value::<__private::ApiResult<Reverse>>(serde_json::json!({
+                "data": { "code": 100, "message": "Reversed" },
+                "errors": []
+            }))
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(result.unwrap().code(), ResultCode::Success);
+
+        let result: ApiResult<Reverse> =
+            serde_json::from_value::<__private::ApiResult<Reverse>>(serde_json::json!({
+                "data": [],
+                "errors": {
+                    "code": -63,
+                    "message": "Maximum time for reverse this session is expired.",
+                    "validations": []
+                }
+            }))
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(result.unwrap_err().code(), ResultCode::ReverseTimeExpired);
+    }
+}
@@ -0,0 +1,420 @@
+//! Storing results in a database using [`sqlx`](::sqlx) (`sqlx-postgres` and `sqlx-sqlite`
+//! features).
+//!
+//! [`ResultCode`] is stored as `INTEGER`, [`FeeType`] and [`Currency`] as `TEXT`. [`Verify`],
+//! [`Request`] and [`Authorities`] can be loaded from rows using [`FromRow`] (like
+//! `query_as`), and stored using [`SqlRecord`]. Amounts and reference ids are `BIGINT`s, wages
+//! and fields that are not modeled by this crate are not stored.
+//!
+//! ```no_run
+//! use zarinpal::prelude::*;
+//! use zarinpal::results::sqlx::SqlRecord;
+//!
+//! # async fn store(pool: sqlx::PgPool, verify: Verify) -> Result::<(), Box<dyn std::error::Error>> {
+//! // CREATE TABLE verified_payments (code INTEGER NOT NULL, message TEXT NOT NULL,
+//! //     card_hash TEXT NOT NULL, card_pan TEXT NOT NULL, ref_id BIGINT PRIMARY KEY,
+//! //     fee_type TEXT NOT NULL, fee BIGINT NOT NULL, shaparak_fee BIGINT, order_id TEXT)
+//! verify
+//!     .bind(sqlx::query(&Verify::insert_sql("verified_payments")))
+//!     .execute(&pool)
+//!     .await?;
+//!
+//! let verified: Vec<Verify> = sqlx::query_as("SELECT * FROM verified_payments")
+//!     .fetch_all(&pool)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use ::sqlx::{
+    encode::IsNull, error::BoxDynError, query::Query, ColumnIndex, Database, Decode, Encode,
+    FromRow, Row, Type,
+};
+
+use super::{
+    request::Request,
+    result_code::ResultCode,
+    verify::{FeeType, Verify},
+};
+use crate::{environment::GatewayUrl, methods::request::Currency};
+
+impl<DB: Database> Type<DB> for ResultCode
+where
+    i32: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i32 as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <i32 as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for ResultCode
+where
+    i32: Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        (self.code() as i32).encode_by_ref(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for ResultCode
+where
+    i32: Decode<'r, DB>,
+{
+    fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(i64::from(i32::decode(value)?).into())
+    }
+}
+
+/// Implements `Type`, `Encode` and `Decode` of a type stored as `TEXT`.
+macro_rules! text_type {
+    ($ty:ty) => {
+        impl<DB: Database> Type<DB> for $ty
+        where
+            String: Type<DB>,
+        {
+            fn type_info() -> DB::TypeInfo {
+                <String as Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <String as Type<DB>>::compatible(ty)
+            }
+        }
+
+        impl<'q, DB: Database> Encode<'q, DB> for $ty
+        where
+            String: Encode<'q, DB>,
+        {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+            ) -> Result<IsNull, BoxDynError> {
+                self.as_str().to_string().encode(buf)
+            }
+        }
+
+        impl<'r, DB: Database> Decode<'r, DB> for $ty
+        where
+            String: Decode<'r, DB>,
+        {
+            fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(String::decode(value)?.into())
+            }
+        }
+    };
+}
+
+text_type!(FeeType);
+text_type!(Currency);
+
+/// A result that can be stored as a row, with [`SqlRecord::COLUMNS`] as columns.
+pub trait SqlRecord {
+    /// Names of the columns, in the order values are bound by [`SqlRecord::bind`].
+    const COLUMNS: &'static [&'static str];
+
+    /// Binds values of the columns to `query`, in the order of [`SqlRecord::COLUMNS`].
+    fn bind<'q, DB: Database>(
+        &self,
+        query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+    where
+        i32: Encode<'q, DB> + Type<DB>,
+        i64: Encode<'q, DB> + Type<DB>,
+        String: Encode<'q, DB> + Type<DB>,
+        Option<i64>: Encode<'q, DB>,
+        Option<String>: Encode<'q, DB>;
+
+    /// An `INSERT` statement of the columns into `table`, with `$1, $2, ...` placeholders
+    /// (which both Postgres and SQLite support).
+    fn insert_sql(table: &str) -> String {
+        let placeholders = (1..=Self::COLUMNS.len())
+            .map(|i| format!("${i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "INSERT INTO {table} ({}) VALUES ({placeholders})",
+            Self::COLUMNS.join(", ")
+        )
+    }
+}
+
+impl SqlRecord for Verify {
+    const COLUMNS: &'static [&'static str] = &[
+        "code",
+        "message",
+        "card_hash",
+        "card_pan",
+        "ref_id",
+        "fee_type",
+        "fee",
+        "shaparak_fee",
+        "order_id",
+    ];
+
+    fn bind<'q, DB: Database>(
+        &self,
+        query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+    where
+        i32: Encode<'q, DB> + Type<DB>,
+        i64: Encode<'q, DB> + Type<DB>,
+        String: Encode<'q, DB> + Type<DB>,
+        Option<i64>: Encode<'q, DB>,
+        Option<String>: Encode<'q, DB>,
+    {
+        use super::RequestResult;
+
+        query
+            .bind(self.code())
+            .bind(self.message().to_string())
+            .bind(self.card_hash().to_string())
+            .bind(self.card_pan().to_string())
+            .bind(self.ref_id() as i64)
+            .bind(self.fee_type().clone())
+            .bind(self.fee() as i64)
+            .bind(self.shaparak_fee().map(|fee| fee as i64))
+            .bind(self.order_id().map(str::to_string))
+    }
+}
+
+impl<'r, R: Row> FromRow<'r, R> for Verify
+where
+    for<'c> &'c str: ColumnIndex<R>,
+    i32: Decode<'r, R::Database> + Type<R::Database>,
+    i64: Decode<'r, R::Database> + Type<R::Database>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn from_row(row: &'r R) -> Result<Self, ::sqlx::Error> {
+        Ok(Verify {
+            code: row.try_get("code")?,
+            message: row.try_get("message")?,
+            card_hash: row.try_get("card_hash")?,
+            card_pan: row.try_get("card_pan")?,
+            ref_id: row.try_get::<i64, _>("ref_id")? as u64,
+            fee_type: row.try_get("fee_type")?,
+            fee: row.try_get::<i64, _>("fee")? as u64,
+            wages: None,
+            shaparak_fee: row
+                .try_get::<Option<i64>, _>("shaparak_fee")?
+                .map(|fee| fee as u64),
+            order_id: row.try_get("order_id")?,
+            extra: Default::default(),
+        })
+    }
+}
+
+impl SqlRecord for Request {
+    const COLUMNS: &'static [&'static str] = &[
+        "code",
+        "message",
+        "authority",
+        "fee_type",
+        "fee",
+        "amount",
+        "currency",
+        "gateway",
+    ];
+
+    fn bind<'q, DB: Database>(
+        &self,
+        query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+    where
+        i32: Encode<'q, DB> + Type<DB>,
+        i64: Encode<'q, DB> + Type<DB>,
+        String: Encode<'q, DB> + Type<DB>,
+        Option<i64>: Encode<'q, DB>,
+        Option<String>: Encode<'q, DB>,
+    {
+        use super::RequestResult;
+
+        query
+            .bind(self.code())
+            .bind(self.message().to_string())
+            .bind(self.authority().to_string())
+            .bind(self.fee_type().clone())
+            .bind(self.fee() as i64)
+            .bind(self.amount() as i64)
+            .bind(self.currency().clone())
+            .bind(self.gateway.to_string())
+    }
+}
+
+impl<'r, R: Row> FromRow<'r, R> for Request
+where
+    for<'c> &'c str: ColumnIndex<R>,
+    i32: Decode<'r, R::Database> + Type<R::Database>,
+    i64: Decode<'r, R::Database> + Type<R::Database>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn from_row(row: &'r R) -> Result<Self, ::sqlx::Error> {
+        let gateway = row
+            .try_get::<String, _>("gateway")?
+            .parse::<GatewayUrl>()
+            .map_err(|e| ::sqlx::Error::ColumnDecode {
+                index: "gateway".to_string(),
+                source: e.into(),
+            })?;
+
+        Ok(Request {
+            code: row.try_get("code")?,
+            message: row.try_get("message")?,
+            authority: row.try_get("authority")?,
+            fee_type: row.try_get("fee_type")?,
+            fee: row.try_get::<i64, _>("fee")? as u64,
+            amount: row.try_get::<i64, _>("amount")? as u64,
+            currency: row.try_get("currency")?,
+            gateway,
+            extra: Default::default(),
+        })
+    }
+}
+
+#[cfg(feature = "unverified")]
+mod unverified {
+    use ::sqlx::{query::Query, ColumnIndex, Database, Decode, Encode, FromRow, Row, Type};
+
+    use super::SqlRecord;
+    use crate::results::unverified::Authorities;
+
+    impl SqlRecord for Authorities {
+        const COLUMNS: &'static [&'static str] =
+            &["authority", "amount", "callback_url", "referer", "date"];
+
+        fn bind<'q, DB: Database>(
+            &self,
+            query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+        ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+        where
+            i32: Encode<'q, DB> + Type<DB>,
+            i64: Encode<'q, DB> + Type<DB>,
+            String: Encode<'q, DB> + Type<DB>,
+            Option<i64>: Encode<'q, DB>,
+            Option<String>: Encode<'q, DB>,
+            Option<i64>: Encode<'q, DB>,
+            Option<String>: Encode<'q, DB>,
+        {
+            query
+                .bind(self.authority().to_string())
+                .bind(self.amount() as i64)
+                .bind(self.callback_url().to_string())
+                .bind(self.referer().to_string())
+                .bind(self.date().to_string())
+        }
+    }
+
+    impl<'r, R: Row> FromRow<'r, R> for Authorities
+    where
+        for<'c> &'c str: ColumnIndex<R>,
+        i64: Decode<'r, R::Database> + Type<R::Database>,
+        String: Decode<'r, R::Database> + Type<R::Database>,
+    {
+        fn from_row(row: &'r R) -> Result<Self, ::sqlx::Error> {
+            Ok(Authorities {
+                authority: row.try_get("authority")?,
+                amount: row.try_get::<i64, _>("amount")? as u64,
+                callback_url: row.try_get("callback_url")?,
+                referer: row.try_get("referer")?,
+                date: row.try_get("date")?,
+                extra: Default::default(),
+            })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite"))]
+mod tests {
+    use ::sqlx::SqlitePool;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_round_trip() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        ::sqlx::query(
+            "CREATE TABLE verified (code INTEGER NOT NULL, message TEXT NOT NULL,
+                card_hash TEXT NOT NULL, card_pan TEXT NOT NULL, ref_id BIGINT PRIMARY KEY,
+                fee_type TEXT NOT NULL, fee BIGINT NOT NULL, shaparak_fee BIGINT, order_id TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let verify: Verify = serde_json::from_value(serde_json::json!({
+            "code": 101,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Merchant",
+            "fee": 100,
+            "order_id": "ORDER-1"
+        }))
+        .unwrap();
+
+        verify
+            .bind(::sqlx::query(&Verify::insert_sql("verified")))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let loaded: Verify = ::sqlx::query_as("SELECT * FROM verified")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        use crate::results::RequestResult;
+        assert_eq!(loaded.code(), ResultCode::Verified);
+        assert_eq!(loaded.ref_id(), 201);
+        assert_eq!(loaded.fee_type(), &FeeType::Merchant);
+        assert_eq!(loaded.fee(), 100);
+        assert_eq!(loaded.shaparak_fee(), None);
+        assert_eq!(loaded.order_id(), Some("ORDER-1"));
+    }
+
+    #[tokio::test]
+    async fn test_request_round_trip() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        ::sqlx::query(
+            "CREATE TABLE requests (code INTEGER NOT NULL, message TEXT NOT NULL,
+                authority TEXT PRIMARY KEY, fee_type TEXT NOT NULL, fee BIGINT NOT NULL,
+                amount BIGINT NOT NULL, currency TEXT NOT NULL, gateway TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut request: Request = serde_json::from_value::<Request>(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A00000000000000000000000000217885159",
+            "fee_type": "Payer",
+            "fee": 0
+        }))
+        .unwrap();
+        request.set_requested(10000, Currency::IRT);
+
+        request
+            .bind(::sqlx::query(&Request::insert_sql("requests")))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let loaded: Request = ::sqlx::query_as("SELECT * FROM requests")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(loaded.authority(), request.authority());
+        assert_eq!(loaded.amount(), 10000);
+        assert_eq!(loaded.currency(), &Currency::IRT);
+        assert_eq!(loaded.gateway_url(), request.gateway_url());
+    }
+}
@@ -1,5 +1,15 @@
-use serde::Deserialize;
+use std::collections::HashMap;
 
+#[cfg(feature = "batch")]
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "batch")]
+use crate::{
+    error::Error,
+    results::verify::{Verify, VerifyOutcome},
+    ZarinpalClient,
+};
 use crate::{
     prelude::{ZarinResult, ZarinpalSendExtension},
     Zarinpal,
@@ -8,20 +18,30 @@ use crate::{
 use super::{result_code::ResultCode, RequestResult};
 
 /// Authority information of a payment request that can be used to verify the payment later.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Authorities {
     /// Unique authority of the payment request.
-    authority: String,
+    pub(crate) authority: String,
     /// Payment amount.
-    amount: u64,
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    pub(crate) amount: u64,
     /// Callback url of the payment.
-    callback_url: String,
+    pub(crate) callback_url: String,
     /// Refer url.
-    referer: String,
+    pub(crate) referer: String,
     /// Date and time of the request in a format like: `2020-06-27 10:22:02`.
-    date: String,
+    pub(crate) date: String,
+
+    /// Fields of the response that are not modeled by this crate (yet), requires
+    /// `capture-extra` feature to be captured.
+    #[cfg_attr(feature = "capture-extra", serde(flatten))]
+    #[cfg_attr(not(feature = "capture-extra"), serde(skip))]
+    pub(crate) extra: HashMap<String, serde_json::Value>,
 }
 
+// Json values are compared structurally, so equality is total here.
+impl Eq for Authorities {}
+
 impl Authorities {
     /// Unique authority of the payment request.
     pub fn authority(&self) -> &str {
@@ -48,6 +68,27 @@ impl Authorities {
         self.date.as_ref()
     }
 
+    /// Fields of the response that are not modeled by this crate (yet).
+    ///
+    /// Always empty, unless `capture-extra` feature is enabled.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+
+    /// Date and time of the request, parsed as Tehran local time.
+    ///
+    /// Returns `None` if the date is malformed, see [`crate::datetime::parse_tehran`].
+    #[cfg(feature = "chrono")]
+    pub fn date_parsed(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::datetime::parse_tehran(&self.date)
+    }
+
+    /// Date and time of the request in Jalali calendar, like `1399/04/11 17:33:25`.
+    #[cfg(feature = "jalali")]
+    pub fn date_jalali(&self) -> Option<crate::jalali::JalaliDateTime> {
+        crate::jalali::JalaliDateTime::parse_gregorian(&self.date)
+    }
+
     /// Directly verify this payment requests using `authority` and `amount`.
     pub async fn verify(&self, zarinpal: &Zarinpal) -> ZarinResult<crate::prelude::Verify> {
         zarinpal
@@ -58,13 +99,19 @@ impl Authorities {
 }
 
 /// The result type of a successful [`crate::methods::unverified::UnverifiedRequests`] request.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Unverified {
-    code: String,
+    code: ResultCode,
     message: String,
 
     /// Extra information about the payment request that can be used to verify a payment later.
     authorities: Vec<Authorities>,
+
+    /// Fields of the response that are not modeled by this crate (yet), requires
+    /// `capture-extra` feature to be captured.
+    #[cfg_attr(feature = "capture-extra", serde(flatten))]
+    #[cfg_attr(not(feature = "capture-extra"), serde(skip))]
+    extra: HashMap<String, serde_json::Value>,
 }
 
 impl Unverified {
@@ -72,11 +119,168 @@ impl Unverified {
     pub fn authorities(&self) -> &[Authorities] {
         self.authorities.as_ref()
     }
+
+    /// Fields of the response that are not modeled by this crate (yet).
+    ///
+    /// Always empty, unless `capture-extra` feature is enabled.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+
+    /// Verifies all [`Unverified::authorities`], with at most `concurrency_limit` requests in
+    /// flight.
+    ///
+    /// Failures don't stop the others, see [`VerifyReport`].
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     let report = zarinpal
+    ///         .unverified_requests()
+    ///         .build()
+    ///         .await?
+    ///         .verify_all(&zarinpal, 10)
+    ///         .await;
+    ///
+    ///     for (authorities, verify) in report.verified() {
+    ///         println!("{}: {}", authorities.authority(), verify.ref_id());
+    ///     }
+    ///     for (authorities, error) in report.failed() {
+    ///         println!("{}: {error}", authorities.authority());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "batch")]
+    pub async fn verify_all<Z>(&self, zarinpal: &Z, concurrency_limit: usize) -> VerifyReport
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        self.verify_where(zarinpal, concurrency_limit, |_| true)
+            .await
+    }
+
+    /// Like [`Unverified::verify_all`], but only verifies authorities matching `predicate`.
+    ///
+    /// Useful to verify only payments that are known to your records:
+    ///
+    /// ```no_run
+    /// use std::collections::HashSet;
+    ///
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///     let known: HashSet<String> = HashSet::new(); // Authorities of your orders.
+    ///
+    ///     let report = zarinpal
+    ///         .unverified_requests()
+    ///         .build()
+    ///         .await?
+    ///         .verify_where(&zarinpal, 10, |authorities| {
+    ///             known.contains(authorities.authority())
+    ///         })
+    ///         .await;
+    ///
+    ///     println!("{} failed", report.failed().len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "batch")]
+    pub async fn verify_where<Z>(
+        &self,
+        zarinpal: &Z,
+        concurrency_limit: usize,
+        mut predicate: impl FnMut(&Authorities) -> bool,
+    ) -> VerifyReport
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        let results = futures_util::stream::iter(
+            self.authorities
+                .iter()
+                .filter(|authorities| predicate(authorities)),
+        )
+        .map(|authorities| async move {
+            let result = zarinpal
+                .verify_payment(authorities.authority(), authorities.amount())
+                .build()
+                .outcome()
+                .await;
+            (authorities.clone(), result)
+        })
+        .buffered(concurrency_limit.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut report = VerifyReport::default();
+        for (authorities, result) in results {
+            match result {
+                Ok(VerifyOutcome::Verified(verify)) => report.verified.push((authorities, verify)),
+                Ok(VerifyOutcome::AlreadyVerified(verify)) => {
+                    report.already_verified.push((authorities, verify))
+                }
+                Err(error) => report.failed.push((authorities, error)),
+            }
+        }
+        report
+    }
+}
+
+/// Results of [`Unverified::verify_all`] and [`Unverified::verify_where`], paired with
+/// the authorities they belong to (in the same order as [`Unverified::authorities`]).
+#[cfg(feature = "batch")]
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    verified: Vec<(Authorities, Verify)>,
+    already_verified: Vec<(Authorities, Verify)>,
+    failed: Vec<(Authorities, Error)>,
+}
+
+#[cfg(feature = "batch")]
+impl VerifyReport {
+    /// Payments that are verified for the first time, you'd usually deliver these.
+    pub fn verified(&self) -> &[(Authorities, Verify)] {
+        &self.verified
+    }
+
+    /// Payments that were verified before ([`ResultCode::Verified`]).
+    pub fn already_verified(&self) -> &[(Authorities, Verify)] {
+        &self.already_verified
+    }
+
+    /// Payments that failed to verify, they can be retried later.
+    pub fn failed(&self) -> &[(Authorities, Error)] {
+        &self.failed
+    }
+
+    /// Returns `true` if no payment failed to verify.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Total number of payments that were tried.
+    pub fn len(&self) -> usize {
+        self.verified.len() + self.already_verified.len() + self.failed.len()
+    }
+
+    /// Returns `true` if no payment was tried.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl RequestResult for Unverified {
     fn code(&self) -> ResultCode {
-        self.code.parse::<i64>().unwrap().into()
+        self.code
     }
 
     fn message(&self) -> &str {
@@ -91,7 +295,7 @@ mod tests {
     #[test]
     fn test_deserialization() {
         let inner_model = Unverified {
-            code: "100".to_string(),
+            code: ResultCode::Success,
             message: "Success".to_string(),
             authorities: vec![Authorities {
                 authority: "A00000000000000000000000000207288780".to_string(),
@@ -99,7 +303,9 @@ mod tests {
                 callback_url: "https://golroz.com/vpay".to_string(),
                 referer: "https://golroz.com/test-form/".to_string(),
                 date: "2020-07-01 17:33:25".to_string(),
+                extra: Default::default(),
             }],
+            extra: Default::default(),
         };
 
         let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Unverified>>(
@@ -128,4 +334,119 @@ mod tests {
         assert_eq!(data.message, inner_model.message);
         assert_eq!(data.authorities.len(), 1)
     }
+
+    #[test]
+    fn test_code_deserialization() {
+        let code = |code: serde_json::Value| {
+            serde_json::from_value::<Unverified>(serde_json::json!({
+                "code": code,
+                "message": "Success",
+                "authorities": []
+            }))
+            .map(|unverified| unverified.code())
+        };
+
+        assert_eq!(code(serde_json::json!(100)).unwrap(), ResultCode::Success);
+        assert_eq!(code(serde_json::json!("100")).unwrap(), ResultCode::Success);
+        assert_eq!(
+            code(serde_json::json!("-999")).unwrap(),
+            ResultCode::Unknown(-999)
+        );
+        assert!(code(serde_json::json!("OK")).is_err());
+    }
+
+    #[cfg(feature = "batch")]
+    #[tokio::test]
+    async fn test_verify_all() {
+        use crate::transport::tests::{responses, CannedTransport};
+
+        // Fails authority `A0`, `A1` was verified before and others are verified now.
+        let transport = CannedTransport::new(|request| {
+            Ok(match request.body["authority"].as_str() {
+                Some("A0") => responses::error(-51, "Session is not valid"),
+                Some("A1") => responses::verify(101),
+                _ => responses::verify(100),
+            })
+        });
+        let zarinpal = Zarinpal::new_with_transport(crate::TEST_UUID, transport).unwrap();
+        let unverified = Unverified {
+            code: ResultCode::Success,
+            message: "Success".to_string(),
+            authorities: (0..5)
+                .map(|i| Authorities {
+                    authority: format!("A{i}"),
+                    amount: 1000,
+                    callback_url: "https://golroz.com/vpay".to_string(),
+                    referer: "https://golroz.com/test-form/".to_string(),
+                    date: "2020-07-01 17:33:25".to_string(),
+                    extra: Default::default(),
+                })
+                .collect(),
+            extra: Default::default(),
+        };
+
+        let report = unverified.verify_all(&zarinpal, 2).await;
+        assert_eq!(report.len(), 5);
+        assert!(!report.is_complete());
+        assert_eq!(report.failed()[0].0.authority(), "A0");
+        assert_eq!(report.already_verified()[0].0.authority(), "A1");
+        assert_eq!(
+            report
+                .verified()
+                .iter()
+                .map(|(authorities, _)| authorities.authority())
+                .collect::<Vec<_>>(),
+            ["A2", "A3", "A4"]
+        );
+
+        let report = unverified
+            .verify_where(&zarinpal, 2, |authorities| authorities.authority() != "A0")
+            .await;
+        assert!(report.is_complete());
+        assert_eq!(report.len(), 4);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_date_parsed() {
+        let authorities = |date: &str| Authorities {
+            authority: "A00000000000000000000000000207288780".to_string(),
+            amount: 50500,
+            callback_url: "https://golroz.com/vpay".to_string(),
+            referer: "https://golroz.com/test-form/".to_string(),
+            date: date.to_string(),
+            extra: Default::default(),
+        };
+
+        let mut list = [
+            authorities("2023-07-02 09:00:00"),
+            authorities("2023-07-01 17:33:25"),
+        ];
+        list.sort_by_key(Authorities::date_parsed);
+
+        assert_eq!(list[0].date(), "2023-07-01 17:33:25");
+        assert_eq!(
+            list[0].date_parsed().unwrap().to_rfc3339(),
+            "2023-07-01T17:33:25+03:30"
+        );
+        assert!(authorities("yesterday").date_parsed().is_none());
+    }
+
+    #[cfg(feature = "jalali")]
+    #[test]
+    fn test_date_jalali() {
+        let authorities = Authorities {
+            authority: "A00000000000000000000000000207288780".to_string(),
+            amount: 50500,
+            callback_url: "https://golroz.com/vpay".to_string(),
+            referer: "https://golroz.com/test-form/".to_string(),
+            date: "2024-06-27 17:33:25".to_string(),
+            extra: Default::default(),
+        };
+
+        assert_eq!(
+            authorities.date_jalali().unwrap().to_string(),
+            "1403/04/07 17:33:25"
+        );
+    }
 }
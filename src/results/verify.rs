@@ -1,11 +1,16 @@
-use serde::Deserialize;
+use std::collections::HashMap;
 
-use crate::methods::request::Wage;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{card::Bank, methods::request::Wage};
 
 use super::{RequestResult, ResultCode};
 
 /// Indicates who's responsible for paying the payment fee.
-#[derive(Debug, Clone, Deserialize, Copy, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Deserializing a fee type never fails, values unknown to this crate are kept in
+/// [`FeeType::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FeeType {
     /// Payer of the payment.
     Payer,
@@ -13,11 +18,57 @@ pub enum FeeType {
     /// Merchant of the payment.
     Merchant,
 
-    /// An unknown fee type.
-    Unknown,
+    /// A fee type that's not known to this crate, as sent by the api.
+    Other(String),
+}
+
+impl<'de> Deserialize<'de> for FeeType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(fee_type) => fee_type.into(),
+            other => FeeType::Other(other.to_string()),
+        })
+    }
+}
+
+impl From<String> for FeeType {
+    fn from(fee_type: String) -> Self {
+        match fee_type.as_str() {
+            "Payer" => FeeType::Payer,
+            "Merchant" => FeeType::Merchant,
+            _ => FeeType::Other(fee_type),
+        }
+    }
+}
+
+impl Serialize for FeeType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl super::__private::MaybeUnknown for FeeType {
+    fn is_unknown(&self) -> bool {
+        matches!(self, Self::Other(_))
+    }
 }
 
 impl FeeType {
+    /// Fee type as sent by the api, like `Payer`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            FeeType::Payer => "Payer",
+            FeeType::Merchant => "Merchant",
+            FeeType::Other(other) => other,
+        }
+    }
+
     /// Returns `true` if the fee type is [`Payer`].
     ///
     /// [`Payer`]: FeeType::Payer
@@ -38,29 +89,52 @@ impl FeeType {
 /// The result type of a successful [`crate::methods::verify::VerifyPayment`] request.
 ///
 /// Error code `101` ([`ResultCode::Verified`]) means this payment was verified before.
-#[derive(Debug, Clone, Deserialize)]
+///
+/// `Debug` output masks the card hash and pan, unless `unredacted-debug` feature is enabled.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "unredacted-debug", derive(Debug))]
 pub struct Verify {
-    code: ResultCode,
-    message: String,
+    pub(crate) code: ResultCode,
+    pub(crate) message: String,
 
     /// SHA256 hash of card number.
-    card_hash: String,
+    pub(crate) card_hash: String,
 
     /// Masked card number in a format like `60379986****5434`.
-    card_pan: String,
+    pub(crate) card_pan: String,
 
     /// Reference id of the payment.
-    ref_id: u64,
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    pub(crate) ref_id: u64,
 
     /// Fee type. Indicates if the [`FeeType::Merchant`] is responsible for payment fee or [`FeeType::Payer`].
-    fee_type: FeeType,
+    #[serde(deserialize_with = "super::__private::known")]
+    pub(crate) fee_type: FeeType,
 
     /// Fee amount.
-    fee: u64,
+    #[serde(deserialize_with = "super::__private::number_or_string")]
+    pub(crate) fee: u64,
 
     /// Wages you've entered while sending payment request, just in case.
     #[serde(default)]
-    wages: Option<Vec<Wage>>,
+    pub(crate) wages: Option<Vec<Wage>>,
+
+    /// Fee of shaparak (the interbank network) for this transaction.
+    #[serde(
+        default,
+        deserialize_with = "super::__private::option_number_or_string"
+    )]
+    pub(crate) shaparak_fee: Option<u64>,
+
+    /// Order id you've entered while sending payment request.
+    #[serde(default)]
+    pub(crate) order_id: Option<String>,
+
+    /// Fields of the response that are not modeled by this crate (yet), requires
+    /// `capture-extra` feature to be captured.
+    #[cfg_attr(feature = "capture-extra", serde(flatten))]
+    #[cfg_attr(not(feature = "capture-extra"), serde(skip))]
+    pub(crate) extra: HashMap<String, serde_json::Value>,
 }
 
 impl Verify {
@@ -74,6 +148,11 @@ impl Verify {
         self.card_pan.as_ref()
     }
 
+    /// Bank that issued the card, if it's a known iranian one, see [`Bank::from_pan`].
+    pub fn card_bank(&self) -> Option<Bank> {
+        Bank::from_pan(&self.card_pan)
+    }
+
     /// Reference id of a successful payment.
     pub fn ref_id(&self) -> u64 {
         self.ref_id
@@ -90,8 +169,76 @@ impl Verify {
     }
 
     /// Fee type. Indicates if the [`FeeType::Merchant`] is responsible for payment fee or [`FeeType::Payer`].
-    pub fn fee_type(&self) -> FeeType {
-        self.fee_type
+    pub fn fee_type(&self) -> &FeeType {
+        &self.fee_type
+    }
+
+    /// Fee of shaparak (the interbank network) for this transaction, if it's reported.
+    pub fn shaparak_fee(&self) -> Option<u64> {
+        self.shaparak_fee
+    }
+
+    /// Order id you've entered while sending payment request, if it's echoed back.
+    pub fn order_id(&self) -> Option<&str> {
+        self.order_id.as_deref()
+    }
+
+    /// Fields of the response that are not modeled by this crate (yet).
+    ///
+    /// Always empty, unless `capture-extra` feature is enabled.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl std::fmt::Debug for Verify {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use crate::redact::{mask, redacted_map};
+
+        f.debug_struct("Verify")
+            .field("code", &self.code)
+            .field("message", &self.message)
+            .field("card_hash", &mask(&self.card_hash, 0, 0))
+            .field("card_pan", &mask(&self.card_pan, 6, 4))
+            .field("ref_id", &self.ref_id)
+            .field("fee_type", &self.fee_type)
+            .field("fee", &self.fee)
+            .field("wages", &self.wages)
+            .field("shaparak_fee", &self.shaparak_fee)
+            .field("order_id", &self.order_id)
+            .field("extra", &redacted_map(&self.extra))
+            .finish()
+    }
+}
+
+#[cfg(feature = "testkit")]
+impl Verify {
+    /// A mocked result of a payment that's verified for the first time.
+    pub fn mock_success(ref_id: u64) -> Self {
+        Self {
+            code: ResultCode::Success,
+            message: "Verified".to_string(),
+            card_hash: "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69"
+                .to_string(),
+            card_pan: "502229******5995".to_string(),
+            ref_id,
+            fee_type: FeeType::Merchant,
+            fee: 0,
+            wages: None,
+            shaparak_fee: None,
+            order_id: None,
+            extra: Default::default(),
+        }
+    }
+
+    /// A mocked result of a payment that was verified before.
+    pub fn mock_already_verified(ref_id: u64) -> Self {
+        Self {
+            code: ResultCode::Verified,
+            message: "Verified".to_string(),
+            ..Self::mock_success(ref_id)
+        }
     }
 }
 
@@ -101,6 +248,64 @@ impl Verify {
     pub fn already_verified(&self) -> bool {
         matches!(self.code, ResultCode::Verified)
     }
+
+    /// Converts this into a [`VerifyOutcome`], which makes it impossible to miss
+    /// whether the payment was verified just now or before.
+    pub fn into_outcome(self) -> VerifyOutcome {
+        self.into()
+    }
+}
+
+/// Outcome of a successful [`crate::methods::verify::VerifyPayment`] request.
+///
+/// Error code `100` ([`ResultCode::Success`]) means the payment is verified for the first time,
+/// while `101` ([`ResultCode::Verified`]) means it was verified before. You'd usually want to
+/// deliver the order only on [`VerifyOutcome::Verified`].
+#[derive(Debug, Clone)]
+pub enum VerifyOutcome {
+    /// The payment is verified for the first time.
+    Verified(Verify),
+
+    /// The payment was verified before.
+    AlreadyVerified(Verify),
+}
+
+impl VerifyOutcome {
+    /// Returns `true` if the payment is verified for the first time.
+    #[must_use]
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified(_))
+    }
+
+    /// Returns `true` if the payment was verified before.
+    #[must_use]
+    pub fn is_already_verified(&self) -> bool {
+        matches!(self, Self::AlreadyVerified(_))
+    }
+
+    /// The underlying [`Verify`] result.
+    pub fn verify(&self) -> &Verify {
+        match self {
+            Self::Verified(verify) | Self::AlreadyVerified(verify) => verify,
+        }
+    }
+
+    /// Consumes the outcome and returns the underlying [`Verify`] result.
+    pub fn into_verify(self) -> Verify {
+        match self {
+            Self::Verified(verify) | Self::AlreadyVerified(verify) => verify,
+        }
+    }
+}
+
+impl From<Verify> for VerifyOutcome {
+    fn from(value: Verify) -> Self {
+        if value.already_verified() {
+            Self::AlreadyVerified(value)
+        } else {
+            Self::Verified(value)
+        }
+    }
 }
 
 impl RequestResult for Verify {
@@ -130,6 +335,9 @@ mod tests {
             fee_type: FeeType::Merchant,
             fee: 0,
             wages: None,
+            shaparak_fee: None,
+            order_id: None,
+            extra: Default::default(),
         };
 
         let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Verify>>(
@@ -157,10 +365,33 @@ mod tests {
         assert_eq!(data.fee, inner_model.fee);
         assert_eq!(data.fee_type, inner_model.fee_type);
         assert_eq!(data.card_pan, inner_model.card_pan);
+        assert_eq!(data.card_bank(), Some(Bank::Pasargad));
         assert_eq!(data.ref_id, inner_model.ref_id);
         assert!(data.wages.is_none());
     }
 
+    #[test]
+    fn test_serialization() {
+        let verify = serde_json::from_value::<Verify>(serde_json::json!({
+            "code": 101,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": "201",
+            "fee_type": "Payer",
+            "fee": 0,
+            "shaparak_fee": 1200
+        }))
+        .unwrap();
+
+        let json = serde_json::to_value(&verify).unwrap();
+        assert_eq!(json["code"], 101);
+        assert_eq!(json["ref_id"], 201);
+        assert_eq!(json["fee_type"], "Payer");
+
+        assert_eq!(serde_json::from_value::<Verify>(json).unwrap(), verify);
+    }
+
     #[test]
     fn test_deserialization_with_wages() {
         // cSpell:disable
@@ -176,16 +407,19 @@ mod tests {
             fee: 0,
             wages: Some(vec![
                 Wage::builder()
-                    .iban("IR130570028780010957775103")
+                    .iban("IR130570028780010957775103".parse().unwrap())
                     .amount(1000)
                     .description("تسهیم سود فروش از محصول به مسعود امینی")
                     .build(),
                 Wage::builder()
-                    .iban("IR670170000000352965862009")
+                    .iban("IR670170000000352965862009".parse().unwrap())
                     .amount(5000)
                     .description("تسهیم سود فروش از محصول به یوسفی")
                     .build()
             ]),
+            shaparak_fee: None,
+            order_id: None,
+            extra: Default::default(),
         };
 
         let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Verify>>(
@@ -231,4 +465,155 @@ mod tests {
         let wages = data.wages.unwrap();
         assert_eq!(wages.len(), 2)
     }
+
+    #[test]
+    fn test_deserialization_with_string_numbers() {
+        let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Verify>>(
+            serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Verified",
+                    "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+                    "card_pan": "502229******5995",
+                    "ref_id": "201",
+                    "fee_type": "Merchant",
+                    "fee": "1500"
+                },
+                "errors": []
+            }),
+        )
+        .unwrap();
+
+        let data: Option<Verify> = from_json.data.into();
+        let data = data.unwrap();
+
+        assert_eq!(data.ref_id, 201);
+        assert_eq!(data.fee, 1500);
+        assert_eq!(data.shaparak_fee(), None);
+        assert_eq!(data.order_id(), None);
+    }
+
+    #[test]
+    fn test_deserialization_with_shaparak_fee_and_order_id() {
+        let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Verify>>(
+            serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Verified",
+                    "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+                    "card_pan": "502229******5995",
+                    "ref_id": 201,
+                    "fee_type": "Merchant",
+                    "fee": 0,
+                    "shaparak_fee": "1200",
+                    "order_id": "ORD-1001"
+                },
+                "errors": []
+            }),
+        )
+        .unwrap();
+
+        let data = from_json.into_result().unwrap().unwrap();
+        assert_eq!(data.shaparak_fee(), Some(1200));
+        assert_eq!(data.order_id(), Some("ORD-1001"));
+    }
+
+    #[cfg(not(feature = "unredacted-debug"))]
+    #[test]
+    fn test_redacted_debug() {
+        let verify = Verify {
+            code: ResultCode::Success,
+            message: "Verified".to_string(),
+            card_hash: "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69"
+                .to_string(),
+            card_pan: "5022291083818920".to_string(),
+            ref_id: 201,
+            fee_type: FeeType::Merchant,
+            fee: 0,
+            wages: Some(vec![Wage::builder()
+                .iban("IR130570028780010957775103".parse().unwrap())
+                .amount(1000)
+                .description("Share")
+                .build()]),
+            shaparak_fee: None,
+            order_id: None,
+            extra: Default::default(),
+        };
+
+        let debug = format!("{verify:#?}");
+        assert!(!debug.contains("1EBE3EBE"));
+        assert!(!debug.contains("5022291083818920"));
+        assert!(debug.contains("502229******8920"));
+        assert!(!debug.contains("IR130570028780010957775103"));
+        assert!(debug.contains("IR13******************5103"));
+    }
+
+    #[test]
+    fn test_fee_type_deserialization() {
+        let fee_type = |value| serde_json::from_value::<FeeType>(value).unwrap();
+
+        assert_eq!(fee_type(serde_json::json!("Payer")), FeeType::Payer);
+        assert_eq!(fee_type(serde_json::json!("Merchant")), FeeType::Merchant);
+        assert_eq!(
+            fee_type(serde_json::json!("Shared")),
+            FeeType::Other("Shared".to_string())
+        );
+        assert_eq!(
+            fee_type(serde_json::json!(2)),
+            FeeType::Other("2".to_string())
+        );
+        assert_eq!(
+            fee_type(serde_json::Value::Null),
+            FeeType::Other("null".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extra_fields() {
+        let from_json = serde_json::from_value::<crate::results::__private::ApiResult<Verify>>(
+            serde_json::json!({
+                "data": {
+                    "code": 100,
+                    "message": "Verified",
+                    "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+                    "card_pan": "502229******5995",
+                    "ref_id": 201,
+                    "fee_type": "Merchant",
+                    "fee": 0,
+                    "wage_id": "1200"
+                },
+                "errors": []
+            }),
+        )
+        .unwrap();
+
+        let data = from_json.into_result().unwrap().unwrap();
+        #[cfg(feature = "capture-extra")]
+        assert_eq!(data.extra()["wage_id"], "1200");
+        #[cfg(not(feature = "capture-extra"))]
+        assert!(data.extra().is_empty());
+    }
+
+    #[test]
+    fn test_outcome() {
+        let verify = |code| Verify {
+            code,
+            message: "Verified".to_string(),
+            card_hash: String::new(),
+            card_pan: "502229******5995".to_string(),
+            ref_id: 201,
+            fee_type: FeeType::Merchant,
+            fee: 0,
+            wages: None,
+            shaparak_fee: None,
+            order_id: None,
+            extra: Default::default(),
+        };
+
+        assert!(verify(ResultCode::Success).into_outcome().is_verified());
+
+        let outcome = verify(ResultCode::Verified).into_outcome();
+        assert!(outcome.is_already_verified());
+        assert_eq!(outcome.verify().ref_id(), 201);
+    }
 }
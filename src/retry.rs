@@ -0,0 +1,201 @@
+//! Retrying transient failures with exponential backoff, see [`RetryPolicy`].
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use typed_builder::TypedBuilder;
+
+use crate::{error::Error, results::result_code::ResultCode};
+
+/// Classes of errors that are retried by a [`RetryPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOn {
+    /// Connection errors, timeouts and other transport failures.
+    pub network: bool,
+
+    /// Http `5xx` responses.
+    pub server_errors: bool,
+
+    /// [`ResultCode::ToManyAttempts`] api errors.
+    pub too_many_attempts: bool,
+}
+
+impl Default for RetryOn {
+    fn default() -> Self {
+        Self {
+            network: true,
+            server_errors: true,
+            too_many_attempts: true,
+        }
+    }
+}
+
+/// Opt-in retry of transient failures, with exponential backoff and jitter.
+///
+/// Install it using [`crate::Zarinpal::with_retry`] or [`crate::builder::ZarinpalBuilder::retry`].
+///
+/// Retrying a verify request is safe: if an earlier attempt was actually verified,
+/// the api answers with [`ResultCode::Verified`] (`101`) which is a successful result.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::prelude::*;
+/// use zarinpal::retry::RetryPolicy;
+///
+/// # fn main() -> Result::<(), Box<dyn std::error::Error>> {
+/// let zarinpal = Zarinpal::new("...")?.with_retry(
+///     RetryPolicy::builder()
+///         .max_attempts(5)
+///         .base_delay(Duration::from_millis(100))
+///         .build(),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RetryPolicy {
+    /// (Optional) Maximum number of attempts, including the first one. Defaults to `3`.
+    #[builder(default = 3)]
+    max_attempts: u32,
+
+    /// (Optional) Delay before the first retry, doubled after each attempt. Defaults to 200ms.
+    #[builder(default = Duration::from_millis(200))]
+    base_delay: Duration,
+
+    /// (Optional) Maximum delay between two attempts. Defaults to 5 seconds.
+    #[builder(default = Duration::from_secs(5))]
+    max_delay: Duration,
+
+    /// (Optional) Which errors are retried. Defaults to all of them.
+    #[builder(default)]
+    retry_on: RetryOn,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns `true` if `error` is a transient failure that should be retried.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::ZarinpalApiError(error) => {
+                self.retry_on.too_many_attempts && error.code() == ResultCode::ToManyAttempts
+            }
+            #[cfg(feature = "reqwest")]
+            Error::HttpClientError(error) => match error.status() {
+                Some(status) => self.retry_on.server_errors && status.is_server_error(),
+                None => self.retry_on.network && !error.is_decode(),
+            },
+            Error::TransportError(_) => self.retry_on.network,
+            Error::UnexpectedResponse {
+                status: Some(status),
+                ..
+            } => self.retry_on.server_errors && (500..600).contains(status),
+            _ => false,
+        }
+    }
+
+    /// Delay before the attempt after `attempt` (starting from `1`), with equal jitter: half of
+    /// the exponential backoff, plus a random part of the other half.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        backoff.mul_f64((500 + jitter / 2) as f64 / 1000.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ApiError,
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::builder()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .build();
+
+        for (attempt, backoff) in [(1, 100), (2, 200), (3, 300), (10, 300)] {
+            let backoff = Duration::from_millis(backoff);
+            for _ in 0..100 {
+                let delay = policy.delay(attempt);
+                assert!(
+                    (backoff / 2..backoff).contains(&delay),
+                    "attempt {attempt}: {delay:?}"
+                );
+            }
+        }
+
+        let delays = (0..100)
+            .map(|_| policy.delay(1))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(delays.len() > 1, "delays are not jittered");
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.is_retryable(&ApiError::local(ResultCode::ToManyAttempts, "", "").into()));
+        assert!(!policy.is_retryable(&ApiError::local(ResultCode::InvalidAuthority, "", "").into()));
+        assert!(policy.is_retryable(&Error::TransportError("connection reset".into())));
+        assert!(!policy.is_retryable(&Error::AmountOverflow));
+        assert!(policy.is_retryable(&Error::unexpected_response(Some(503), None, "")));
+        assert!(!policy.is_retryable(&Error::unexpected_response(Some(403), None, "")));
+
+        let policy = RetryPolicy::builder()
+            .retry_on(RetryOn {
+                network: false,
+                ..Default::default()
+            })
+            .build();
+        assert!(!policy.is_retryable(&Error::TransportError("connection reset".into())));
+    }
+
+    #[tokio::test]
+    async fn test_send_retries() {
+        let policy = RetryPolicy::builder()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(1))
+            .build();
+
+        let transport = CannedTransport::always(responses::inquiry("PAID"));
+        transport.fail_next(2);
+        let zarinpal = Zarinpal::builder(TEST_UUID)
+            .transport(transport.clone())
+            .retry(policy.clone())
+            .build()
+            .unwrap();
+        assert!(zarinpal.inquiry_payment("A0").build().await.is_ok());
+        assert_eq!(transport.request_count(), 3);
+
+        transport.fail_next(3);
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport)
+            .unwrap()
+            .with_retry(policy);
+        assert!(zarinpal.inquiry_payment("A0").build().await.is_err());
+    }
+}
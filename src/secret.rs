@@ -0,0 +1,92 @@
+//! Keeping credentials out of logs.
+
+/// Defines a credential type that's never printed by `Debug`, and with `secrecy` feature is kept
+/// in a [`secrecy::SecretString`] that's zeroized on drop.
+macro_rules! secret {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub(crate) struct $name {
+            #[cfg(feature = "secrecy")]
+            inner: secrecy::SecretString,
+            #[cfg(not(feature = "secrecy"))]
+            inner: String,
+        }
+
+        impl $name {
+            pub(crate) fn new(secret: String) -> Self {
+                Self {
+                    #[cfg(feature = "secrecy")]
+                    inner: secret.into(),
+                    #[cfg(not(feature = "secrecy"))]
+                    inner: secret,
+                }
+            }
+
+            /// The secret itself, to be sent to the api.
+            pub(crate) fn expose(&self) -> &str {
+                #[cfg(feature = "secrecy")]
+                return secrecy::ExposeSecret::expose_secret(&self.inner);
+
+                #[cfg(not(feature = "secrecy"))]
+                self.inner.as_str()
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(concat!(stringify!($name), "([REDACTED])"))
+            }
+        }
+    };
+}
+
+secret! {
+    /// Merchant id of a client, which is effectively an api credential.
+    MerchantId
+}
+
+secret! {
+    /// Access token of zarinpal dashboard, see [`crate::auth::Auth::AccessToken`].
+    AccessToken
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transport::tests::OfflineTransport, Zarinpal, ZarinpalClient, TEST_UUID};
+
+    #[test]
+    fn test_merchant_id_is_not_printed() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, OfflineTransport).unwrap();
+        assert_eq!(zarinpal.merchant_id(), TEST_UUID);
+
+        let debug = format!("{zarinpal:?}");
+        assert!(!debug.contains(TEST_UUID));
+        assert!(debug.contains("MerchantId([REDACTED])"));
+
+        let builder = format!("{:?}", Zarinpal::builder(TEST_UUID));
+        assert!(!builder.contains(TEST_UUID));
+    }
+
+    #[test]
+    fn test_access_token_is_not_printed() {
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, OfflineTransport)
+            .unwrap()
+            .with_access_token("top-secret");
+        assert_eq!(zarinpal.access_token(), Some("top-secret"));
+        assert!(!format!("{zarinpal:?}").contains("top-secret"));
+
+        let builder = Zarinpal::builder(TEST_UUID).access_token("top-secret");
+        assert!(!format!("{builder:?}").contains("top-secret"));
+    }
+
+    #[cfg(feature = "graphql")]
+    #[test]
+    fn test_graphql_access_token_is_not_printed() {
+        let graphql =
+            crate::graphql::GraphqlClient::new_with_transport("top-secret", OfflineTransport);
+        let debug = format!("{graphql:?}");
+        assert!(!debug.contains("top-secret"));
+        assert!(debug.contains("AccessToken([REDACTED])"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Diagnostics of a client setup, see [`Zarinpal::self_test`].
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    environment::Environment,
+    error::{Error, ZarinResult},
+    extensions::ZarinpalSendExtension,
+    Zarinpal,
+};
+
+/// Result of a single step of [`Zarinpal::self_test`].
+#[derive(Debug, Clone)]
+pub struct SelfTestCheck {
+    /// Name of the checked api, Eg: `"request"`.
+    pub name: &'static str,
+
+    /// Time it took to get a response.
+    pub latency: Duration,
+
+    /// Error of the step, if it's failed.
+    pub error: Option<String>,
+}
+
+impl SelfTestCheck {
+    /// Returns `true` if the step succeeded.
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A structured report of [`Zarinpal::self_test`].
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    /// Environment that the test ran against.
+    pub environment: Environment,
+
+    /// If the api responded at all (even with an error).
+    pub reachable: bool,
+
+    /// If the merchant id is accepted by the api.
+    pub terminal_valid: bool,
+
+    /// Steps that ran, in order.
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if all steps succeeded.
+    pub fn passed(&self) -> bool {
+        self.reachable && self.terminal_valid && self.checks.iter().all(SelfTestCheck::passed)
+    }
+
+    /// Names of the apis that worked.
+    pub fn supported_features(&self) -> Vec<&'static str> {
+        self.checks
+            .iter()
+            .filter(|check| check.passed())
+            .map(|check| check.name)
+            .collect()
+    }
+
+    /// Total latency of all steps.
+    pub fn latency(&self) -> Duration {
+        self.checks.iter().map(|check| check.latency).sum()
+    }
+}
+
+impl Zarinpal {
+    /// Runs a scripted request → inquiry flow against the [`Environment::Sandbox`],
+    /// using merchant id and http client of this instance.
+    ///
+    /// Useful for deployment smoke checks, this never fails and reports errors instead.
+    ///
+    /// ```no_run
+    /// use zarinpal::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let zarinpal = Zarinpal::new("...")?;
+    ///
+    ///     let report = zarinpal.self_test().await;
+    ///     if !report.passed() {
+    ///         eprintln!("{report:#?}");
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn self_test(&self) -> SelfTestReport {
+        let sandbox = self.clone().with_environment(Environment::Sandbox);
+        let mut report = SelfTestReport {
+            environment: Environment::Sandbox,
+            reachable: false,
+            terminal_valid: false,
+            checks: Vec::new(),
+        };
+
+        let started = Instant::now();
+        let request = sandbox
+            .request_payment(
+                10000,
+                "https://example.com/zarinpal/self-test".parse().unwrap(),
+                "Zarinpal self test",
+            )
+            .build()
+            .await;
+        report.reachable = is_reachable(&request);
+        report.terminal_valid = request.is_ok();
+        report.checks.push(check("request", started, &request));
+
+        if let Ok(request) = request {
+            let started = Instant::now();
+            let inquiry = sandbox.inquiry_payment(request.authority()).build().await;
+            report.checks.push(check("inquiry", started, &inquiry));
+        }
+
+        report
+    }
+}
+
+fn is_reachable<T>(result: &ZarinResult<T>) -> bool {
+    match result {
+        Ok(_) | Err(Error::ZarinpalApiError(_)) | Err(Error::JsonError(_)) => true,
+        #[cfg(feature = "reqwest")]
+        Err(Error::HttpClientError(error)) => error.status().is_some() || error.is_decode(),
+        Err(Error::UnexpectedResponse { status, .. }) => status.is_some(),
+        Err(_) => false,
+    }
+}
+
+fn check<T>(name: &'static str, started: Instant, result: &ZarinResult<T>) -> SelfTestCheck {
+    SelfTestCheck {
+        name,
+        latency: started.elapsed(),
+        error: result.as_ref().err().map(ToString::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let report = SelfTestReport {
+            environment: Environment::Sandbox,
+            reachable: true,
+            terminal_valid: true,
+            checks: vec![
+                SelfTestCheck {
+                    name: "request",
+                    latency: Duration::from_millis(120),
+                    error: None,
+                },
+                SelfTestCheck {
+                    name: "inquiry",
+                    latency: Duration::from_millis(80),
+                    error: Some("Http client error".to_string()),
+                },
+            ],
+        };
+
+        assert!(!report.passed());
+        assert_eq!(report.supported_features(), ["request"]);
+        assert_eq!(report.latency(), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,174 @@
+//! Reporting payment failures to Sentry (requires `sentry` feature).
+//!
+//! Every call of [`crate::ZarinpalClient::send`] is recorded as a breadcrumb (category
+//! `zarinpal`) with its path, result code, authority and duration, so events captured later
+//! show the payment calls that led to them.
+//!
+//! Api errors and response decode failures of [`crate::ZarinpalClient::send`] are captured
+//! using the current Sentry hub, so make sure Sentry is initialized in your application.
+//!
+//! Each event carries the api `path` and `result_code` as tags, failed validations and the
+//! request json as contexts. Sensitive fields of the request are masked
+//! using [`crate::redact::redact_json`].
+
+use std::{collections::BTreeMap, time::Duration};
+
+use sentry_core::protocol::{Breadcrumb, Context, Level, Map};
+
+use crate::{
+    error::{Error, ZarinResult},
+    redact::redact_json,
+    results::RequestResult,
+};
+
+/// Records a call to `path` as a breadcrumb.
+pub(crate) fn add_breadcrumb<T: RequestResult>(
+    path: &str,
+    body: &serde_json::Value,
+    result: &ZarinResult<T>,
+    elapsed: Duration,
+) {
+    let mut data = Map::new();
+    data.insert("path".to_string(), path.into());
+    if let Some(authority) = body.get("authority") {
+        data.insert("authority".to_string(), authority.clone());
+    }
+    data.insert(
+        "duration_ms".to_string(),
+        u64::try_from(elapsed.as_millis())
+            .unwrap_or(u64::MAX)
+            .into(),
+    );
+
+    let code = match result {
+        Ok(result) => Some(result.code()),
+        Err(error) => error.result_code(),
+    };
+    if let Some(code) = code {
+        data.insert("result_code".to_string(), code.code().into());
+    }
+
+    sentry_core::add_breadcrumb(Breadcrumb {
+        ty: "http".to_string(),
+        category: Some("zarinpal".to_string()),
+        level: if result.is_ok() {
+            Level::Info
+        } else {
+            Level::Error
+        },
+        message: Some(format!("POST {path}")),
+        data,
+        ..Default::default()
+    });
+}
+
+/// Reports `error` to Sentry, if it's an api error or a decode failure.
+pub(crate) fn report_error(path: &str, body: &serde_json::Value, error: &Error) {
+    let kind = match error {
+        Error::ZarinpalApiError(_) => "api",
+        #[cfg(feature = "reqwest")]
+        Error::HttpClientError(error) if error.is_decode() => "decode",
+        Error::JsonError(_) => "decode",
+        Error::UnexpectedResponse { .. } => "unexpected_response",
+        _ => return,
+    };
+
+    let mut body = body.clone();
+    redact_json(&mut body);
+
+    sentry_core::with_scope(
+        |scope| {
+            scope.set_tag("zarinpal.path", path);
+            scope.set_tag("zarinpal.error", kind);
+
+            if let Error::ZarinpalApiError(api_error) = error {
+                scope.set_tag("zarinpal.result_code", i64::from(api_error.code()));
+
+                let validations: BTreeMap<_, _> = api_error
+                    .validations()
+                    .iter()
+                    .map(|(field, messages)| (field.to_string(), messages.to_vec().into()))
+                    .collect();
+                scope.set_context("zarinpal.validations", Context::Other(validations));
+            }
+
+            let request = match body {
+                serde_json::Value::Object(map) => map.into_iter().collect(),
+                other => BTreeMap::from([("body".to_string(), other)]),
+            };
+            scope.set_context("zarinpal.request", Context::Other(request));
+        },
+        || sentry_core::capture_error(error),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ApiError, results::verify::Verify};
+
+    use super::*;
+
+    #[test]
+    fn test_breadcrumbs_and_errors() {
+        let verify: Verify = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Verified",
+            "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+            "card_pan": "502229******5995",
+            "ref_id": 201,
+            "fee_type": "Merchant",
+            "fee": 0
+        }))
+        .unwrap();
+        let error = Error::from(
+            serde_json::from_value::<ApiError>(serde_json::json!({
+                "code": -9,
+                "message": "The input params invalid, validation error.",
+                "validations": [{ "amount": "The amount must be at least 1000." }]
+            }))
+            .unwrap(),
+        );
+        let body = serde_json::json!({
+            "merchant_id": "1344b5d4-0048-11e8-94db-005056a205be",
+            "authority": "A00000000000000000000000000217885159",
+            "amount": 100
+        });
+
+        let events = sentry_core::test::with_captured_events(|| {
+            add_breadcrumb(
+                "/pg/v4/payment/verify.json",
+                &body,
+                &Ok(verify),
+                Duration::ZERO,
+            );
+            let result: ZarinResult<Verify> = Err(error);
+            add_breadcrumb("/pg/v4/payment/verify.json", &body, &result, Duration::ZERO);
+            report_error(
+                "/pg/v4/payment/verify.json",
+                &body,
+                result.as_ref().unwrap_err(),
+            );
+        });
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.tags["zarinpal.result_code"], "-9");
+
+        let breadcrumbs = &event.breadcrumbs.values;
+        assert_eq!(breadcrumbs.len(), 2);
+        assert_eq!(breadcrumbs[0].data["result_code"], 100);
+        assert_eq!(breadcrumbs[1].level, Level::Error);
+        assert_eq!(
+            breadcrumbs[1].data["authority"],
+            "A00000000000000000000000000217885159"
+        );
+
+        let Some(Context::Other(request)) = event.contexts.get("zarinpal.request") else {
+            panic!("request context is missing");
+        };
+        assert_ne!(
+            request["merchant_id"],
+            "1344b5d4-0048-11e8-94db-005056a205be"
+        );
+    }
+}
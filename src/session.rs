@@ -0,0 +1,392 @@
+//! The whole lifecycle of a payment in a single object, see [`PaymentSession`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    callback::{CallbackStatus, PaymentCallback},
+    environment::GatewayUrl,
+    error::{Error, ZarinResult},
+    extensions::ZarinpalSendExtension,
+    methods::request::{Currency, RequestPayment},
+    results::{
+        request::{GatewayKind, Request},
+        result_code::ResultCode,
+    },
+    ZarinpalClient,
+};
+
+/// State of a [`PaymentSession`].
+///
+/// `Requested` → `Redirected` → `CallbackReceived` → `Verified` or `Failed`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SessionState {
+    /// Payment is requested, and an authority is issued.
+    Requested,
+
+    /// Payer is redirected to the gateway.
+    Redirected,
+
+    /// Payer came back to the callback url.
+    CallbackReceived {
+        /// Status reported in the callback.
+        status: CallbackStatus,
+    },
+
+    /// Payment is verified.
+    Verified {
+        /// Reference id of the payment.
+        ref_id: u64,
+
+        /// Masked card number of the payer.
+        card_pan: String,
+    },
+
+    /// Payment is failed.
+    Failed {
+        /// Result code verify is rejected with, `None` if the payer canceled the payment.
+        code: Option<ResultCode>,
+    },
+}
+
+impl SessionState {
+    /// Name of the state, as used in serialization.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionState::Requested => "requested",
+            SessionState::Redirected => "redirected",
+            SessionState::CallbackReceived { .. } => "callback_received",
+            SessionState::Verified { .. } => "verified",
+            SessionState::Failed { .. } => "failed",
+        }
+    }
+
+    /// Returns `true` if the session is verified or failed, and won't change anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            SessionState::Verified { .. } | SessionState::Failed { .. }
+        )
+    }
+}
+
+/// A payment, from request to verification.
+///
+/// Instead of juggling a [`Request`], callback params and a verify result, keep a session
+/// (it's serializable, so you can persist it between steps) and move it forward.
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::session::{PaymentSession, SessionState};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let mut session = PaymentSession::start(
+///         zarinpal
+///             .request_payment(10000, "https://example.com/verify".parse()?, "...")
+///             .build(),
+///     )
+///     .await?;
+///
+///     // Redirect the payer to this url.
+///     let gateway_url = session.redirect()?;
+///
+///     // Later, in the callback handler.
+///     let callback = PaymentCallback::from_query(
+///         "Authority=A00000000000000000000000000217885159&Status=OK",
+///     )?;
+///     session.receive_callback(&callback)?;
+///
+///     if let SessionState::Verified { ref_id, .. } = session.verify(&zarinpal).await? {
+///         println!("Paid: {ref_id}");
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentSession {
+    authority: String,
+    amount: u64,
+    currency: Currency,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gateway_url: Option<url::Url>,
+    #[serde(flatten)]
+    state: SessionState,
+}
+
+impl PaymentSession {
+    /// Sends a payment request, and starts a session for it.
+    pub async fn start<Z: ZarinpalClient + Sync + Send>(
+        request: RequestPayment<'_, &Z>,
+    ) -> ZarinResult<Self> {
+        Ok(Self::from_request(&request.await?))
+    }
+
+    /// Starts a session for an already sent payment request.
+    pub fn from_request(request: &Request) -> Self {
+        Self {
+            authority: request.authority().to_string(),
+            amount: request.amount(),
+            currency: request.currency().clone(),
+            gateway_url: Some(request.gateway_url()),
+            state: SessionState::Requested,
+        }
+    }
+
+    /// Unique authority of the payment.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Requested amount, in [`PaymentSession::currency`].
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Currency the payment is requested in.
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Current state of the session.
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    /// Marks the payer as redirected, and returns the gateway url to redirect to.
+    ///
+    /// That's [`Request::gateway_url`] of the payment request, or the production gateway for
+    /// sessions persisted without one.
+    pub fn redirect(&mut self) -> ZarinResult<url::Url> {
+        match self.state {
+            SessionState::Requested | SessionState::Redirected => {
+                self.state = SessionState::Redirected;
+                Ok(self.gateway_url.clone().unwrap_or_else(|| {
+                    GatewayUrl::default().payment_url(&self.authority, &GatewayKind::Default)
+                }))
+            }
+            _ => Err(self.invalid_transition("redirect")),
+        }
+    }
+
+    /// Records the callback of this payment.
+    ///
+    /// Fails if the callback belongs to another authority.
+    pub fn receive_callback(&mut self, callback: &PaymentCallback) -> ZarinResult<()> {
+        if callback.authority() != self.authority {
+            return Err(Error::SessionAuthorityMismatch {
+                expected: self.authority.clone(),
+                actual: callback.authority().to_string(),
+            });
+        }
+
+        match self.state {
+            SessionState::Requested | SessionState::Redirected => {
+                self.state = SessionState::CallbackReceived {
+                    status: callback.status(),
+                };
+                Ok(())
+            }
+            _ => Err(self.invalid_transition("receive callback")),
+        }
+    }
+
+    /// Verifies the payment, unless the payer canceled it, with the requested amount.
+    ///
+    /// Rejections of the session itself (see [`ResultCode::is_session_error`]) move the session to
+    /// [`SessionState::Failed`]. Other errors, like [`ResultCode::ToManyAttempts`] or network ones,
+    /// are returned and leave the session as is, so it can be verified again.
+    /// Verifying an already verified session is a no-op.
+    pub async fn verify<Z: ZarinpalClient + Sync + Send>(
+        &mut self,
+        zarinpal: &Z,
+    ) -> ZarinResult<&SessionState> {
+        match self.state {
+            SessionState::CallbackReceived {
+                status: CallbackStatus::Ok,
+            } => {}
+            SessionState::CallbackReceived {
+                status: CallbackStatus::Nok,
+            } => {
+                self.state = SessionState::Failed { code: None };
+                return Ok(&self.state);
+            }
+            SessionState::Verified { .. } => return Ok(&self.state),
+            _ => return Err(self.invalid_transition("verify")),
+        }
+
+        let result = zarinpal
+            .verify_payment(self.authority.as_str(), self.amount)
+            .currency(self.currency.clone())
+            .build()
+            .await;
+
+        self.state = match result {
+            Ok(verify) => SessionState::Verified {
+                ref_id: verify.ref_id(),
+                card_pan: verify.card_pan().to_string(),
+            },
+            Err(Error::ZarinpalApiError(error)) if error.code().is_session_error() => {
+                SessionState::Failed {
+                    code: Some(error.code()),
+                }
+            }
+            Err(error) => return Err(error),
+        };
+        Ok(&self.state)
+    }
+
+    fn invalid_transition(&self, action: &'static str) -> Error {
+        Error::InvalidSessionTransition {
+            state: self.state.name(),
+            action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Zarinpal, TEST_UUID};
+
+    use super::*;
+
+    fn session() -> PaymentSession {
+        PaymentSession {
+            authority: "A00000000000000000000000000217885159".to_string(),
+            amount: 10000,
+            currency: Currency::IRT,
+            gateway_url: None,
+            state: SessionState::Requested,
+        }
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut session = session();
+
+        assert_eq!(
+            session.redirect().unwrap().as_str(),
+            "https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159"
+        );
+        assert_eq!(session.state(), &SessionState::Redirected);
+
+        let other = PaymentCallback::new("A1", CallbackStatus::Ok);
+        assert!(matches!(
+            session.receive_callback(&other),
+            Err(Error::SessionAuthorityMismatch { .. })
+        ));
+
+        let callback = PaymentCallback::new(session.authority(), CallbackStatus::Ok);
+        session.receive_callback(&callback).unwrap();
+        assert!(matches!(
+            session.redirect(),
+            Err(Error::InvalidSessionTransition {
+                state: "callback_received",
+                action: "redirect"
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_canceled() {
+        let zarinpal =
+            Zarinpal::new_with_transport(TEST_UUID, crate::transport::tests::OfflineTransport)
+                .unwrap();
+        let mut session = session();
+
+        assert!(matches!(
+            session.verify(&zarinpal).await,
+            Err(Error::InvalidSessionTransition { .. })
+        ));
+
+        let callback = PaymentCallback::new(session.authority(), CallbackStatus::Nok);
+        session.receive_callback(&callback).unwrap();
+        assert_eq!(
+            session.verify(&zarinpal).await.unwrap(),
+            &SessionState::Failed { code: None }
+        );
+        assert!(session.state().is_final());
+    }
+
+    #[tokio::test]
+    async fn test_verify_network_error() {
+        let zarinpal =
+            Zarinpal::new_with_transport(TEST_UUID, crate::transport::tests::OfflineTransport)
+                .unwrap();
+        let mut session = session();
+
+        let callback = PaymentCallback::new(session.authority(), CallbackStatus::Ok);
+        session.receive_callback(&callback).unwrap();
+        assert!(session.verify(&zarinpal).await.is_err());
+        assert_eq!(
+            session.state(),
+            &SessionState::CallbackReceived {
+                status: CallbackStatus::Ok
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejected() {
+        use crate::transport::tests::{responses, CannedTransport};
+
+        let callback = PaymentCallback::new(session().authority(), CallbackStatus::Ok);
+
+        let busy = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::error(-12, "Too many attempts")),
+        )
+        .unwrap();
+        let mut session = session();
+        session.receive_callback(&callback).unwrap();
+        let error = session.verify(&busy).await.unwrap_err();
+        assert_eq!(error.result_code(), Some(ResultCode::ToManyAttempts));
+        assert_eq!(
+            session.state(),
+            &SessionState::CallbackReceived {
+                status: CallbackStatus::Ok
+            }
+        );
+
+        let rejecting = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::error(-51, "Session is not valid")),
+        )
+        .unwrap();
+        assert_eq!(
+            session.verify(&rejecting).await.unwrap(),
+            &SessionState::Failed {
+                code: Some(ResultCode::InvalidSeasonNoActivePayment)
+            }
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut session = session();
+        session.state = SessionState::Verified {
+            ref_id: 201,
+            card_pan: "502229******5995".to_string(),
+        };
+
+        let json = serde_json::to_value(&session).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "authority": "A00000000000000000000000000217885159",
+                "amount": 10000,
+                "currency": "IRT",
+                "state": "verified",
+                "ref_id": 201,
+                "card_pan": "502229******5995"
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<PaymentSession>(json).unwrap(),
+            session
+        );
+    }
+}
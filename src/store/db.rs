@@ -0,0 +1,40 @@
+//! Encoding of records as they're stored in databases, shared by `sqlx` stores and `orm`
+//! entities.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, ZarinResult};
+
+use super::PaymentState;
+
+/// Milliseconds since unix epoch, as times are stored in databases.
+pub(crate) fn to_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+pub(crate) fn from_millis(millis: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64)
+}
+
+/// State as stored in databases, a name and the reference id of verified payments.
+pub(crate) fn encode_state(state: PaymentState) -> (&'static str, Option<i64>) {
+    match state {
+        PaymentState::Pending => ("pending", None),
+        PaymentState::Verified { ref_id } => ("verified", Some(ref_id as i64)),
+        PaymentState::Failed => ("failed", None),
+        PaymentState::Expired => ("expired", None),
+    }
+}
+
+pub(crate) fn decode_state(state: &str, ref_id: Option<i64>) -> ZarinResult<PaymentState> {
+    Ok(match (state, ref_id) {
+        ("pending", _) => PaymentState::Pending,
+        ("verified", Some(ref_id)) => PaymentState::Verified {
+            ref_id: ref_id as u64,
+        },
+        ("failed", _) => PaymentState::Failed,
+        ("expired", _) => PaymentState::Expired,
+        _ => return Err(Error::StoreError(format!("invalid state {state:?}").into())),
+    })
+}
@@ -0,0 +1,299 @@
+//! Splitting a total amount into scheduled installments that share an order reference.
+//!
+//! Scheduling itself (cron, timers, ...) is left to the application: ask the
+//! [`InstallmentSchedule`] which installments are [due](InstallmentSchedule::due) and
+//! [request](Installment::request) them.
+
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime};
+
+use typed_builder::TypedBuilder;
+
+use crate::{
+    error::{Error, ZarinResult},
+    extensions::ZarinpalSendExtension,
+    methods::request::Metadata,
+    results::request::Request,
+    ZarinpalClient,
+};
+
+use super::{order::OrderStatus, PaymentRecord, PaymentState, PaymentStore};
+
+/// Definition of an installment plan.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct InstallmentPlan {
+    /// Number of installments, including the down payment (if any).
+    count: usize,
+
+    /// Time between two installments.
+    interval: Duration,
+
+    /// Due date of the first installment.
+    #[builder(default = SystemTime::now())]
+    first_due: SystemTime,
+
+    /// (Optional) Amount of the first installment. The rest is split evenly.
+    #[builder(default, setter(strip_option))]
+    down_payment: Option<u64>,
+}
+
+impl InstallmentPlan {
+    /// Splits `total` into installments of `order_id`.
+    ///
+    /// Amounts that can't be split evenly are added to the first installments,
+    /// so they always sum up to `total`.
+    ///
+    /// Fails with [`Error::InvalidInstallmentPlan`] if an installment would be of zero amount
+    /// (eg: a down payment that's not less than `total`, or fewer rials than installments), or
+    /// a due date is too far in the future to be represented.
+    pub fn schedule(
+        &self,
+        total: u64,
+        order_id: impl Into<String>,
+    ) -> ZarinResult<InstallmentSchedule> {
+        let invalid = Error::InvalidInstallmentPlan;
+        let order_id = order_id.into();
+        if self.count == 0 {
+            return Err(invalid("count must be at least 1.".to_string()));
+        }
+
+        let mut amounts = Vec::with_capacity(self.count);
+        let mut rest = total;
+        if let Some(down_payment) = self.down_payment.filter(|_| self.count > 1) {
+            if down_payment == 0 || down_payment >= total {
+                return Err(invalid(format!(
+                    "down payment ({down_payment}) must be more than zero and less than the total ({total})."
+                )));
+            }
+            amounts.push(down_payment);
+            rest -= down_payment;
+        }
+
+        let parts = (self.count - amounts.len()) as u64;
+        let (share, remainder) = (rest / parts, rest % parts);
+        if share == 0 {
+            return Err(invalid(format!(
+                "{rest} can't be split into {parts} installments."
+            )));
+        }
+        amounts.extend((0..parts).map(|i| share + u64::from(i < remainder)));
+
+        let installments = amounts
+            .into_iter()
+            .enumerate()
+            .map(|(index, amount)| {
+                let due = u32::try_from(index)
+                    .ok()
+                    .and_then(|index| self.interval.checked_mul(index))
+                    .and_then(|offset| self.first_due.checked_add(offset))
+                    .ok_or_else(|| {
+                        invalid(format!("due date of installment #{index} overflows."))
+                    })?;
+                Ok(Installment {
+                    index,
+                    amount,
+                    due,
+                    order_id: order_id.clone(),
+                })
+            })
+            .collect::<ZarinResult<_>>()?;
+
+        Ok(InstallmentSchedule {
+            order_id,
+            installments,
+        })
+    }
+}
+
+/// A single installment of an [`InstallmentSchedule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installment {
+    index: usize,
+    amount: u64,
+    due: SystemTime,
+    order_id: String,
+}
+
+impl Installment {
+    /// Zero-based index of the installment.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Amount of the installment.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Due date of the installment.
+    pub fn due(&self) -> SystemTime {
+        self.due
+    }
+
+    /// Order id shared between all installments of the schedule.
+    pub fn order_id(&self) -> &str {
+        self.order_id.as_ref()
+    }
+
+    /// Requests payment of this installment and tracks it in the `store`.
+    pub async fn request<Z, S>(
+        &self,
+        zarinpal: &Z,
+        store: &S,
+        callback_url: url::Url,
+        description: impl Into<Cow<'_, str>>,
+    ) -> ZarinResult<Request>
+    where
+        Z: ZarinpalClient + Sync + Send,
+        S: PaymentStore + ?Sized,
+    {
+        let description: Cow<'_, str> = description.into();
+        let request = zarinpal
+            .request_payment(self.amount, callback_url, description)
+            .metadata(Metadata::builder().order_id(self.order_id.as_str()).build())
+            .build()
+            .await?;
+
+        store
+            .save(PaymentRecord::from_request(&request).with_order_id(self.order_id.as_str()))
+            .await?;
+
+        Ok(request)
+    }
+}
+
+/// Installments generated by an [`InstallmentPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallmentSchedule {
+    order_id: String,
+    installments: Vec<Installment>,
+}
+
+impl InstallmentSchedule {
+    /// Order id shared between all installments.
+    pub fn order_id(&self) -> &str {
+        self.order_id.as_ref()
+    }
+
+    /// All installments, ordered by due date.
+    pub fn installments(&self) -> &[Installment] {
+        self.installments.as_ref()
+    }
+
+    /// Installments that are due at `now`. Useful for sending reminders or requesting them.
+    pub fn due(&self, now: SystemTime) -> impl Iterator<Item = &Installment> {
+        self.installments
+            .iter()
+            .filter(move |installment| installment.due <= now)
+    }
+
+    /// Aggregate status of the installments, counting the ones that are not requested yet as unpaid.
+    pub async fn status<S: PaymentStore + ?Sized>(&self, store: &S) -> ZarinResult<OrderStatus> {
+        let paid = store
+            .load_order(&self.order_id)
+            .await?
+            .iter()
+            .filter(|record| matches!(record.state(), PaymentState::Verified { .. }))
+            .count();
+
+        Ok(match (paid, self.installments.len()) {
+            (_, 0) => OrderStatus::Empty,
+            (0, _) => OrderStatus::Unpaid,
+            (paid, total) if paid >= total => OrderStatus::AllPaid,
+            (paid, total) => OrderStatus::PartiallyPaid { paid, total },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{methods::request::Currency, store::MemoryStore};
+
+    use super::*;
+
+    const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+    #[test]
+    fn test_schedule() {
+        let start = SystemTime::UNIX_EPOCH;
+        let plan = InstallmentPlan::builder()
+            .count(3)
+            .interval(MONTH)
+            .first_due(start)
+            .build();
+
+        let schedule = plan.schedule(100_000, "order-1").unwrap();
+        let amounts = schedule
+            .installments()
+            .iter()
+            .map(Installment::amount)
+            .collect::<Vec<_>>();
+
+        assert_eq!(amounts, [33_334, 33_333, 33_333]);
+        assert_eq!(schedule.installments()[2].due(), start + MONTH * 2);
+        assert_eq!(schedule.due(start + MONTH).count(), 2);
+    }
+
+    #[test]
+    fn test_schedule_with_down_payment() {
+        let plan = InstallmentPlan::builder()
+            .count(3)
+            .interval(MONTH)
+            .down_payment(50_000)
+            .build();
+
+        let schedule = plan.schedule(100_000, "order-1").unwrap();
+        let amounts = schedule
+            .installments()
+            .iter()
+            .map(Installment::amount)
+            .collect::<Vec<_>>();
+
+        assert_eq!(amounts, [50_000, 25_000, 25_000]);
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        let invalid = |plan: InstallmentPlan, total: u64| {
+            assert!(matches!(
+                plan.schedule(total, "order-1"),
+                Err(Error::InvalidInstallmentPlan(_))
+            ));
+        };
+        let plan = |count| InstallmentPlan::builder().count(count).interval(MONTH);
+
+        invalid(plan(3).down_payment(100_000).build(), 100_000);
+        invalid(plan(3).down_payment(0).build(), 100_000);
+        invalid(plan(3).build(), 2);
+        invalid(plan(0).build(), 100_000);
+
+        let far = InstallmentPlan::builder()
+            .count(3)
+            .interval(Duration::MAX / 2)
+            .build();
+        invalid(far, 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let store = MemoryStore::new();
+        let schedule = InstallmentPlan::builder()
+            .count(2)
+            .interval(MONTH)
+            .build()
+            .schedule(20_000, "order-1")
+            .unwrap();
+
+        store
+            .save(PaymentRecord::new("A1", 10_000, Currency::IRR).with_order_id("order-1"))
+            .await
+            .unwrap();
+        assert_eq!(schedule.status(&store).await.unwrap(), OrderStatus::Unpaid);
+
+        store.mark_verified("A1", 201).await.unwrap();
+        assert_eq!(
+            schedule.status(&store).await.unwrap(),
+            OrderStatus::PartiallyPaid { paid: 1, total: 2 }
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Persistence of payment requests, so they can be tracked and verified later.
+//!
+//! [`PaymentStore`] is the abstraction, and [`MemoryStore`] is a simple in-memory implementation.
+//! Database backed stores are in `sqlx` module (`sqlx-postgres` and `sqlx-sqlite` features).
+//!
+//! Processed records can be moved out of the hot store using [`PaymentStore::archive`]
+//! and [`PaymentStore::expire_pending`], and queried later with [`PaymentStore::load_archived`].
+//!
+//! Api calls that must not be lost (like verifications) can go through an [`outbox::Outbox`].
+
+#[cfg(any(feature = "sqlx-postgres", feature = "sqlx-sqlite", feature = "orm"))]
+pub(crate) mod db;
+pub mod installment;
+pub mod order;
+pub mod outbox;
+#[cfg(any(feature = "sqlx-postgres", feature = "sqlx-sqlite"))]
+pub mod sqlx;
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use typed_builder::TypedBuilder;
+
+use crate::{error::ZarinResult, methods::request::Currency, results::request::Request};
+
+/// State of a tracked payment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentState {
+    /// Payment is requested, but not verified yet.
+    Pending,
+
+    /// Payment is verified.
+    Verified {
+        /// Reference id of the payment.
+        ref_id: u64,
+    },
+
+    /// Payment is failed or canceled.
+    Failed,
+
+    /// Payment stayed pending for too long, see [`PaymentStore::expire_pending`].
+    Expired,
+}
+
+/// A payment request tracked by a [`PaymentStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRecord {
+    pub(crate) authority: String,
+    pub(crate) amount: u64,
+    pub(crate) currency: Currency,
+    pub(crate) order_id: Option<String>,
+    pub(crate) state: PaymentState,
+    pub(crate) created_at: SystemTime,
+    pub(crate) archived_at: Option<SystemTime>,
+}
+
+impl PaymentRecord {
+    /// Creates a new [`PaymentState::Pending`] record.
+    pub fn new(authority: impl Into<String>, amount: u64, currency: Currency) -> Self {
+        Self {
+            authority: authority.into(),
+            amount,
+            currency,
+            order_id: None,
+            state: PaymentState::Pending,
+            created_at: SystemTime::now(),
+            archived_at: None,
+        }
+    }
+
+    /// Creates a new [`PaymentState::Pending`] record from a [`Request`] result.
+    pub fn from_request(request: &Request) -> Self {
+        Self::new(
+            request.authority(),
+            request.amount(),
+            request.currency().clone(),
+        )
+    }
+
+    /// Sets order id of the record.
+    pub fn with_order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.order_id = Some(order_id.into());
+        self
+    }
+
+    /// Unique authority of the payment request.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Payment amount, in [`PaymentRecord::currency`].
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Currency used in the payment request.
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Order id that this payment belongs to.
+    pub fn order_id(&self) -> Option<&str> {
+        self.order_id.as_deref()
+    }
+
+    /// Current state of the payment.
+    pub fn state(&self) -> PaymentState {
+        self.state
+    }
+
+    /// When the payment was requested.
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    /// When the record was archived, if it's archived.
+    pub fn archived_at(&self) -> Option<SystemTime> {
+        self.archived_at
+    }
+
+    /// Returns `true` if the record is archived.
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+}
+
+/// A filter over archived records, see [`PaymentStore::load_archived`].
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct ArchiveQuery {
+    /// (Optional) Only records of this order.
+    #[builder(default, setter(strip_option, into))]
+    order_id: Option<String>,
+
+    /// (Optional) Only records in this state.
+    #[builder(default, setter(strip_option))]
+    state: Option<PaymentState>,
+
+    /// (Optional) Only records requested at or after this time.
+    #[builder(default, setter(strip_option))]
+    since: Option<SystemTime>,
+
+    /// (Optional) Only records requested before this time.
+    #[builder(default, setter(strip_option))]
+    until: Option<SystemTime>,
+}
+
+impl ArchiveQuery {
+    /// Returns `true` if `record` passes the filter.
+    pub fn matches(&self, record: &PaymentRecord) -> bool {
+        self.order_id
+            .as_deref()
+            .is_none_or(|order_id| record.order_id() == Some(order_id))
+            && self.state.is_none_or(|state| record.state() == state)
+            && self.since.is_none_or(|since| record.created_at() >= since)
+            && self.until.is_none_or(|until| record.created_at() < until)
+    }
+}
+
+/// A storage of payment requests.
+///
+/// Implement this to keep track of pending payments in your own database.
+#[async_trait::async_trait]
+pub trait PaymentStore: Send + Sync {
+    /// Saves a record, replacing any record with the same authority.
+    async fn save(&self, record: PaymentRecord) -> ZarinResult<()>;
+
+    /// Loads a record by its authority.
+    async fn load(&self, authority: &str) -> ZarinResult<Option<PaymentRecord>>;
+
+    /// Loads all (not archived) records belonging to an order.
+    async fn load_order(&self, order_id: &str) -> ZarinResult<Vec<PaymentRecord>>;
+
+    /// Loads all (not archived) records that are still [`PaymentState::Pending`], oldest first.
+    async fn load_pending(&self) -> ZarinResult<Vec<PaymentRecord>>;
+
+    /// Changes state of a record. Does nothing if there's no such record.
+    async fn set_state(&self, authority: &str, state: PaymentState) -> ZarinResult<()>;
+
+    /// Marks a record as verified.
+    async fn mark_verified(&self, authority: &str, ref_id: u64) -> ZarinResult<()> {
+        self.set_state(authority, PaymentState::Verified { ref_id })
+            .await
+    }
+
+    /// Marks a record as failed.
+    async fn mark_failed(&self, authority: &str) -> ZarinResult<()> {
+        self.set_state(authority, PaymentState::Failed).await
+    }
+
+    /// Moves a processed record out of the hot store, into the archive.
+    ///
+    /// Returns `false` if there's no such (not archived) record.
+    async fn archive(&self, authority: &str) -> ZarinResult<bool>;
+
+    /// Marks records that stayed [`PaymentState::Pending`] longer than `max_age`
+    /// as [`PaymentState::Expired`] and archives them.
+    ///
+    /// Returns authorities of expired records.
+    async fn expire_pending(&self, max_age: Duration) -> ZarinResult<Vec<String>>;
+
+    /// Loads archived records passing `query`, oldest first.
+    async fn load_archived(&self, query: &ArchiveQuery) -> ZarinResult<Vec<PaymentRecord>>;
+
+    /// Permanently removes records archived before `before`.
+    ///
+    /// Returns number of removed records.
+    async fn purge_archived(&self, before: SystemTime) -> ZarinResult<usize>;
+}
+
+/// An in-memory [`PaymentStore`].
+///
+/// Records are lost when the process exits, so it's mostly useful for tests and prototypes.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, PaymentRecord>>,
+    archived: Mutex<HashMap<String, PaymentRecord>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl PaymentStore for MemoryStore {
+    async fn save(&self, record: PaymentRecord) -> ZarinResult<()> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(record.authority.clone(), record);
+        Ok(())
+    }
+
+    async fn load(&self, authority: &str) -> ZarinResult<Option<PaymentRecord>> {
+        Ok(self.records.lock().unwrap().get(authority).cloned())
+    }
+
+    async fn load_order(&self, order_id: &str) -> ZarinResult<Vec<PaymentRecord>> {
+        let mut records = self
+            .records
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|record| record.order_id() == Some(order_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn load_pending(&self) -> ZarinResult<Vec<PaymentRecord>> {
+        let mut records = self
+            .records
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|record| record.state == PaymentState::Pending)
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn set_state(&self, authority: &str, state: PaymentState) -> ZarinResult<()> {
+        if let Some(record) = self.records.lock().unwrap().get_mut(authority) {
+            record.state = state;
+        }
+        Ok(())
+    }
+
+    async fn archive(&self, authority: &str) -> ZarinResult<bool> {
+        let Some(mut record) = self.records.lock().unwrap().remove(authority) else {
+            return Ok(false);
+        };
+
+        record.archived_at = Some(SystemTime::now());
+        self.archived
+            .lock()
+            .unwrap()
+            .insert(record.authority.clone(), record);
+        Ok(true)
+    }
+
+    async fn expire_pending(&self, max_age: Duration) -> ZarinResult<Vec<String>> {
+        let now = SystemTime::now();
+        let mut records = self.records.lock().unwrap();
+
+        let stale = records
+            .values()
+            .filter(|record| {
+                record.state == PaymentState::Pending
+                    && now
+                        .duration_since(record.created_at)
+                        .is_ok_and(|age| age > max_age)
+            })
+            .map(|record| record.authority.clone())
+            .collect::<Vec<_>>();
+
+        let mut archived = self.archived.lock().unwrap();
+        for authority in &stale {
+            if let Some(mut record) = records.remove(authority) {
+                record.state = PaymentState::Expired;
+                record.archived_at = Some(now);
+                archived.insert(authority.clone(), record);
+            }
+        }
+
+        Ok(stale)
+    }
+
+    async fn load_archived(&self, query: &ArchiveQuery) -> ZarinResult<Vec<PaymentRecord>> {
+        let mut records = self
+            .archived
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|record| query.matches(record))
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn purge_archived(&self, before: SystemTime) -> ZarinResult<usize> {
+        let mut archived = self.archived.lock().unwrap();
+        let count = archived.len();
+        archived.retain(|_, record| record.archived_at.is_none_or(|at| at >= before));
+        Ok(count - archived.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryStore::new();
+
+        store
+            .save(PaymentRecord::new("A1", 10000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+        store
+            .save(PaymentRecord::new("A2", 5000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+        store
+            .save(PaymentRecord::new("A3", 5000, Currency::IRT))
+            .await
+            .unwrap();
+
+        store.mark_verified("A1", 201).await.unwrap();
+
+        let record = store.load("A1").await.unwrap().unwrap();
+        assert_eq!(record.state(), PaymentState::Verified { ref_id: 201 });
+        assert_eq!(record.amount(), 10000);
+
+        assert_eq!(store.load_order("order-1").await.unwrap().len(), 2);
+        assert!(store.load("A4").await.unwrap().is_none());
+
+        let pending = store.load_pending().await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|record| record.authority() != "A1"));
+    }
+
+    #[tokio::test]
+    async fn test_archive() {
+        let store = MemoryStore::new();
+
+        let mut stale = PaymentRecord::new("A1", 10000, Currency::IRT).with_order_id("order-1");
+        stale.created_at -= Duration::from_secs(3 * 24 * 60 * 60);
+        store.save(stale).await.unwrap();
+        store
+            .save(PaymentRecord::new("A2", 5000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+        store
+            .save(PaymentRecord::new("A3", 5000, Currency::IRT))
+            .await
+            .unwrap();
+
+        store.mark_verified("A2", 202).await.unwrap();
+        assert!(store.archive("A2").await.unwrap());
+        assert!(!store.archive("A2").await.unwrap());
+
+        let expired = store
+            .expire_pending(Duration::from_secs(24 * 60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(expired, ["A1"]);
+
+        // Only A3 is left in hot store.
+        assert!(store.load_order("order-1").await.unwrap().is_empty());
+        assert!(store.load("A3").await.unwrap().is_some());
+
+        let archived = store
+            .load_archived(&ArchiveQuery::builder().order_id("order-1").build())
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 2);
+        assert_eq!(archived[0].state(), PaymentState::Expired);
+        assert!(archived.iter().all(PaymentRecord::is_archived));
+
+        let verified = store
+            .load_archived(
+                &ArchiveQuery::builder()
+                    .state(PaymentState::Verified { ref_id: 202 })
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(verified.len(), 1);
+
+        let purged = store
+            .purge_archived(SystemTime::now() + Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 2);
+        assert!(store
+            .load_archived(&ArchiveQuery::default())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
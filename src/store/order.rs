@@ -0,0 +1,216 @@
+//! Splitting one logical order into several payment requests that share an `order_id`.
+
+use crate::{
+    error::ZarinResult,
+    extensions::ZarinpalSendExtension,
+    methods::request::{Currency, Metadata},
+    results::request::Request,
+    ZarinpalClient,
+};
+
+use super::{PaymentRecord, PaymentState, PaymentStore};
+
+/// Aggregate status of all payments of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    /// There's no payment for the order.
+    Empty,
+
+    /// None of the payments are verified.
+    Unpaid,
+
+    /// Some of the payments are verified.
+    PartiallyPaid {
+        /// Number of verified payments.
+        paid: usize,
+
+        /// Number of all payments.
+        total: usize,
+    },
+
+    /// All of the payments are verified.
+    AllPaid,
+}
+
+impl OrderStatus {
+    /// Calculates status of an order from its records.
+    pub fn from_records<'r>(records: impl IntoIterator<Item = &'r PaymentRecord>) -> Self {
+        let (paid, total) = records
+            .into_iter()
+            .fold((0, 0), |(paid, total), record| match record.state() {
+                PaymentState::Verified { .. } => (paid + 1, total + 1),
+                _ => (paid, total + 1),
+            });
+
+        match (paid, total) {
+            (_, 0) => OrderStatus::Empty,
+            (0, _) => OrderStatus::Unpaid,
+            (paid, total) if paid == total => OrderStatus::AllPaid,
+            (paid, total) => OrderStatus::PartiallyPaid { paid, total },
+        }
+    }
+
+    /// Returns `true` if all payments of the order are verified.
+    #[must_use]
+    pub fn is_all_paid(&self) -> bool {
+        matches!(self, Self::AllPaid)
+    }
+}
+
+/// A single payment request of an [`OrderCheckout`].
+#[derive(Debug, Clone)]
+struct OrderPart {
+    amount: u64,
+    description: String,
+}
+
+/// Splits one logical order into several payment requests that share an `order_id`,
+/// and tracks them together in a [`PaymentStore`].
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::store::{order::{order_status, OrderCheckout}, MemoryStore};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///     let store = MemoryStore::new();
+///
+///     let callback_url = "https://example.com/verify".parse()?;
+///     let requests = OrderCheckout::new(&zarinpal, &store, "order-1", callback_url)
+///         .part(50000, "First shipment")
+///         .part(70000, "Second shipment")
+///         .request_all()
+///         .await?;
+///
+///     // ~~~ Later, after verifying payments ~~~
+///     let status = order_status(&store, "order-1").await?;
+///     println!("{status:?}");
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct OrderCheckout<'z, Z, S: ?Sized> {
+    zarinpal: &'z Z,
+    store: &'z S,
+    order_id: String,
+    callback_url: url::Url,
+    currency: Option<Currency>,
+    parts: Vec<OrderPart>,
+}
+
+impl<'z, Z, S> OrderCheckout<'z, Z, S>
+where
+    Z: ZarinpalClient + Sync + Send,
+    S: PaymentStore + ?Sized,
+{
+    /// Creates a new checkout for `order_id`, whose payments are all redirected to `callback_url`.
+    pub fn new(
+        zarinpal: &'z Z,
+        store: &'z S,
+        order_id: impl Into<String>,
+        callback_url: url::Url,
+    ) -> Self {
+        Self {
+            zarinpal,
+            store,
+            order_id: order_id.into(),
+            callback_url,
+            currency: None,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Sets currency of all payments.
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Adds a payment request to the order.
+    pub fn part(mut self, amount: u64, description: impl Into<String>) -> Self {
+        self.parts.push(OrderPart {
+            amount,
+            description: description.into(),
+        });
+        self
+    }
+
+    /// Sends all payment requests and saves them in the store, in order.
+    ///
+    /// Stops at the first failure; requests made before that are already saved.
+    pub async fn request_all(self) -> ZarinResult<Vec<Request>> {
+        let mut requests = Vec::with_capacity(self.parts.len());
+
+        for part in self.parts {
+            let metadata = Metadata::builder().order_id(self.order_id.as_str()).build();
+            let request = match &self.currency {
+                Some(currency) => {
+                    self.zarinpal
+                        .request_payment(part.amount, self.callback_url.clone(), part.description)
+                        .currency(currency.clone())
+                        .metadata(metadata)
+                        .build()
+                        .await?
+                }
+                None => {
+                    self.zarinpal
+                        .request_payment(part.amount, self.callback_url.clone(), part.description)
+                        .metadata(metadata)
+                        .build()
+                        .await?
+                }
+            };
+
+            self.store
+                .save(PaymentRecord::from_request(&request).with_order_id(self.order_id.as_str()))
+                .await?;
+            requests.push(request);
+        }
+
+        Ok(requests)
+    }
+}
+
+/// Aggregate status of all payments of an order.
+pub async fn order_status<S: PaymentStore + ?Sized>(
+    store: &S,
+    order_id: &str,
+) -> ZarinResult<OrderStatus> {
+    Ok(OrderStatus::from_records(
+        &store.load_order(order_id).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::MemoryStore;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_status() {
+        let store = MemoryStore::new();
+        let status = || order_status(&store, "order-1");
+
+        assert_eq!(status().await.unwrap(), OrderStatus::Empty);
+
+        for authority in ["A1", "A2"] {
+            store
+                .save(PaymentRecord::new(authority, 10000, Currency::IRR).with_order_id("order-1"))
+                .await
+                .unwrap();
+        }
+        assert_eq!(status().await.unwrap(), OrderStatus::Unpaid);
+
+        store.mark_verified("A1", 201).await.unwrap();
+        assert_eq!(
+            status().await.unwrap(),
+            OrderStatus::PartiallyPaid { paid: 1, total: 2 }
+        );
+
+        store.mark_verified("A2", 202).await.unwrap();
+        assert!(status().await.unwrap().is_all_paid());
+    }
+}
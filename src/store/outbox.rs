@@ -0,0 +1,504 @@
+//! Persisted api calls, that survive crashes and network outages.
+//!
+//! An [`Outbox`] saves a request to its [`OutboxStore`] before sending it, and removes it once
+//! the api has answered. If the process dies (or the network fails) in between, the request
+//! is still there to be sent by [`Outbox::replay`], so a verification is never lost.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Auth,
+    error::{Error, ZarinResult},
+    methods::ApiMethod,
+    results::{result_code::ResultCode, RequestResult},
+    ZarinpalClient,
+};
+
+/// A pending api call, persisted by an [`OutboxStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    id: u64,
+    path: String,
+    #[serde(default)]
+    auth: Auth,
+    body: serde_json::Value,
+    created_at: SystemTime,
+    attempts: u32,
+    last_error: Option<String>,
+}
+
+impl OutboxEntry {
+    /// Unique id of the entry, in its store.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Api path of the call, the same as [`ApiMethod::PATH`].
+    pub fn path(&self) -> &str {
+        self.path.as_ref()
+    }
+
+    /// How the call is authenticated, the same as [`ApiMethod::AUTH`].
+    ///
+    /// Credentials themselves are not saved, they're taken from the client when it's sent.
+    pub fn auth(&self) -> Auth {
+        self.auth
+    }
+
+    /// Json body of the call, with merchant id and anything else from the client filled in.
+    pub fn body(&self) -> &serde_json::Value {
+        &self.body
+    }
+
+    /// When the call was saved.
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    /// Number of failed attempts to send the call.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Error of the last failed attempt.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+}
+
+/// A storage of [`OutboxEntry`]s.
+///
+/// Implement this to keep pending calls in your own database.
+#[async_trait::async_trait]
+pub trait OutboxStore: Send + Sync {
+    /// Saves a new entry, and returns its id.
+    async fn push(&self, path: &str, auth: Auth, body: serde_json::Value) -> ZarinResult<u64>;
+
+    /// Loads all entries, oldest first.
+    async fn load_all(&self) -> ZarinResult<Vec<OutboxEntry>>;
+
+    /// Records a failed attempt of an entry. Does nothing if there's no such entry.
+    async fn record_failure(&self, id: u64, error: &str) -> ZarinResult<()>;
+
+    /// Removes an entry, once it's done.
+    async fn remove(&self, id: u64) -> ZarinResult<()>;
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Entries {
+    next_id: u64,
+    entries: BTreeMap<u64, OutboxEntry>,
+}
+
+impl Entries {
+    fn push(&mut self, path: &str, auth: Auth, body: serde_json::Value) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.entries.insert(
+            id,
+            OutboxEntry {
+                id,
+                path: path.to_string(),
+                auth,
+                body,
+                created_at: SystemTime::now(),
+                attempts: 0,
+                last_error: None,
+            },
+        );
+        id
+    }
+
+    fn record_failure(&mut self, id: u64, error: &str) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.attempts += 1;
+            entry.last_error = Some(error.to_string());
+        }
+    }
+}
+
+/// An in-memory [`OutboxStore`].
+///
+/// Entries are lost when the process exits, so it's mostly useful for tests.
+#[derive(Debug, Default)]
+pub struct MemoryOutbox {
+    entries: Mutex<Entries>,
+}
+
+impl MemoryOutbox {
+    /// Creates an empty outbox store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboxStore for MemoryOutbox {
+    async fn push(&self, path: &str, auth: Auth, body: serde_json::Value) -> ZarinResult<u64> {
+        Ok(self.entries.lock().unwrap().push(path, auth, body))
+    }
+
+    async fn load_all(&self) -> ZarinResult<Vec<OutboxEntry>> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn record_failure(&self, id: u64, error: &str) -> ZarinResult<()> {
+        self.entries.lock().unwrap().record_failure(id, error);
+        Ok(())
+    }
+
+    async fn remove(&self, id: u64) -> ZarinResult<()> {
+        self.entries.lock().unwrap().entries.remove(&id);
+        Ok(())
+    }
+}
+
+/// An [`OutboxStore`] kept in a json file.
+///
+/// The whole file is rewritten (atomically, through a temporary file) on every change,
+/// so it's meant for a small number of pending calls.
+#[derive(Debug)]
+pub struct FileOutbox {
+    path: PathBuf,
+    entries: tokio::sync::Mutex<Entries>,
+}
+
+impl FileOutbox {
+    /// Opens an outbox file, or starts an empty one if it doesn't exist.
+    pub fn open(path: impl Into<PathBuf>) -> ZarinResult<Self> {
+        let path = path.into();
+        let entries = match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Entries::default(),
+            Err(error) => return Err(Error::StoreError(error.into())),
+        };
+
+        Ok(Self {
+            path,
+            entries: tokio::sync::Mutex::new(entries),
+        })
+    }
+
+    /// Path of the outbox file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `entries` to the file. Called with the entries locked, so writes don't interleave.
+    async fn write(&self, entries: &Entries) -> ZarinResult<()> {
+        let temp = self.path.with_extension("tmp");
+        let content = serde_json::to_vec(entries)?;
+        async {
+            tokio::fs::write(&temp, content).await?;
+            tokio::fs::rename(&temp, &self.path).await
+        }
+        .await
+        .map_err(|error| Error::StoreError(error.into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboxStore for FileOutbox {
+    async fn push(&self, path: &str, auth: Auth, body: serde_json::Value) -> ZarinResult<u64> {
+        let mut entries = self.entries.lock().await;
+        let id = entries.push(path, auth, body);
+        self.write(&entries).await?;
+        Ok(id)
+    }
+
+    async fn load_all(&self) -> ZarinResult<Vec<OutboxEntry>> {
+        Ok(self
+            .entries
+            .lock()
+            .await
+            .entries
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn record_failure(&self, id: u64, error: &str) -> ZarinResult<()> {
+        let mut entries = self.entries.lock().await;
+        entries.record_failure(id, error);
+        self.write(&entries).await
+    }
+
+    async fn remove(&self, id: u64) -> ZarinResult<()> {
+        let mut entries = self.entries.lock().await;
+        if entries.entries.remove(&id).is_some() {
+            self.write(&entries).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends api calls through an [`OutboxStore`], see [module docs](self).
+///
+/// ```no_run
+/// use zarinpal::prelude::*;
+/// use zarinpal::store::outbox::{FileOutbox, Outbox};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///     let outbox = Outbox::new(FileOutbox::open("outbox.json")?);
+///
+///     // On startup, send whatever is left from last run.
+///     for (entry, result) in outbox.replay(&zarinpal).await? {
+///         println!("{} ({}): {:?}", entry.path(), entry.id(), result);
+///     }
+///
+///     // In the callback handler.
+///     let verify: VerifyPayment = VerifyPayment::builder()
+///         .authority("A00000000000000000000000000217885159")
+///         .amount(10000)
+///         .build();
+///     let verified = outbox.send(&zarinpal, verify).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Outbox<S> {
+    store: S,
+}
+
+impl<S: OutboxStore> Outbox<S> {
+    /// Creates an outbox saving calls to `store`.
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Saves `method`, prepared to be sent by `zarinpal`, without sending it.
+    ///
+    /// Returns id of the saved entry.
+    pub async fn enqueue<Z, M>(&self, zarinpal: &Z, method: M) -> ZarinResult<u64>
+    where
+        Z: ZarinpalClient + ?Sized,
+        M: ApiMethod,
+    {
+        let body = prepared_body(zarinpal, method)?;
+        self.store.push(M::PATH, M::AUTH, body).await
+    }
+
+    /// Saves `method` and sends it using [`ZarinpalClient::send`].
+    ///
+    /// The entry is removed once the api answers (successfully or not), and kept for
+    /// [`Outbox::replay`] if the request may have not reached the api (network errors or timeouts).
+    pub async fn send<Z, M>(&self, zarinpal: &Z, method: M) -> ZarinResult<M::Result>
+    where
+        Z: ZarinpalClient + Sync + Send,
+        M: ApiMethod + Clone + Send + Sync,
+    {
+        let body = prepared_body(zarinpal, method.clone())?;
+        let id = self.store.push(M::PATH, M::AUTH, body).await?;
+
+        let result = zarinpal.send(method).await;
+        self.settle(id, &result).await?;
+        result
+    }
+
+    /// Sends all saved calls, oldest first, the same way [`ZarinpalClient::send`] does (credentials,
+    /// request hook, retries, rate limiting, metrics, audit and the deserialization mode of `zarinpal`).
+    ///
+    /// Returns each entry with its result, entries that failed with network errors or timeouts
+    /// (or couldn't be sent at all) are kept for the next replay. Decode successful results using [`OutboxEntry::path`],
+    /// Eg: `Verify` for [`crate::methods::verify::VerifyPayment::PATH`].
+    pub async fn replay<Z>(
+        &self,
+        zarinpal: &Z,
+    ) -> ZarinResult<Vec<(OutboxEntry, ZarinResult<serde_json::Value>)>>
+    where
+        Z: ZarinpalClient + Sync + Send,
+    {
+        let mut results = Vec::new();
+        for entry in self.store.load_all().await? {
+            let prepared =
+                crate::prepared_request(zarinpal, &entry.path, entry.auth, entry.body.clone());
+            let result = match prepared {
+                Ok(request) => {
+                    let result = crate::send_request(zarinpal, &entry.path, request, None)
+                        .await
+                        .map(|RawData(data)| data);
+                    self.settle(entry.id, &result).await?;
+                    result
+                }
+                // Never sent, like when the client has no access token (yet).
+                Err(error) => {
+                    self.store
+                        .record_failure(entry.id, &error.to_string())
+                        .await?;
+                    Err(error)
+                }
+            };
+            results.push((entry, result));
+        }
+        Ok(results)
+    }
+
+    /// Removes entry `id` once the api has answered, or records the failure if the request may
+    /// have not reached it.
+    async fn settle<T>(&self, id: u64, result: &ZarinResult<T>) -> ZarinResult<()> {
+        match result {
+            Err(error) if is_undelivered(error) => {
+                self.store.record_failure(id, &error.to_string()).await
+            }
+            _ => self.store.remove(id).await,
+        }
+    }
+}
+
+/// `data` of a response to a replayed call, kept as is.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+struct RawData(serde_json::Value);
+
+impl RequestResult for RawData {
+    fn code(&self) -> ResultCode {
+        self.0["code"]
+            .as_i64()
+            .map_or(ResultCode::Success, ResultCode::from)
+    }
+
+    fn message(&self) -> &str {
+        self.0["message"].as_str().unwrap_or_default()
+    }
+}
+
+/// Json body of `method`, as `zarinpal` would send it.
+fn prepared_body<Z, M>(zarinpal: &Z, mut method: M) -> ZarinResult<serde_json::Value>
+where
+    Z: ZarinpalClient + ?Sized,
+    M: ApiMethod,
+{
+    method.set_merchant_id_if_needed(zarinpal.merchant_id());
+    method.prepare(zarinpal)?;
+    Ok(serde_json::to_value(&method)?)
+}
+
+/// Returns `true` if the request may have not reached the api.
+fn is_undelivered(error: &Error) -> bool {
+    matches!(error, Error::Timeout) || (error.as_api_error().is_none() && error.is_retryable())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        methods::verify::VerifyPayment,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_and_replay() {
+        let network = CannedTransport::new(|request| {
+            assert_eq!(request.body["merchant_id"], TEST_UUID);
+            Ok(responses::verify(100))
+        });
+        // Offline until told otherwise.
+        network.fail_next(usize::MAX);
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, network.clone()).unwrap();
+        let outbox = Outbox::new(MemoryOutbox::new());
+
+        let verify: VerifyPayment = VerifyPayment::builder()
+            .authority("A00000000000000000000000000217885159")
+            .amount(10000)
+            .build();
+        assert!(outbox.send(&zarinpal, verify.clone()).await.is_err());
+
+        let entries = outbox.store().load_all().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].attempts(), 1);
+        assert_eq!(entries[0].path(), <VerifyPayment>::PATH);
+
+        network.fail_next(0);
+        let replayed = outbox.replay(&zarinpal).await.unwrap();
+        assert_eq!(replayed[0].1.as_ref().unwrap()["ref_id"], 201);
+        assert!(outbox.store().load_all().await.unwrap().is_empty());
+
+        let verified = outbox.send(&zarinpal, verify).await.unwrap();
+        assert_eq!(verified.ref_id(), 201);
+        assert!(outbox.store().load_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_authenticated() {
+        let transport = CannedTransport::always(responses::inquiry("PAID"));
+        let outbox = Outbox::new(MemoryOutbox::new());
+        let body = serde_json::json!({ "merchant_id": TEST_UUID });
+        outbox
+            .store()
+            .push("/pg/v4/refund.json", Auth::AccessToken, body)
+            .await
+            .unwrap();
+
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport.clone()).unwrap();
+        let replayed = outbox.replay(&zarinpal).await.unwrap();
+        assert!(matches!(replayed[0].1, Err(Error::MissingAccessToken)));
+        assert_eq!(outbox.store().load_all().await.unwrap()[0].attempts(), 1);
+        assert_eq!(transport.request_count(), 0);
+
+        let zarinpal = zarinpal.with_access_token("token");
+        let replayed = outbox.replay(&zarinpal).await.unwrap();
+        assert_eq!(replayed[0].1.as_ref().unwrap()["status"], "PAID");
+        assert!(outbox.store().load_all().await.unwrap().is_empty());
+        assert_eq!(
+            transport.requests()[0].headers,
+            [("Authorization".to_string(), "Bearer token".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_outbox() {
+        let path = std::env::temp_dir().join("zarinpal-outbox-test.json");
+        let _ = std::fs::remove_file(&path);
+
+        let outbox = FileOutbox::open(&path).unwrap();
+        let first = outbox
+            .push("a", Auth::MerchantId, serde_json::json!(1))
+            .await
+            .unwrap();
+        let second = outbox
+            .push("b", Auth::MerchantId, serde_json::json!(2))
+            .await
+            .unwrap();
+        outbox.record_failure(second, "offline").await.unwrap();
+        outbox.remove(first).await.unwrap();
+
+        let reopened = FileOutbox::open(&path).unwrap();
+        let entries = reopened.load_all().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path(), "b");
+        assert_eq!(entries[0].last_error(), Some("offline"));
+        assert!(
+            reopened
+                .push("c", Auth::MerchantId, serde_json::json!(3))
+                .await
+                .unwrap()
+                > second
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+//! [`PaymentStore`] implementations backed by a database, using [`sqlx`](::sqlx).
+//!
+//! All records live in a single `zarinpal_payments` table, that's created by `migrate`.
+//! Times are stored as milliseconds since unix epoch, so the schema is the same on all databases.
+
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    error::{Error, ZarinResult},
+    methods::request::Currency,
+};
+
+use super::{
+    db::{decode_state, encode_state, from_millis, to_millis},
+    ArchiveQuery, PaymentRecord, PaymentState, PaymentStore,
+};
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS zarinpal_payments (
+    authority TEXT PRIMARY KEY,
+    amount BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    order_id TEXT,
+    state TEXT NOT NULL,
+    ref_id BIGINT,
+    created_at BIGINT NOT NULL,
+    archived_at BIGINT
+)";
+
+const COLUMNS: &str =
+    "authority, amount, currency, order_id, state, ref_id, created_at, archived_at";
+
+fn store_error(error: ::sqlx::Error) -> Error {
+    Error::StoreError(Box::new(error))
+}
+
+macro_rules! sqlx_store {
+    ($(#[$meta:meta])* $name:ident, $feature:literal, $db:ty, $pool:ty, $row:ty) => {
+        $(#[$meta])*
+        #[cfg(feature = $feature)]
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        #[cfg(feature = $feature)]
+        impl $name {
+            /// Creates a store using a connection pool.
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+
+            /// The connection pool.
+            pub fn pool(&self) -> &$pool {
+                &self.pool
+            }
+
+            /// Creates `zarinpal_payments` table, if it doesn't exist.
+            pub async fn migrate(&self) -> ZarinResult<()> {
+                ::sqlx::query(CREATE_TABLE)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(store_error)?;
+                Ok(())
+            }
+
+            fn decode(row: &$row) -> ZarinResult<PaymentRecord> {
+                use ::sqlx::Row;
+
+                let get = |row: &$row| -> Result<_, ::sqlx::Error> {
+                    Ok((
+                        row.try_get::<String, _>("authority")?,
+                        row.try_get::<i64, _>("amount")?,
+                        row.try_get::<String, _>("currency")?,
+                        row.try_get::<Option<String>, _>("order_id")?,
+                        row.try_get::<String, _>("state")?,
+                        row.try_get::<Option<i64>, _>("ref_id")?,
+                        row.try_get::<i64, _>("created_at")?,
+                        row.try_get::<Option<i64>, _>("archived_at")?,
+                    ))
+                };
+                let (authority, amount, currency, order_id, state, ref_id, created_at, archived_at) =
+                    get(row).map_err(store_error)?;
+
+                Ok(PaymentRecord {
+                    authority,
+                    amount: amount as u64,
+                    currency: Currency::from(currency),
+                    order_id,
+                    state: decode_state(&state, ref_id)?,
+                    created_at: from_millis(created_at),
+                    archived_at: archived_at.map(from_millis),
+                })
+            }
+
+            async fn fetch<'q>(
+                &self,
+                query: ::sqlx::query::Query<'q, $db, <$db as ::sqlx::Database>::Arguments<'q>>,
+            ) -> ZarinResult<Vec<PaymentRecord>> {
+                query
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(store_error)?
+                    .iter()
+                    .map(Self::decode)
+                    .collect()
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        #[async_trait::async_trait]
+        impl PaymentStore for $name {
+            async fn save(&self, record: PaymentRecord) -> ZarinResult<()> {
+                let (state, ref_id) = encode_state(record.state);
+                ::sqlx::query(&format!(
+                    "INSERT INTO zarinpal_payments ({COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (authority) DO UPDATE SET
+                        amount = excluded.amount, currency = excluded.currency,
+                        order_id = excluded.order_id, state = excluded.state,
+                        ref_id = excluded.ref_id, created_at = excluded.created_at,
+                        archived_at = excluded.archived_at"
+                ))
+                .bind(record.authority)
+                .bind(record.amount as i64)
+                .bind(record.currency.as_str().to_string())
+                .bind(record.order_id)
+                .bind(state)
+                .bind(ref_id)
+                .bind(to_millis(record.created_at))
+                .bind(record.archived_at.map(to_millis))
+                .execute(&self.pool)
+                .await
+                .map_err(store_error)?;
+                Ok(())
+            }
+
+            async fn load(&self, authority: &str) -> ZarinResult<Option<PaymentRecord>> {
+                let sql = format!(
+                    "SELECT {COLUMNS} FROM zarinpal_payments WHERE authority = $1 AND archived_at IS NULL"
+                );
+                Ok(self
+                    .fetch(::sqlx::query(&sql).bind(authority))
+                    .await?
+                    .pop())
+            }
+
+            async fn load_order(&self, order_id: &str) -> ZarinResult<Vec<PaymentRecord>> {
+                let sql = format!(
+                    "SELECT {COLUMNS} FROM zarinpal_payments
+                    WHERE order_id = $1 AND archived_at IS NULL ORDER BY created_at"
+                );
+                self.fetch(::sqlx::query(&sql).bind(order_id)).await
+            }
+
+            async fn load_pending(&self) -> ZarinResult<Vec<PaymentRecord>> {
+                let sql = format!(
+                    "SELECT {COLUMNS} FROM zarinpal_payments
+                    WHERE state = 'pending' AND archived_at IS NULL ORDER BY created_at"
+                );
+                self.fetch(::sqlx::query(&sql)).await
+            }
+
+            async fn set_state(&self, authority: &str, state: PaymentState) -> ZarinResult<()> {
+                let (state, ref_id) = encode_state(state);
+                ::sqlx::query(
+                    "UPDATE zarinpal_payments SET state = $2, ref_id = $3
+                    WHERE authority = $1 AND archived_at IS NULL",
+                )
+                .bind(authority)
+                .bind(state)
+                .bind(ref_id)
+                .execute(&self.pool)
+                .await
+                .map_err(store_error)?;
+                Ok(())
+            }
+
+            async fn archive(&self, authority: &str) -> ZarinResult<bool> {
+                let result = ::sqlx::query(
+                    "UPDATE zarinpal_payments SET archived_at = $2
+                    WHERE authority = $1 AND archived_at IS NULL",
+                )
+                .bind(authority)
+                .bind(to_millis(SystemTime::now()))
+                .execute(&self.pool)
+                .await
+                .map_err(store_error)?;
+                Ok(result.rows_affected() > 0)
+            }
+
+            async fn expire_pending(&self, max_age: Duration) -> ZarinResult<Vec<String>> {
+                use ::sqlx::Row;
+
+                let now = SystemTime::now();
+                let rows = ::sqlx::query(
+                    "UPDATE zarinpal_payments SET state = 'expired', archived_at = $1
+                    WHERE state = 'pending' AND archived_at IS NULL AND created_at < $2
+                    RETURNING authority",
+                )
+                .bind(to_millis(now))
+                .bind(to_millis(now - max_age))
+                .fetch_all(&self.pool)
+                .await
+                .map_err(store_error)?;
+
+                rows.iter()
+                    .map(|row| row.try_get::<String, _>("authority").map_err(store_error))
+                    .collect()
+            }
+
+            async fn load_archived(&self, query: &ArchiveQuery) -> ZarinResult<Vec<PaymentRecord>> {
+                let (state, ref_id) = query.state.map(encode_state).unzip();
+                let sql = format!(
+                    "SELECT {COLUMNS} FROM zarinpal_payments
+                    WHERE archived_at IS NOT NULL
+                        AND ($1 IS NULL OR order_id = $1)
+                        AND ($2 IS NULL OR state = $2)
+                        AND ($3 IS NULL OR ref_id = $3)
+                        AND ($4 IS NULL OR created_at >= $4)
+                        AND ($5 IS NULL OR created_at < $5)
+                    ORDER BY created_at"
+                );
+                self.fetch(
+                    ::sqlx::query(&sql)
+                        .bind(query.order_id.clone())
+                        .bind(state)
+                        .bind(ref_id.flatten())
+                        .bind(query.since.map(to_millis))
+                        .bind(query.until.map(to_millis)),
+                )
+                .await
+            }
+
+            async fn purge_archived(&self, before: SystemTime) -> ZarinResult<usize> {
+                let result = ::sqlx::query(
+                    "DELETE FROM zarinpal_payments WHERE archived_at IS NOT NULL AND archived_at < $1",
+                )
+                .bind(to_millis(before))
+                .execute(&self.pool)
+                .await
+                .map_err(store_error)?;
+                Ok(result.rows_affected() as usize)
+            }
+        }
+    };
+}
+
+sqlx_store!(
+    /// A [`PaymentStore`] backed by Postgres.
+    ///
+    /// ```no_run
+    /// use zarinpal::store::sqlx::PostgresStore;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let pool = sqlx::PgPool::connect("postgres://localhost/shop").await?;
+    ///     let store = PostgresStore::new(pool);
+    ///     store.migrate().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    PostgresStore,
+    "sqlx-postgres",
+    ::sqlx::Postgres,
+    ::sqlx::PgPool,
+    ::sqlx::postgres::PgRow
+);
+
+sqlx_store!(
+    /// A [`PaymentStore`] backed by SQLite.
+    ///
+    /// ```no_run
+    /// use zarinpal::store::sqlx::SqliteStore;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+    ///     let pool = sqlx::SqlitePool::connect("sqlite://payments.db").await?;
+    ///     let store = SqliteStore::new(pool);
+    ///     store.migrate().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    SqliteStore,
+    "sqlx-sqlite",
+    ::sqlx::Sqlite,
+    ::sqlx::SqlitePool,
+    ::sqlx::sqlite::SqliteRow
+);
+
+#[cfg(all(test, feature = "sqlx-sqlite"))]
+mod tests {
+    use super::*;
+
+    async fn store() -> SqliteStore {
+        let pool = ::sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let store = SqliteStore::new(pool);
+        store.migrate().await.unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        let store = store().await;
+
+        store
+            .save(PaymentRecord::new("A1", 10000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+        store
+            .save(PaymentRecord::new("A2", 5000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+
+        store.mark_verified("A1", 201).await.unwrap();
+
+        let record = store.load("A1").await.unwrap().unwrap();
+        assert_eq!(record.state(), PaymentState::Verified { ref_id: 201 });
+        assert_eq!(record.amount(), 10000);
+        assert_eq!(record.currency(), &Currency::IRT);
+        assert_eq!(store.load_order("order-1").await.unwrap().len(), 2);
+        assert!(store.load("A3").await.unwrap().is_none());
+        assert_eq!(store.load_pending().await.unwrap()[0].authority(), "A2");
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_archive() {
+        let store = store().await;
+
+        let mut stale = PaymentRecord::new("A1", 10000, Currency::IRT).with_order_id("order-1");
+        stale.created_at -= Duration::from_secs(3 * 24 * 60 * 60);
+        store.save(stale).await.unwrap();
+        store
+            .save(PaymentRecord::new("A2", 5000, Currency::IRT).with_order_id("order-1"))
+            .await
+            .unwrap();
+
+        store.mark_verified("A2", 202).await.unwrap();
+        assert!(store.archive("A2").await.unwrap());
+        assert!(!store.archive("A2").await.unwrap());
+
+        let expired = store
+            .expire_pending(Duration::from_secs(24 * 60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(expired, ["A1"]);
+        assert!(store.load_order("order-1").await.unwrap().is_empty());
+
+        let archived = store
+            .load_archived(&ArchiveQuery::builder().order_id("order-1").build())
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 2);
+        assert_eq!(archived[0].state(), PaymentState::Expired);
+
+        let verified = store
+            .load_archived(
+                &ArchiveQuery::builder()
+                    .state(PaymentState::Verified { ref_id: 202 })
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(verified.len(), 1);
+
+        let purged = store
+            .purge_archived(SystemTime::now() + Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 2);
+    }
+}
@@ -0,0 +1,273 @@
+//! Test doubles for code that sends zarinpal requests (requires `testkit` feature).
+//!
+//! [`MockZarinpal`] is a [`ZarinpalClient`] with scripted results per api method,
+//! so you can test your payment flow without hitting the real api.
+
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::Mutex,
+};
+
+use crate::{
+    error::{ApiError, Error, ZarinResult},
+    methods::{
+        inquiry::InquiryPayment, request::RequestPayment, reverse::ReversePayment,
+        verify::VerifyPayment, ApiMethod,
+    },
+    results::{
+        inquiry::Inquiry, request::Request, result_code::ResultCode, reverse::Reverse,
+        verify::Verify, RequestResult,
+    },
+    transport::{HttpTransport, JsonRequest},
+    ZarinpalClient,
+};
+
+type Scripted = Result<Box<dyn Any + Send>, ApiError>;
+
+/// A [`ZarinpalClient`] that returns scripted results instead of sending requests.
+///
+/// Results are returned in the same order they're scripted, per api method.
+/// Calling a method with no scripted result left fails with [`Error::TransportError`].
+///
+/// ```
+/// use zarinpal::prelude::*;
+/// use zarinpal::testing::MockZarinpal;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let zarinpal = MockZarinpal::new();
+///     zarinpal.expect_verify().returning(Verify::mock_success(201));
+///     zarinpal
+///         .expect_verify()
+///         .returning_error(ResultCode::InvalidAuthority);
+///
+///     let verify = zarinpal
+///         .verify_payment("A00000000000000000000000000217885159", 10000)
+///         .build()
+///         .await
+///         .unwrap();
+///     assert_eq!(verify.ref_id(), 201);
+///
+///     assert!(zarinpal
+///         .verify_payment("A00000000000000000000000000217885159", 10000)
+///         .build()
+///         .await
+///         .is_err());
+///
+///     assert_eq!(zarinpal.calls(<VerifyPayment>::PATH).len(), 2);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MockZarinpal {
+    merchant_id: String,
+    base_url: url::Url,
+    transport: MockTransport,
+    results: Mutex<HashMap<&'static str, VecDeque<Scripted>>>,
+    calls: Mutex<Vec<(&'static str, serde_json::Value)>>,
+}
+
+impl Default for MockZarinpal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockZarinpal {
+    /// Creates a mock client with nothing scripted.
+    pub fn new() -> Self {
+        Self {
+            merchant_id: "00000000-0000-0000-0000-000000000000".to_string(),
+            base_url: "https://api.zarinpal.com/".parse().unwrap(),
+            transport: MockTransport,
+            results: Mutex::new(HashMap::new()),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Scripts the next result of an api method.
+    pub fn expect<M: ApiMethod>(&self) -> Expectation<'_, M::Result> {
+        Expectation {
+            mock: self,
+            path: M::PATH,
+            result: PhantomData,
+        }
+    }
+
+    /// Scripts the next result of [`RequestPayment`].
+    pub fn expect_request(&self) -> Expectation<'_, Request> {
+        self.expect::<RequestPayment<Self>>()
+    }
+
+    /// Scripts the next result of [`VerifyPayment`].
+    pub fn expect_verify(&self) -> Expectation<'_, Verify> {
+        self.expect::<VerifyPayment<Self>>()
+    }
+
+    /// Scripts the next result of [`InquiryPayment`].
+    pub fn expect_inquiry(&self) -> Expectation<'_, Inquiry> {
+        self.expect::<InquiryPayment<Self>>()
+    }
+
+    /// Scripts the next result of [`ReversePayment`].
+    pub fn expect_reverse(&self) -> Expectation<'_, Reverse> {
+        self.expect::<ReversePayment<Self>>()
+    }
+
+    /// Request json of calls made to an api method (by its path), in order.
+    pub fn calls(&self, path: &str) -> Vec<serde_json::Value> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(call_path, _)| *call_path == path)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+
+    /// Returns `true` if all scripted results are used.
+    pub fn is_done(&self) -> bool {
+        self.results
+            .lock()
+            .unwrap()
+            .values()
+            .all(VecDeque::is_empty)
+    }
+
+    fn push(&self, path: &'static str, result: Scripted) {
+        self.results
+            .lock()
+            .unwrap()
+            .entry(path)
+            .or_default()
+            .push_back(result);
+    }
+}
+
+/// Scripts the next result of an api method on a [`MockZarinpal`].
+#[derive(Debug)]
+pub struct Expectation<'m, R> {
+    mock: &'m MockZarinpal,
+    path: &'static str,
+    result: PhantomData<R>,
+}
+
+impl<'m, R: RequestResult + Send> Expectation<'m, R> {
+    /// Returns `result` on the next call.
+    pub fn returning(self, result: R) -> &'m MockZarinpal {
+        self.mock.push(self.path, Ok(Box::new(result)));
+        self.mock
+    }
+
+    /// Fails the next call with an api error of `code`.
+    pub fn returning_error(self, code: ResultCode) -> &'m MockZarinpal {
+        self.mock.push(self.path, Err(ApiError::from_code(code)));
+        self.mock
+    }
+}
+
+#[async_trait::async_trait]
+impl ZarinpalClient for MockZarinpal {
+    fn transport(&self) -> &dyn HttpTransport {
+        &self.transport
+    }
+
+    fn merchant_id(&self) -> &str {
+        &self.merchant_id
+    }
+
+    fn base_url(&self) -> &url::Url {
+        &self.base_url
+    }
+
+    async fn send<M: ApiMethod + Send + Sync>(&self, mut method: M) -> ZarinResult<M::Result> {
+        method.set_merchant_id_if_needed(self.merchant_id());
+        method.prepare(self)?;
+
+        let body = serde_json::to_value(&method)?;
+        self.calls.lock().unwrap().push((M::PATH, body));
+
+        let scripted = self
+            .results
+            .lock()
+            .unwrap()
+            .get_mut(M::PATH)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| {
+                Error::TransportError(format!("unexpected call to {}", M::PATH).into())
+            })?;
+
+        match scripted {
+            Ok(result) => Ok(*result
+                .downcast::<M::Result>()
+                .expect("scripted result type matches the api method")),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Transport of [`MockZarinpal`], it's never used since results are scripted.
+#[derive(Debug)]
+struct MockTransport;
+
+#[async_trait::async_trait]
+impl HttpTransport for MockTransport {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        Err(Error::TransportError(
+            format!("mock client can't send requests to {}", request.url).into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        results::inquiry::{CheckOutcome, PaymentStatus},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_and_verify() {
+        let zarinpal = MockZarinpal::new();
+        zarinpal
+            .expect_inquiry()
+            .returning(Inquiry::mock(PaymentStatus::Paid))
+            .expect_verify()
+            .returning(Verify::mock_success(201));
+
+        let outcome = zarinpal
+            .check_and_verify("A00000000000000000000000000217885159", 10000)
+            .await
+            .unwrap();
+
+        assert!(matches!(outcome, CheckOutcome::Verified(verify) if verify.ref_id() == 201));
+        assert!(zarinpal.is_done());
+        assert_eq!(zarinpal.calls(<VerifyPayment>::PATH)[0]["amount"], 10000);
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_call() {
+        let zarinpal = MockZarinpal::new();
+        zarinpal
+            .expect_inquiry()
+            .returning(Inquiry::mock(PaymentStatus::Expired));
+
+        let outcome = zarinpal
+            .check_and_verify("A00000000000000000000000000217885159", 10000)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, CheckOutcome::Expired));
+
+        // Nothing is scripted for the second call.
+        assert!(matches!(
+            zarinpal
+                .inquiry_payment("A00000000000000000000000000217885159")
+                .build()
+                .await,
+            Err(Error::TransportError(_))
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+//! Waiting for a while, in retries, rate limiting, deadlines and polling.
+//!
+//! Uses the timer of tokio with `tokio` feature. Otherwise each wait is done on a separate
+//! thread, which works with any async runtime.
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+/// Waits for `duration`, forever if it's too long to have an end.
+pub(crate) async fn sleep(duration: Duration) {
+    match Instant::now().checked_add(duration) {
+        Some(deadline) => sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits until `deadline`.
+pub(crate) async fn sleep_until(deadline: Instant) {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep_until(deadline.into()).await;
+
+    #[cfg(not(feature = "tokio"))]
+    thread::Sleep::new(deadline).await;
+}
+
+/// Runs `future`, returning `None` if it's not done before `deadline`.
+pub(crate) async fn timeout_at<F: Future>(deadline: Instant, future: F) -> Option<F::Output> {
+    #[cfg(feature = "tokio")]
+    return tokio::time::timeout_at(deadline.into(), future).await.ok();
+
+    #[cfg(not(feature = "tokio"))]
+    {
+        use std::task::Poll;
+
+        let mut future = std::pin::pin!(future);
+        let mut sleep = thread::Sleep::new(deadline);
+        std::future::poll_fn(|cx| {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(Some(output));
+            }
+            std::pin::Pin::new(&mut sleep).poll(cx).map(|()| None)
+        })
+        .await
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+mod thread {
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll, Waker},
+        time::Instant,
+    };
+
+    #[derive(Debug, Default)]
+    struct State {
+        done: bool,
+        waker: Option<Waker>,
+    }
+
+    /// A future that's ready at `deadline`, woken by a thread that sleeps until then.
+    ///
+    /// The thread is spawned on first poll, so a sleep that's never polled costs nothing.
+    #[derive(Debug)]
+    pub(super) struct Sleep {
+        deadline: Instant,
+        state: Option<Arc<Mutex<State>>>,
+    }
+
+    impl Sleep {
+        pub(super) fn new(deadline: Instant) -> Self {
+            Self {
+                deadline,
+                state: None,
+            }
+        }
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let deadline = self.deadline;
+            if deadline <= Instant::now() {
+                return Poll::Ready(());
+            }
+
+            let state = self.state.get_or_insert_with(|| {
+                let state = Arc::new(Mutex::new(State::default()));
+                let shared = state.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    let mut state = shared.lock().unwrap();
+                    state.done = true;
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                });
+                state
+            });
+
+            let mut state = state.lock().unwrap();
+            if state.done {
+                Poll::Ready(())
+            } else {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_timeout_at() {
+        let deadline = Instant::now() + Duration::from_millis(20);
+        assert_eq!(super::timeout_at(deadline, async { 1 }).await, Some(1));
+
+        let slow = super::sleep(Duration::from_secs(60));
+        assert_eq!(super::timeout_at(deadline, slow).await, None);
+        assert!(Instant::now() >= deadline);
+    }
+}
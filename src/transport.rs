@@ -0,0 +1,471 @@
+//! Http layer of the crate, pluggable through [`HttpTransport`].
+//!
+//! [`ReqwestTransport`] is the default implementation (requires `reqwest` feature),
+//! implement [`HttpTransport`] yourself to use another http client or a test double.
+
+use std::{fmt::Debug, sync::Arc};
+
+use crate::error::ZarinResult;
+
+/// A json `POST` request to be sent by an [`HttpTransport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonRequest {
+    /// Full url of the endpoint.
+    pub url: url::Url,
+
+    /// Extra headers of the request, `Content-Type` and `Accept` are always json.
+    pub headers: Vec<(String, String)>,
+
+    /// Json body of the request.
+    pub body: serde_json::Value,
+}
+
+impl JsonRequest {
+    /// Creates a request without extra headers.
+    pub fn new(url: url::Url, body: serde_json::Value) -> Self {
+        Self {
+            url,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Adds a `Authorization: Bearer <token>` header.
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.headers
+            .push(("Authorization".to_string(), format!("Bearer {token}")));
+        self
+    }
+
+    /// Renders the request as a curl command, to attach to support tickets.
+    ///
+    /// Sensitive values of the body are masked using [`crate::redact::redact_json`], and so
+    /// is the `Authorization` header.
+    ///
+    /// ```
+    /// use zarinpal::transport::JsonRequest;
+    ///
+    /// let request = JsonRequest::new(
+    ///     "https://api.zarinpal.com/pg/v4/payment/inquiry.json".parse().unwrap(),
+    ///     serde_json::json!({ "authority": "A1" }),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     request.to_curl(),
+    ///     "curl -X POST 'https://api.zarinpal.com/pg/v4/payment/inquiry.json' \\\n  \
+    ///      -H 'Content-Type: application/json' \\\n  \
+    ///      -H 'Accept: application/json' \\\n  \
+    ///      -d '{\"authority\":\"A1\"}'"
+    /// );
+    /// ```
+    pub fn to_curl(&self) -> String {
+        fn quote(value: &str) -> String {
+            format!("'{}'", value.replace('\'', r"'\''"))
+        }
+
+        let mut body = self.body.clone();
+        crate::redact::redact_json(&mut body);
+
+        let mut curl = format!("curl -X POST {}", quote(self.url.as_str()));
+        let headers = [
+            ("Content-Type", "application/json"),
+            ("Accept", "application/json"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .chain(self.headers.iter().map(|(name, value)| {
+            let value = if name.eq_ignore_ascii_case("authorization") {
+                crate::redact::mask(value, 7, 0)
+            } else {
+                value.clone()
+            };
+            (name.clone(), value)
+        }));
+        for (name, value) in headers {
+            curl.push_str(" \\\n  -H ");
+            curl.push_str(&quote(&format!("{name}: {value}")));
+        }
+        curl.push_str(" \\\n  -d ");
+        curl.push_str(&quote(&body.to_string()));
+        curl
+    }
+}
+
+/// Sends json requests and returns json responses.
+///
+/// Response body must be returned whatever the status code is, since zarinpal reports
+/// api errors using a json body with non-success status codes.
+/// Errors of the http client can be wrapped in [`crate::error::Error::TransportError`].
+#[async_trait::async_trait]
+pub trait HttpTransport: Debug + Send + Sync {
+    /// Sends a json `POST` request and parses the response body as json.
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value>;
+}
+
+#[async_trait::async_trait]
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        self.as_ref().post_json(request).await
+    }
+}
+
+/// [`HttpTransport`] implementation using [`reqwest::Client`].
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    /// Creates a transport using a custom [`reqwest::Client`].
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Inner http client.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait::async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        let mut builder = self.client.post(request.url).json(&request.body);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        // Zarinpal reports api errors with 4xx responses, 5xx ones has no useful json body.
+        // Json bodies without `data` or `errors` (eg: from a proxy) are reported with their status.
+        let body = response.bytes().await?;
+        match serde_json::from_slice::<serde_json::Value>(&body) {
+            Ok(json)
+                if !status.is_server_error()
+                    && json.as_object().is_some_and(|map| {
+                        map.contains_key("data") || map.contains_key("errors")
+                    }) =>
+            {
+                Ok(json)
+            }
+            _ => Err(crate::error::Error::unexpected_response(
+                Some(status.as_u16()),
+                content_type,
+                &String::from_utf8_lossy(&body),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    use super::*;
+
+    /// A transport that fails all requests, for tests that never hit the network.
+    #[derive(Debug)]
+    pub(crate) struct OfflineTransport;
+
+    #[async_trait::async_trait]
+    impl HttpTransport for OfflineTransport {
+        async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+            Err(crate::error::Error::TransportError(
+                format!("offline, can't reach {}", request.url).into(),
+            ))
+        }
+    }
+
+    type Respond = dyn Fn(&JsonRequest) -> ZarinResult<serde_json::Value> + Send + Sync;
+
+    /// A transport answering each request with a response picked by a function, usually one of
+    /// [`responses`].
+    ///
+    /// Clones share their state, so keep one to inspect the requests that were sent.
+    #[derive(Clone)]
+    pub(crate) struct CannedTransport {
+        respond: Arc<Respond>,
+        delay: Duration,
+        failures: Arc<AtomicUsize>,
+        requests: Arc<Mutex<Vec<JsonRequest>>>,
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl CannedTransport {
+        pub(crate) fn new(
+            respond: impl Fn(&JsonRequest) -> ZarinResult<serde_json::Value> + Send + Sync + 'static,
+        ) -> Self {
+            Self {
+                respond: Arc::new(respond),
+                delay: Duration::ZERO,
+                failures: Default::default(),
+                requests: Default::default(),
+                in_flight: Default::default(),
+                peak: Default::default(),
+            }
+        }
+
+        /// Answers every request with `response`.
+        pub(crate) fn always(response: serde_json::Value) -> Self {
+            Self::new(move |_| Ok(response.clone()))
+        }
+
+        /// Waits for `delay` before answering each request.
+        pub(crate) fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        /// Fails the next `count` requests with a transport error, like a flaky network.
+        pub(crate) fn fail_next(&self, count: usize) {
+            self.failures.store(count, Ordering::SeqCst);
+        }
+
+        /// Requests that were sent so far.
+        pub(crate) fn requests(&self) -> Vec<JsonRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        /// Number of requests that were sent so far, failed ones included.
+        pub(crate) fn request_count(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+
+        /// Most requests that were in flight at the same time.
+        #[cfg(feature = "batch")]
+        pub(crate) fn peak_in_flight(&self) -> usize {
+            self.peak.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Debug for CannedTransport {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("CannedTransport")
+                .field("delay", &self.delay)
+                .field("requests", &self.request_count())
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HttpTransport for CannedTransport {
+        async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+            self.requests.lock().unwrap().push(request.clone());
+
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(in_flight, Ordering::SeqCst);
+            if !self.delay.is_zero() {
+                crate::timer::sleep(self.delay).await;
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+            if failing {
+                return Err(crate::error::Error::TransportError(
+                    "connection reset".into(),
+                ));
+            }
+
+            (self.respond)(&request)
+        }
+    }
+
+    /// Canned responses of zarinpal api.
+    pub(crate) mod responses {
+        use serde_json::{json, Value};
+
+        /// A successful payment request, with `authority`.
+        pub(crate) fn request(authority: &str) -> Value {
+            json!({
+                "data": {
+                    "code": 100,
+                    "message": "Success",
+                    "authority": authority,
+                    "fee_type": "Merchant",
+                    "fee": 100
+                },
+                "errors": []
+            })
+        }
+
+        /// A successful verify with ref id `201`, `code` is `100` or `101` (verified before).
+        pub(crate) fn verify(code: i64) -> Value {
+            json!({
+                "data": {
+                    "code": code,
+                    "message": "Verified",
+                    "card_hash": "1EBE3EBEBE35C7EC0F8D6EE4F2F859107A87822CA179BC9528767EA7B5489B69",
+                    "card_pan": "502229******5995",
+                    "ref_id": 201,
+                    "fee_type": "Merchant",
+                    "fee": 0
+                },
+                "errors": []
+            })
+        }
+
+        /// An inquiry of a payment in `status`, like `PAID`.
+        pub(crate) fn inquiry(status: &str) -> Value {
+            json!({
+                "data": { "code": 100, "message": "Success", "status": status },
+                "errors": []
+            })
+        }
+
+        /// A list of unverified payments of 1000 rials.
+        #[cfg(all(feature = "unverified", feature = "batch"))]
+        pub(crate) fn unverified(authorities: &[&str]) -> Value {
+            json!({
+                "data": {
+                    "code": 100,
+                    "message": "Success",
+                    "authorities": authorities.iter().map(|authority| json!({
+                        "authority": authority,
+                        "amount": 1000,
+                        "callback_url": "https://example.com/",
+                        "referer": "https://example.com/",
+                        "date": "2024-06-27 17:33:25"
+                    })).collect::<Vec<_>>()
+                },
+                "errors": []
+            })
+        }
+
+        /// An api error with `code`, like `-51`.
+        pub(crate) fn error(code: i64, message: &str) -> Value {
+            json!({
+                "data": [],
+                "errors": { "code": code, "message": message, "validations": [] }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        use crate::{error::Error, extensions::ZarinpalSendExtension, Zarinpal, TEST_UUID};
+
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, OfflineTransport).unwrap();
+        let result = zarinpal
+            .inquiry_payment("A00000000000000000000000000217885159")
+            .build()
+            .await;
+
+        match result {
+            Err(Error::TransportError(error)) => assert_eq!(
+                error.to_string(),
+                "offline, can't reach https://api.zarinpal.com/pg/v4/payment/inquiry.json"
+            ),
+            other => panic!("expected a transport error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_raw() {
+        use crate::{error::Error, Zarinpal, ZarinpalClient, TEST_UUID};
+
+        // Echoes the request body as data, or fails like zarinpal if there's no authority.
+        let transport = CannedTransport::new(|request| {
+            Ok(if request.body.get("authority").is_some() {
+                serde_json::json!({ "data": request.body, "errors": [] })
+            } else {
+                responses::error(-9, "The input params invalid, validation error.")
+            })
+        });
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+        let data = zarinpal
+            .send_raw(
+                "/pg/v4/payment/inquiry.json",
+                serde_json::json!({ "authority": "A1" }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            data,
+            serde_json::json!({ "authority": "A1", "merchant_id": TEST_UUID })
+        );
+
+        let result = zarinpal
+            .send_raw("/pg/v4/payment/inquiry.json", serde_json::json!({}))
+            .await;
+        assert!(
+            matches!(result, Err(Error::ZarinpalApiError(error)) if error.code() == crate::results::result_code::ResultCode::Validation)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_response() {
+        use crate::{error::Error, extensions::ZarinpalSendExtension, Zarinpal, TEST_UUID};
+
+        // Answers with a json that's not an api result, like a gateway of a CDN would.
+        let transport =
+            CannedTransport::always(serde_json::json!({ "challenge": "x".repeat(1000) }));
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+        match zarinpal.inquiry_payment("A1").build().await {
+            Err(Error::UnexpectedResponse {
+                status,
+                body_snippet,
+                ..
+            }) => {
+                assert_eq!(status, None);
+                assert!(body_snippet.starts_with(r#"{"challenge":"xxx"#));
+                assert_eq!(body_snippet.chars().count(), 512);
+            }
+            other => panic!("expected an unexpected response, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_without_data_or_errors() {
+        use crate::{error::Error, extensions::ZarinpalSendExtension, Zarinpal, TEST_UUID};
+
+        for response in [
+            serde_json::json!({ "data": [] }),
+            serde_json::json!({ "data": [], "errors": [] }),
+        ] {
+            let transport = CannedTransport::always(response.clone());
+            let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+            match zarinpal.inquiry_payment("A1").build().await {
+                Err(Error::UnexpectedResponse { body_snippet, .. }) => {
+                    assert_eq!(body_snippet, response.to_string());
+                }
+                other => panic!("expected an unexpected response, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bearer_auth() {
+        let request = JsonRequest::new(
+            "https://example.com/".parse().unwrap(),
+            serde_json::Value::Null,
+        )
+        .bearer_auth("token");
+
+        assert_eq!(
+            request.headers,
+            [("Authorization".to_string(), "Bearer token".to_string())]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Record/replay of http interactions, for deterministic and offline integration tests
+//! (requires `testkit` feature).
+//!
+//! Run your tests once with a [`RecordingTransport`] against the real (or sandbox) api and save
+//! the [`Cassette`], then run them with a [`ReplayTransport`] serving the saved responses.
+//! Sensitive values (merchant ids, card pans, ...) are redacted in recordings using
+//! [`crate::redact::redact_json`], so cassettes are safe to commit.
+//!
+//! ```no_run
+//! use zarinpal::prelude::*;
+//! use zarinpal::transport::ReqwestTransport;
+//! use zarinpal::vcr::{Cassette, RecordingTransport, ReplayTransport};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+//!     // Recording.
+//!     let recorder = std::sync::Arc::new(RecordingTransport::new(ReqwestTransport::default()));
+//!     let zarinpal = Zarinpal::new_with_transport("...", recorder.clone())?;
+//!     zarinpal
+//!         .inquiry_payment("A00000000000000000000000000217885159")
+//!         .build()
+//!         .await?;
+//!     recorder.cassette().save("tests/fixtures/inquiry.json")?;
+//!
+//!     // Replaying.
+//!     let replay = ReplayTransport::new(Cassette::load("tests/fixtures/inquiry.json")?);
+//!     let zarinpal = Zarinpal::new_with_transport("...", replay)?;
+//!     zarinpal
+//!         .inquiry_payment("A00000000000000000000000000217885159")
+//!         .build()
+//!         .await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::VecDeque, path::Path, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ZarinResult},
+    redact::redact_json,
+    transport::{HttpTransport, JsonRequest},
+};
+
+/// A recorded request and its response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Full url of the endpoint.
+    pub url: String,
+
+    /// Redacted json body of the request.
+    pub request: serde_json::Value,
+
+    /// Redacted json body of the response.
+    pub response: serde_json::Value,
+}
+
+/// A list of recorded [`Interaction`]s, in the order they're made.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Creates a cassette of `interactions`.
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        Self { interactions }
+    }
+
+    /// Recorded interactions.
+    pub fn interactions(&self) -> &[Interaction] {
+        self.interactions.as_ref()
+    }
+
+    /// Reads a cassette from a json file.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    /// Writes the cassette to a (pretty) json file, creating parent directories if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// An [`HttpTransport`] that sends requests using another transport and records them.
+///
+/// Failed requests (transport errors) are not recorded.
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl<T: HttpTransport> RecordingTransport<T> {
+    /// Creates a recorder sending requests with `inner`.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        Cassette::new(self.interactions.lock().unwrap().clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: HttpTransport> HttpTransport for RecordingTransport<T> {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        let url = request.url.to_string();
+        let mut recorded_request = request.body.clone();
+        let response = self.inner.post_json(request).await?;
+
+        let mut recorded_response = response.clone();
+        redact_json(&mut recorded_request);
+        redact_json(&mut recorded_response);
+        self.interactions.lock().unwrap().push(Interaction {
+            url,
+            request: recorded_request,
+            response: recorded_response,
+        });
+
+        Ok(response)
+    }
+}
+
+/// An [`HttpTransport`] serving responses of a [`Cassette`], without sending any request.
+///
+/// Interactions are served in order, and each request must match the recorded one (url and
+/// redacted body), otherwise it fails with [`Error::TransportError`].
+#[derive(Debug)]
+pub struct ReplayTransport {
+    interactions: Mutex<VecDeque<Interaction>>,
+}
+
+impl ReplayTransport {
+    /// Creates a transport replaying `cassette`.
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            interactions: Mutex::new(cassette.interactions.into()),
+        }
+    }
+
+    /// Returns `true` if all interactions are replayed.
+    pub fn is_done(&self) -> bool {
+        self.interactions.lock().unwrap().is_empty()
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn post_json(&self, request: JsonRequest) -> ZarinResult<serde_json::Value> {
+        let interaction = self
+            .interactions
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| {
+                Error::TransportError(format!("no recorded interaction for {}", request.url).into())
+            })?;
+
+        let mut body = request.body;
+        redact_json(&mut body);
+        if interaction.url != request.url.as_str() || interaction.request != body {
+            return Err(Error::TransportError(
+                format!(
+                    "request to {} doesn't match the recorded one to {}",
+                    request.url, interaction.url
+                )
+                .into(),
+            ));
+        }
+
+        Ok(interaction.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        extensions::ZarinpalSendExtension,
+        transport::tests::{responses, CannedTransport},
+        Zarinpal, TEST_UUID,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let authority = "A00000000000000000000000000217885159";
+
+        let recorder = std::sync::Arc::new(RecordingTransport::new(CannedTransport::always(
+            responses::inquiry("PAID"),
+        )));
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, recorder.clone()).unwrap();
+        zarinpal.inquiry_payment(authority).build().await.unwrap();
+
+        let cassette = recorder.cassette();
+        assert_eq!(cassette.interactions().len(), 1);
+        assert_ne!(
+            cassette.interactions()[0].request["merchant_id"],
+            serde_json::json!(TEST_UUID)
+        );
+
+        let path = std::env::temp_dir().join("zarinpal-vcr-test.json");
+        cassette.save(&path).unwrap();
+        let cassette = Cassette::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let replay = std::sync::Arc::new(ReplayTransport::new(cassette));
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, replay.clone()).unwrap();
+        zarinpal.inquiry_payment(authority).build().await.unwrap();
+        assert!(replay.is_done());
+
+        assert!(matches!(
+            zarinpal.inquiry_payment(authority).build().await,
+            Err(Error::TransportError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_mismatch() {
+        let replay = ReplayTransport::new(Cassette::new(vec![Interaction {
+            url: "https://api.zarinpal.com/pg/v4/payment/inquiry.json".to_string(),
+            request: serde_json::json!({ "authority": "A1" }),
+            response: serde_json::Value::Null,
+        }]));
+
+        let result = replay
+            .post_json(JsonRequest::new(
+                "https://api.zarinpal.com/pg/v4/payment/inquiry.json"
+                    .parse()
+                    .unwrap(),
+                serde_json::json!({ "authority": "A2" }),
+            ))
+            .await;
+        assert!(matches!(result, Err(Error::TransportError(_))));
+    }
+}
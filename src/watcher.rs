@@ -0,0 +1,356 @@
+//! Waiting for a payment to be settled, or for new unverified payments, by polling.
+
+#[cfg(all(feature = "unverified", feature = "batch"))]
+use std::collections::{HashSet, VecDeque};
+use std::{
+    future::IntoFuture,
+    time::{Duration, Instant},
+};
+
+use typed_builder::TypedBuilder;
+
+use crate::{
+    error::{Error, ZarinResult},
+    extensions::ZarinpalSendExtension,
+    results::inquiry::PaymentStatus,
+    ZarinpalClient,
+};
+
+/// Outcome of [`PaymentWatcher::watch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchOutcome {
+    /// The payment reached a final status, see [`PaymentStatus::is_settled`].
+    Settled(PaymentStatus),
+
+    /// The deadline is passed before the payment is settled, with the last seen status.
+    DeadlineExceeded(PaymentStatus),
+}
+
+impl WatchOutcome {
+    /// Last seen status of the payment.
+    pub fn status(&self) -> PaymentStatus {
+        match self {
+            WatchOutcome::Settled(status) | WatchOutcome::DeadlineExceeded(status) => *status,
+        }
+    }
+}
+
+/// Polls the inquiry endpoint for a payment, until it's paid, failed or expired.
+///
+/// Polling interval starts at `interval` and is multiplied by `multiplier` after each attempt,
+/// up to `max_interval`. Useful for bots and kiosk UIs awaiting a payment.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use zarinpal::prelude::*;
+/// use zarinpal::watcher::{PaymentWatcher, WatchOutcome};
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let outcome = PaymentWatcher::builder()
+///         .authority("A00000000000000000000000000217885159")
+///         .deadline(Duration::from_secs(5 * 60))
+///         .build()
+///         .watch(&zarinpal)
+///         .await?;
+///
+///     if let WatchOutcome::Settled(PaymentStatus::Paid) = outcome {
+///         // Verify the payment.
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PaymentWatcher {
+    /// Authority of the payment to watch.
+    #[builder(setter(into))]
+    authority: String,
+
+    /// (Optional) Delay before the first retry. Defaults to 2 seconds.
+    #[builder(default = Duration::from_secs(2))]
+    interval: Duration,
+
+    /// (Optional) Maximum delay between two attempts. Defaults to 30 seconds.
+    #[builder(default = Duration::from_secs(30))]
+    max_interval: Duration,
+
+    /// (Optional) Backoff multiplier of the delay. Defaults to `2`.
+    #[builder(default = 2)]
+    multiplier: u32,
+
+    /// (Optional) How long to watch the payment. Defaults to 15 minutes.
+    #[builder(default = Duration::from_secs(15 * 60))]
+    deadline: Duration,
+}
+
+impl PaymentWatcher {
+    /// Authority of the payment to watch.
+    pub fn authority(&self) -> &str {
+        self.authority.as_ref()
+    }
+
+    /// Polls the payment status until it's settled or the deadline is passed.
+    ///
+    /// Api errors are returned immediately. Each inquiry is bounded by the time that's left, and
+    /// fails with [`Error::Timeout`] if the first one doesn't complete before the deadline.
+    pub async fn watch<Z: ZarinpalClient + Sync + Send>(
+        &self,
+        zarinpal: &Z,
+    ) -> ZarinResult<WatchOutcome> {
+        // A deadline too far to be represented is no deadline.
+        let deadline = Instant::now().checked_add(self.deadline);
+        let mut interval = self.interval;
+        let mut last_status = None;
+
+        loop {
+            let inquiry = zarinpal.inquiry_payment(self.authority.as_str()).build();
+            let status = match (
+                crate::methods::with_deadline(deadline, inquiry.into_future()).await,
+                last_status,
+            ) {
+                (Ok(inquiry), _) => inquiry.status(),
+                (Err(Error::Timeout), Some(status)) => {
+                    return Ok(WatchOutcome::DeadlineExceeded(status))
+                }
+                (Err(error), _) => return Err(error),
+            };
+
+            if status.is_settled() {
+                return Ok(WatchOutcome::Settled(status));
+            }
+            last_status = Some(status);
+
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|remaining| remaining.is_zero()) {
+                return Ok(WatchOutcome::DeadlineExceeded(status));
+            }
+
+            crate::timer::sleep(remaining.map_or(interval, |remaining| interval.min(remaining)))
+                .await;
+            interval = self.next_interval(interval);
+        }
+    }
+
+    fn next_interval(&self, interval: Duration) -> Duration {
+        interval
+            .saturating_mul(self.multiplier)
+            .min(self.max_interval)
+    }
+}
+
+/// Polls the unverified endpoint, yielding payments that are not seen before.
+///
+/// This is the backbone of an auto-verify daemon, see also [`ZarinpalSendExtension::watch_unverified`].
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use futures_util::StreamExt;
+/// use zarinpal::prelude::*;
+/// use zarinpal::watcher::UnverifiedWatcher;
+///
+/// #[tokio::main]
+/// async fn main() -> Result::<(), Box<dyn std::error::Error>> {
+///     let zarinpal = Zarinpal::new("...")?;
+///
+///     let mut new_payments = std::pin::pin!(UnverifiedWatcher::builder()
+///         .interval(Duration::from_secs(60))
+///         // Authorities handled before a restart.
+///         .seen(["A00000000000000000000000000217885159".to_string()])
+///         .build()
+///         .watch(&zarinpal));
+///
+///     while let Some(unverified) = new_payments.next().await {
+///         let unverified = unverified?;
+///         unverified.verify(&zarinpal).await?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(all(feature = "unverified", feature = "batch"))]
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct UnverifiedWatcher {
+    /// (Optional) Delay between two polls. Defaults to 30 seconds.
+    #[builder(default = Duration::from_secs(30))]
+    interval: Duration,
+
+    /// (Optional) Authorities that are already handled, and must not be yielded.
+    #[builder(default, setter(transform = |seen: impl IntoIterator<Item = String>| seen.into_iter().collect()))]
+    seen: HashSet<String>,
+}
+
+#[cfg(all(feature = "unverified", feature = "batch"))]
+impl UnverifiedWatcher {
+    /// Returns a never ending stream of payments that newly appeared in unverified requests.
+    ///
+    /// The first poll is sent immediately, and errors are yielded without stopping the stream.
+    /// Authorities that left the unverified list are forgotten, to keep memory bounded.
+    pub fn watch<'z, Z: ZarinpalClient + Sync + Send>(
+        self,
+        zarinpal: &'z Z,
+    ) -> impl futures_util::Stream<Item = ZarinResult<crate::results::unverified::Authorities>> + Send + 'z
+    {
+        let interval = self.interval;
+        let state = (self.seen, VecDeque::new(), false);
+
+        futures_util::stream::unfold(
+            state,
+            move |(mut seen, mut pending, mut polled)| async move {
+                loop {
+                    if let Some(next) = pending.pop_front() {
+                        return Some((Ok(next), (seen, pending, polled)));
+                    }
+
+                    if polled {
+                        crate::timer::sleep(interval).await;
+                    }
+                    polled = true;
+
+                    let unverified = match zarinpal.unverified_requests().build().await {
+                        Ok(unverified) => unverified,
+                        Err(error) => return Some((Err(error), (seen, pending, polled))),
+                    };
+
+                    let current = unverified
+                        .authorities()
+                        .iter()
+                        .map(|unverified| unverified.authority())
+                        .collect::<HashSet<_>>();
+                    seen.retain(|authority| current.contains(authority.as_str()));
+
+                    for unverified in unverified.authorities() {
+                        if seen.insert(unverified.authority().to_string()) {
+                            pending.push_back(unverified.clone());
+                        }
+                    }
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let watcher = PaymentWatcher::builder()
+            .authority("A00000000000000000000000000217885159")
+            .interval(Duration::from_secs(2))
+            .max_interval(Duration::from_secs(10))
+            .build();
+
+        let intervals: Vec<_> = std::iter::successors(Some(watcher.interval), |interval| {
+            Some(watcher.next_interval(*interval))
+        })
+        .take(5)
+        .map(|interval| interval.as_secs())
+        .collect();
+
+        assert_eq!(intervals, [2, 4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn test_settled() {
+        assert!(PaymentStatus::Paid.is_settled());
+        assert!(PaymentStatus::Expired.is_settled());
+        assert!(!PaymentStatus::InBank.is_settled());
+        assert!(!PaymentStatus::Unknown.is_settled());
+    }
+
+    #[tokio::test]
+    async fn test_watch_deadline() {
+        use crate::{
+            transport::tests::{responses, CannedTransport},
+            Zarinpal, TEST_UUID,
+        };
+
+        let watcher = |deadline| {
+            PaymentWatcher::builder()
+                .authority("A1")
+                .interval(Duration::from_millis(10))
+                .deadline(deadline)
+                .build()
+        };
+
+        // Far deadlines don't overflow.
+        let zarinpal = Zarinpal::new_with_transport(
+            TEST_UUID,
+            CannedTransport::always(responses::inquiry("PAID")),
+        )
+        .unwrap();
+        assert_eq!(
+            watcher(Duration::MAX).watch(&zarinpal).await.unwrap(),
+            WatchOutcome::Settled(PaymentStatus::Paid)
+        );
+
+        // Slow inquiries are cut at the deadline.
+        let transport = CannedTransport::always(responses::inquiry("IN_BANK"))
+            .with_delay(Duration::from_millis(30));
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+        let started = Instant::now();
+        assert_eq!(
+            watcher(Duration::from_millis(100))
+                .watch(&zarinpal)
+                .await
+                .unwrap(),
+            WatchOutcome::DeadlineExceeded(PaymentStatus::InBank)
+        );
+        assert!(started.elapsed() < Duration::from_millis(150));
+
+        assert!(matches!(
+            watcher(Duration::from_millis(10)).watch(&zarinpal).await,
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[cfg(all(feature = "unverified", feature = "batch"))]
+    #[tokio::test]
+    async fn test_watch_unverified() {
+        use std::sync::Mutex;
+
+        use futures_util::StreamExt;
+
+        use crate::{
+            transport::tests::{responses, CannedTransport},
+            Zarinpal, TEST_UUID,
+        };
+
+        // A list of authorities per poll, repeating the last one.
+        let polls = Mutex::new(VecDeque::from([
+            vec!["A1", "A2"],
+            vec!["A2", "A3"],
+            vec!["A3"],
+            vec!["A4"],
+        ]));
+        let transport = CannedTransport::new(move |_| {
+            let mut polls = polls.lock().unwrap();
+            let authorities = if polls.len() > 1 {
+                polls.pop_front().unwrap()
+            } else {
+                polls[0].clone()
+            };
+            Ok(responses::unverified(&authorities))
+        });
+        let zarinpal = Zarinpal::new_with_transport(TEST_UUID, transport).unwrap();
+
+        let yielded = UnverifiedWatcher::builder()
+            .interval(Duration::from_millis(1))
+            .seen(["A1".to_string()])
+            .build()
+            .watch(&zarinpal)
+            .take(3)
+            .map(|unverified| unverified.unwrap().authority().to_string())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(yielded, ["A2", "A3", "A4"]);
+    }
+}
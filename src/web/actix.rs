@@ -0,0 +1,102 @@
+//! Actix-web integration (requires `actix` feature).
+//!
+//! - [`PaymentCallback`] can be used as an extractor in callback handlers.
+//! - [`Request`] is a responder redirecting the payer to [`Request::gateway_url`].
+//!
+//! ```
+//! use zarinpal::prelude::*;
+//!
+//! // Request a payment and redirect the payer to the gateway.
+//! async fn pay(zarinpal: &Zarinpal) -> ZarinResult<Request> {
+//!     zarinpal
+//!         .request_payment(10000, "https://example.com/verify".parse().unwrap(), "...")
+//!         .build()
+//!         .await
+//! }
+//!
+//! // Verify the payment when the payer is back.
+//! async fn verify(zarinpal: &Zarinpal, callback: PaymentCallback) -> ZarinResult<CallbackOutcome> {
+//!     zarinpal.verify_callback(callback, 10000).await
+//! }
+//! ```
+//!
+//! Malformed callback queries are rejected with `400 Bad Request` and a [`CallbackError`] message.
+
+use std::future::{ready, Ready};
+
+use actix_web::{
+    body::BoxBody, dev::Payload, http::header, http::StatusCode, FromRequest, HttpRequest,
+    HttpResponse, Responder, ResponseError,
+};
+
+use crate::{
+    callback::{CallbackError, PaymentCallback},
+    results::request::Request,
+};
+
+impl ResponseError for CallbackError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl FromRequest for PaymentCallback {
+    type Error = CallbackError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(PaymentCallback::from_query(req.query_string()))
+    }
+}
+
+impl Responder for Request {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Found()
+            .insert_header((header::LOCATION, self.gateway_url().as_str()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use crate::callback::CallbackStatus;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_extractor() {
+        let req = TestRequest::with_uri(
+            "/verify?Authority=A00000000000000000000000000217885159&Status=NOK",
+        )
+        .to_http_request();
+        let callback = PaymentCallback::extract(&req).await.unwrap();
+        assert_eq!(callback.status(), CallbackStatus::Nok);
+
+        let req = TestRequest::with_uri("/verify?Status=OK").to_http_request();
+        let error = PaymentCallback::extract(&req).await.unwrap_err();
+        assert_eq!(error.error_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_responder() {
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "code": 100,
+            "message": "Success",
+            "authority": "A00000000000000000000000000217885159",
+            "fee_type": "Merchant",
+            "fee": 100
+        }))
+        .unwrap();
+
+        let response = request.respond_to(&TestRequest::default().to_http_request());
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "https://www.zarinpal.com/pg/StartPay/A00000000000000000000000000217885159"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Axum integration (requires `axum` feature).
+//!
+//! [`PaymentCallback`] can be used as an extractor in callback handlers:
+//!
+//! ```
+//! use zarinpal::prelude::*;
+//!
+//! async fn callback(callback: PaymentCallback) -> String {
+//!     format!("{} is {:?}", callback.authority(), callback.status())
+//! }
+//! ```
+//!
+//! Malformed callback queries are rejected with `400 Bad Request` and a [`CallbackError`] message.
+
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{request::Parts, StatusCode};
+
+use crate::callback::{CallbackError, PaymentCallback};
+
+impl IntoResponse for CallbackError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for PaymentCallback {
+    type Rejection = CallbackError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        PaymentCallback::from_query(parts.uri.query().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callback::CallbackStatus;
+
+    use super::*;
+
+    async fn extract(uri: &str) -> Result<PaymentCallback, CallbackError> {
+        let (mut parts, _) = http::Request::builder()
+            .uri(uri)
+            .body(())
+            .unwrap()
+            .into_parts();
+        PaymentCallback::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_extractor() {
+        let callback = extract("/verify?Authority=A00000000000000000000000000217885159&Status=OK")
+            .await
+            .unwrap();
+        assert_eq!(callback.status(), CallbackStatus::Ok);
+
+        let rejection = extract("/verify").await.unwrap_err();
+        assert!(rejection.to_string().contains("Authority"));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,10 @@
+//! Integrations with web frameworks (requires `web` feature, plus a feature per framework).
+//!
+//! - `axum`: [`crate::callback::PaymentCallback`] is an axum extractor.
+//! - `actix`: [`crate::callback::PaymentCallback`] is an actix-web extractor,
+//!   and [`crate::results::request::Request`] redirects to the gateway.
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
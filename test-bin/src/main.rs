@@ -6,7 +6,7 @@ async fn main() -> anyhow::Result<()> {
     let zarinpal = Zarinpal::new("merchant_id")?;
 
     let request = zarinpal
-        .request_payment(10000, "example.com".parse()?, "Test payment")
+        .request_payment(10000, "https://example.com/verify".parse()?, "Test payment")
         .build()
         .await?;
 
@@ -16,10 +16,10 @@ async fn main() -> anyhow::Result<()> {
         .await?;
 
     let _request_2 = zarinpal
-        .request_payment(10000, "example.com".parse()?, "Test payment")
+        .request_payment(10000, "https://example.com/verify".parse()?, "Test payment")
         .metadata(
             Metadata::builder()
-                .mobile("98912345678")
+                .mobile("+989121234567".parse()?)
                 .email("test@example.com")
                 .build(),
         )
@@ -27,21 +27,21 @@ async fn main() -> anyhow::Result<()> {
         .await?;
 
     let _request_3 = zarinpal
-        .request_payment(10000, "example.com".parse()?, "Test payment")
+        .request_payment(10000, "https://example.com/verify".parse()?, "Test payment")
         .currency(Currency::IRT) // Tomans
         .build()
         .await?;
 
     let _request_4 = zarinpal
-        .request_payment(10000, "example.com".parse()?, "Test payment")
+        .request_payment(10000, "https://example.com/verify".parse()?, "Test payment")
         .wages([
             Wage::builder()
-                .iban("...")
+                .iban("IR130570028780010957775103".parse()?)
                 .amount(5000)
                 .description("To my first friend")
                 .build(),
             Wage::builder()
-                .iban("...")
+                .iban("IR670170000000352965862009".parse()?)
                 .amount(5000)
                 .description("To my second friend")
                 .build(),